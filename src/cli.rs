@@ -0,0 +1,849 @@
+use anyhow::{Context, Result};
+use std::path::PathBuf;
+
+use crate::grid::MAX_TILES;
+use crate::effects::{Level, Mirror};
+use crate::i18n::{Lang, Msg};
+use crate::pattern::Pattern;
+use crate::rewind;
+use crate::subtitles::Burn;
+use crate::render::{AsciiColor, Backdrop, ColorDepth, Crop, Fit, PixelFormat, RenderMode, Scaler};
+
+/// `--help` and the usage after a bad argument, in each language; shown through `Msg::Usage`.
+pub const USAGE_ZH: &str = "\
+用法: Vodeo2ASCII [选项] [文件]
+
+指定文件时直接播放该文件, 否则启动交互界面。
+
+选项:
+  --info <文件>    以 JSON 格式输出视频元数据后退出
+  --list           列出当前目录下的视频及其元数据后退出
+  --dry-run <文件> 打印播放该文件时将执行的 ffmpeg 命令、解码尺寸与滤镜后退出
+  --grid <文件>... 在终端中分格同时播放 2-4 个视频 (无声音, 按 q 退出)
+  --test-pattern [图案]
+                   不读取视频, 显示测试图案以检查终端色彩与字符宽高比:
+                   bars (SMPTE 彩条, 默认), sweep (RGB 渐变), ramp (灰阶), circle (圆形)
+  --profile-csv <输出>
+                   将每帧的解码等待、渲染、写入耗时记录到 CSV 文件, 用于性能分析
+  --log <文件>     将 ffmpeg 路径、探测结果、解码尺寸、ffmpeg 命令与错误写入日志文件, 便于反馈问题
+                   (设置了 RUST_LOG 环境变量时默认写入临时目录下的 vodeo2ascii.log)
+  --verbose        将执行的 ffmpeg/ffprobe 命令输出到 stderr (界面模式下在退出后输出)
+  --threads <N>    限制 ffmpeg 解码线程数 (默认由 ffmpeg 自动选择)
+  --fps-cap <N>    限制每秒渲染的帧数以节省电量/CPU (默认不限制)
+  --max-decode-fps <N>
+                   帧率高于 N 的视频 (如 120/240fps 游戏录像) 由 ffmpeg 抽帧到 N fps 再解码输出,
+                   减少解码与管道开销 (默认 60, 0 关闭; 也可设置 max_decode_fps=)
+  --rewind-frames <N>
+                   保留最近渲染的 N 帧, 检查模式下按 , 后退时无需重启 ffmpeg (默认 60, 0 关闭, 最多 10000;
+                   占用内存约为 解码尺寸的一帧 × N, 也可设置 rewind_frames=)
+  --prebuffer <N>  开始播放前先由后台线程解码 N 帧放入队列, 再按帧率开始计时, 避免慢速解码时开头卡顿
+                   (默认 0 关闭; 也可设置 prebuffer=)
+  --reconnect <N>  网络视频播放中断时最多自动重连 N 次, 每次等待时间加倍 (默认 5, 0 关闭; 也可设置 reconnect=)
+  --skip-black     从头播放时自动跳过开头的黑场
+  --black-threshold <N>
+                   黑场判定的平均亮度阈值 0-255 (默认 16, 隐含 --skip-black)
+  --reduce-motion  减少闪动: 画面变化很小时保持上一帧, 不重新绘制
+  --motion-threshold <N>
+                   每字节平均变化不超过此值时保持画面 0-255 (默认 3, 隐含 --reduce-motion)
+  --no-portrait-auto
+                   竖屏视频不自动切换为像素艺术模式
+  --no-deinterlace 隔行扫描视频不自动反交错 (播放时仍可按 d 开关)
+  --no-tonemap     HDR 视频不自动色调映射为 SDR (播放时仍可按 e 开关)
+  --no-cfr         可变帧率视频不转换为恒定帧率, 按原始时间戳播放 (动作更顺滑, 但进度与字幕可能逐渐偏移)
+  --no-catch-up    终端绘制跟不上时仍逐帧绘制队列中积压的帧, 而不是直接跳到最新解码的一帧 (画面会越来越落后)
+  --fifo <路径>     播放时将渲染好的 ANSI 帧持续写入命名管道 (仅 Unix)
+  --mode <M>       渲染模式: pixel, ascii, compact, blocks, image 或 plain (无颜色) (默认 pixel)
+  --ramp <字符>    ASCII 模式由暗到亮使用的字符, 至少两个 (默认 \" .:-=+*#%@\")
+  --invert-ramp    反转 ASCII 字符顺序, 适合浅色背景终端 (也可在 settings.txt 中设置 invert_ramp=true)
+  --adaptive-ramp  按每个场景的整体亮度自动选择字符分布: 暗场景把更多字符用在暗部, 亮场景用在亮部 (播放时按 l 开关)
+  --brightness <N>, --contrast <N>, --gamma <N>
+                   画面亮度 (-10 至 10)、对比度与伽马 (-5 至 10), 单位与画面调节界面的档位相同, 优先于 settings.txt 中的模式设置
+  --hflip, --vflip 水平/垂直翻转画面 (播放时按 h / v 开关)
+  --mirror <M>     万花筒对称: horizontal, vertical 或 quad (播放时按 k 切换)
+  --saturation <F> 色彩饱和度 0-3 (默认 1, 播放时按 [ ] 调整)
+  --auto-contrast  开启逐帧自动对比度 (播放时按 a 开关)
+  --border <on|off>
+                   播放时是否在画面四周绘制边框 (播放时按 b 开关, 也可设置 border=)
+  --auto-quality <on|off>
+                   渲染跟不上帧率时是否自动降低分辨率 (播放时按 r 开关, 也可设置 auto_quality=)
+  --color-mode <C>, --ascii-color <C>
+                   ASCII 模式单色配色: original, green, amber, white 或 #RRGGBB;
+                   --color-mode 还可设为 truecolor, 256 或 16 (终端支持的颜色数, 也可在 settings.txt 中设置 color_depth), 或 mono (单色)
+  --smooth <F>     开启时域平滑, F 为上一帧权重 0-0.95 (播放时按 t 开关, 默认 0.5)
+  --fit <F>        画面适配方式: contain (保持比例, 默认), stretch (铺满终端),
+                   width (始终填满宽度, 超出的行被裁掉) 或 height (始终填满高度); 播放时按 w 切换
+  --cols <N>, --rows <N>
+                   按指定的字符列数/行数渲染而不是适配终端, 便于录制或嵌入固定尺寸的输出
+                   (仍按比例居中; 超出终端时画面会换行或被截断)
+  --scaler <S>     缩放算法: bicubic (默认), bilinear, area, lanczos 或 neighbor (也可在 settings.txt 中设置 scaler=)
+  --upscale-scaler <S>
+                   放大小尺寸视频时改用的缩放算法, 如 neighbor 得到清晰的像素风格 (也可设置 upscale_scaler=)
+  --ffmpeg-args <参数>
+                   附加到 ffmpeg 解码命令输出参数之前的自定义参数, 可用引号, 如 \"-vf hqdn3d\" (也可设置 ffmpeg_args=)
+                   不做任何校验, 错误的参数可能导致无法播放
+  --sync-output <on|off>
+                   是否用同步输出 (?2026) 一次性显示整帧以减少闪烁 (默认按终端自动判断, 也可设置 sync_output=)
+  --pix-fmt <rgb24|rgb48>
+                   ffmpeg 输出的像素格式: rgb48 以 16 位精度解码再抖动到 8 位, 减少 10 位视频渐变处的色带
+                   (更耗 CPU, 默认 rgb24, 也可设置 pix_fmt=)
+  --crop <W:H[:X:Y]>
+                   只渲染源视频中宽 W、高 H 的区域, 左上角位于 (X, Y), 省略时居中 (单位为源像素)
+  --auto-crop      用 ffmpeg cropdetect 检测并裁掉视频自带的黑边 (也可在 settings.txt 中设置 auto_crop=true)
+  --burn-subs <N|文件>
+                   由 ffmpeg 将第 N 条内嵌字幕轨道 (从 1 开始, 图形字幕也可) 或外挂字幕文件直接绘制进画面
+  --backdrop <C>   用颜色填充画面四周的留白: green、#RRGGBB 等, 或 顶部色:底部色 的渐变 (也可设置 backdrop=)
+  --dir <目录>     扫描该目录而不是当前目录 (界面与 --list)
+  --exit-on-finish 播放完毕后立即退出程序, 不显示播放统计 (单帧图片仍停留显示直到按 q; 直接播放单个文件时默认开启; 界面中播完一个文件即退出)
+  --debug-sync     播放时在左下角显示帧号、按帧率计算的时间戳 (帧号 / fps)、实际经过的时间及两者的偏差, 用于排查音画不同步
+  --show-lag       播放时在左下角显示实际经过的时间、已播放的媒体时长 (帧数 / fps) 及两者之差, 即终端累计落后了多少
+  --shuffle        界面中按 < > 切换文件时按随机顺序播放列表, 每轮播完重新打乱, 列表本身的顺序不变 (也可按 H 切换)
+  --shuffle-seed <N>
+                   用种子 N 打乱顺序 (隐含 --shuffle), 同一种子每次得到相同的播放顺序
+  --manual-step    手动逐帧: 画面不自动前进, 每按一次空格或 → 前进一帧并一直停留, 显示帧号, 便于录制教程 (q 退出)
+  --stdin          从标准输入逐行读取文件路径作为列表, 代替扫描目录, 如
+                   find . -name '*.mp4' | Vodeo2ASCII --stdin (不存在或不是音视频的行会被跳过)
+  --watch          界面中自动检测扫描目录的文件增减并刷新列表 (也可在 settings.txt 中设置 watch_dir=true)
+  --export <输出>  与文件一起使用: 不播放, 将每一帧渲染结果写入输出文件
+  --export-stats   在导出的每一帧右上角叠加导出时的 CPU 与内存占用
+  --text-out <输出>
+                   与文件一起使用: 将一帧保存为无颜色的纯文本字符画
+  --at <时间>      --text-out 截取的时间点, 如 5、01:30 或 00:00:05 (默认开头)
+  --width <N>      --text-out 字符画的宽度 (默认 80)
+  --lang <L>       界面语言: zh 或 en (默认按 settings.txt 的 lang 或系统 LANG, 否则中文)
+  -h, --help       显示此帮助";
+
+/// `USAGE_ZH` in English.
+pub const USAGE_EN: &str = "\
+Usage: Vodeo2ASCII [options] [file]
+
+Plays the file when one is given, otherwise starts the interactive interface.
+
+Options:
+  --info <file>    Print the video's metadata as JSON and exit
+  --list           List the videos in the current directory with their metadata and exit
+  --dry-run <file> Print the ffmpeg command, decode size and filters playing the file would use, and exit
+  --grid <file>... Play 2-4 videos at once in tiles of the terminal (no sound, q to quit)
+  --test-pattern [pattern]
+                   Show a test pattern instead of a video, to check the terminal's colors and character aspect:
+                   bars (SMPTE color bars, default), sweep (RGB gradients), ramp (gray scale), circle
+  --profile-csv <output>
+                   Record each frame's decode wait, render and write times to a CSV file, for profiling
+  --log <file>     Write the ffmpeg path, probe results, decode size, ffmpeg commands and errors to a log file,
+                   for bug reports (with RUST_LOG set, vodeo2ascii.log in the temp directory by default)
+  --verbose        Print the ffmpeg/ffprobe commands run to stderr (after exiting, in the interface)
+  --threads <N>    Limit ffmpeg's decoding threads (ffmpeg decides by default)
+  --fps-cap <N>    Limit the frames rendered per second to save power/CPU (unlimited by default)
+  --max-decode-fps <N>
+                   Videos faster than N fps (like 120/240 fps game captures) are decimated to N fps by ffmpeg
+                   before output, cutting decode and pipe work (default 60, 0 turns it off; also max_decode_fps=)
+  --rewind-frames <N>
+                   Keep the last N frames drawn, so , in the inspector steps back without restarting ffmpeg
+                   (default 60, 0 turns it off, at most 10000; takes about N decode-size frames of memory,
+                   also rewind_frames=)
+  --prebuffer <N>  Decode N frames into the queue on a background thread before the clock starts, so slow
+                   decoding doesn't stutter the start (default 0, off; also prebuffer=)
+  --reconnect <N>  Reconnect a network video that drops up to N times, doubling the wait each time
+                   (default 5, 0 turns it off; also reconnect=)
+  --skip-black     Skip black frames at the start when playing from the beginning
+  --black-threshold <N>
+                   Average luma 0-255 below which a frame counts as black (default 16, implies --skip-black)
+  --reduce-motion  Less flicker: keep the previous frame up when the picture barely changes
+  --motion-threshold <N>
+                   Mean change per byte 0-255 up to which the frame is kept (default 3, implies --reduce-motion)
+  --no-portrait-auto
+                   Don't switch portrait videos to PixelArt mode
+  --no-deinterlace Don't deinterlace interlaced videos automatically (d still toggles it during playback)
+  --no-tonemap     Don't tonemap HDR videos to SDR automatically (e still toggles it during playback)
+  --no-cfr         Play variable frame rate videos by their own timestamps instead of converting them to a
+                   constant rate (smoother motion, but the position and subtitles can drift)
+  --no-catch-up    Draw every queued frame when drawing falls behind, instead of skipping to the newest
+                   decoded one (the picture falls further and further behind)
+  --fifo <path>    Keep writing the rendered ANSI frames to a named pipe during playback (Unix only)
+  --mode <M>       Render mode: pixel, ascii, compact, blocks, image or plain (no color) (default pixel)
+  --ramp <chars>   Characters ASCII mode uses from dark to bright, at least two (default \" .:-=+*#%@\")
+  --invert-ramp    Reverse the ASCII characters, for light terminal backgrounds (also invert_ramp=true in settings.txt)
+  --adaptive-ramp  Spread the characters by each scene's brightness: dark scenes spend more of them on the
+                   shadows, bright scenes on the highlights (l toggles it during playback)
+  --brightness <N>, --contrast <N>, --gamma <N>
+                   Brightness (-10 to 10), contrast and gamma (-5 to 10), in the tune screen's steps; they
+                   take precedence over the mode's settings in settings.txt
+  --hflip, --vflip Flip the picture horizontally/vertically (h / v toggle them during playback)
+  --mirror <M>     Kaleidoscope: horizontal, vertical or quad (k cycles it during playback)
+  --saturation <F> Color saturation 0-3 (default 1, [ ] adjust it during playback)
+  --auto-contrast  Turn on per-frame auto contrast (a toggles it during playback)
+  --border <on|off>
+                   Whether to draw a border around the picture (b toggles it during playback; also border=)
+  --auto-quality <on|off>
+                   Whether to lower the resolution when rendering can't keep up (r toggles it during playback;
+                   also auto_quality=)
+  --color-mode <C>, --ascii-color <C>
+                   One color for ASCII mode: original, green, amber, white or #RRGGBB;
+                   --color-mode also takes truecolor, 256 or 16 (the colors the terminal supports, also
+                   color_depth in settings.txt), or mono
+  --smooth <F>     Turn on temporal smoothing, F being the previous frame's weight 0-0.95 (t toggles it during
+                   playback, default 0.5)
+  --fit <F>        How the picture fits: contain (keep the aspect, default), stretch (fill the terminal),
+                   width (always fill the width, cropping rows) or height (always fill the height); w cycles it
+                   during playback
+  --cols <N>, --rows <N>
+                   Render at this many columns/rows instead of fitting the terminal, for recording or
+                   embedding fixed-size output (still centered at the aspect; wraps or is cut off past the terminal)
+  --scaler <S>     Scaling algorithm: bicubic (default), bilinear, area, lanczos or neighbor (also scaler= in settings.txt)
+  --upscale-scaler <S>
+                   Scaling algorithm for enlarging small videos, like neighbor for crisp pixels (also upscale_scaler=)
+  --ffmpeg-args <args>
+                   Extra arguments ahead of the ffmpeg decode command's output options, quotes allowed, like
+                   \"-vf hqdn3d\" (also ffmpeg_args=). Not checked at all: wrong ones can stop playback
+  --sync-output <on|off>
+                   Whether to show each frame at once with synchronized output (?2026) to reduce flicker
+                   (decided by the terminal by default; also sync_output=)
+  --pix-fmt <rgb24|rgb48>
+                   ffmpeg's output pixel format: rgb48 decodes at 16 bits and dithers down to 8, reducing
+                   banding in 10-bit gradients (costs more CPU, default rgb24; also pix_fmt=)
+  --crop <W:H[:X:Y]>
+                   Render only the W by H region of the source with its top-left corner at (X, Y), centered
+                   when left out (in source pixels)
+  --auto-crop      Detect and crop the video's own black bars with ffmpeg's cropdetect (also auto_crop=true in settings.txt)
+  --burn-subs <N|file>
+                   Have ffmpeg draw embedded subtitle track N (from 1, bitmap ones too) or a subtitle file into the picture
+  --backdrop <C>   Fill the margins around the picture with a color: green, #RRGGBB and so on, or a
+                   top:bottom gradient (also backdrop=)
+  --dir <dir>      Scan this directory instead of the current one (the interface and --list)
+  --exit-on-finish Exit as soon as playback ends, without the statistics (a single-frame image still stays up
+                   until q; on by default when playing one file directly; in the interface, quit after one file)
+  --debug-sync     Show the frame number, its timestamp (frame / fps), the wall-clock time and the drift between
+                   them in the lower-left corner during playback, for tracking down sync problems
+  --show-lag       Show the wall-clock time played, the media time played (frames / fps) and the difference
+                   in the lower-left corner during playback: how far the terminal has fallen behind in all
+  --shuffle        Step through the list with < > in the interface in a shuffled order, reshuffled for every
+                   pass; the list keeps its own order (H also toggles it)
+  --shuffle-seed <N>
+                   Shuffle with seed N (implies --shuffle); the same seed plays the same order every time
+  --manual-step    Step by hand: the picture doesn't advance by itself, each space or → shows the next frame and
+                   stays, with its frame number, for recording tutorials (q to quit)
+  --stdin          Take the list from file paths on standard input, one per line, instead of scanning, like
+                   find . -name '*.mp4' | Vodeo2ASCII --stdin (lines that aren't existing media are skipped)
+  --watch          Refresh the list in the interface when files appear in or leave the scanned directory
+                   (also watch_dir=true in settings.txt)
+  --export <output>
+                   With a file: write every rendered frame to the output file instead of playing
+  --export-stats   Overlay the CPU and memory use while exporting in the top-right corner of each exported frame
+  --text-out <output>
+                   With a file: save one frame as plain text art without colors
+  --at <time>      Where --text-out takes its frame, like 5, 01:30 or 00:00:05 (default the start)
+  --width <N>      Width of the --text-out art (default 80)
+  --lang <L>       Interface language: zh or en (default the lang setting in settings.txt or the system LANG,
+                   otherwise Chinese)
+  -h, --help       Show this help";
+
+/// Columns of a `--text-out` still when `--width` isn't given.
+pub const DEFAULT_TEXT_WIDTH: u32 = 80;
+
+/// Average luma below which a frame counts as black for `--skip-black`.
+pub const DEFAULT_BLACK_THRESHOLD: u8 = 16;
+
+/// Mean change per byte up to which `--reduce-motion` keeps the previous frame on screen.
+pub const DEFAULT_MOTION_THRESHOLD: u8 = 3;
+
+/// What the process should do, decided from the command line.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum CliCommand {
+    #[default]
+    Tui,
+    Info(PathBuf),
+    /// A positional file: play it directly, or export it with `--export`.
+    Play(PathBuf),
+    List,
+    DryRun(PathBuf),
+    /// Several files at once, each in its own tile.
+    Grid(Vec<PathBuf>),
+    /// Synthetic frames instead of a video, starting with this pattern.
+    TestPattern(Pattern),
+    Help,
+}
+
+/// Parsed command line: the command plus options that apply to playback.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Cli {
+    pub command: CliCommand,
+    /// Passed to ffmpeg as `-threads`; `None` keeps ffmpeg's automatic choice.
+    pub threads: Option<u32>,
+    /// Upper bound on frames drawn per second; `None` draws every decoded frame.
+    pub fps_cap: Option<u32>,
+    /// Recent frames kept for stepping back in the inspector.
+    pub rewind_frames: Option<usize>,
+    /// Frames decoded ahead before playback starts.
+    pub prebuffer: Option<usize>,
+    /// Reconnection attempts when a network source drops mid-stream.
+    pub reconnects: Option<u32>,
+    /// Frame rate faster sources are decimated to by ffmpeg; `Some(0)` turns that off.
+    pub max_decode_fps: Option<u32>,
+    pub ascii_color: Option<AsciiColor>,
+    /// Colors the terminal can show, from `--color-mode 16`, `256` or `truecolor`.
+    pub color_depth: Option<ColorDepth>,
+    pub mode: Option<RenderMode>,
+    /// `AsciiArt` glyphs from darkest to brightest.
+    pub ramp: Option<&'static [char]>,
+    /// Reverse the ramp for light terminal backgrounds.
+    pub invert_ramp: bool,
+    pub fit: Fit,
+    /// Character columns and rows to render into instead of the terminal's.
+    pub cols: Option<u16>,
+    pub rows: Option<u16>,
+    /// ffmpeg scaling algorithm, ahead of the `scaler` setting.
+    pub scaler: Option<Scaler>,
+    /// Scaling algorithm when the decode size is larger than the source, ahead of `upscale_scaler`.
+    pub upscale_scaler: Option<Scaler>,
+    /// Pixel format decoded for color output, ahead of the `pix_fmt` setting.
+    pub pix_fmt: Option<PixelFormat>,
+    /// Region of the source to render.
+    pub crop: Option<Crop>,
+    /// Detect baked-in black bars and crop them off, when `--crop` doesn't say what to render.
+    pub auto_crop: bool,
+    /// Subtitles for ffmpeg to draw into the frames.
+    pub burn_subs: Option<Burn>,
+    /// Letterbox fill, ahead of the `backdrop` setting.
+    pub backdrop: Option<Backdrop>,
+    /// Extra ffmpeg arguments for decoding, ahead of the `ffmpeg_args` setting.
+    pub ffmpeg_args: Option<Vec<String>>,
+    /// Wrap frames in synchronized updates, ahead of the `sync_output` setting and detection.
+    pub sync_output: Option<bool>,
+    /// Previous-frame weight for temporal smoothing, which starts enabled when given.
+    pub smooth: Option<f32>,
+    /// Directory to scan instead of the current one.
+    pub dir: Option<PathBuf>,
+    /// Re-scan the directory when files are added or removed.
+    pub watch: bool,
+    /// Take the file list from newline-separated paths on stdin instead of scanning.
+    pub stdin: bool,
+    /// Quit as soon as a video plays to the end, for scripts.
+    pub exit_on_finish: bool,
+    /// Show the frame number, its timestamp and the drift from the wall clock during playback.
+    pub debug_sync: bool,
+    /// Advance one frame per space or right arrow instead of playing.
+    pub manual_step: bool,
+    /// Show the wall-clock time played against the media time, and how far behind it is.
+    pub show_lag: bool,
+    /// Step through the list with `<`/`>` in a shuffled order.
+    pub shuffle: bool,
+    /// Seed for the shuffled order, so it repeats from run to run.
+    pub shuffle_seed: Option<u64>,
+    /// Start with the ramp chosen by each scene's brightness.
+    pub adaptive_ramp: bool,
+    /// Brightness, contrast and gamma steps, ahead of the render mode's settings.
+    pub levels: Vec<(Level, i32)>,
+    pub hflip: bool,
+    pub vflip: bool,
+    pub mirror: Mirror,
+    /// Color saturation factor, 1.0 leaving colors as they are.
+    pub saturation: Option<f32>,
+    pub auto_contrast: bool,
+    /// Start with a border, ahead of the `border` setting.
+    pub border: Option<bool>,
+    /// Start with adaptive resolution, ahead of the `auto_quality` setting.
+    pub auto_quality: Option<bool>,
+    /// Write the rendered frames of the positional file here instead of playing it.
+    pub export: Option<PathBuf>,
+    /// Burn the CPU and memory use at the time into the corner of every exported frame.
+    pub export_stats: bool,
+    /// Write one frame of the positional file here as plain ASCII text.
+    pub text_out: Option<PathBuf>,
+    /// Seconds into the file of the `--text-out` frame.
+    pub at: Option<f64>,
+    /// Columns of the `--text-out` frame.
+    pub width: Option<u32>,
+    /// Average-luma threshold below which leading frames count as a black intro to skip.
+    pub skip_black: Option<u8>,
+    /// Mean per-byte change up to which a frame is held instead of drawn.
+    pub reduce_motion: Option<u8>,
+    /// Keep the chosen render mode for portrait videos instead of switching to PixelArt.
+    pub no_portrait_auto: bool,
+    /// Play variable frame rate videos on their own timestamps instead of converting them to constant.
+    pub no_cfr: bool,
+    /// Draw every frame even when drawing falls behind, instead of skipping to the newest.
+    pub no_catch_up: bool,
+    /// Leave interlaced videos as they are instead of deinterlacing them.
+    pub no_deinterlace: bool,
+    /// Leave HDR videos as they are instead of tonemapping them to SDR.
+    pub no_tonemap: bool,
+    /// Log external commands and playback decisions to stderr.
+    pub verbose: bool,
+    /// Named pipe that receives a live copy of the rendered frames.
+    pub fifo: Option<PathBuf>,
+    /// CSV file receiving per-frame timings.
+    pub profile_csv: Option<PathBuf>,
+    /// Diagnostics log file for the session.
+    pub log: Option<PathBuf>,
+    /// Interface language, ahead of the `lang` setting and the locale.
+    pub lang: Option<Lang>,
+}
+
+pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Cli> {
+    let mut cli = Cli::default();
+    let mut args = args.into_iter().peekable();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--info" => cli.command = CliCommand::Info(PathBuf::from(args.next().context("--info requires a file path")?)),
+            "--threads" => {
+                let value = args.next().context("--threads requires a number")?;
+                let threads: u32 = value.parse().with_context(|| format!("Invalid thread count: {}", value))?;
+                // ffmpeg treats 0 as "auto", which is what leaving the flag out already does.
+                cli.threads = (threads > 0).then_some(threads);
+            }
+            "--fps-cap" => {
+                let value = args.next().context("--fps-cap requires a number")?;
+                let cap: u32 = value.parse().with_context(|| format!("Invalid FPS cap: {}", value))?;
+                cli.fps_cap = (cap > 0).then_some(cap);
+            }
+            "--max-decode-fps" => {
+                let value = args.next().context("--max-decode-fps requires a number")?;
+                cli.max_decode_fps = Some(value.parse().with_context(|| format!("Invalid frame rate: {}", value))?);
+            }
+            "--prebuffer" => {
+                let value = args.next().context("--prebuffer requires a number")?;
+                cli.prebuffer = Some(value.parse().with_context(|| format!("Invalid frame count: {}", value))?);
+            }
+            "--reconnect" => {
+                let value = args.next().context("--reconnect requires a number")?;
+                cli.reconnects = Some(value.parse().with_context(|| format!("Invalid retry count: {}", value))?);
+            }
+            "--rewind-frames" => {
+                let value = args.next().context("--rewind-frames requires a number")?;
+                let frames: usize = value.parse().with_context(|| format!("Invalid frame count: {}", value))?;
+                if frames > rewind::MAX_FRAMES {
+                    anyhow::bail!("--rewind-frames must be at most {}: {}", rewind::MAX_FRAMES, value);
+                }
+                cli.rewind_frames = Some(frames);
+            }
+            "--ascii-color" | "--color-mode" => {
+                let value = args.next().with_context(|| format!("{} requires a color", arg))?;
+                if let Some(depth) = ColorDepth::parse(&value).filter(|_| arg == "--color-mode") {
+                    cli.color_depth = Some(depth);
+                } else if arg == "--color-mode" && value.eq_ignore_ascii_case("mono") {
+                    cli.ascii_color = Some(AsciiColor::WHITE);
+                } else {
+                    cli.ascii_color = Some(AsciiColor::parse(&value).with_context(|| format!("Invalid color: {}", value))?);
+                }
+            }
+            "--mode" => {
+                let value = args.next().context("--mode requires pixel, ascii, compact, blocks, image or plain")?;
+                cli.mode = Some(RenderMode::parse(&value).with_context(|| format!("Invalid render mode: {}", value))?);
+            }
+            "--ramp" => {
+                let value = args.next().context("--ramp requires characters")?;
+                let ramp: Vec<char> = value.chars().collect();
+                if ramp.len() < 2 {
+                    anyhow::bail!("--ramp needs at least two characters");
+                }
+                // Parsed once per run and used for its whole lifetime.
+                cli.ramp = Some(Box::leak(ramp.into_boxed_slice()));
+            }
+            "--invert-ramp" => cli.invert_ramp = true,
+            "--brightness" | "--contrast" | "--gamma" => {
+                let level = match arg.as_str() {
+                    "--contrast" => Level::Contrast,
+                    "--gamma" => Level::Gamma,
+                    _ => Level::Brightness,
+                };
+                let value = args.next().with_context(|| format!("{} requires a number of steps", arg))?;
+                let steps: i32 = value.parse().with_context(|| format!("Invalid steps: {}", value))?;
+                let range = level.range();
+                if !range.contains(&steps) {
+                    anyhow::bail!("{} must be between {} and {}: {}", arg, range.start(), range.end(), value);
+                }
+                cli.levels.retain(|(l, _)| *l != level);
+                cli.levels.push((level, steps));
+            }
+            "--hflip" => cli.hflip = true,
+            "--vflip" => cli.vflip = true,
+            "--mirror" => {
+                let value = args.next().context("--mirror requires horizontal, vertical or quad")?;
+                cli.mirror = Mirror::parse(&value).with_context(|| format!("Invalid mirror: {}", value))?;
+            }
+            "--saturation" => {
+                let value = args.next().context("--saturation requires a factor")?;
+                let factor: f32 = value.parse().with_context(|| format!("Invalid saturation: {}", value))?;
+                if !(0.0..=3.0).contains(&factor) {
+                    anyhow::bail!("Saturation must be between 0 and 3: {}", value);
+                }
+                cli.saturation = Some(factor);
+            }
+            "--auto-contrast" => cli.auto_contrast = true,
+            "--border" | "--auto-quality" => {
+                let on = match args.next().as_deref() {
+                    Some("on") => true,
+                    Some("off") => false,
+                    _ => anyhow::bail!("{} requires on or off", arg),
+                };
+                if arg == "--border" { cli.border = Some(on) } else { cli.auto_quality = Some(on) }
+            }
+            "--fit" => {
+                let value = args.next().context("--fit requires contain, stretch, width or height")?;
+                cli.fit = Fit::parse(&value).with_context(|| format!("Invalid fit: {}", value))?;
+            }
+            "--cols" => cli.cols = Some(parse_cells(&arg, args.next())?),
+            "--rows" => cli.rows = Some(parse_cells(&arg, args.next())?),
+            "--scaler" | "--upscale-scaler" => {
+                let value = args.next().with_context(|| format!("{} requires bicubic, bilinear, area, lanczos or neighbor", arg))?;
+                let scaler = Some(Scaler::parse(&value).with_context(|| format!("Invalid scaler: {}", value))?);
+                if arg == "--scaler" { cli.scaler = scaler } else { cli.upscale_scaler = scaler }
+            }
+            "--sync-output" => {
+                cli.sync_output = match args.next().as_deref() {
+                    Some("on") => Some(true),
+                    Some("off") => Some(false),
+                    _ => anyhow::bail!("--sync-output requires on or off"),
+                };
+            }
+            "--ffmpeg-args" => {
+                let value = args.next().context("--ffmpeg-args requires arguments")?;
+                cli.ffmpeg_args = Some(split_args(&value).with_context(|| format!("Unterminated quote in --ffmpeg-args: {}", value))?);
+            }
+            "--pix-fmt" => {
+                let value = args.next().context("--pix-fmt requires rgb24 or rgb48")?;
+                cli.pix_fmt = Some(PixelFormat::parse(&value).with_context(|| format!("Unsupported pixel format: {}", value))?);
+            }
+            "--crop" => {
+                let value = args.next().context("--crop requires W:H or W:H:X:Y")?;
+                cli.crop = Some(Crop::parse(&value).with_context(|| format!("Invalid crop: {}", value))?);
+            }
+            "--auto-crop" => cli.auto_crop = true,
+            "--burn-subs" => {
+                let value = args.next().context("--burn-subs requires a track number or a subtitle file")?;
+                cli.burn_subs = Some(Burn::parse(&value).with_context(|| format!("Invalid subtitle track: {}", value))?);
+            }
+            "--backdrop" => {
+                let value = args.next().context("--backdrop requires a color or top:bottom colors")?;
+                cli.backdrop = Some(Backdrop::parse(&value).with_context(|| format!("Invalid backdrop: {}", value))?);
+            }
+            "--smooth" => {
+                let value = args.next().context("--smooth requires a factor")?;
+                let factor: f32 = value.parse().with_context(|| format!("Invalid smoothing factor: {}", value))?;
+                if !(0.0..=0.95).contains(&factor) {
+                    anyhow::bail!("Smoothing factor must be between 0 and 0.95: {}", value);
+                }
+                cli.smooth = Some(factor);
+            }
+            "--dir" => cli.dir = Some(PathBuf::from(args.next().context("--dir requires a directory")?)),
+            "--export" => cli.export = Some(PathBuf::from(args.next().context("--export requires an output path")?)),
+            "--export-stats" => cli.export_stats = true,
+            "--text-out" => cli.text_out = Some(PathBuf::from(args.next().context("--text-out requires an output path")?)),
+            "--at" => {
+                let value = args.next().context("--at requires a time")?;
+                cli.at = Some(parse_time(&value).with_context(|| format!("Invalid time: {}", value))?);
+            }
+            "--width" => {
+                let value = args.next().context("--width requires a number")?;
+                let width: u32 = value.parse().with_context(|| format!("Invalid width: {}", value))?;
+                if !(2..=u16::MAX as u32).contains(&width) {
+                    anyhow::bail!("Width must be between 2 and {}: {}", u16::MAX, value);
+                }
+                cli.width = Some(width);
+            }
+            "--lang" => {
+                let value = args.next().context("--lang requires zh or en")?;
+                cli.lang = Some(Lang::parse(&value).with_context(|| format!("Unsupported language: {}", value))?);
+            }
+            "--profile-csv" => cli.profile_csv = Some(PathBuf::from(args.next().context("--profile-csv requires an output path")?)),
+            "--log" => cli.log = Some(PathBuf::from(args.next().context("--log requires a file path")?)),
+            "--fifo" => cli.fifo = Some(PathBuf::from(args.next().context("--fifo requires a path")?)),
+            "--skip-black" => {
+                cli.skip_black.get_or_insert(DEFAULT_BLACK_THRESHOLD);
+            }
+            "--black-threshold" => {
+                let value = args.next().context("--black-threshold requires a number")?;
+                let threshold: u8 = value.parse().with_context(|| format!("Invalid threshold (0-255): {}", value))?;
+                cli.skip_black = Some(threshold);
+            }
+            "--reduce-motion" => {
+                cli.reduce_motion.get_or_insert(DEFAULT_MOTION_THRESHOLD);
+            }
+            "--motion-threshold" => {
+                let value = args.next().context("--motion-threshold requires a number")?;
+                let threshold: u8 = value.parse().with_context(|| format!("Invalid threshold (0-255): {}", value))?;
+                cli.reduce_motion = Some(threshold);
+            }
+            "--no-portrait-auto" => cli.no_portrait_auto = true,
+            "--no-cfr" => cli.no_cfr = true,
+            "--no-catch-up" => cli.no_catch_up = true,
+            "--no-deinterlace" => cli.no_deinterlace = true,
+            "--no-tonemap" => cli.no_tonemap = true,
+            "--watch" => cli.watch = true,
+            "--stdin" => cli.stdin = true,
+            "--exit-on-finish" => cli.exit_on_finish = true,
+            "--debug-sync" => cli.debug_sync = true,
+            "--manual-step" => cli.manual_step = true,
+            "--show-lag" => cli.show_lag = true,
+            "--shuffle" => cli.shuffle = true,
+            "--shuffle-seed" => {
+                let value = args.next().context("--shuffle-seed requires a number")?;
+                cli.shuffle_seed = Some(value.parse().with_context(|| format!("Invalid seed: {}", value))?);
+            }
+            "--adaptive-ramp" => cli.adaptive_ramp = true,
+            "--dry-run" => cli.command = CliCommand::DryRun(PathBuf::from(args.next().context("--dry-run requires a file path")?)),
+            "--grid" => {
+                let mut files = Vec::new();
+                while let Some(file) = args.next_if(|a| !a.starts_with('-')) {
+                    files.push(PathBuf::from(file));
+                }
+                if !(2..=MAX_TILES).contains(&files.len()) {
+                    anyhow::bail!("--grid requires 2 to {} files", MAX_TILES);
+                }
+                cli.command = CliCommand::Grid(files);
+            }
+            "--test-pattern" => {
+                let pattern = match args.next_if(|a| !a.starts_with('-')) {
+                    Some(name) => Pattern::parse(&name).with_context(|| format!("Unknown test pattern: {}", name))?,
+                    None => Pattern::default(),
+                };
+                cli.command = CliCommand::TestPattern(pattern);
+            }
+            "--verbose" => cli.verbose = true,
+            "--list" => cli.command = CliCommand::List,
+            "-h" | "--help" => cli.command = CliCommand::Help,
+            other if other.starts_with('-') => anyhow::bail!("Unknown argument: {}\n\n{}", other, Msg::Usage.text()),
+            file => cli.command = CliCommand::Play(PathBuf::from(file)),
+        }
+    }
+    if cli.export.is_some() && !matches!(cli.command, CliCommand::Play(_)) {
+        anyhow::bail!("--export needs a file to render");
+    }
+    if cli.export_stats && cli.export.is_none() {
+        anyhow::bail!("--export-stats only applies to --export");
+    }
+    if cli.text_out.is_some() && !matches!(cli.command, CliCommand::Play(_)) {
+        anyhow::bail!("--text-out needs a file to render");
+    }
+    if (cli.at.is_some() || cli.width.is_some()) && cli.text_out.is_none() {
+        anyhow::bail!("--at and --width only apply to --text-out");
+    }
+    Ok(cli)
+}
+
+/// A `--cols` or `--rows` value: a positive number of character cells.
+fn parse_cells(flag: &str, value: Option<String>) -> Result<u16> {
+    let value = value.with_context(|| format!("{} requires a number", flag))?;
+    value.parse().ok().filter(|&n| n > 0).with_context(|| format!("{} must be between 1 and {}: {}", flag, u16::MAX, value))
+}
+
+/// Seconds from `SS`, `MM:SS` or `HH:MM:SS`, each part allowing a fraction.
+pub fn parse_time(s: &str) -> Option<f64> {
+    let mut secs = 0.0;
+    for part in s.trim().split(':') {
+        let value: f64 = part.parse().ok().filter(|v: &f64| *v >= 0.0 && v.is_finite())?;
+        secs = secs * 60.0 + value;
+    }
+    Some(secs)
+}
+
+/// Splits `s` into arguments like a shell would: on whitespace, except inside `'…'` or `"…"`, and
+/// with `\` escaping the next character outside single quotes. `None` if a quote is left open.
+pub fn split_args(s: &str) -> Option<Vec<String>> {
+    let mut args = Vec::new();
+    let mut current: Option<String> = None;
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => args.extend(current.take()),
+            '\'' => {
+                let arg = current.get_or_insert_with(String::new);
+                loop {
+                    match chars.next()? {
+                        '\'' => break,
+                        c => arg.push(c),
+                    }
+                }
+            }
+            '"' => {
+                let arg = current.get_or_insert_with(String::new);
+                loop {
+                    match chars.next()? {
+                        '"' => break,
+                        '\\' => arg.push(chars.next()?),
+                        c => arg.push(c),
+                    }
+                }
+            }
+            '\\' => current.get_or_insert_with(String::new).push(chars.next()?),
+            c => current.get_or_insert_with(String::new).push(c),
+        }
+    }
+    args.extend(current);
+    Some(args)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_args(args: &[&str]) -> Result<Cli> {
+        parse(args.iter().map(|a| a.to_string()))
+    }
+
+    #[test]
+    fn ffmpeg_args_split_like_a_shell() {
+        let split = |s| split_args(s).unwrap();
+        assert_eq!(split("  -vf  hqdn3d "), ["-vf", "hqdn3d"]);
+        assert_eq!(split(r#"-vf "eq=contrast=1.2, hqdn3d" -an"#), ["-vf", "eq=contrast=1.2, hqdn3d", "-an"]);
+        assert_eq!(split(r#"-metadata 'title=a "b"' x\ y "" "#), ["-metadata", "title=a \"b\"", "x y", ""]);
+        assert_eq!(split(""), Vec::<String>::new());
+        assert_eq!(split_args("\"open"), None);
+    }
+
+    #[test]
+    fn no_args_launches_tui() {
+        assert_eq!(parse_args(&[]).unwrap(), Cli::default());
+    }
+
+    #[test]
+    fn info_takes_a_path() {
+        assert_eq!(parse_args(&["--info", "a.mp4"]).unwrap().command, CliCommand::Info(PathBuf::from("a.mp4")));
+        assert!(parse_args(&["--info"]).is_err());
+    }
+
+    #[test]
+    fn threads_is_optional_and_numeric() {
+        assert_eq!(parse_args(&["--threads", "2"]).unwrap().threads, Some(2));
+        assert_eq!(parse_args(&["--threads", "0"]).unwrap().threads, None);
+        assert!(parse_args(&["--threads", "many"]).is_err());
+    }
+
+    #[test]
+    fn fps_cap_zero_means_uncapped() {
+        assert_eq!(parse_args(&["--fps-cap", "15"]).unwrap().fps_cap, Some(15));
+        assert_eq!(parse_args(&["--fps-cap", "0"]).unwrap().fps_cap, None);
+        assert!(parse_args(&["--fps-cap"]).is_err());
+        assert_eq!(parse_args(&["--rewind-frames", "0"]).unwrap().rewind_frames, Some(0));
+        assert_eq!(parse_args(&["--prebuffer", "12"]).unwrap().prebuffer, Some(12));
+        assert_eq!(parse_args(&["--reconnect", "0"]).unwrap().reconnects, Some(0));
+        assert!(parse_args(&["--rewind-frames", "-5"]).is_err());
+        assert!(parse_args(&["--rewind-frames", "18446744073709551615"]).is_err());
+        assert_eq!(parse_args(&["--rewind-frames", "10000"]).unwrap().rewind_frames, Some(10_000));
+        assert!(parse_args(&["--exit-on-finish"]).unwrap().exit_on_finish);
+        assert!(parse_args(&["--debug-sync"]).unwrap().debug_sync);
+        assert!(parse_args(&["--manual-step"]).unwrap().manual_step);
+        assert!(parse_args(&["--show-lag"]).unwrap().show_lag);
+        assert!(parse_args(&["--shuffle"]).unwrap().shuffle);
+        assert_eq!(parse_args(&["--shuffle-seed", "42"]).unwrap().shuffle_seed, Some(42));
+        assert!(parse_args(&["--shuffle-seed", "-1"]).is_err());
+        assert!(parse_args(&["--adaptive-ramp"]).unwrap().adaptive_ramp);
+        assert_eq!(parse_args(&["--max-decode-fps", "0"]).unwrap().max_decode_fps, Some(0));
+        assert!(parse_args(&["--max-decode-fps", "fast"]).is_err());
+    }
+
+    #[test]
+    fn picture_adjustments_take_steps_and_factors() {
+        let cli = parse_args(&["--brightness", "3", "--gamma", "-2", "--brightness", "-1"]).unwrap();
+        assert_eq!(cli.levels, [(Level::Gamma, -2), (Level::Brightness, -1)]);
+        assert!(parse_args(&["--contrast", "11"]).is_err());
+        let cli = parse_args(&["--hflip", "--mirror", "quad", "--saturation", "1.5", "--border", "off", "--auto-quality", "on"]).unwrap();
+        assert!(cli.hflip && !cli.vflip);
+        assert_eq!((cli.mirror, cli.saturation, cli.border, cli.auto_quality), (Mirror::Quad, Some(1.5), Some(false), Some(true)));
+        assert!(parse_args(&["--saturation", "4"]).is_err());
+        assert!(parse_args(&["--border"]).is_err());
+    }
+
+    #[test]
+    fn black_threshold_implies_skip_black() {
+        assert_eq!(parse_args(&["--skip-black"]).unwrap().skip_black, Some(DEFAULT_BLACK_THRESHOLD));
+        assert_eq!(parse_args(&["--black-threshold", "30", "--skip-black"]).unwrap().skip_black, Some(30));
+        assert!(parse_args(&["--black-threshold", "300"]).is_err());
+    }
+
+    #[test]
+    fn motion_threshold_implies_reduce_motion() {
+        assert_eq!(parse_args(&["--reduce-motion"]).unwrap().reduce_motion, Some(DEFAULT_MOTION_THRESHOLD));
+        assert_eq!(parse_args(&["--motion-threshold", "8", "--reduce-motion"]).unwrap().reduce_motion, Some(8));
+        assert!(parse_args(&["--motion-threshold", "-1"]).is_err());
+    }
+
+    #[test]
+    fn cols_and_rows_must_be_positive() {
+        let cli = parse_args(&["--cols", "120", "--rows", "40"]).unwrap();
+        assert_eq!((cli.cols, cli.rows), (Some(120), Some(40)));
+        assert!(parse_args(&["--cols", "0"]).is_err());
+        assert!(parse_args(&["--rows", "70000"]).is_err());
+        assert!(parse_args(&["--rows"]).is_err());
+    }
+
+    #[test]
+    fn positional_file_plays_with_options() {
+        let cli = parse_args(&["--mode", "ascii", "clip.mp4", "--ramp", " .#", "--fit", "stretch"]).unwrap();
+        assert_eq!(cli.command, CliCommand::Play(PathBuf::from("clip.mp4")));
+        assert_eq!(cli.mode, Some(RenderMode::AsciiArt));
+        assert_eq!(cli.ramp, Some(&[' ', '.', '#'][..]));
+        assert_eq!(cli.fit, Fit::Stretch);
+        assert_eq!(parse_args(&["--fit", "Height"]).unwrap().fit, Fit::Height);
+        assert_eq!(parse_args(&["--color-mode", "green"]).unwrap().ascii_color, Some(AsciiColor::GREEN));
+        assert_eq!(parse_args(&["--color-mode", "16"]).unwrap().color_depth, Some(ColorDepth::Ansi16));
+        assert_eq!(parse_args(&["--color-mode", "mono"]).unwrap().ascii_color, Some(AsciiColor::WHITE));
+        assert!(parse_args(&["--ascii-color", "256"]).is_err());
+        assert_eq!(parse_args(&["--upscale-scaler", "nearest"]).unwrap().upscale_scaler, Some(Scaler::Neighbor));
+        assert!(parse_args(&["--scaler", "sinc"]).is_err());
+        assert_eq!(parse_args(&["--backdrop", "#202020"]).unwrap().backdrop, Some(Backdrop::solid((32, 32, 32))));
+        assert!(parse_args(&["--backdrop", "original"]).is_err());
+        assert_eq!(parse_args(&["--crop", "640:360:0:720"]).unwrap().crop, Some(Crop { w: 640, h: 360, offset: Some((0, 720)) }));
+        assert!(parse_args(&["--crop", "640"]).is_err());
+        assert_eq!(parse_args(&["--burn-subs", "1"]).unwrap().burn_subs, Some(Burn::Text(0)));
+        assert!(parse_args(&["--burn-subs", "0"]).is_err());
+        assert_eq!(parse_args(&["--pix-fmt", "rgb48"]).unwrap().pix_fmt, Some(PixelFormat::Rgb48));
+        assert!(parse_args(&["--pix-fmt", "gray"]).is_err());
+
+        assert_eq!(parse_args(&["--smooth", "0.7"]).unwrap().smooth, Some(0.7));
+        assert!(parse_args(&["--smooth", "1"]).is_err());
+        assert!(parse_args(&["--ramp", "#"]).is_err());
+        assert!(parse_args(&["--ffmpeg-args", "-vf 'hqdn3d"]).is_err());
+        assert_eq!(parse_args(&["--sync-output", "off"]).unwrap().sync_output, Some(false));
+        assert!(parse_args(&["--sync-output", "auto"]).is_err());
+        assert!(parse_args(&["--mode", "sixel"]).is_err());
+        assert!(parse_args(&["--export", "out.txt"]).is_err());
+        assert!(parse_args(&["clip.mp4", "--export", "out.txt"]).unwrap().export.is_some());
+        assert!(parse_args(&["clip.mp4", "--export-stats"]).is_err());
+        assert!(parse_args(&["clip.mp4", "--export", "out.txt", "--export-stats"]).unwrap().export_stats);
+    }
+
+    #[test]
+    fn dry_run_takes_a_file() {
+        let cli = parse_args(&["--verbose", "--dry-run", "a.mp4"]).unwrap();
+        assert_eq!(cli.command, CliCommand::DryRun(PathBuf::from("a.mp4")));
+        assert!(cli.verbose);
+        assert!(parse_args(&["--dry-run"]).is_err());
+        assert_eq!(parse_args(&["--profile-csv", "frames.csv"]).unwrap().profile_csv, Some(PathBuf::from("frames.csv")));
+        assert_eq!(parse_args(&["--log", "debug.log"]).unwrap().log, Some(PathBuf::from("debug.log")));
+        assert_eq!(parse_args(&["--lang", "en"]).unwrap().lang, Some(Lang::En));
+        assert!(parse_args(&["--lang", "fr"]).is_err());
+    }
+
+    #[test]
+    fn grid_takes_two_to_four_files() {
+        let cli = parse_args(&["--grid", "a.mp4", "b.mp4", "--mode", "ascii"]).unwrap();
+        assert_eq!(cli.command, CliCommand::Grid(vec![PathBuf::from("a.mp4"), PathBuf::from("b.mp4")]));
+        assert_eq!(cli.mode, Some(RenderMode::AsciiArt));
+        assert!(parse_args(&["--grid", "a.mp4"]).is_err());
+    }
+
+    #[test]
+    fn test_pattern_defaults_to_color_bars() {
+        assert_eq!(parse_args(&["--test-pattern"]).unwrap().command, CliCommand::TestPattern(Pattern::Bars));
+        let cli = parse_args(&["--test-pattern", "ramp", "--mode", "ascii"]).unwrap();
+        assert_eq!(cli.command, CliCommand::TestPattern(Pattern::Ramp));
+        assert_eq!(cli.mode, Some(RenderMode::AsciiArt));
+        assert!(parse_args(&["--test-pattern", "noise"]).is_err());
+        assert!(parse_args(&["--grid", "a", "b", "c", "d", "e"]).is_err());
+    }
+
+    #[test]
+    fn text_out_takes_a_time_and_width() {
+        let cli = parse_args(&["clip.mp4", "--text-out", "still.txt", "--at", "01:30.5", "--width", "120"]).unwrap();
+        assert_eq!(cli.text_out, Some(PathBuf::from("still.txt")));
+        assert_eq!(cli.at, Some(90.5));
+        assert_eq!(cli.width, Some(120));
+
+        assert_eq!(parse_time("5"), Some(5.0));
+        assert_eq!(parse_time("00:00:05"), Some(5.0));
+        assert_eq!(parse_time("1:-5"), None);
+        assert!(parse_args(&["--text-out", "still.txt"]).is_err());
+        assert!(parse_args(&["clip.mp4", "--at", "5"]).is_err());
+        assert!(parse_args(&["clip.mp4", "--text-out", "still.txt", "--width", "1"]).is_err());
+    }
+}
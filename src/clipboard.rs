@@ -0,0 +1,53 @@
+use anyhow::{Context, Result};
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+};
+
+/// Clipboard helpers to try in order, as `(program, args)`.
+fn candidates() -> &'static [(&'static str, &'static [&'static str])] {
+    if cfg!(target_os = "windows") {
+        // `clip` mangles non-ASCII text, so go through PowerShell first.
+        &[("powershell", &["-NoProfile", "-Command", "$input | Set-Clipboard"]), ("clip", &[])]
+    } else if cfg!(target_os = "macos") {
+        &[("pbcopy", &[])]
+    } else {
+        &[
+            ("wl-copy", &[]),
+            ("xclip", &["-selection", "clipboard"]),
+            ("xsel", &["--clipboard", "--input"]),
+        ]
+    }
+}
+
+/// Puts `text` on the system clipboard using whichever helper is installed.
+pub fn copy(text: &str) -> Result<()> {
+    let mut last_err = None;
+    for (program, args) in candidates() {
+        match pipe_to(program, args, text) {
+            Ok(()) => return Ok(()),
+            Err(e) => last_err = Some(e),
+        }
+    }
+    Err(last_err.unwrap_or_else(|| anyhow::anyhow!("No clipboard helper available")))
+}
+
+fn pipe_to(program: &str, args: &[&str], text: &str) -> Result<()> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .with_context(|| format!("Failed to run {}", program))?;
+    child
+        .stdin
+        .take()
+        .context("Failed to open stdin")?
+        .write_all(text.as_bytes())?;
+    let status = child.wait()?;
+    if !status.success() {
+        anyhow::bail!("{} exited with {}", program, status);
+    }
+    Ok(())
+}
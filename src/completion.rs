@@ -0,0 +1,87 @@
+use std::path::{is_separator, MAIN_SEPARATOR};
+
+/// Tab completion for the manual path input.
+///
+/// The first Tab completes to the first matching entry; pressing Tab again while the input still
+/// holds that completion moves on to the next match, wrapping around.
+#[derive(Default)]
+pub struct PathCompleter {
+    candidates: Vec<String>,
+    index: usize,
+}
+
+impl PathCompleter {
+    /// Returns the completion to put in the input box, or `None` when nothing matches.
+    pub fn complete(&mut self, input: &str) -> Option<String> {
+        if self.candidates.len() > 1 && self.candidates.get(self.index).is_some_and(|c| c == input) {
+            self.index = (self.index + 1) % self.candidates.len();
+            return Some(self.candidates[self.index].clone());
+        }
+        self.candidates = candidates(input);
+        self.index = 0;
+        self.candidates.first().cloned()
+    }
+}
+
+/// Entries of the directory named by `partial` whose names start with its last component, sorted.
+///
+/// Each candidate is the full input with that component completed; directories get a trailing
+/// separator so the next Tab descends into them. Hidden entries only match a prefix starting with `.`.
+pub fn candidates(partial: &str) -> Vec<String> {
+    let (dir, prefix) = match partial.rfind(is_separator) {
+        Some(i) => partial.split_at(i + 1),
+        None => ("", partial),
+    };
+    let read_from = if dir.is_empty() { "." } else { dir };
+    let Ok(entries) = std::fs::read_dir(read_from) else { return Vec::new() };
+
+    let mut found: Vec<String> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if !starts_with(&name, prefix) || (name.starts_with('.') && !prefix.starts_with('.')) {
+                return None;
+            }
+            let is_dir = entry.path().is_dir();
+            Some(format!("{}{}{}", dir, name, if is_dir { MAIN_SEPARATOR.to_string() } else { String::new() }))
+        })
+        .collect();
+    found.sort();
+    found
+}
+
+/// Prefix match, ignoring case where the file system usually does.
+fn starts_with(name: &str, prefix: &str) -> bool {
+    if cfg!(any(target_os = "windows", target_os = "macos")) {
+        name.to_lowercase().starts_with(&prefix.to_lowercase())
+    } else {
+        name.starts_with(prefix)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tab_cycles_through_matches() {
+        let dir = std::env::temp_dir().join(format!("vodeo2ascii-complete-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("albums")).unwrap();
+        for name in ["a.mp4", "b.mkv", ".hidden"] {
+            std::fs::write(dir.join(name), "").unwrap();
+        }
+        let base = format!("{}{}", dir.display(), MAIN_SEPARATOR);
+
+        let mut completer = PathCompleter::default();
+        let first = completer.complete(&format!("{}a", base)).unwrap();
+        assert_eq!(first, format!("{}a.mp4", base));
+        let second = completer.complete(&first).unwrap();
+        assert_eq!(second, format!("{}albums{}", base, MAIN_SEPARATOR));
+        assert_eq!(completer.complete(&second).unwrap(), first);
+
+        assert_eq!(candidates(&base).len(), 3);
+        assert_eq!(candidates(&format!("{}.h", base)), vec![format!("{}.hidden", base)]);
+        assert!(completer.complete(&format!("{}zzz", base)).is_none());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,616 @@
+use std::{collections::BTreeMap, ops::RangeInclusive, sync::Mutex};
+
+/// Rec. 601 luma, matching the weights the ASCII ramp uses.
+pub fn luminance(r: u8, g: u8, b: u8) -> u8 {
+    ((r as u16 * 77 + g as u16 * 150 + b as u16 * 29) >> 8) as u8
+}
+
+/// Average luma of a single-channel `gray` frame.
+pub fn mean_luminance(gray: &[u8]) -> f32 {
+    if gray.is_empty() {
+        return 0.0;
+    }
+    gray.iter().map(|&v| v as u64).sum::<u64>() as f32 / gray.len() as f32
+}
+
+/// Scales the color of every `rgb24` pixel in `frame` by `saturation`, moving each channel toward
+/// (below 1.0) or away from (above it) the pixel's luminance. 0.0 gives grayscale.
+pub fn saturate(frame: &mut [u8], saturation: f32) {
+    let sat = (saturation.max(0.0) * 256.0) as i32;
+    for px in frame.chunks_exact_mut(3) {
+        let luma = luminance(px[0], px[1], px[2]) as i32;
+        for channel in px.iter_mut() {
+            *channel = (luma + (((*channel as i32 - luma) * sat) >> 8)).clamp(0, 255) as u8;
+        }
+    }
+}
+
+/// One of the adjustments in `Levels`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    Brightness,
+    Contrast,
+    Gamma,
+}
+
+impl Level {
+    pub const ALL: [Level; 3] = [Level::Brightness, Level::Contrast, Level::Gamma];
+
+    /// The name in per-mode settings keys.
+    pub fn key(self) -> &'static str {
+        match self {
+            Level::Brightness => "brightness",
+            Level::Contrast => "contrast",
+            Level::Gamma => "gamma",
+        }
+    }
+
+    /// Steps from neutral the level can be moved to.
+    pub fn range(self) -> RangeInclusive<i32> {
+        match self {
+            Level::Brightness => -10..=10,
+            Level::Contrast | Level::Gamma => -5..=10,
+        }
+    }
+}
+
+/// Brightness, contrast and gamma picked on the tune screen, kept as whole steps from neutral so
+/// repeated adjustments land back on it exactly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Levels {
+    /// Steps of 0.05 of full scale added to every channel.
+    pub brightness: i32,
+    /// Steps of 0.1 on the contrast around mid-gray, from 0.5x to 2x.
+    pub contrast: i32,
+    /// Steps of 0.1 on the gamma, from 0.5 to 2.0; above 1.0 lifts the shadows.
+    pub gamma: i32,
+}
+
+impl Levels {
+    pub fn is_neutral(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Steps `level` stands at from neutral.
+    pub fn steps(&self, level: Level) -> i32 {
+        match level {
+            Level::Brightness => self.brightness,
+            Level::Contrast => self.contrast,
+            Level::Gamma => self.gamma,
+        }
+    }
+
+    /// Moves `level` by `delta` steps, stopping at the ends of its range.
+    pub fn adjust(&mut self, level: Level, delta: i32) {
+        let value = match level {
+            Level::Brightness => &mut self.brightness,
+            Level::Contrast => &mut self.contrast,
+            Level::Gamma => &mut self.gamma,
+        };
+        let range = level.range();
+        *value = (*value + delta).clamp(*range.start(), *range.end());
+    }
+
+    /// The value `level` stands at: an offset for brightness, a factor for the others.
+    pub fn value(&self, level: Level) -> f32 {
+        match level {
+            Level::Brightness => self.brightness as f32 * 0.05,
+            level => 1.0 + self.steps(level) as f32 * 0.1,
+        }
+    }
+
+    /// Maps every byte of `frame` through the adjustments, so it suits rgb24 and gray frames alike.
+    pub fn apply(&self, frame: &mut [u8]) {
+        let (brightness, contrast, gamma) = (self.value(Level::Brightness), self.value(Level::Contrast), self.value(Level::Gamma));
+        let table: [u8; 256] = std::array::from_fn(|i| {
+            let v = ((i as f32 / 255.0 - 0.5) * contrast + 0.5 + brightness).clamp(0.0, 1.0);
+            (v.powf(1.0 / gamma) * 255.0).round() as u8
+        });
+        for v in frame.iter_mut() {
+            *v = table[*v as usize];
+        }
+    }
+}
+
+/// 4x4 Bayer matrix, spreading the ordered dither thresholds evenly over a block.
+const BAYER_4X4: [[u16; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+/// Converts a `width` pixels wide `rgb48le` frame into `rgb24` in `out`, with ordered dithering so
+/// gradients from 10-bit sources don't band. The pattern is fixed per pixel, so still areas don't
+/// shimmer from frame to frame.
+pub fn dither_rgb48(frame: &[u8], width: usize, out: &mut [u8]) {
+    let width = width.max(1);
+    for (i, (px, dst)) in frame.chunks_exact(6).zip(out.chunks_exact_mut(3)).enumerate() {
+        // Threshold in 1/256ths of an 8-bit step, centered in its 16th.
+        let threshold = BAYER_4X4[(i / width) % 4][(i % width) % 4] as u32 * 16 + 8;
+        for (channel, bytes) in dst.iter_mut().zip(px.chunks_exact(2)) {
+            let value = u16::from_le_bytes([bytes[0], bytes[1]]) as u32;
+            // 8-bit levels sit at multiples of 257; rescaled to multiples of 256 they come out exact.
+            *channel = ((value * 256 / 257 + threshold) >> 8).min(255) as u8;
+        }
+    }
+}
+
+/// Whether no pixel of `frame`, with `channels` bytes per pixel, is brighter than `threshold`.
+/// Stops at the first bright pixel, so ordinary frames cost next to nothing.
+pub fn is_black(frame: &[u8], channels: usize, threshold: u8) -> bool {
+    frame.chunks_exact(channels).all(|px| {
+        let luma = if channels >= 3 { luminance(px[0], px[1], px[2]) } else { px[0] };
+        luma <= threshold
+    })
+}
+
+/// Keeps near-static scenes on screen for `--reduce-motion`: a frame that barely differs from the
+/// last one let through is held back instead of drawn.
+#[derive(Debug, Clone, Default)]
+pub struct MotionHold {
+    reference: Vec<u8>,
+    held: u32,
+}
+
+impl MotionHold {
+    /// Whether `frame` should be held: its mean absolute difference per byte from the last frame
+    /// let through is at most `threshold`, and fewer than `max_held` frames in a row were held.
+    /// Anything else becomes the new reference, so slow drift adds up until it shows.
+    pub fn hold(&mut self, frame: &[u8], threshold: u8, max_held: u32) -> bool {
+        if self.held < max_held && self.reference.len() == frame.len() && !frame.is_empty() {
+            let limit = threshold as u64 * frame.len() as u64;
+            let diff: u64 = self.reference.iter().zip(frame).map(|(&a, &b)| a.abs_diff(b) as u64).sum();
+            if diff <= limit {
+                self.held += 1;
+                return true;
+            }
+        }
+        self.reference.clear();
+        self.reference.extend_from_slice(frame);
+        self.held = 0;
+        false
+    }
+
+    /// Forgets the reference, so the next frame is drawn; for when the screen was cleared.
+    pub fn reset(&mut self) {
+        self.reference.clear();
+        self.held = 0;
+    }
+}
+
+/// Number of leading frames darker than `threshold`, if they last at least `min_frames` and
+/// something brighter follows. `None` means there's no intro worth skipping: too short, or dark
+/// throughout, which is more likely an intentionally dark video than a black lead-in.
+pub fn black_intro_frames(lumas: &[f32], threshold: f32, min_frames: usize) -> Option<usize> {
+    let dark = lumas.iter().take_while(|&&l| l < threshold).count();
+    (dark >= min_frames.max(1) && dark < lumas.len()).then_some(dark)
+}
+
+/// Per-frame contrast stretch: maps the frame's 1st..99th luminance percentiles onto 0..255.
+///
+/// The percentiles are smoothed across frames so the picture doesn't pump from frame to frame.
+#[derive(Debug, Clone, Default)]
+pub struct AutoContrast {
+    range: Option<(f32, f32)>,
+}
+
+impl AutoContrast {
+    /// Weight given to the newest frame's percentiles.
+    const SMOOTHING: f32 = 0.2;
+    /// Ranges narrower than this are left alone rather than amplifying noise.
+    const MIN_RANGE: f32 = 8.0;
+
+    /// Forgets the smoothed range, e.g. after a seek.
+    pub fn reset(&mut self) {
+        self.range = None;
+    }
+
+    /// Stretches `frame`, with `channels` bytes per pixel: 3 for `rgb24`, 1 for `gray`.
+    pub fn apply(&mut self, frame: &mut [u8], channels: usize) {
+        let mut histogram = [0u32; 256];
+        for px in frame.chunks_exact(channels) {
+            let luma = if channels >= 3 { luminance(px[0], px[1], px[2]) } else { px[0] };
+            histogram[luma as usize] += 1;
+        }
+        let total: u32 = histogram.iter().sum();
+        if total == 0 {
+            return;
+        }
+        let low = percentile(&histogram, total / 100) as f32;
+        let high = percentile(&histogram, total - total / 100 - 1) as f32;
+
+        let (low, high) = match self.range {
+            Some((l, h)) => (l + (low - l) * Self::SMOOTHING, h + (high - h) * Self::SMOOTHING),
+            None => (low, high),
+        };
+        self.range = Some((low, high));
+
+        if high - low < Self::MIN_RANGE {
+            return;
+        }
+        let mut lut = [0u8; 256];
+        for (v, out) in lut.iter_mut().enumerate() {
+            *out = ((v as f32 - low) * 255.0 / (high - low)).clamp(0.0, 255.0) as u8;
+        }
+        for v in frame.iter_mut() {
+            *v = lut[*v as usize];
+        }
+    }
+}
+
+/// `AdaptiveRamp`'s versions of each ramp it has been given, keyed by the ramp's glyphs.
+static SPREADS: Mutex<BTreeMap<Vec<char>, Vec<&'static [char]>>> = Mutex::new(BTreeMap::new());
+
+/// Chooses, per scene, a version of the ASCII ramp that spends more of its glyphs on the dark end
+/// for dark scenes and on the bright end for bright ones.
+///
+/// Each version is the ramp re-spread over all 256 brightness levels through a gamma curve, so the
+/// renderer's lookup is unchanged. The scene's mean brightness is smoothed across frames, and the
+/// version only changes once that mean is clearly past the boundary to the next, so cuts and
+/// flickering highlights don't make the glyphs jump back and forth.
+#[derive(Debug, Clone)]
+pub struct AdaptiveRamp {
+    /// One ramp per entry of `GAMMAS`.
+    ramps: Vec<&'static [char]>,
+    mean: Option<f32>,
+    level: usize,
+}
+
+impl AdaptiveRamp {
+    /// Exponents on brightness (0-1) for scenes from darkest to brightest; below 1 spreads the
+    /// darks over more glyphs.
+    const GAMMAS: [f32; 5] = [0.5, 0.7, 1.0, 1.4, 2.0];
+    /// The plain ramp's entry in `GAMMAS`.
+    const NEUTRAL: usize = 2;
+    /// Weight given to the newest frame's mean brightness.
+    const SMOOTHING: f32 = 0.1;
+    /// How far (0-1) past a boundary the mean has to go before the ramp changes.
+    const HYSTERESIS: f32 = 0.03;
+    /// Pixels sampled per frame, at most.
+    const SAMPLES: usize = 4096;
+
+    /// The versions of `ramp`. `RenderOptions` borrows ramps for the program's life, so they're
+    /// leaked, but only once per distinct ramp: playing another file reuses them.
+    pub fn new(ramp: &'static [char]) -> Self {
+        let ramps = match SPREADS.lock() {
+            Ok(mut spreads) => spreads.entry(ramp.to_vec()).or_insert_with(|| Self::spread(ramp)).clone(),
+            Err(_) => Self::spread(ramp),
+        };
+        Self { ramps, mean: None, level: Self::NEUTRAL }
+    }
+
+    fn spread(ramp: &'static [char]) -> Vec<&'static [char]> {
+        Self::GAMMAS
+            .iter()
+            .map(|&gamma| {
+                if gamma == 1.0 {
+                    return ramp;
+                }
+                let last = ramp.len() - 1;
+                let spread: Vec<char> = (0..256)
+                    .map(|v| ramp[((v as f32 / 255.0).powf(gamma) * last as f32).round() as usize])
+                    .collect();
+                &*Box::leak(spread.into_boxed_slice())
+            })
+            .collect()
+    }
+
+    /// Forgets the smoothed brightness, e.g. after a seek.
+    pub fn reset(&mut self) {
+        self.mean = None;
+    }
+
+    /// The ramp for `frame`, with `channels` bytes per pixel: 3 for `rgb24`, 1 for `gray`.
+    pub fn ramp(&mut self, frame: &[u8], channels: usize) -> &'static [char] {
+        let pixels = frame.len() / channels;
+        if pixels == 0 {
+            return self.ramps[self.level];
+        }
+        let step = pixels.div_ceil(Self::SAMPLES);
+        let (sum, count) = frame.chunks_exact(channels).step_by(step).fold((0u64, 0u64), |(sum, count), px| {
+            let luma = if channels >= 3 { luminance(px[0], px[1], px[2]) } else { px[0] };
+            (sum + luma as u64, count + 1)
+        });
+        let latest = sum as f32 / count as f32 / 255.0;
+        let mean = match self.mean {
+            Some(m) => m + (latest - m) * Self::SMOOTHING,
+            None => {
+                // A fresh start takes the scene as it is.
+                self.level = Self::level_for(latest);
+                latest
+            }
+        };
+        self.mean = Some(mean);
+
+        let width = 1.0 / Self::GAMMAS.len() as f32;
+        let (low, high) = (self.level as f32 * width, (self.level + 1) as f32 * width);
+        if mean < low - Self::HYSTERESIS || mean > high + Self::HYSTERESIS {
+            self.level = Self::level_for(mean);
+        }
+        self.ramps[self.level]
+    }
+
+    fn level_for(mean: f32) -> usize {
+        ((mean * Self::GAMMAS.len() as f32) as usize).min(Self::GAMMAS.len() - 1)
+    }
+}
+
+/// Weight of the previous frame when temporal smoothing is toggled on without `--smooth`.
+pub const DEFAULT_SMOOTHING: f32 = 0.5;
+
+/// Blends every channel of every pixel with the same pixel of the previous output (an exponential
+/// moving average), so compression noise doesn't make colors shimmer from frame to frame.
+#[derive(Debug, Clone, Default)]
+pub struct TemporalSmoothing {
+    previous: Vec<u8>,
+}
+
+impl TemporalSmoothing {
+    /// Forgets the previous frame, so nothing is blended across a seek or cut.
+    pub fn reset(&mut self) {
+        self.previous.clear();
+    }
+
+    /// `factor` is the previous frame's weight, from 0.0 (no smoothing) up to 0.95.
+    pub fn apply(&mut self, frame: &mut [u8], factor: f32) {
+        // First frame, or a different size: there's nothing compatible to blend with.
+        if self.previous.len() != frame.len() {
+            self.previous = frame.to_vec();
+            return;
+        }
+        let keep = (factor.clamp(0.0, 0.95) * 256.0) as u16;
+        for (cur, prev) in frame.iter_mut().zip(self.previous.iter_mut()) {
+            *cur = ((*prev as u16 * keep + *cur as u16 * (256 - keep) + 128) >> 8) as u8;
+            *prev = *cur;
+        }
+    }
+}
+
+/// Kaleidoscope effect: one part of the frame copied, flipped, over the rest so the picture is
+/// symmetric.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Mirror {
+    #[default]
+    Off,
+    /// The left half reflected onto the right.
+    Horizontal,
+    /// The top half reflected onto the bottom.
+    Vertical,
+    /// The top-left quarter reflected into the other three.
+    Quad,
+}
+
+impl Mirror {
+    /// Accepts `off`, `horizontal` (or `h`), `vertical` (or `v`) or `quad`, as given to `--mirror`.
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "off" => Some(Mirror::Off),
+            "horizontal" | "h" => Some(Mirror::Horizontal),
+            "vertical" | "v" => Some(Mirror::Vertical),
+            "quad" => Some(Mirror::Quad),
+            _ => None,
+        }
+    }
+
+    /// The name `parse` takes.
+    pub fn key(self) -> &'static str {
+        match self {
+            Mirror::Off => "off",
+            Mirror::Horizontal => "horizontal",
+            Mirror::Vertical => "vertical",
+            Mirror::Quad => "quad",
+        }
+    }
+
+    /// The next variant, in the order the playback key cycles through them.
+    pub fn next(self) -> Self {
+        match self {
+            Mirror::Off => Mirror::Horizontal,
+            Mirror::Horizontal => Mirror::Vertical,
+            Mirror::Vertical => Mirror::Quad,
+            Mirror::Quad => Mirror::Off,
+        }
+    }
+
+    /// Mirrors a `width`x`height` frame in place, with `channels` bytes per pixel.
+    pub fn apply(self, frame: &mut [u8], width: usize, height: usize, channels: usize) {
+        if frame.len() < width * height * channels {
+            return;
+        }
+        let row_len = width * channels;
+        if matches!(self, Mirror::Horizontal | Mirror::Quad) {
+            for row in frame.chunks_exact_mut(row_len).take(height) {
+                for x in 0..width / 2 {
+                    let (src, dst) = (x * channels, (width - 1 - x) * channels);
+                    row.copy_within(src..src + channels, dst);
+                }
+            }
+        }
+        if matches!(self, Mirror::Vertical | Mirror::Quad) {
+            for y in 0..height / 2 {
+                let (src, dst) = (y * row_len, (height - 1 - y) * row_len);
+                frame.copy_within(src..src + row_len, dst);
+            }
+        }
+    }
+}
+
+/// Smallest value whose cumulative count exceeds `rank`.
+fn percentile(histogram: &[u32; 256], rank: u32) -> u8 {
+    let mut seen = 0;
+    for (v, &count) in histogram.iter().enumerate() {
+        seen += count;
+        if seen > rank {
+            return v as u8;
+        }
+    }
+    255
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn auto_contrast_stretches_to_full_range() {
+        let mut frame: Vec<u8> = (0..100u8).flat_map(|i| { let v = 100 + i / 2; [v, v, v] }).collect();
+        AutoContrast::default().apply(&mut frame, 3);
+        assert_eq!(frame[0], 0);
+        assert_eq!(*frame.last().unwrap(), 255);
+    }
+
+    #[test]
+    fn adaptive_ramp_follows_scene_brightness_without_flicker() {
+        const RAMP: &[char] = &[' ', '.', ':', '#', '@'];
+        let mut adaptive = AdaptiveRamp::new(RAMP);
+        let dark = vec![20u8; 300];
+        let ramp = adaptive.ramp(&dark, 3);
+        // Dark scenes: the darks reach further up the ramp than with the plain one.
+        assert_eq!(ramp.len(), 256);
+        assert_eq!((ramp[0], ramp[40], ramp[255]), (' ', ':', '@'));
+
+        // A single bright frame doesn't switch; a bright scene does, after a few frames.
+        let bright = vec![240u8; 300];
+        assert_eq!(adaptive.ramp(&bright, 3)[40], ':');
+        let switched = (0..60).map(|_| adaptive.ramp(&bright, 3)).last().unwrap();
+        assert_eq!((switched[40], switched[200]), (' ', ':'));
+
+        adaptive.reset();
+        assert_eq!(adaptive.ramp(&[128; 3], 3), RAMP);
+        // Another playback of the same ramp reuses its versions rather than leaking new ones.
+        assert!(std::ptr::eq(AdaptiveRamp::new(RAMP).ramps[0], adaptive.ramps[0]));
+    }
+
+    #[test]
+    fn black_intro_needs_length_and_a_bright_frame_after() {
+        let lumas = [2.0, 3.0, 1.0, 4.0, 90.0, 5.0];
+        assert_eq!(black_intro_frames(&lumas, 16.0, 3), Some(4));
+        assert_eq!(black_intro_frames(&lumas, 16.0, 5), None);
+        assert_eq!(black_intro_frames(&[2.0, 3.0, 1.0], 16.0, 1), None);
+        assert_eq!(black_intro_frames(&[90.0, 2.0], 16.0, 1), None);
+        assert_eq!(mean_luminance(&[0, 10, 20]), 10.0);
+        assert!(is_black(&[0, 0, 0, 10, 12, 8], 3, 24));
+        assert!(!is_black(&[0, 0, 0, 200, 200, 200], 3, 24));
+        assert!(!is_black(&[3, 90], 1, 24));
+    }
+
+    #[test]
+    fn saturation_scales_the_distance_from_luminance() {
+        let mut gray = [200, 100, 50];
+        saturate(&mut gray, 0.0);
+        let luma = luminance(200, 100, 50);
+        assert_eq!(gray, [luma; 3]);
+
+        let mut same = [200, 100, 50];
+        saturate(&mut same, 1.0);
+        assert_eq!(same, [200, 100, 50]);
+
+        let mut boosted = [200, 100, 50];
+        saturate(&mut boosted, 3.0);
+        assert_eq!(boosted[0], 255);
+        assert_eq!(boosted[2], 0);
+    }
+
+    #[test]
+    fn levels_adjust_within_range_and_map_every_channel() {
+        let mut levels = Levels::default();
+        let mut frame = [0, 64, 128, 255];
+        levels.apply(&mut frame);
+        assert_eq!(frame, [0, 64, 128, 255]);
+
+        levels.adjust(Level::Brightness, 20);
+        assert_eq!(levels.brightness, 10);
+        levels.adjust(Level::Brightness, -6);
+        levels.apply(&mut frame);
+        assert_eq!(frame, [51, 115, 179, 255]);
+
+        let mut levels = Levels::default();
+        levels.adjust(Level::Contrast, -100);
+        assert_eq!(levels.value(Level::Contrast), 0.5);
+        let mut frame = [0, 255];
+        levels.apply(&mut frame);
+        assert_eq!(frame, [64, 191]);
+
+        let mut levels = Levels::default();
+        levels.adjust(Level::Gamma, 10);
+        let mut frame = [64];
+        levels.apply(&mut frame);
+        assert!(frame[0] > 64);
+        levels.adjust(Level::Gamma, -10);
+        assert!(levels.is_neutral());
+    }
+
+    #[test]
+    fn mirror_reflects_the_first_half_onto_the_second() {
+        // 3x2 gray frame: the middle column and any odd middle row stay as they are.
+        let frame = [1, 2, 3, 4, 5, 6];
+        let mirrored = |mirror: Mirror| {
+            let mut f = frame;
+            mirror.apply(&mut f, 3, 2, 1);
+            f
+        };
+        assert_eq!(mirrored(Mirror::Off), frame);
+        assert_eq!(mirrored(Mirror::Horizontal), [1, 2, 1, 4, 5, 4]);
+        assert_eq!(mirrored(Mirror::Vertical), [1, 2, 3, 1, 2, 3]);
+        assert_eq!(mirrored(Mirror::Quad), [1, 2, 1, 1, 2, 1]);
+        assert_eq!(Mirror::Quad.next(), Mirror::Off);
+
+        let mut rgb = [1, 1, 1, 9, 9, 9];
+        Mirror::Horizontal.apply(&mut rgb, 2, 1, 3);
+        assert_eq!(rgb, [1, 1, 1, 1, 1, 1]);
+    }
+
+    #[test]
+    fn smoothing_blends_with_the_previous_frame_until_reset() {
+        let mut smoothing = TemporalSmoothing::default();
+        let mut first = vec![0u8, 100, 200];
+        smoothing.apply(&mut first, 0.5);
+        assert_eq!(first, [0, 100, 200]);
+
+        let mut second = vec![100u8, 100, 0];
+        smoothing.apply(&mut second, 0.5);
+        assert_eq!(second, [50, 100, 100]);
+
+        smoothing.reset();
+        let mut after_cut = vec![255u8, 255, 255];
+        smoothing.apply(&mut after_cut, 0.5);
+        assert_eq!(after_cut, [255, 255, 255]);
+    }
+
+    #[test]
+    fn flat_frame_is_left_alone() {
+        let mut frame = vec![40u8; 30];
+        AutoContrast::default().apply(&mut frame, 3);
+        assert!(frame.iter().all(|&v| v == 40));
+    }
+
+    #[test]
+    fn dithering_keeps_8_bit_levels_and_spreads_the_ones_between() {
+        let rgb48 = |levels: &[u16]| levels.iter().flat_map(|v| v.to_le_bytes()).collect::<Vec<u8>>();
+        // White, black and 8-bit gray 100 come through unchanged.
+        let frame = rgb48(&[65535, 65535, 65535, 0, 0, 0, 100 * 257, 100 * 257, 100 * 257]);
+        let mut out = vec![0u8; 9];
+        dither_rgb48(&frame, 3, &mut out);
+        assert_eq!(out, [255, 255, 255, 0, 0, 0, 100, 100, 100]);
+
+        // Halfway between 100 and 101 over a 4x4 block: half the pixels each way.
+        let frame = rgb48(&[100 * 257 + 128; 16 * 3]);
+        let mut out = vec![0u8; 16 * 3];
+        dither_rgb48(&frame, 4, &mut out);
+        assert_eq!(out.iter().filter(|&&v| v == 101).count(), 24);
+        assert!(out.iter().all(|&v| v == 100 || v == 101));
+    }
+
+    #[test]
+    fn motion_hold_keeps_small_changes_back_until_they_add_up() {
+        let mut motion = MotionHold::default();
+        assert!(!motion.hold(&[100; 8], 2, 10));
+        assert!(motion.hold(&[101; 8], 2, 10));
+        assert!(motion.hold(&[102; 8], 2, 10));
+        // Compared with the frame on screen, not the previous one.
+        assert!(!motion.hold(&[103; 8], 2, 10));
+        assert!(!motion.hold(&[200; 8], 2, 10));
+
+        // Even a still scene is redrawn after `max_held` frames, and after a reset.
+        assert!(motion.hold(&[200; 8], 0, 1));
+        assert!(!motion.hold(&[200; 8], 0, 1));
+        motion.reset();
+        assert!(!motion.hold(&[200; 8], 0, 1));
+    }
+}
@@ -0,0 +1,49 @@
+/// Accumulates ffmpeg `-vf` filters in the order they should run.
+///
+/// Each entry is a single filter (`scale=320:180`, `hflip`); entries are joined with commas,
+/// so a filter whose options contain a comma must quote them itself.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FilterChain {
+    video: Vec<String>,
+}
+
+impl FilterChain {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn video(mut self, filter: impl Into<String>) -> Self {
+        self.video.push(filter.into());
+        self
+    }
+
+    /// Adds `filter` only when `enabled`, so optional transforms read as a flat chain.
+    pub fn video_if(self, enabled: bool, filter: impl Into<String>) -> Self {
+        if enabled { self.video(filter) } else { self }
+    }
+
+    /// The comma-joined `-vf` value, or `None` when no filters were added.
+    pub fn video_arg(&self) -> Option<String> {
+        if self.video.is_empty() { None } else { Some(self.video.join(",")) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_chain_renders_no_args() {
+        assert_eq!(FilterChain::new().video_arg(), None);
+    }
+
+    #[test]
+    fn video_filters_keep_insertion_order() {
+        let chain = FilterChain::new()
+            .video("scale=320:180")
+            .video_if(true, "hflip")
+            .video_if(false, "vflip")
+            .video("format=rgb24");
+        assert_eq!(chain.video_arg().as_deref(), Some("scale=320:180,hflip,format=rgb24"));
+    }
+}
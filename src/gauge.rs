@@ -0,0 +1,117 @@
+use ratatui::{prelude::*, widgets::LineGauge};
+
+/// How the menu's stats pane draws its CPU and memory gauges, from the `gauge_style` setting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GaugeStyle {
+    /// Full blocks shaded from one color to the other, over a light-shade track.
+    #[default]
+    Gradient,
+    /// Braille dots, two columns to a cell, so the fill moves in half-cell steps.
+    Braille,
+    /// ratatui's `LineGauge`: the percentage followed by a thin line.
+    Line,
+}
+
+impl GaugeStyle {
+    /// Accepts `gradient` (or `blocks`), `braille` or `line`.
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "gradient" | "blocks" => Some(GaugeStyle::Gradient),
+            "braille" => Some(GaugeStyle::Braille),
+            "line" => Some(GaugeStyle::Line),
+            _ => None,
+        }
+    }
+}
+
+/// A one-row gauge filled to `ratio`, colored from `start` at the left to `end` at the right.
+pub struct Gauge {
+    style: GaugeStyle,
+    ratio: f64,
+    start: (u8, u8, u8),
+    end: (u8, u8, u8),
+}
+
+impl Gauge {
+    pub fn new(style: GaugeStyle, ratio: f64, start: (u8, u8, u8), end: (u8, u8, u8)) -> Self {
+        // NaN (nothing known yet) shows as empty.
+        let ratio = if ratio.is_nan() { 0.0 } else { ratio.clamp(0.0, 1.0) };
+        Self { style, ratio, start, end }
+    }
+
+    /// The gradient's color `t` of the way across.
+    fn color_at(&self, t: f64) -> Color {
+        let mix = |a: u8, b: u8| (a as f64 * (1.0 - t) + b as f64 * t) as u8;
+        Color::Rgb(mix(self.start.0, self.end.0), mix(self.start.1, self.end.1), mix(self.start.2, self.end.2))
+    }
+}
+
+/// A full braille cell, its left column, and the bottom row left as the empty track.
+const BRAILLE_FULL: char = '⣿';
+const BRAILLE_HALF: char = '⡇';
+const BRAILLE_TRACK: char = '⣀';
+
+impl Widget for Gauge {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        if area.width < 1 || area.height < 1 {
+            return;
+        }
+        let width = area.width as usize;
+        match self.style {
+            GaugeStyle::Gradient => {
+                let filled = (self.ratio * width as f64).round() as usize;
+                for i in 0..width {
+                    let cell = &mut buf[(area.x + i as u16, area.y)];
+                    if i < filled {
+                        cell.set_char('█').set_fg(self.color_at(i as f64 / width as f64));
+                    } else {
+                        cell.set_char('░').set_fg(Color::DarkGray);
+                    }
+                }
+            }
+            GaugeStyle::Braille => {
+                let filled = (self.ratio * (width * 2) as f64).round() as usize;
+                for i in 0..width {
+                    let cell = &mut buf[(area.x + i as u16, area.y)];
+                    match filled.saturating_sub(i * 2) {
+                        0 => cell.set_char(BRAILLE_TRACK).set_fg(Color::DarkGray),
+                        1 => cell.set_char(BRAILLE_HALF).set_fg(self.color_at(i as f64 / width as f64)),
+                        _ => cell.set_char(BRAILLE_FULL).set_fg(self.color_at(i as f64 / width as f64)),
+                    };
+                }
+            }
+            GaugeStyle::Line => {
+                // One color for the whole line: how far along the gradient the reading is.
+                LineGauge::default()
+                    .ratio(self.ratio)
+                    .filled_style(Style::default().fg(self.color_at(self.ratio)))
+                    .unfilled_style(Style::default().fg(Color::DarkGray))
+                    .line_set(symbols::line::THICK)
+                    .render(area, buf);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(style: GaugeStyle, ratio: f64, width: u16) -> String {
+        let area = Rect::new(0, 0, width, 1);
+        let mut buf = Buffer::empty(area);
+        Gauge::new(style, ratio, (0, 255, 0), (255, 0, 0)).render(area, &mut buf);
+        (0..width).map(|x| buf[(x, 0)].symbol().to_string()).collect()
+    }
+
+    #[test]
+    fn every_style_fills_in_proportion() {
+        assert_eq!(row(GaugeStyle::Gradient, 0.5, 4), "██░░");
+        // Half cells are a braille gauge's finer steps.
+        assert_eq!(row(GaugeStyle::Braille, 0.25, 6), "⣿⡇⣀⣀⣀⣀");
+        assert_eq!(row(GaugeStyle::Braille, f64::NAN, 2), "⣀⣀");
+        assert!(row(GaugeStyle::Line, 0.5, 20).starts_with("50%"));
+        assert_eq!(GaugeStyle::parse(" Braille "), Some(GaugeStyle::Braille));
+        assert_eq!(GaugeStyle::parse("dots"), None);
+    }
+}
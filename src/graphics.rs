@@ -0,0 +1,269 @@
+use std::fmt::Write;
+
+use image::{codecs::png::PngEncoder, ExtendedColorType, ImageEncoder};
+
+/// Widest frame decoded for an image protocol; every frame is re-encoded and sent as an image, so
+/// this bounds the bytes written per frame rather than the picture's on-screen size.
+pub const MAX_IMAGE_WIDTH: u32 = 640;
+
+/// Cell size assumed when the terminal doesn't report its size in pixels.
+const FALLBACK_CELL_PX: (u32, u32) = (8, 16);
+
+/// Kitty reads at most this many base64 bytes per escape sequence.
+const KITTY_CHUNK: usize = 4096;
+
+/// A way of drawing real images inline in the terminal.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ImageProtocol {
+    Kitty,
+    Iterm2,
+    Sixel,
+}
+
+impl ImageProtocol {
+    /// Accepts `kitty`, `iterm2` or `sixel`.
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "kitty" => Some(ImageProtocol::Kitty),
+            "iterm2" | "iterm" => Some(ImageProtocol::Iterm2),
+            "sixel" => Some(ImageProtocol::Sixel),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for ImageProtocol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ImageProtocol::Kitty => write!(f, "kitty"),
+            ImageProtocol::Iterm2 => write!(f, "iTerm2"),
+            ImageProtocol::Sixel => write!(f, "sixel"),
+        }
+    }
+}
+
+/// The image protocol the current terminal supports, judged from its environment variables.
+///
+/// `VODEO2ASCII_IMAGE_PROTOCOL` (`kitty`, `iterm2`, `sixel` or `none`) overrides the guess, e.g.
+/// for terminals that don't identify themselves or when running inside tmux.
+pub fn detect() -> Option<ImageProtocol> {
+    detect_from(|key| std::env::var(key).ok())
+}
+
+fn detect_from(var: impl Fn(&str) -> Option<String>) -> Option<ImageProtocol> {
+    if let Some(forced) = var("VODEO2ASCII_IMAGE_PROTOCOL") {
+        return ImageProtocol::parse(&forced);
+    }
+    let term = var("TERM").unwrap_or_default();
+    let program = var("TERM_PROGRAM").unwrap_or_default();
+    if var("KITTY_WINDOW_ID").is_some() || term == "xterm-kitty" || program == "ghostty" {
+        Some(ImageProtocol::Kitty)
+    } else if matches!(program.as_str(), "iTerm.app" | "WezTerm") || var("LC_TERMINAL").as_deref() == Some("iTerm2") {
+        Some(ImageProtocol::Iterm2)
+    } else if term.contains("sixel") || term.starts_with("foot") || term.starts_with("mlterm") || program == "mintty" {
+        Some(ImageProtocol::Sixel)
+    } else {
+        None
+    }
+}
+
+/// Whether the terminal is known to support synchronized output (`?2026`), which shows a frame's
+/// updates all at once instead of as they arrive. Others ignore the mode, but it's only used where
+/// it's known to work.
+pub fn sync_supported() -> bool {
+    sync_supported_from(|key| std::env::var(key).ok())
+}
+
+fn sync_supported_from(var: impl Fn(&str) -> Option<String>) -> bool {
+    let term = var("TERM").unwrap_or_default();
+    let program = var("TERM_PROGRAM").unwrap_or_default();
+    var("KITTY_WINDOW_ID").is_some()
+        || var("WT_SESSION").is_some()
+        || matches!(program.as_str(), "iTerm.app" | "WezTerm" | "ghostty" | "vscode" | "mintty" | "rio")
+        || ["xterm-kitty", "alacritty", "foot", "contour", "xterm-ghostty", "wezterm"].iter().any(|t| term.starts_with(t))
+}
+
+/// Pixel size of one terminal cell, from the terminal if it reports one.
+pub fn cell_size_px() -> (u32, u32) {
+    match crossterm::terminal::window_size() {
+        Ok(size) if size.width > 0 && size.height > 0 && size.columns > 0 && size.rows > 0 => (
+            (size.width / size.columns) as u32,
+            (size.height / size.rows) as u32,
+        ),
+        _ => FALLBACK_CELL_PX,
+    }
+}
+
+/// Decode resolution for a frame shown in `cols` x `rows` cells, capped at `MAX_IMAGE_WIDTH`.
+pub fn decode_size((cols, rows): (u32, u32), (cell_w, cell_h): (u32, u32)) -> (u32, u32) {
+    let (mut w, mut h) = (cols * cell_w, rows * cell_h);
+    if w > MAX_IMAGE_WIDTH {
+        h = h * MAX_IMAGE_WIDTH / w;
+        w = MAX_IMAGE_WIDTH;
+    }
+    ((w / 2 * 2).max(2), (h / 2 * 2).max(2))
+}
+
+/// Appends an escape sequence drawing an `rgb24` frame at the cursor, scaled to `cols` x `rows`
+/// cells where the protocol can scale (sixel is drawn at its pixel size).
+pub fn push_image(out: &mut String, protocol: ImageProtocol, frame: &[u8], width: u32, height: u32, (cols, rows): (u32, u32)) -> Option<()> {
+    match protocol {
+        ImageProtocol::Kitty => {
+            let data = base64(&encode_png(frame, width, height)?);
+            // One image id that every frame replaces; drawn below text so overlays stay readable.
+            let mut chunks = data.as_bytes().chunks(KITTY_CHUNK).peekable();
+            let mut first = true;
+            while let Some(chunk) = chunks.next() {
+                let more = chunks.peek().is_some() as u8;
+                let chunk = std::str::from_utf8(chunk).ok()?;
+                if first {
+                    write!(out, "\x1b_Ga=T,f=100,i=1,q=2,C=1,z=-1,c={},r={},m={};{}\x1b\\", cols, rows, more, chunk).ok()?;
+                    first = false;
+                } else {
+                    write!(out, "\x1b_Gm={};{}\x1b\\", more, chunk).ok()?;
+                }
+            }
+        }
+        ImageProtocol::Iterm2 => {
+            let png = encode_png(frame, width, height)?;
+            write!(
+                out,
+                "\x1b]1337;File=inline=1;size={};width={};height={};preserveAspectRatio=0:{}\x07",
+                png.len(), cols, rows, base64(&png)
+            ).ok()?;
+        }
+        ImageProtocol::Sixel => push_sixel(out, frame, width, height)?,
+    }
+    Some(())
+}
+
+fn encode_png(frame: &[u8], width: u32, height: u32) -> Option<Vec<u8>> {
+    let mut png = Vec::new();
+    PngEncoder::new(&mut png).write_image(frame, width, height, ExtendedColorType::Rgb8).ok()?;
+    Some(png)
+}
+
+fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let n = chunk.iter().enumerate().fold(0u32, |n, (i, &b)| n | (b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 63) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+/// Levels per channel of the fixed sixel palette (6 x 7 x 6 = 252 colors).
+const SIXEL_LEVELS: (u32, u32, u32) = (6, 7, 6);
+
+fn sixel_index(r: u8, g: u8, b: u8) -> usize {
+    let (lr, lg, lb) = SIXEL_LEVELS;
+    let level = |v: u8, levels: u32| (v as u32 * (levels - 1) + 127) / 255;
+    (level(r, lr) * lg * lb + level(g, lg) * lb + level(b, lb)) as usize
+}
+
+/// Sixel with a fixed palette: no per-frame quantization pass, at the cost of some banding.
+fn push_sixel(out: &mut String, frame: &[u8], width: u32, height: u32) -> Option<()> {
+    if frame.len() < (width * height * 3) as usize {
+        return None;
+    }
+    let (lr, lg, lb) = SIXEL_LEVELS;
+    write!(out, "\x1bP0;1q\"1;1;{};{}", width, height).ok()?;
+    for r in 0..lr {
+        for g in 0..lg {
+            for b in 0..lb {
+                let pct = |v: u32, levels: u32| v * 100 / (levels - 1);
+                write!(out, "#{};2;{};{};{}", (r * lg * lb + g * lb + b), pct(r, lr), pct(g, lg), pct(b, lb)).ok()?;
+            }
+        }
+    }
+
+    let width = width as usize;
+    let mut bands: Vec<Option<Vec<u8>>> = vec![None; (lr * lg * lb) as usize];
+    for band_top in (0..height as usize).step_by(6) {
+        for y in band_top..(band_top + 6).min(height as usize) {
+            for x in 0..width {
+                let i = (y * width + x) * 3;
+                let color = sixel_index(frame[i], frame[i + 1], frame[i + 2]);
+                bands[color].get_or_insert_with(|| vec![0; width])[x] |= 1 << (y - band_top);
+            }
+        }
+        let mut first = true;
+        for (color, bits) in bands.iter_mut().enumerate() {
+            let Some(bits) = bits.take() else { continue };
+            if !first {
+                out.push('$');
+            }
+            first = false;
+            write!(out, "#{}", color).ok()?;
+            push_sixel_run(out, &bits);
+        }
+        out.push('-');
+    }
+    out.push_str("\x1b\\");
+    Some(())
+}
+
+/// One color's row of sixels, run-length encoded.
+fn push_sixel_run(out: &mut String, bits: &[u8]) {
+    let mut x = 0;
+    while x < bits.len() {
+        let run = bits[x..].iter().take_while(|&&b| b == bits[x]).count();
+        let glyph = (63 + bits[x]) as char;
+        if run > 3 {
+            write!(out, "!{}{}", run, glyph).unwrap();
+        } else {
+            (0..run).for_each(|_| out.push(glyph));
+        }
+        x += run;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn protocol_is_detected_from_the_environment() {
+        let env = |vars: &'static [(&'static str, &'static str)]| {
+            move |key: &str| vars.iter().find(|(k, _)| *k == key).map(|(_, v)| v.to_string())
+        };
+        assert_eq!(detect_from(env(&[("TERM", "xterm-kitty")])), Some(ImageProtocol::Kitty));
+        assert_eq!(detect_from(env(&[("TERM_PROGRAM", "iTerm.app")])), Some(ImageProtocol::Iterm2));
+        assert_eq!(detect_from(env(&[("TERM", "foot")])), Some(ImageProtocol::Sixel));
+        assert_eq!(detect_from(env(&[("TERM", "xterm-256color")])), None);
+        assert_eq!(detect_from(env(&[("TERM", "xterm-kitty"), ("VODEO2ASCII_IMAGE_PROTOCOL", "none")])), None);
+
+        assert!(sync_supported_from(env(&[("TERM", "alacritty")])));
+        assert!(sync_supported_from(env(&[("TERM", "xterm-256color"), ("WT_SESSION", "1")])));
+        assert!(!sync_supported_from(env(&[("TERM", "xterm-256color")])));
+    }
+
+    #[test]
+    fn base64_pads_partial_chunks() {
+        assert_eq!(base64(b"Man"), "TWFu");
+        assert_eq!(base64(b"Ma"), "TWE=");
+        assert_eq!(base64(b"M"), "TQ==");
+    }
+
+    #[test]
+    fn sixel_encodes_each_color_band() {
+        // 2x1: a black and a white pixel, each in its own color run of the single band.
+        let mut out = String::new();
+        push_sixel(&mut out, &[0, 0, 0, 255, 255, 255], 2, 1).unwrap();
+        let body = out.rsplit_once("#251;2;100;100;100").unwrap().1;
+        assert_eq!(body, "#0@?$#251?@-\x1b\\");
+    }
+
+    #[test]
+    fn decode_size_follows_cells_up_to_the_cap() {
+        assert_eq!(decode_size((40, 10), (8, 16)), (320, 160));
+        assert_eq!(decode_size((200, 50), (8, 16)), (640, 320));
+    }
+}
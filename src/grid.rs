@@ -0,0 +1,47 @@
+/// Most videos `--grid` plays at once.
+pub const MAX_TILES: usize = 4;
+
+/// A tile's place on screen, in cells: a label row at `y`, then the video below it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tile {
+    pub x: u16,
+    pub y: u16,
+    pub w: u16,
+    pub h: u16,
+}
+
+impl Tile {
+    /// Rows left for the video under the label.
+    pub fn video_rows(&self) -> u16 {
+        self.h.saturating_sub(1)
+    }
+}
+
+/// Splits a `cols` x `rows` terminal into `count` tiles: side by side for two, a 2x2 grid for
+/// three or four, with a blank column between neighbours.
+pub fn layout(count: usize, cols: u16, rows: u16) -> Vec<Tile> {
+    let count = count.clamp(1, MAX_TILES);
+    let across = count.min(2) as u16;
+    let down = count.div_ceil(2) as u16;
+    let w = cols.saturating_sub(across - 1) / across;
+    let h = rows / down;
+    (0..count as u16)
+        .map(|i| Tile { x: (i % across) * (w + 1), y: (i / across) * h, w, h })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tiles_split_the_screen_without_overlapping() {
+        assert_eq!(layout(2, 81, 24), vec![Tile { x: 0, y: 0, w: 40, h: 24 }, Tile { x: 41, y: 0, w: 40, h: 24 }]);
+        let four = layout(4, 80, 24);
+        assert_eq!(four[3], Tile { x: 40, y: 12, w: 39, h: 12 });
+        assert_eq!(four[3].video_rows(), 11);
+        // A third video leaves the bottom-right quarter empty.
+        assert_eq!(layout(3, 80, 24).len(), 3);
+        assert_eq!(layout(9, 80, 24).len(), MAX_TILES);
+    }
+}
@@ -0,0 +1,65 @@
+use ratatui::prelude::*;
+
+/// An `rgb24` frame drawn the way PixelArt draws it, one `▀` per two pixels with the top one as
+/// the foreground and the bottom one as the background, but into a ratatui `Buffer` instead of
+/// as escapes. It fills whatever area it's given, sampling the nearest pixel, so a frame decoded
+/// at the area's columns by twice its rows maps one pixel to each half cell.
+pub struct HalfBlocks<'a> {
+    frame: &'a [u8],
+    width: u32,
+    height: u32,
+}
+
+impl<'a> HalfBlocks<'a> {
+    pub fn new(frame: &'a [u8], width: u32, height: u32) -> Self {
+        Self { frame, width, height }
+    }
+
+    fn pixel(&self, x: u32, y: u32) -> Color {
+        let i = ((y.min(self.height - 1) * self.width + x.min(self.width - 1)) * 3) as usize;
+        Color::Rgb(self.frame[i], self.frame[i + 1], self.frame[i + 2])
+    }
+}
+
+impl Widget for HalfBlocks<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let area = area.intersection(buf.area);
+        // A frame that doesn't hold `width`x`height` pixels leaves the area as it is.
+        if area.is_empty() || self.width == 0 || self.height == 0 || self.frame.len() < (self.width * self.height * 3) as usize {
+            return;
+        }
+        let (cols, rows) = (area.width as u32, area.height as u32);
+        for row in 0..rows {
+            let top = row * 2 * self.height / (rows * 2);
+            let bottom = (row * 2 + 1) * self.height / (rows * 2);
+            for col in 0..cols {
+                let x = col * self.width / cols;
+                buf[(area.x + col as u16, area.y + row as u16)]
+                    .set_symbol("▀")
+                    .set_fg(self.pixel(x, top))
+                    .set_bg(self.pixel(x, bottom));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pixels_map_to_half_cells_within_the_area() {
+        // 2x4 pixels: red over green, then blue over white, in both columns.
+        let frame: Vec<u8> = [[255, 0, 0], [0, 255, 0], [0, 0, 255], [255, 255, 255]].iter().flat_map(|px| px.repeat(2)).collect();
+        let mut buf = Buffer::empty(Rect::new(0, 0, 6, 4));
+        HalfBlocks::new(&frame, 2, 4).render(Rect::new(3, 1, 2, 2), &mut buf);
+        let cell = &buf[(4, 2)];
+        assert_eq!((cell.symbol(), cell.fg, cell.bg), ("▀", Color::Rgb(0, 0, 255), Color::Rgb(255, 255, 255)));
+        assert_eq!((buf[(3, 1)].fg, buf[(3, 1)].bg), (Color::Rgb(255, 0, 0), Color::Rgb(0, 255, 0)));
+        // Cells outside the area are untouched.
+        assert_eq!(buf[(2, 1)].symbol(), " ");
+        // An area hanging off the buffer is clipped rather than indexed out of bounds.
+        HalfBlocks::new(&frame, 2, 4).render(Rect::new(5, 3, 4, 4), &mut buf);
+        assert_eq!(buf[(5, 3)].fg, Color::Rgb(255, 0, 0));
+    }
+}
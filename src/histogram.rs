@@ -0,0 +1,72 @@
+/// Pixels looked at per frame, spread evenly over it, so the cost stays flat whatever the decode size.
+const SAMPLES: usize = 4096;
+/// Brightness bins per channel, one terminal column each.
+pub const BINS: usize = 16;
+/// Levels per channel colors are rounded to when finding the dominant ones: 4³ = 64 buckets.
+const LEVELS: usize = 4;
+/// Dominant colors kept for the palette.
+pub const PALETTE_SIZE: usize = 6;
+
+/// Color distribution of a sample of a frame's pixels, for the playback histogram overlay.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ColorStats {
+    /// Sampled pixels per brightness bin, for red, green and blue; a gray frame has one channel.
+    pub channels: Vec<[u32; BINS]>,
+    /// The most common colors, most common first, each the average of its bucket.
+    pub palette: Vec<(u8, u8, u8)>,
+}
+
+impl ColorStats {
+    /// Buckets a sample of `frame`'s pixels, with `channels` bytes per pixel (3 for rgb24, 1 for gray).
+    pub fn sample(frame: &[u8], channels: usize) -> Self {
+        let pixels = frame.len() / channels.max(1);
+        if pixels == 0 {
+            return Self::default();
+        }
+        let step = pixels.div_ceil(SAMPLES);
+        let mut histogram = vec![[0u32; BINS]; channels.min(3)];
+        // Per bucket: sum of each channel and the pixel count.
+        let mut buckets = [[0u32; 4]; LEVELS * LEVELS * LEVELS];
+        for px in frame.chunks_exact(channels).step_by(step) {
+            let [r, g, b] = if channels >= 3 { [px[0], px[1], px[2]] } else { [px[0]; 3] };
+            for (bins, &value) in histogram.iter_mut().zip(&[r, g, b]) {
+                bins[value as usize * BINS / 256] += 1;
+            }
+            let level = |v: u8| v as usize * LEVELS / 256;
+            let bucket = &mut buckets[(level(r) * LEVELS + level(g)) * LEVELS + level(b)];
+            for (sum, value) in bucket.iter_mut().zip([r, g, b, 1]) {
+                *sum += value as u32;
+            }
+        }
+        let mut ranked: Vec<&[u32; 4]> = buckets.iter().filter(|b| b[3] > 0).collect();
+        ranked.sort_by(|a, b| b[3].cmp(&a[3]));
+        let palette = ranked
+            .into_iter()
+            .take(PALETTE_SIZE)
+            .map(|&[r, g, b, n]| ((r / n) as u8, (g / n) as u8, (b / n) as u8))
+            .collect();
+        Self { channels: histogram, palette }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn buckets_sampled_pixels_by_channel_and_color() {
+        // Three red pixels for every blue one.
+        let frame: Vec<u8> = (0..400).flat_map(|i| if i % 4 == 3 { [0, 0, 255] } else { [250, 0, 0] }).collect();
+        let stats = ColorStats::sample(&frame, 3);
+        assert_eq!(stats.channels.len(), 3);
+        assert_eq!(stats.channels[0][BINS - 1], 300);
+        assert_eq!(stats.channels[1][0], 400);
+        assert_eq!(stats.palette, vec![(250, 0, 0), (0, 0, 255)]);
+
+        let gray = ColorStats::sample(&[128; 10], 1);
+        assert_eq!(gray.channels.len(), 1);
+        assert_eq!(gray.channels[0][8], 10);
+        assert_eq!(gray.palette, vec![(128, 128, 128)]);
+        assert_eq!(ColorStats::sample(&[], 3), ColorStats::default());
+    }
+}
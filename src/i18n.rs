@@ -0,0 +1,380 @@
+use std::{
+    fmt::Display,
+    sync::atomic::{AtomicU8, Ordering},
+};
+
+/// Language of the interface.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Lang {
+    #[default]
+    Zh,
+    En,
+}
+
+impl Lang {
+    /// Accepts `zh` or `en`, and locale names like `en_US.UTF-8` or `zh_CN`.
+    pub fn parse(s: &str) -> Option<Self> {
+        let s = s.to_ascii_lowercase();
+        if s.starts_with("zh") {
+            Some(Lang::Zh)
+        } else if s.starts_with("en") {
+            Some(Lang::En)
+        } else {
+            None
+        }
+    }
+
+    /// The first of `--lang`, the `lang` setting and the locale variables that names a supported
+    /// language; Chinese otherwise.
+    pub fn choose(cli: Option<Lang>, setting: Option<&str>) -> Self {
+        let locale = ["LC_ALL", "LC_MESSAGES", "LANG"].iter().find_map(|var| std::env::var(var).ok().filter(|v| !v.is_empty()));
+        cli.or_else(|| setting.and_then(Lang::parse)).or_else(|| locale.as_deref().and_then(Lang::parse)).unwrap_or_default()
+    }
+}
+
+static CURRENT: AtomicU8 = AtomicU8::new(Lang::Zh as u8);
+
+/// Switches the whole interface to `lang`; called at startup, before and after `--lang` is read.
+pub fn set(lang: Lang) {
+    CURRENT.store(lang as u8, Ordering::Relaxed);
+}
+
+pub fn current() -> Lang {
+    if CURRENT.load(Ordering::Relaxed) == Lang::En as u8 { Lang::En } else { Lang::Zh }
+}
+
+/// Declares `Msg` with its Chinese and English text side by side, so a string can't be added in
+/// one language only.
+macro_rules! messages {
+    ($($name:ident => $zh:expr, $en:expr;)*) => {
+        /// Every user-facing interface string.
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        pub enum Msg {
+            $($name),*
+        }
+
+        impl Msg {
+            #[cfg(test)]
+            const ALL: &'static [Msg] = &[$(Msg::$name),*];
+
+            pub fn text_in(self, lang: Lang) -> &'static str {
+                match (self, lang) {
+                    $((Msg::$name, Lang::Zh) => $zh,
+                    (Msg::$name, Lang::En) => $en,)*
+                }
+            }
+        }
+    };
+}
+
+impl Msg {
+    /// The text in the current language.
+    pub fn text(self) -> &'static str {
+        self.text_in(current())
+    }
+
+    /// The text with each `{}` replaced by the next of `args`.
+    pub fn fill(self, args: &[&dyn Display]) -> String {
+        fill(self.text(), args)
+    }
+}
+
+fn fill(template: &str, args: &[&dyn Display]) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut args = args.iter();
+    let mut parts = template.split("{}");
+    out.push_str(parts.next().unwrap_or_default());
+    for part in parts {
+        if let Some(arg) = args.next() {
+            out.push_str(&arg.to_string());
+        }
+        out.push_str(part);
+    }
+    out
+}
+
+messages! {
+    // Menu
+    AppTitle => " 视频转字符画播放器 Vodeo2ASCII v0.1.0 ", " Video to ASCII Player Vodeo2ASCII v0.1.0 ";
+    Footer => " [↑/↓]: 导航 | [回车]: 播放/确认 | [M/S/Tab]: 切换模式 | [O]: 打开文件 | [F]: 收藏 | [N]: 笔记 | [L]: 路径/文件名 | [T]: 系统状态 | [V]: 切换视图 | [C]: 复制路径 | [E]: 外部打开 | [D]: 精确时长 | [W]: 幻灯片 | [G]: 场景胶片 | [R]: 重播 | [H]: 随机播放 | [Z]: 专注模式 | [P]: ASCII配色 | [A]: 媒体库排序 | [/]: 媒体库筛选 | [U]: 重建媒体库 | [B]: 画面调节 | [X]: 标记对比 | [Q/Esc]: 退出/返回 ",
+        " [↑/↓]: Navigate | [Enter]: Play/Confirm | [M/S/Tab]: Mode | [O]: Open file | [F]: Favorite | [N]: Note | [L]: Paths/Names | [T]: System stats | [V]: View | [C]: Copy path | [E]: Open externally | [D]: Exact duration | [W]: Slideshow | [G]: Scene filmstrip | [R]: Replay | [H]: Shuffle | [Z]: Focus mode | [P]: ASCII color | [A]: Library sort | [/]: Library filter | [U]: Rebuild library | [B]: Tune picture | [X]: Mark to compare | [Q/Esc]: Quit/Back ";
+    ListTitleAll => " 视频文件列表 ", " Video Files ";
+    ListTitleFavorites => " 收藏夹 ", " Favorites ";
+    ListTitleRecent => " 最近播放 ", " Recently Played ";
+    ListTitleLibrary => " 媒体库 · 按{}排序 ", " Library · by {} ";
+    LibraryFilter => "筛选: {} ", "Filter: {} ";
+    LibraryProbing => "探测中 {}/{} ", "Probing {}/{} ";
+    DetailsTitle => " 视频详情 ", " Details ";
+    StatsTitle => " 系统状态 ", " System ";
+    CpuUsage => "CPU 使用率: {}%", "CPU usage: {}%";
+    MemoryUsage => "内存使用率: {} GB / {} GB", "Memory usage: {} GB / {} GB";
+    ThreadsFromFlag => "解码线程: {} (--threads)", "Decode threads: {} (--threads)";
+    ThreadsAuto => "解码线程: 自动", "Decode threads: auto";
+    NoteLine => "📝 笔记: {}", "📝 Note: {}";
+    ModePopupTitle => " 选择渲染模式 ", " Render Mode ";
+    ResumeTitle => " 继续播放 ", " Resume ";
+    ResumeQuestion => "从 {} 继续播放?", "Resume from {}?";
+    ResumeKeys => "[回车/Y]: 继续 | [N]: 从头开始 | [Esc]: 取消", "[Enter/Y]: Resume | [N]: Start over | [Esc]: Cancel";
+    NoteTitle => " 笔记 ", " Note ";
+    NoteHeader => "为该文件添加笔记 ([回车]: 保存, 留空则删除 | [Esc]: 取消):", "Note for this file ([Enter]: save, empty to delete | [Esc]: cancel):";
+    InputTitle => " 手动输入文件路径 ", " Open File ";
+    InputHeader => "请输入视频文件的完整路径 (支持拖拽, [Tab]: 补全, [↑/↓]: 历史):", "Full path of a video file (drag and drop works, [Tab]: complete, [↑/↓]: history):";
+    NoPreview => "无预览", "No preview";
+    PreviewLoading => "正在生成预览...", "Generating preview...";
+    SlideshowKeys => "[←/→]: 上一个/下一个 | [回车]: 播放 | [Esc]: 返回", "[←/→]: Previous/Next | [Enter]: Play | [Esc]: Back";
+
+    // Details pane
+    SelectFilePrompt => "请选择一个视频文件以查看详情。", "Select a video file to see its details.";
+    TerminalTooSmall => "终端太小", "Terminal too small";
+    NoFileSelected => "未选择文件", "No file selected";
+    MetadataFailed => "无法解析视频元数据", "Couldn't read the video's metadata";
+    ConnectingDetails => "正在连接…\n\n{}", "Connecting…\n\n{}";
+    AudioDetails => "{}音频文件: 没有视频流, 将以频谱可视化播放\n时长: {}\n大小: {} MB\n码率: {}\n音频编码: {}\n采样率: {} Hz\n声道: {}",
+        "{}Audio file: no video stream, plays as a spectrum visualizer\nDuration: {}\nSize: {} MB\nBitrate: {}\nAudio codec: {}\nSample rate: {} Hz\nChannels: {}";
+    SequenceDetails => "{}图片序列: {} 帧\n分辨率: {}x{}\n帧率: {} FPS ([ / ] 调整)\n时长: {}\n图片格式: {}",
+        "{}Image sequence: {} frames\nResolution: {}x{}\nFrame rate: {} FPS ([ / ] to adjust)\nDuration: {}\nImage format: {}";
+    VfrFps => "{} (可变帧率, 平均 {})", "{} (variable, {} average)";
+    DecodeCappedFps => "{} (解码时抽帧至 {})", "{} (decoded at {})";
+    VideoDetails => "{}分辨率: {}x{}\n帧率: {} FPS\n扫描: {}\n时长: {}\n大小: {} MB\n码率: {}\n视频编码: {}\n音频编码: {}",
+        "{}Resolution: {}x{}\nFrame rate: {} FPS\nScan: {}\nDuration: {}\nSize: {} MB\nBitrate: {}\nVideo codec: {}\nAudio codec: {}";
+    ScanInterlaced => "隔行 ({})", "interlaced ({})";
+    ScanProgressive => "逐行", "progressive";
+    ScanUnknown => "未知", "unknown";
+    HdrCodec => "{} (HDR {})", "{} (HDR {})";
+    NoAudio => "无", "none";
+    AudioFile => "音频文件", "audio file";
+    UnknownDuration => "未知时长", "Unknown duration";
+    DurationCounted => "{} (容器) / {} (逐帧, {} 帧)", "{} (container) / {} (counted, {} frames)";
+    DurationFrames => "{} (容器) / {} 帧", "{} (container) / {} frames";
+    DurationCounting => "{} (逐帧统计中...)", "{} (counting frames...)";
+    DurationCountHint => "{} ([D] 精确统计)", "{} ([D] count frames)";
+    TagTitle => "标题", "Title";
+    TagArtist => "作者", "Artist";
+    TagDate => "日期", "Date";
+    TagComment => "备注", "Comment";
+    ChapterFallback => "章节 {}", "Chapter {}";
+
+    // Menu notifications
+    DirUpdated => "目录已更新: 新增 {} 个, 移除 {} 个", "Directory updated: {} added, {} removed";
+    FavoriteAdded => "★ 已加入收藏", "★ Added to favorites";
+    FavoriteRemoved => "已取消收藏", "Removed from favorites";
+    FavoriteSaveFailed => "保存收藏失败: {}", "Failed to save favorites: {}";
+    SettingsSaveFailed => "保存设置失败: {}", "Failed to save settings: {}";
+    ListShowsPaths => "列表显示: 相对路径", "List shows: relative paths";
+    ListShowsNames => "列表显示: 文件名", "List shows: file names";
+    ShuffleOn => "随机播放: 开启 (按 < > 切换文件时随机顺序)", "Shuffle: on (< > step in a random order)";
+    ShuffleOff => "随机播放: 关闭 (按列表顺序)", "Shuffle: off (list order)";
+    StatsOn => "系统状态: 开启", "System stats: on";
+    StatsOff => "系统状态: 关闭 (不再采样)", "System stats: off (no sampling)";
+    FocusOn => "专注模式: 只显示文件列表 (按 Z 返回)", "Focus mode: file list only (Z to go back)";
+    NoteDeleted => "已删除笔记", "Note deleted";
+    NoteSaved => "已保存笔记", "Note saved";
+    NoteSaveFailed => "保存笔记失败: {}", "Failed to save note: {}";
+    AsciiColorSet => "ASCII 配色: {}", "ASCII color: {}";
+    RenderModeSet => "渲染模式: {}", "Render mode: {}";
+    PathCopied => "已复制路径到剪贴板", "Path copied to clipboard";
+    ClipboardUnavailable => "无法访问剪贴板，退出时将打印路径", "Clipboard unavailable, the path will be printed on exit";
+    SequenceNotExternal => "图像序列无法用外部播放器打开", "Image sequences can't be opened in an external player";
+    OpenedExternally => "已用默认程序打开: {}", "Opened with the default app: {}";
+    OpenFailed => "无法打开: {}", "Couldn't open: {}";
+    CountingOther => "正在统计其他文件的帧数, 请稍候", "Already counting another file's frames, please wait";
+    CountingStarted => "正在逐帧统计, 大文件可能需要一段时间...", "Counting frames, large files can take a while...";
+    CountDone => "帧数统计完成: {} 帧", "Frame count done: {} frames";
+    CountFailed => "帧数统计失败", "Frame count failed";
+    NothingToReplay => "还没有播放过任何文件", "Nothing has been played yet";
+    ScenesOther => "正在查找其他文件的场景, 请稍候", "Already finding another file's scenes, please wait";
+    ScenesStarted => "正在查找场景切换, 需要解码整个文件...", "Finding scene changes, this decodes the whole file...";
+    ScenesDone => "已找到场景, 胶片显示 {} 帧", "Scenes found, the filmstrip shows {} frames";
+    ScenesFailed => "场景查找失败", "Finding scenes failed";
+    NothingToPlay => "没有可播放的文件", "No playable files";
+    ViewAll => "视图: 全部文件", "View: all files";
+    ViewFavorites => "视图: 收藏夹", "View: favorites";
+    ViewRecent => "视图: 最近播放", "View: recently played";
+    ViewLibrary => "视图: 媒体库", "View: library";
+    SortName => "名称", "name";
+    SortDuration => "时长", "duration";
+    SortResolution => "分辨率", "resolution";
+    SortSize => "大小", "size";
+    SortModified => "修改时间", "modified time";
+    LibraryUpdated => "媒体库已更新, 探测了 {} 个文件", "Library updated, probed {} files";
+    LibraryRebuilding => "正在重建媒体库, 重新探测 {} 个文件...", "Rebuilding the library, probing {} files again...";
+    LibraryBusy => "媒体库正在探测, 请稍候", "The library is still probing, please wait";
+    LibrarySaveFailed => "保存媒体库失败: {}", "Failed to save the library: {}";
+    NoMatchingPath => "没有匹配的路径", "No matching paths";
+    NoPlayableFound => "未找到可播放的文件", "No playable files found";
+    FilesAdded => "已添加 {} 个文件", "Added {} files";
+    StdinFiles => "已从标准输入读取 {} 个文件, 跳过 {} 行", "Read {} files from stdin, skipped {} lines";
+    StdinIsTerminal => "标准输入不是管道, 已改为扫描目录", "Nothing was piped to stdin, scanned the directory instead";
+    HistorySaveFailed => "保存播放记录失败: {}", "Failed to save playback history: {}";
+    PlaybackFailed => "播放失败: {}", "Playback failed: {}";
+
+    // Render modes and colors
+    ModePixelArt => "像素艺术 (半块字符 - 高保真)", "Pixel Art (half blocks - high fidelity)";
+    ModeAsciiArt => "ASCII 艺术 (经典字符模式)", "ASCII Art (classic characters)";
+    ModeCompactAscii => "紧凑 ASCII (双像素字符 - 纵向细节加倍)", "Compact ASCII (two pixels per glyph - double vertical detail)";
+    ModeBlocks => "色块艺术 (背景色块 - 无字形依赖)", "Color Blocks (background cells - no glyph needed)";
+    ModeImage => "图像协议 (kitty/iTerm2/sixel - 真实画面)", "Image Protocol (kitty/iTerm2/sixel - real frames)";
+    ModePlain => "纯文本 (无颜色 - 终端默认配色, 输出最小)", "Plain (no color - terminal theme, smallest output)";
+    ColorOriginal => "原色", "Original";
+    ColorGreen => "黑客绿", "Hacker green";
+    ColorAmber => "琥珀", "Amber";
+    ColorWhite => "白色", "White";
+
+    // Playback
+    StatusPortrait => "竖屏: 像素艺术模式", "Portrait: Pixel Art mode";
+    StatusImageFallback => "终端不支持图像协议: 像素艺术模式", "No image protocol in this terminal: Pixel Art mode";
+    StatusChapter => "章节: {}", "Chapter: {}";
+    StatusSubtitle => "字幕: {}", "Subtitles: {}";
+    StatusThreads => "解码线程: {}", "Decode threads: {}";
+    StatusFps => "帧率: {}/{} fps", "Frame rate: {}/{} fps";
+    StatusDecodeCap => "抽帧: {}→{} fps", "Decimated: {}→{} fps";
+    StatusHflip => "水平翻转", "Flipped horizontally";
+    StatusVflip => "垂直翻转", "Flipped vertically";
+    StatusAutoContrast => "自动对比度", "Auto contrast";
+    StatusAdaptiveRamp => "自适应字符", "Adaptive ramp";
+    AdaptiveRampUnused => "当前渲染模式不使用字符梯度", "This render mode doesn't use the character ramp";
+    StatusMirror => "万花筒: {}", "Kaleidoscope: {}";
+    StatusSaturation => "饱和度: {}", "Saturation: {}";
+    SaturationMono => "单色输出没有颜色可调", "Monochrome output has no color to adjust";
+    MirrorHorizontal => "左右对称", "left-right";
+    MirrorVertical => "上下对称", "top-bottom";
+    MirrorQuad => "四向对称", "quad";
+    StatusPan => "平移: 横向 {}% 纵向 {}%", "Pan: {}% across, {}% down";
+    PanNotNeeded => "画面已完整显示, 没有可平移的细节", "The whole picture already fits; there's nothing to pan";
+    PanImage => "图像模式不支持平移", "Panning isn't available in Image mode";
+    StatusSmoothing => "时域平滑", "Smoothing";
+    StatusGuides => "参考线", "Guides";
+    StatusShift => "位置偏移 {},{} (X 居中)", "Moved {},{} (X to center)";
+    StatusDeinterlace => "反交错", "Deinterlaced";
+    CompareMarked => "已标记 {} 用于对比, 在另一个文件上按 X 打开对比", "Marked {} for comparison, press X on another file to compare";
+    CompareUnmarked => "已取消对比标记", "Unmarked for comparison";
+    CompareTitle => " 文件对比 ", " Compare Files ";
+    CompareHint => "[Esc]: 关闭 | 黄色为不同项", "[Esc]: Close | Differences in yellow";
+    CompareName => "文件", "File";
+    CompareResolution => "分辨率", "Resolution";
+    CompareFps => "帧率", "Frame rate";
+    CompareDuration => "时长", "Duration";
+    CompareBitrate => "码率", "Bitrate";
+    CompareCodec => "编码", "Codec";
+    CompareSize => "大小", "Size";
+    StatusTonemap => "HDR→SDR", "HDR→SDR";
+    StatusAutoCrop => "去黑边 {}", "Bars cropped {}";
+    StatusAutoQuality => "自动画质", "Auto quality";
+    StatusAutoQualityLowered => "自动画质: -{}%", "Auto quality: -{}%";
+    StatusScale => "分辨率: {}%", "Resolution: {}%";
+    StatusBadFrames => "跳过坏帧: {}", "Bad frames skipped: {}";
+    StatusBlack => "● 黑场 {}", "● Black screen {}";
+    StatsMemory => "内存 {}/{} GB", "Mem  {}/{} GB";
+    StatsProcess => "本进程 {} MB", "App  {} MB";
+    StatsBuffers => "缓冲 帧 {} MB ×{} · 输出 {} MB", "Bufs frame {} MB ×{} · out {} MB";
+    BlackSkipped => "已跳过黑场 ({})", "Skipped black intro ({})";
+    CellsExceedTerminal => "输出尺寸 {}x{} 超出终端 {}x{}, 画面会换行或被截断", "Output size {}x{} is larger than the {}x{} terminal; the picture will wrap or be cut off";
+    Connecting => "正在连接 {} …", "Connecting to {}…";
+    Buffering => "缓冲中 {}/{}", "Buffering {}/{}";
+    ManualStepHint => "逐帧 第 {} 帧 · 空格/→ 下一帧 · q 退出", "Step: frame {} · Space/→ next · q quit";
+    Reconnecting => "连接中断, 重新连接中 ({}/{}), {} 秒后重试… (q 退出)", "Connection lost, reconnecting ({}/{}) in {}s… (q to quit)";
+    GridEnded => "已结束", "ended";
+    NowPlaying => "正在播放 ({}/{}): {}", "Now playing ({}/{}): {}";
+    PatternStatus => "测试图案: {} | {} | [空格] 下一个图案  [M] 切换模式  [Q] 退出", "Test pattern: {} | {} | [Space] next pattern  [M] mode  [Q] quit";
+    PatternBars => "SMPTE 彩条", "SMPTE color bars";
+    PatternSweep => "RGB 渐变", "RGB sweep";
+    PatternRamp => "灰阶", "Grayscale ramp";
+    PatternCircle => "圆形 (检查宽高比)", "Circle (aspect ratio check)";
+    SummaryTitle => "播放统计", "Playback summary";
+    SummaryDuration => "播放时长    {}", "Duration          {}";
+    SummaryFrames => "渲染帧数    {}", "Frames drawn      {}";
+    SummaryFps => "帧率        平均 {} / 最低 {} / 最高 {}", "FPS               avg {} / min {} / max {}";
+    SummaryDropped => "丢弃帧数    {} (帧率上限 {}, 追帧跳过 {}, 无法解码 {})", "Frames dropped    {} (FPS cap {}, catching up {}, undecodable {})";
+    SummaryBytes => "写入终端    {} MB", "Written to tty    {} MB";
+    SummaryHint => "按任意键返回", "Press any key to continue";
+    StallDecode => "解码停滞 {}s: ffmpeg 未及时输出画面 (源文件或网络过慢?)", "Decoding stalled {}s: ffmpeg sent no frame (slow source or network?)";
+    StallRender => "渲染停滞 {}s: 终端输出受阻 (终端过慢或被挂起?)", "Rendering stalled {}s: terminal output blocked (slow or suspended terminal?)";
+    SeekUnknownDuration => "未知时长, 无法按比例跳转", "Unknown duration, can't jump by percentage";
+    JumpedTo => "跳转到 {}", "Jumped to {}";
+    FitSet => "画面适配：{}", "Fit: {}";
+    Usage => crate::cli::USAGE_ZH, crate::cli::USAGE_EN;
+    DryRunAudioOnly => "无视频流, 将以频谱可视化播放", "No video stream; it would play as a spectrum visualizer";
+    DryRunMode => "渲染模式: {}", "Render mode: {}";
+    DryRunProtocol => "图像协议: {}", "Image protocol: {}";
+    DryRunSizes => "终端: {}x{}  源: {}x{}  解码尺寸: {}x{}", "Terminal: {}x{}  Source: {}x{}  Decode size: {}x{}";
+    DryRunCrop => "裁剪: {}", "Crop: {}";
+    DryRunDecimate => "抽帧: {} → {} fps (--max-decode-fps)", "Decimated: {} → {} fps (--max-decode-fps)";
+    DryRunTonemap => "HDR ({}): 色调映射为 SDR", "HDR ({}): tonemapped to SDR";
+    DryRunNoTonemap => "HDR ({}): 不做色调映射 (--no-tonemap)", "HDR ({}): not tonemapped (--no-tonemap)";
+    DryRunFilters => "滤镜: {}", "Filters: {}";
+    ExportDone => "已导出 {} 帧到 {}", "Exported {} frames to {}";
+    TextOutDone => "已将 {}x{} 字符画写入 {}", "Wrote {}x{} text art to {}";
+    ListName => "文件名", "Name";
+    ListResolution => "分辨率", "Resolution";
+    ListDuration => "时长", "Duration";
+    ListCodec => "编码", "Codec";
+    ListSize => "大小", "Size";
+    ListAudio => "音频", "Audio";
+    ListEmpty => "(当前目录下没有找到视频文件)", "(No video files found in the current directory)";
+    FrameCopiedPlain => "已将当前画面以纯文本复制到剪贴板", "Frame copied to the clipboard as plain text";
+    FrameCopiedAnsi => "已将当前画面连同 ANSI 颜色复制到剪贴板", "Frame copied to the clipboard with ANSI colors";
+    FrameCopyFailed => "复制画面失败: {}", "Couldn't copy the frame: {}";
+    CommandLineCopied => "当前设置对应的命令行, 已复制到剪贴板:", "The current settings as a command line, copied to the clipboard:";
+    CommandLineNotCopied => "当前设置对应的命令行 (无法访问剪贴板, 请手动复制):", "The current settings as a command line (the clipboard is unavailable, copy it by hand):";
+    CommandLineHint => "按任意键继续播放", "Press any key to resume playback";
+    SyncFrame => "帧号  {}", "Frame  {}";
+    SyncPts => "PTS   {} s", "PTS    {} s";
+    SyncClock => "时钟  {} s", "Clock  {} s";
+    SyncDrift => "偏差  {} ms", "Drift  {} ms";
+    LagWall => "实际  {} s", "Wall   {} s";
+    LagMedia => "媒体  {} s", "Media  {} s";
+    LagBehind => "落后  {} s", "Behind {} s";
+    FitContain => "自动", "automatic";
+    FitStretch => "拉伸", "stretch";
+    FitWidth => "填满宽度", "fill width";
+    FitHeight => "填满高度", "fill height";
+    ChapterMenuHint => "章节列表 | [↑/↓]: 选择 | [回车]: 跳转 | [C/Esc]: 返回", "Chapters | [↑/↓]: Select | [Enter]: Jump | [C/Esc]: Back";
+    SubtitleMenuHint => "字幕轨道 | [↑/↓]: 选择 | [回车]: 确认 | [U/Esc]: 返回", "Subtitle tracks | [↑/↓]: Select | [Enter]: Confirm | [U/Esc]: Back";
+    SubtitlesOff => "关闭字幕", "Subtitles off";
+    NoSubtitles => "此文件没有内嵌字幕", "This file has no embedded subtitles";
+    BitmapSubtitles => "图形字幕 (PGS/VOBSUB) 无法以文字叠加", "Bitmap subtitles (PGS/VOBSUB) can't be shown as text";
+    BitmapTrack => "图形字幕, 不支持", "bitmap, unsupported";
+    EmptySubtitles => "该字幕轨道没有文字内容", "This subtitle track has no text";
+    SubtitleFailed => "字幕提取失败: {}", "Failed to extract subtitles: {}";
+    SingleFrameHint => "单帧画面 | 按 q 退出", "Single frame | Press q to quit";
+    InspectCell => "单元格 ({}, {})", "Cell ({}, {})";
+    InspectTop => "上 ", "Top ";
+    InspectBottom => "下 ", "Bottom ";
+    InspectPixel => "{}像素 ({}, {})", "{}Pixel ({}, {})";
+    InspectColor => "{}RGB {}, {}, {}  亮度 {}", "{}RGB {}, {}, {}  Luma {}";
+    InspectHalfBlock => "字符 ▀ (前景上/背景下)", "Glyph ▀ (top as foreground, bottom as background)";
+    InspectGlyph => "字符 '{}'", "Glyph '{}'";
+    InspectPlainGlyph => "字符 '{}' (无颜色)", "Glyph '{}' (no color)";
+    InspectPairGlyph => "字符 '{}' (平均亮度 {}, 上像素着色)", "Glyph '{}' (average luma {}, top pixel's color)";
+    InspectBlock => "字符 空格 (背景色块)", "Glyph space (background color)";
+    InspectImage => "图像 (单元格中心像素)", "Image (pixel at the cell center)";
+    TuneHint => "画面调节 | [↑/↓]: 选择 | [←/→]: 调整 | [退格]: 重置 | [回车]: 应用并播放 | [Esc]: 取消", "Tune | [Up/Down]: Select | [Left/Right]: Adjust | [Backspace]: Reset | [Enter]: Apply and play | [Esc]: Cancel";
+    TuneBrightness => "亮度   {}", "Brightness {}";
+    TuneContrast => "对比度 {}", "Contrast   {}";
+    TuneGamma => "伽马   {}", "Gamma      {}";
+    TuneRamp => "字符   {}", "Ramp       {}";
+    TuneFailed => "无法取得预览画面: {}", "Couldn't grab a preview frame: {}";
+    InspectHint => "已暂停 - 检查模式 | [方向键]: 移动 | [,/.]: 逐帧 | [z/Z]: 复制画面 (纯文本/彩色) | [I/空格/Esc]: 继续 | [Q]: 退出", "Paused - inspect | [Arrows]: Move | [,/.]: Step | [z/Z]: Copy frame (plain/color) | [I/Space/Esc]: Resume | [Q]: Quit";
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn languages_parse_and_translations_line_up() {
+        assert_eq!(Lang::parse("en_US.UTF-8"), Some(Lang::En));
+        assert_eq!(Lang::parse("zh_CN"), Some(Lang::Zh));
+        assert_eq!(Lang::parse("C"), None);
+        assert_eq!(Lang::choose(Some(Lang::En), Some("zh")), Lang::En);
+        assert_eq!(Lang::choose(None, Some("en")), Lang::En);
+
+        assert_eq!(fill("{}x{} px", &[&4, &"2"]), "4x2 px");
+        // Every message takes the same arguments in both languages.
+        for msg in Msg::ALL {
+            let (zh, en) = (msg.text_in(Lang::Zh), msg.text_in(Lang::En));
+            assert_eq!(zh.matches("{}").count(), en.matches("{}").count(), "{:?}", msg);
+            assert!(!en.is_empty() && !zh.is_empty(), "{:?}", msg);
+        }
+    }
+}
@@ -0,0 +1,87 @@
+use std::fmt::{Display, Write};
+
+/// Minimal pretty-printed JSON object writer for the CLI's machine-readable output.
+#[derive(Default)]
+pub struct JsonObject {
+    fields: Vec<(String, String)>,
+}
+
+impl JsonObject {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn str(mut self, key: &str, value: &str) -> Self {
+        self.fields.push((key.to_string(), quote(value)));
+        self
+    }
+
+    pub fn num(mut self, key: &str, value: impl Display) -> Self {
+        self.fields.push((key.to_string(), value.to_string()));
+        self
+    }
+
+    /// A nested value that is already valid JSON, e.g. another object's `finish()`.
+    pub fn raw(mut self, key: &str, json: String) -> Self {
+        self.fields.push((key.to_string(), json));
+        self
+    }
+
+    pub fn opt_num(self, key: &str, value: Option<impl Display>) -> Self {
+        match value {
+            Some(v) => self.num(key, v),
+            None => self.raw(key, "null".to_string()),
+        }
+    }
+
+    pub fn finish(self) -> String {
+        if self.fields.is_empty() {
+            return "{}".to_string();
+        }
+        let mut out = String::from("{\n");
+        for (i, (key, value)) in self.fields.iter().enumerate() {
+            let value = value.replace('\n', "\n  ");
+            let comma = if i + 1 < self.fields.len() { "," } else { "" };
+            writeln!(out, "  {}: {}{}", quote(key), value, comma).unwrap();
+        }
+        out.push('}');
+        out
+    }
+}
+
+pub fn quote(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => write!(out, "\\u{:04x}", c as u32).unwrap(),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn object_with_nested_and_null_fields() {
+        let inner = JsonObject::new().num("channels", 2).finish();
+        let json = JsonObject::new()
+            .str("path", "C:\\videos\\\"a\".mp4")
+            .opt_num("bitrate", None::<u64>)
+            .raw("audio", inner)
+            .finish();
+        assert_eq!(
+            json,
+            "{\n  \"path\": \"C:\\\\videos\\\\\\\"a\\\".mp4\",\n  \"bitrate\": null,\n  \"audio\": {\n    \"channels\": 2\n  }\n}"
+        );
+    }
+}
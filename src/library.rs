@@ -0,0 +1,219 @@
+use crate::i18n::Msg;
+use crate::state::{load_lines, save_lines};
+use anyhow::Result;
+use std::{
+    cmp::Ordering,
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+const LIBRARY_FILE: &str = "library.txt";
+
+/// Probed metadata the library view sorts and filters by, kept with the modification time and
+/// size the file had when probed so it's only probed again once the file changes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    /// Seconds since the epoch.
+    pub mtime: u64,
+    pub size: u64,
+    pub width: u32,
+    pub height: u32,
+    /// Seconds, 0 when unknown.
+    pub duration: f64,
+    /// Frames per second, 0 when unknown.
+    pub fps: f64,
+    /// Bits per second, 0 when unknown.
+    pub bitrate: u64,
+    /// The video codec, or the audio codec for audio-only files; empty if probing failed.
+    pub codec: String,
+}
+
+impl Entry {
+    /// `(mtime, size)` of `path`, or `None` for paths without one on disk (sequence patterns, URLs).
+    pub fn stamp(path: &Path) -> Option<(u64, u64)> {
+        let meta = std::fs::metadata(path).ok()?;
+        let mtime = meta.modified().ok()?.duration_since(UNIX_EPOCH).ok()?.as_secs();
+        Some((mtime, meta.len()))
+    }
+
+    /// A probed file with no video stream, whose `codec` is then the audio one.
+    pub fn is_audio_only(&self) -> bool {
+        self.width == 0 && !self.codec.is_empty()
+    }
+
+    fn pixels(&self) -> u64 {
+        self.width as u64 * self.height as u64
+    }
+
+    /// Whether every whitespace-separated term of `filter` appears in the name, codec or
+    /// `WxH` resolution, ignoring case.
+    fn matches(entry: Option<&Self>, path: &Path, filter: &str) -> bool {
+        let mut haystack = path.file_name().unwrap_or_default().to_string_lossy().to_lowercase();
+        if let Some(e) = entry {
+            haystack.push_str(&format!("\n{}\n{}x{}", e.codec.to_lowercase(), e.width, e.height));
+        }
+        filter.split_whitespace().all(|term| haystack.contains(&term.to_lowercase()))
+    }
+}
+
+/// How the library view orders files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Sort {
+    #[default]
+    Name,
+    Duration,
+    Resolution,
+    Size,
+    Modified,
+}
+
+impl Sort {
+    const ALL: [Sort; 5] = [Sort::Name, Sort::Duration, Sort::Resolution, Sort::Size, Sort::Modified];
+
+    pub fn parse(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|sort| sort.key() == s)
+    }
+
+    /// The name kept in settings.
+    pub fn key(self) -> &'static str {
+        match self {
+            Sort::Name => "name",
+            Sort::Duration => "duration",
+            Sort::Resolution => "resolution",
+            Sort::Size => "size",
+            Sort::Modified => "modified",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Sort::Name => Msg::SortName,
+            Sort::Duration => Msg::SortDuration,
+            Sort::Resolution => Msg::SortResolution,
+            Sort::Size => Msg::SortSize,
+            Sort::Modified => Msg::SortModified,
+        }
+        .text()
+    }
+
+    pub fn next(self) -> Self {
+        let idx = Self::ALL.iter().position(|&s| s == self).unwrap_or(0);
+        Self::ALL[(idx + 1) % Self::ALL.len()]
+    }
+
+    /// Orders two entries, largest or newest first except by name; files that haven't been
+    /// probed (or couldn't be) go last.
+    fn compare(self, a: Option<&Entry>, b: Option<&Entry>) -> Ordering {
+        let (a, b) = match (a, b) {
+            _ if self == Sort::Name => return Ordering::Equal,
+            (Some(a), Some(b)) => (a, b),
+            (a, b) => return a.is_none().cmp(&b.is_none()),
+        };
+        match self {
+            Sort::Name => Ordering::Equal,
+            Sort::Duration => b.duration.total_cmp(&a.duration),
+            Sort::Resolution => b.pixels().cmp(&a.pixels()),
+            Sort::Size => b.size.cmp(&a.size),
+            Sort::Modified => b.mtime.cmp(&a.mtime),
+        }
+    }
+}
+
+/// Metadata of every file probed for the library view, keyed by absolute path and stored as
+/// `mtime\tsize\twidth\theight\tduration\tfps\tbitrate\tcodec\tpath` lines. Lines from before
+/// the frame rate and bitrate were kept don't parse, so those files are simply probed again.
+#[derive(Default)]
+pub struct Library {
+    entries: BTreeMap<PathBuf, Entry>,
+}
+
+impl Library {
+    pub fn load() -> Self {
+        Self { entries: load_lines(LIBRARY_FILE).iter().filter_map(|line| parse_line(line)).collect() }
+    }
+
+    pub fn get(&self, path: &Path) -> Option<&Entry> {
+        self.entries.get(path)
+    }
+
+    /// The cached entry for `path` if the file hasn't changed since it was probed.
+    pub fn fresh(&self, path: &Path) -> Option<&Entry> {
+        let (mtime, size) = Entry::stamp(path)?;
+        self.get(path).filter(|e| e.mtime == mtime && e.size == size)
+    }
+
+    pub fn insert(&mut self, path: PathBuf, entry: Entry) {
+        self.entries.insert(path, entry);
+    }
+
+    pub fn save(&self) -> Result<()> {
+        save_lines(
+            LIBRARY_FILE,
+            self.entries.iter().map(|(path, e)| {
+                format!(
+                    "{}\t{}\t{}\t{}\t{:.3}\t{:.3}\t{}\t{}\t{}",
+                    e.mtime, e.size, e.width, e.height, e.duration, e.fps, e.bitrate, e.codec, path.display()
+                )
+            }),
+        )
+    }
+
+    /// `files` whose cached metadata matches `filter`, in `sort` order; ties keep their order in
+    /// `files`, which is by name.
+    pub fn arrange(&self, files: &[PathBuf], sort: Sort, filter: &str) -> Vec<PathBuf> {
+        let mut shown: Vec<PathBuf> =
+            files.iter().filter(|p| Entry::matches(self.get(p), p, filter)).cloned().collect();
+        shown.sort_by(|a, b| sort.compare(self.get(a), self.get(b)));
+        shown
+    }
+}
+
+fn parse_line(line: &str) -> Option<(PathBuf, Entry)> {
+    let mut fields = line.splitn(9, '\t');
+    let mut next = || fields.next();
+    let entry = Entry {
+        mtime: next()?.parse().ok()?,
+        size: next()?.parse().ok()?,
+        width: next()?.parse().ok()?,
+        height: next()?.parse().ok()?,
+        duration: next()?.parse().ok()?,
+        fps: next()?.parse().ok()?,
+        bitrate: next()?.parse().ok()?,
+        codec: next()?.to_string(),
+    };
+    Some((PathBuf::from(next()?), entry))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(size: u64, width: u32, codec: &str) -> Entry {
+        Entry { mtime: 1, size, width, height: width / 2, duration: size as f64, fps: 25.0, bitrate: 0, codec: codec.to_string() }
+    }
+
+    #[test]
+    fn sorts_and_filters_cached_entries() {
+        let mut library = Library::default();
+        let (path, parsed) = parse_line("7\t100\t640\t320\t12.500\t29.970\t800000\th264\t/v/a b.mp4").unwrap();
+        assert_eq!(path, PathBuf::from("/v/a b.mp4"));
+        assert_eq!(parsed, Entry { mtime: 7, size: 100, width: 640, height: 320, duration: 12.5, fps: 29.97, bitrate: 800_000, codec: "h264".to_string() });
+        assert_eq!(parse_line("7\t100\t640\t320\t12.500\th264\t/v/old.mp4"), None);
+
+        library.insert(PathBuf::from("/v/a.mp4"), entry(10, 1920, "h264"));
+        library.insert(PathBuf::from("/v/b.mkv"), entry(30, 640, "hevc"));
+        let files: Vec<PathBuf> = ["/v/a.mp4", "/v/b.mkv", "/v/c.webm"].iter().map(PathBuf::from).collect();
+
+        assert_eq!(library.arrange(&files, Sort::Name, ""), files);
+        assert_eq!(library.arrange(&files, Sort::Size, ""), [&files[1], &files[0], &files[2]].map(PathBuf::clone));
+        assert_eq!(library.arrange(&files, Sort::Resolution, ""), files);
+        assert_eq!(library.arrange(&files, Sort::Name, "HEVC"), [files[1].clone()]);
+        assert_eq!(library.arrange(&files, Sort::Name, "1920x h264"), [files[0].clone()]);
+        assert_eq!(library.arrange(&files, Sort::Name, "webm"), [files[2].clone()]);
+        assert_eq!(Sort::parse(Sort::Modified.key()), Some(Sort::Modified));
+        assert_eq!(Sort::Modified.next(), Sort::Name);
+        assert!(entry(30, 0, "aac").is_audio_only());
+        assert!(!entry(30, 0, "").is_audio_only() && !entry(30, 640, "hevc").is_audio_only());
+    }
+}
@@ -0,0 +1,81 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+/// What each command resolved to, so the directories are only searched once per run.
+static RESOLVED: Mutex<BTreeMap<String, String>> = Mutex::new(BTreeMap::new());
+
+/// How to invoke `cmd` (e.g. `ffmpeg`): a copy in the current directory first, then `PATH`, then
+/// the usual install locations that often aren't on `PATH`. Falls back to the bare name, so a
+/// missing tool still fails with the usual "not found" error.
+pub fn command_path(cmd: &str) -> String {
+    if let Some(path) = RESOLVED.lock().ok().and_then(|r| r.get(cmd).cloned()) {
+        return path;
+    }
+    let path = resolve(cmd);
+    if let Ok(mut resolved) = RESOLVED.lock() {
+        resolved.insert(cmd.to_string(), path.clone());
+    }
+    path
+}
+
+fn resolve(cmd: &str) -> String {
+    let exe_name = if cfg!(target_os = "windows") { format!("{}.exe", cmd) } else { cmd.to_string() };
+
+    if Path::new(&exe_name).exists() {
+        if let Ok(path) = std::env::current_dir() {
+            return path.join(exe_name).to_string_lossy().to_string();
+        }
+    }
+    let on_path = std::env::var_os("PATH").is_some_and(|paths| find_in(std::env::split_paths(&paths), &exe_name).is_some());
+    if !on_path {
+        if let Some(path) = find_in(install_dirs(), &exe_name) {
+            crate::trace::note(format!("{} not on PATH, using {}", cmd, path.display()));
+            return path.to_string_lossy().to_string();
+        }
+    }
+    cmd.to_string()
+}
+
+/// Where ffmpeg builds are commonly unpacked or installed by package managers.
+fn install_dirs() -> Vec<PathBuf> {
+    if cfg!(target_os = "windows") {
+        let env_dir = |var: &str, sub: &str| std::env::var_os(var).map(|base| PathBuf::from(base).join(sub));
+        [PathBuf::from(r"C:\ffmpeg\bin"), PathBuf::from(r"C:\ProgramData\chocolatey\bin")]
+            .into_iter()
+            .chain(env_dir("ProgramFiles", r"ffmpeg\bin"))
+            .chain(env_dir("ProgramFiles(x86)", r"ffmpeg\bin"))
+            .chain(env_dir("LOCALAPPDATA", r"Microsoft\WinGet\Links"))
+            .chain(env_dir("USERPROFILE", r"scoop\shims"))
+            .collect()
+    } else {
+        ["/opt/homebrew/bin", "/usr/local/bin", "/opt/local/bin", "/usr/bin", "/snap/bin"].into_iter().map(PathBuf::from).collect()
+    }
+}
+
+/// `exe_name` in the first of `dirs` that has it.
+fn find_in(dirs: impl IntoIterator<Item = PathBuf>, exe_name: &str) -> Option<PathBuf> {
+    dirs.into_iter().map(|dir| dir.join(exe_name)).find(|path| path.is_file())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn first_directory_with_the_executable_wins() {
+        let root = std::env::temp_dir().join(format!("vodeo2ascii-locate-{}", std::process::id()));
+        let (empty, first, second) = (root.join("empty"), root.join("first"), root.join("second"));
+        for dir in [&empty, &first, &second] {
+            std::fs::create_dir_all(dir).unwrap();
+        }
+        std::fs::write(first.join("ffmpeg"), "").unwrap();
+        std::fs::write(second.join("ffmpeg"), "").unwrap();
+
+        assert_eq!(find_in([empty.clone(), first.clone(), second], "ffmpeg"), Some(first.join("ffmpeg")));
+        assert_eq!(find_in([empty, root.join("missing")], "ffmpeg"), None);
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -2,73 +2,324 @@ use anyhow::{Context, Result};
 use chrono::Local;
 use crossterm::{
     event::{Event, KeyCode, KeyEventKind},
-    execute,
-    terminal::{self, EnterAlternateScreen, LeaveAlternateScreen},
+    terminal,
 };
 use glob::glob;
+use regex::Regex;
 use ratatui::{
     prelude::*,
-    widgets::{Block, Borders, BorderType, Clear, LineGauge, List, ListItem, ListState, Paragraph, Wrap},
+    widgets::{Block, Borders, BorderType, Clear, List, ListItem, ListState, Paragraph, Wrap},
     Terminal,
 };
 use std::{
-    fmt::Write,
-    io::{self, Read, Write as IoWrite},
+    collections::{BTreeMap, HashMap, HashSet, VecDeque},
+    io::{self, BufRead, BufReader, IsTerminal, Read, Write},
+    ops::RangeInclusive,
     path::{Path, PathBuf},
-    process::{Command, Stdio},
+    process::{Child, ChildStderr, ChildStdout, Command, Stdio},
+    sync::mpsc,
+    thread,
     time::{Duration, Instant},
 };
-use sysinfo::{CpuRefreshKind, MemoryRefreshKind, RefreshKind, System};
+use sysinfo::{CpuRefreshKind, MemoryRefreshKind, ProcessRefreshKind, ProcessesToUpdate, RefreshKind, System};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+mod cli;
+mod clipboard;
+mod completion;
+mod effects;
+mod filters;
+mod gauge;
+mod graphics;
+mod grid;
+mod halfblock;
+mod histogram;
+mod i18n;
+mod json;
+mod library;
+mod locate;
+mod opener;
+mod pattern;
+mod poster;
+mod profile;
+mod quality;
+mod remote;
+mod render;
+mod rewind;
+mod sequence;
+mod shuffle;
+mod spectrum;
+mod state;
+mod stream;
+mod subtitles;
+mod summary;
+mod terminal_guard;
+mod trace;
+mod watch;
+
+use cli::CliCommand;
+use completion::PathCompleter;
+use effects::{AdaptiveRamp, AutoContrast, Level, Levels, Mirror, MotionHold, TemporalSmoothing};
+use filters::FilterChain;
+use gauge::{Gauge, GaugeStyle};
+use json::JsonObject;
+use pattern::Pattern;
+use poster::Poster;
+use profile::{FrameProfile, FrameTiming};
+use quality::{Adjust, AutoQuality};
+use rewind::RecentFrames;
+use graphics::ImageProtocol;
+use histogram::ColorStats;
+use i18n::{Lang, Msg};
+use library::Library;
+use render::{AsciiColor, Backdrop, ColorDepth, Crop, Fit, PixelFormat, RenderMode, RenderOptions, Scaler, Scaling};
+use stream::FrameStream;
+use summary::{LagClock, PlaybackStats};
+use terminal_guard::TerminalGuard;
+use watch::DirWatcher;
+use spectrum::Spectrum;
+use subtitles::{Burn, Cue, Track};
+use state::{Favorites, History, InputHistory, Notes, ResumePoints, Settings};
 
 #[derive(Debug, Clone, Copy, PartialEq)]
-enum RenderMode {
-    PixelArt,
-    AsciiArt,
+enum ListView {
+    All,
+    Favorites,
+    Recent,
+    Library,
 }
 
-impl std::fmt::Display for RenderMode {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            RenderMode::PixelArt => write!(f, "像素艺术 (半块字符 - 高保真)"),
-            RenderMode::AsciiArt => write!(f, "ASCII 艺术 (经典字符模式)"),
-        }
-    }
+/// How often the menu redraws and system stats refresh, in the menu and during playback.
+const TICK_RATE: Duration = Duration::from_millis(250);
+
+/// How long a toast from `App::notify` stays on screen.
+const TOAST_DURATION: Duration = Duration::from_secs(2);
+/// Relative gap between `r_frame_rate` and `avg_frame_rate` above which a video counts as variable
+/// frame rate; rounding in either rate stays well below it.
+const VFR_TOLERANCE: f32 = 0.01;
+/// Converts PQ or HLG video to SDR ahead of the scaling: linearize, tonemap the highlights with
+/// Hable's curve, then encode for bt709. Needs ffmpeg built with zimg.
+const TONEMAP_FILTER: &str = "zscale=transfer=linear:npl=100,format=gbrpf32le,zscale=primaries=bt709,tonemap=hable:desat=0,zscale=transfer=bt709:matrix=bt709:range=tv";
+
+/// ffmpeg progress lines shown while a URL connects.
+const CONNECT_STATUS_LINES: usize = 4;
+/// Frames the reader thread decodes ahead of rendering during playback, at least.
+const READ_AHEAD_FRAMES: usize = 4;
+/// How long to wait for ffmpeg to report the output stream; URLs get longer to connect and buffer.
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(10);
+const REMOTE_STARTUP_TIMEOUT: Duration = Duration::from_secs(60);
+/// Lines of ffmpeg's stderr kept to explain a decode that fails part way through.
+const STDERR_TAIL_LINES: usize = 5;
+/// A decode that fails within this many seconds of the expected end has played everything that matters.
+const DECODE_END_SLACK: f64 = 1.0;
+
+/// Saved positions closer than this to the start aren't worth a resume prompt.
+const MIN_RESUME_SECS: f64 = 5.0;
+
+/// `Settings` key for the list's path/name display toggle.
+const SHOW_PATHS_SETTING: &str = "show_paths";
+const SHUFFLE_SETTING: &str = "shuffle";
+/// `Settings` key for the menu's system stats pane, off meaning sysinfo isn't refreshed at all.
+const SHOW_STATS_SETTING: &str = "show_stats";
+/// `Settings` key for how the stats pane draws its gauges: `gradient`, `braille` or `line`.
+const GAUGE_STYLE_SETTING: &str = "gauge_style";
+/// `Settings` key equivalent to `--invert-ramp`, for users on light terminals.
+const INVERT_RAMP_SETTING: &str = "invert_ramp";
+/// `Settings` key that draws a border around the video during playback (`B` toggles it).
+const BORDER_SETTING: &str = "border";
+/// `Settings` key for the border color: a `#RRGGBB` hex color or a preset name like `green`.
+const BORDER_COLOR_SETTING: &str = "border_color";
+/// `Settings` key that starts playback with adaptive resolution on (`R` toggles it).
+const AUTO_QUALITY_SETTING: &str = "auto_quality";
+/// `Settings` key for the status-line indicator shown while frames stay black; on unless `false`.
+const BLACK_INDICATOR_SETTING: &str = "black_indicator";
+/// Saturation change per press of `[`/`]`, and how many steps either way from 1.0 (unchanged)
+/// are allowed: down to grayscale, up to 3.0.
+const SATURATION_STEP: f32 = 0.1;
+const SATURATION_STEPS: RangeInclusive<i32> = -10..=20;
+/// Brightest a pixel can be in a frame that counts as all black.
+const BLACK_SCREEN_LUMA: u8 = 24;
+/// Seconds of playback that have to stay black before the indicator shows.
+const BLACK_INDICATOR_DELAY: f64 = 1.0;
+/// `Settings` key for the interface language, `zh` or `en` (`--lang` overrides it).
+const LANG_SETTING: &str = "lang";
+/// `Settings` keys for the ffmpeg scaling algorithm, and the one used when enlarging the source
+/// (`--scaler` and `--upscale-scaler` override them).
+const SCALER_SETTING: &str = "scaler";
+const UPSCALE_SCALER_SETTING: &str = "upscale_scaler";
+/// `Settings` key for extra ffmpeg decoding arguments, split like `--ffmpeg-args`, which overrides it.
+const FFMPEG_ARGS_SETTING: &str = "ffmpeg_args";
+/// `Settings` key for wrapping frames in synchronized updates; detected from the terminal when unset
+/// (`--sync-output` overrides it).
+const SYNC_OUTPUT_SETTING: &str = "sync_output";
+/// `Settings` key for the pixel format ffmpeg decodes to in color modes: `rgb24`, or `rgb48` to be
+/// dithered down (`--pix-fmt` overrides it).
+const PIX_FMT_SETTING: &str = "pix_fmt";
+/// `Settings` key equivalent to `--rewind-frames`.
+const REWIND_FRAMES_SETTING: &str = "rewind_frames";
+/// `Settings` key equivalent to `--prebuffer`.
+const PREBUFFER_SETTING: &str = "prebuffer";
+/// `Settings` key equivalent to `--reconnect`.
+const RECONNECT_SETTING: &str = "reconnect";
+/// Times a network source that drops mid-stream is reconnected without `--reconnect`.
+const DEFAULT_RECONNECTS: u32 = 5;
+/// Seconds a reconnected source has to play before its failures count from zero again.
+const RECONNECT_STABLE_SECS: f64 = 30.0;
+/// `Settings` key equivalent to `--max-decode-fps`.
+const MAX_DECODE_FPS_SETTING: &str = "max_decode_fps";
+/// Fastest frame rate decoded by default: about what a terminal can show, well below the 120 or
+/// 240 fps of gaming captures.
+const DEFAULT_MAX_DECODE_FPS: u32 = 60;
+/// `Settings` key equivalent to `--auto-crop`.
+const AUTO_CROP_SETTING: &str = "auto_crop";
+/// Seconds of video `cropdetect` looks at, starting a tenth of the way in but no later than
+/// `CROP_SCAN_MAX_START`, past intros that are often letterboxed differently.
+const CROP_SCAN_SECS: f64 = 2.0;
+const CROP_SCAN_MAX_START: f64 = 60.0;
+/// `Settings` keys equivalent to `--reduce-motion` and `--motion-threshold`.
+const REDUCE_MOTION_SETTING: &str = "reduce_motion";
+const MOTION_THRESHOLD_SETTING: &str = "motion_threshold";
+/// Longest a frame is held by reduce motion, so slow movement and the status line still update.
+const MAX_MOTION_HOLD: f64 = 1.0;
+/// `Settings` key for the letterbox fill: a color, or `top:bottom` for a gradient (`--backdrop` overrides it).
+const BACKDROP_SETTING: &str = "backdrop";
+/// `Settings` key for the terminal's colors: truecolor, 256 or 16 (`--color-mode` overrides it).
+const COLOR_DEPTH_SETTING: &str = "color_depth";
+/// `Settings` key for the seconds each slideshow poster stays up.
+const SLIDESHOW_SECS_SETTING: &str = "slideshow_secs";
+const DEFAULT_SLIDESHOW_SECS: u64 = 3;
+/// `Settings` key that turns on the directory watcher without `--watch`; off by default since
+/// every poll re-reads the scan root.
+const WATCH_SETTING: &str = "watch_dir";
+/// `Settings` key for the library view's sort order.
+const LIBRARY_SORT_SETTING: &str = "library_sort";
+
+
+/// Files being probed in the background for the library view.
+struct LibraryJob {
+    total: usize,
+    done: usize,
+    results: mpsc::Receiver<(PathBuf, library::Entry)>,
 }
 
 struct App {
     files: Vec<PathBuf>,
     list_state: ListState,
+    view: ListView,
+    favorites: Favorites,
+    settings: Settings,
+    /// List entries show their path relative to the scan root instead of just the file name.
+    show_paths: bool,
+    /// `<`/`>` during playback step through the list in a shuffled order.
+    shuffle: bool,
+    /// Seeds the shuffle, for the same order every session; otherwise it's seeded from the clock.
+    shuffle_seed: Option<u64>,
+    /// The system stats pane is shown and refreshed every tick.
+    show_stats: bool,
+    /// How the stats pane's gauges are drawn, from the `gauge_style` setting.
+    gauge_style: GaugeStyle,
+    /// Focus mode: the file list takes the whole screen, with no header, footer or side panes.
+    focus: bool,
+    /// Re-scans the scan root for added or removed files, when enabled.
+    watcher: Option<DirWatcher>,
+    /// Decoded frame counts from `D`, kept for the session since counting reads the whole file.
+    frame_counts: HashMap<PathBuf, u64>,
+    /// The file being counted in the background and where its count arrives.
+    counting: Option<(PathBuf, mpsc::Receiver<Option<u64>>)>,
+    /// Poster frames shown in the details pane; `None` for files that have none (e.g. audio).
+    posters: HashMap<PathBuf, Option<Poster>>,
+    /// The file whose poster is being extracted in the background.
+    poster_job: Option<(PathBuf, mpsc::Receiver<Option<Poster>>)>,
+    /// Scene frames from `G`, shown as a filmstrip under the poster; kept for the session since
+    /// finding them decodes the whole file.
+    filmstrips: HashMap<PathBuf, Vec<Poster>>,
+    /// The file whose scenes are being found in the background.
+    filmstrip_job: Option<(PathBuf, mpsc::Receiver<Option<Vec<Poster>>>)>,
+    /// Cached metadata of local files for the library view, persisted between runs.
+    library: Library,
+    library_sort: library::Sort,
+    /// Terms the library view is filtered by, and whether keys are typing into it.
+    library_filter: String,
+    editing_filter: bool,
+    /// Files being probed in the background for the library.
+    library_job: Option<LibraryJob>,
+    /// The file marked with `x` to compare against the next one marked.
+    compare_mark: Option<PathBuf>,
+    /// Two files' metadata shown side by side in the compare popup.
+    comparison: Option<[(PathBuf, library::Entry); 2]>,
+    /// Files the details pane found to have audio but no video, labelled in the list whatever
+    /// their extension.
+    audio_only: HashSet<PathBuf>,
+    /// Probed URLs, kept for the session instead of re-probed every tick; `None` when it failed.
+    remote_infos: HashMap<PathBuf, Option<VideoInfo>>,
+    /// The selected local file's probe, kept until the selection moves instead of re-probed every
+    /// tick; `None` when it failed.
+    selected_info: Option<(PathBuf, Option<VideoInfo>)>,
+    /// The URL being probed in the background and where its progress and result arrive.
+    remote_probe: Option<(PathBuf, mpsc::Receiver<ProbeEvent>)>,
+    /// The latest progress lines ffprobe printed for `remote_probe`.
+    connect_status: Vec<String>,
+    /// Full-screen poster slideshow over the visible files, following the list selection.
+    slideshow: Option<Slideshow>,
+    history: History,
+    resume_points: ResumePoints,
+    resume_prompt: Option<(PathBuf, f64)>,
+    /// The file played last this session and the options it played with, for `R` to replay.
+    last_played: Option<(PathBuf, PlayOptions)>,
+    notes: Notes,
+    /// File whose note is being edited in the note popup, and the text so far.
+    note_editor: Option<(PathBuf, String)>,
+    /// Playback options from the command line and settings; `play_options` puts the menu's own
+    /// choices over them.
+    base: PlayOptions,
+    /// The command line, for each render mode's ramp and levels as the mode changes.
+    cli: cli::Cli,
     render_mode: RenderMode,
+    sequence_fps: u32,
+    ascii_color: AsciiColor,
+    /// Custom color from `--ascii-color`, offered alongside the presets.
+    custom_ascii_color: Option<AsciiColor>,
+    /// AsciiArt glyphs from `--ramp`, the render mode's settings or the tune screen.
+    ramp: &'static [char],
+    /// Brightness, contrast and gamma from the render mode's settings or the tune screen.
+    levels: Levels,
+    /// The tune screen's choices were applied; they stay over every mode's defaults for the rest
+    /// of the session.
+    tuned: bool,
     system: System,
     should_quit: bool,
     video_metadata: String,
     show_mode_popup: bool,
     mode_list_state: ListState,
     show_input_popup: bool,
+    input_history: InputHistory,
+    /// Position in `input_history` while browsing it with Up/Down; `None` while editing.
+    input_history_index: Option<usize>,
+    /// What was typed before browsing the history, restored by moving past the newest entry.
+    input_draft: String,
+    completer: PathCompleter,
     input_buffer: String,
+    last_error: Option<String>,
+    toast: Option<(String, Instant)>,
+    /// Paths that couldn't reach the clipboard, printed after the TUI closes.
+    print_on_exit: Vec<String>,
 }
 
 impl App {
-    fn new() -> Result<Self> {
-        let mut files = Vec::new();
-        let patterns = ["*.mp4", "*.mkv", "*.avi", "*.mov", "*.flv", "*.webm", "*.MP4"];
-        for pattern in patterns {
-            if let Ok(paths) = glob(pattern) {
-                for entry in paths {
-                    if let Ok(path) = entry {
-                        files.push(path);
-                    }
-                }
-            }
-        }
-        files.sort();
+    /// The menu over `base`, the options `play_options` worked out from `cli` and the settings.
+    fn new(cli: &cli::Cli, base: PlayOptions) -> Result<Self> {
+        let files = scan_files();
 
         let mut system = System::new_with_specifics(
             RefreshKind::nothing()
                 .with_cpu(CpuRefreshKind::everything())
                 .with_memory(MemoryRefreshKind::everything()),
         );
-        system.refresh_all();
+        let settings = Settings::load();
+        let show_stats = settings.flag(SHOW_STATS_SETTING, true);
+        if show_stats {
+            system.refresh_all();
+        }
 
         let mut list_state = ListState::default();
         if !files.is_empty() {
@@ -81,72 +332,708 @@ impl App {
         Ok(Self {
             files,
             list_state,
-            render_mode: RenderMode::PixelArt,
+            view: ListView::All,
+            favorites: Favorites::load(),
+            show_paths: settings.flag(SHOW_PATHS_SETTING, false),
+            shuffle: settings.flag(SHUFFLE_SETTING, false),
+            shuffle_seed: None,
+            show_stats,
+            gauge_style: settings.get(GAUGE_STYLE_SETTING).and_then(GaugeStyle::parse).unwrap_or_default(),
+            focus: false,
+            watcher: None,
+            frame_counts: HashMap::new(),
+            counting: None,
+            posters: HashMap::new(),
+            poster_job: None,
+            filmstrips: HashMap::new(),
+            filmstrip_job: None,
+            library: Library::load(),
+            library_sort: settings.get(LIBRARY_SORT_SETTING).and_then(library::Sort::parse).unwrap_or_default(),
+            library_filter: String::new(),
+            editing_filter: false,
+            library_job: None,
+            compare_mark: None,
+            audio_only: HashSet::new(),
+            comparison: None,
+            remote_infos: HashMap::new(),
+            selected_info: None,
+            remote_probe: None,
+            connect_status: Vec::new(),
+            slideshow: None,
+            settings,
+            history: History::load(),
+            resume_points: ResumePoints::load(),
+            resume_prompt: None,
+            last_played: None,
+            notes: Notes::load(),
+            note_editor: None,
+            render_mode: base.mode,
+            sequence_fps: base.sequence_fps,
+            ascii_color: base.ascii_color,
+            custom_ascii_color: cli.ascii_color,
+            ramp: base.ramp,
+            levels: base.levels,
+            tuned: false,
+            base,
+            cli: cli.clone(),
             system,
             should_quit: false,
-            video_metadata: String::from("请选择一个视频文件以查看详情。"),
+            video_metadata: Msg::SelectFilePrompt.text().to_string(),
             show_mode_popup: false,
             mode_list_state,
             show_input_popup: false,
+            input_history: InputHistory::load(),
+            input_history_index: None,
+            input_draft: String::new(),
+            completer: PathCompleter::default(),
             input_buffer: String::new(),
+            last_error: None,
+            toast: None,
+            print_on_exit: Vec::new(),
         })
     }
 
+    /// Shows a transient message above the footer.
+    fn notify(&mut self, msg: impl Into<String>) {
+        self.toast = Some((msg.into(), Instant::now() + TOAST_DURATION));
+    }
+
     fn on_tick(&mut self) {
-        self.system.refresh_cpu_all();
-        self.system.refresh_memory();
+        if self.toast.as_ref().is_some_and(|(_, expiry)| Instant::now() >= *expiry) {
+            self.toast = None;
+        }
+        if self.show_stats && !self.focus {
+            self.system.refresh_cpu_all();
+            self.system.refresh_memory();
+        }
+        if let Some((before, after)) = self.watcher.as_mut().and_then(|w| w.poll(scan_files)) {
+            self.apply_rescan(&before, after);
+        }
+        self.poll_frame_count();
+        self.poll_poster();
+        self.poll_filmstrip();
+        self.poll_remote_probe();
+        self.poll_library();
+        self.advance_slideshow();
         self.update_metadata();
     }
 
+    /// Replaces the scanned part of the list with `after`, keeping files added by hand and the
+    /// selected file where it still exists.
+    fn apply_rescan(&mut self, before: &[PathBuf], after: Vec<PathBuf>) {
+        let selected = self.selected_file();
+        let added = after.iter().filter(|p| !before.contains(p)).count();
+        let removed = before.iter().filter(|p| !after.contains(p)).count();
+        let by_hand: Vec<PathBuf> = self.files.iter().filter(|p| !before.contains(p) && !after.contains(p)).cloned().collect();
+        self.files = after;
+        self.files.extend(by_hand);
+        // A file deleted and written again under the same name is probed afresh.
+        self.selected_info = None;
+
+        let position = selected.and_then(|sel| self.visible_files().iter().position(|p| *p == sel));
+        if position.is_some() {
+            self.list_state.select(position);
+        }
+        self.clamp_selection();
+        self.notify(Msg::DirUpdated.fill(&[&added, &removed]));
+        if self.view == ListView::Library {
+            self.refresh_library(false);
+        }
+    }
+
+    /// Files shown by the current view, in display order.
+    fn visible_files(&self) -> Vec<PathBuf> {
+        match self.view {
+            ListView::All => self.files.clone(),
+            ListView::Favorites => self.files.iter().filter(|p| self.favorites.contains(p)).cloned().collect(),
+            ListView::Recent => self.history.entries().iter().map(|e| e.path.clone()).collect(),
+            ListView::Library => self.library.arrange(&self.files, self.library_sort, &self.library_filter),
+        }
+    }
+
+    fn selected_file(&self) -> Option<PathBuf> {
+        let idx = self.list_state.selected()?;
+        self.visible_files().into_iter().nth(idx)
+    }
+
+    /// Playback options as currently chosen in the menu.
+    fn play_options(&self) -> PlayOptions {
+        PlayOptions {
+            mode: self.render_mode,
+            sequence_fps: self.sequence_fps,
+            ascii_color: self.ascii_color,
+            ramp: self.ramp,
+            levels: self.levels,
+            browse: true,
+            ..self.base.clone()
+        }
+    }
+
+    fn toggle_favorite(&mut self) {
+        if let Some(path) = self.selected_file() {
+            match self.favorites.toggle(&path) {
+                Ok(true) => self.notify(Msg::FavoriteAdded.text()),
+                Ok(false) => self.notify(Msg::FavoriteRemoved.text()),
+                Err(e) => self.notify(Msg::FavoriteSaveFailed.fill(&[&format!("{:#}", e)])),
+            }
+            self.clamp_selection();
+        }
+    }
+
+    fn toggle_show_paths(&mut self) {
+        self.show_paths = !self.show_paths;
+        let saved = self.settings.set(SHOW_PATHS_SETTING, self.show_paths);
+        self.notify(match (saved, self.show_paths) {
+            (Err(e), _) => Msg::SettingsSaveFailed.fill(&[&format!("{:#}", e)]),
+            (Ok(()), true) => Msg::ListShowsPaths.text().to_string(),
+            (Ok(()), false) => Msg::ListShowsNames.text().to_string(),
+        });
+    }
+
+    fn toggle_shuffle(&mut self) {
+        self.shuffle = !self.shuffle;
+        let saved = self.settings.set(SHUFFLE_SETTING, self.shuffle);
+        self.notify(match (saved, self.shuffle) {
+            (Err(e), _) => Msg::SettingsSaveFailed.fill(&[&format!("{:#}", e)]),
+            (Ok(()), true) => Msg::ShuffleOn.text().to_string(),
+            (Ok(()), false) => Msg::ShuffleOff.text().to_string(),
+        });
+    }
+
+    fn toggle_stats(&mut self) {
+        self.show_stats = !self.show_stats;
+        if self.show_stats {
+            // The last reading is stale; CPU usage needs two refreshes to be meaningful again.
+            self.system.refresh_cpu_all();
+            self.system.refresh_memory();
+        }
+        let saved = self.settings.set(SHOW_STATS_SETTING, self.show_stats);
+        self.notify(match (saved, self.show_stats) {
+            (Err(e), _) => Msg::SettingsSaveFailed.fill(&[&format!("{:#}", e)]),
+            (Ok(()), true) => Msg::StatsOn.text().to_string(),
+            (Ok(()), false) => Msg::StatsOff.text().to_string(),
+        });
+    }
+
+    fn toggle_focus(&mut self) {
+        self.focus = !self.focus;
+        if self.focus {
+            self.notify(Msg::FocusOn.text());
+        }
+    }
+
+    fn edit_note(&mut self) {
+        if let Some(path) = self.selected_file() {
+            let note = self.notes.get(&path).unwrap_or_default().to_string();
+            self.note_editor = Some((path, note));
+        }
+    }
+
+    fn save_note(&mut self) {
+        let Some((path, note)) = self.note_editor.take() else { return };
+        match self.notes.set(&path, &note) {
+            Ok(()) if note.trim().is_empty() => self.notify(Msg::NoteDeleted.text()),
+            Ok(()) => self.notify(Msg::NoteSaved.text()),
+            Err(e) => self.notify(Msg::NoteSaveFailed.fill(&[&format!("{:#}", e)])),
+        }
+    }
+
+    /// Steps the AsciiArt color theme: original → green → amber → white → custom (if given) → original.
+    fn cycle_ascii_color(&mut self) {
+        let mut themes = vec![AsciiColor::Original, AsciiColor::GREEN, AsciiColor::AMBER, AsciiColor::WHITE];
+        if let Some(custom) = self.custom_ascii_color.filter(|c| !themes.contains(c)) {
+            themes.push(custom);
+        }
+        let idx = themes.iter().position(|&c| c == self.ascii_color).map_or(0, |i| (i + 1) % themes.len());
+        self.ascii_color = themes[idx];
+        self.notify(Msg::AsciiColorSet.fill(&[&self.ascii_color]));
+    }
+
+    fn copy_selected_path(&mut self) {
+        let Some(path) = self.selected_file() else { return };
+        let text = path.to_string_lossy().to_string();
+        match clipboard::copy(&text) {
+            Ok(()) => self.notify(Msg::PathCopied.text()),
+            Err(_) => {
+                self.notify(Msg::ClipboardUnavailable.text());
+                self.print_on_exit.push(text);
+            }
+        }
+    }
+
+    fn open_externally(&mut self) {
+        let Some(path) = self.selected_file() else { return };
+        if sequence::is_sequence(&path) {
+            self.notify(Msg::SequenceNotExternal.text());
+            return;
+        }
+        match opener::open(&path) {
+            Ok(()) => self.notify(Msg::OpenedExternally.fill(&[&sequence::display_name(&path)])),
+            Err(e) => self.notify(Msg::OpenFailed.fill(&[&format!("{:#}", e)])),
+        }
+    }
+
+    /// Starts counting the selected file's frames in the background for a frame-accurate duration.
+    fn count_frames(&mut self) {
+        let Some(path) = self.selected_file() else { return };
+        if sequence::is_sequence(&path) || self.frame_counts.contains_key(&path) {
+            return;
+        }
+        if self.counting.is_some() {
+            self.notify(Msg::CountingOther.text());
+            return;
+        }
+        let (tx, rx) = mpsc::channel();
+        let target = path.clone();
+        thread::spawn(move || {
+            let _ = tx.send(count_video_frames(&target).ok());
+        });
+        self.counting = Some((path, rx));
+        self.notify(Msg::CountingStarted.text());
+    }
+
+    fn poll_frame_count(&mut self) {
+        let Some((path, rx)) = &self.counting else { return };
+        let result = match rx.try_recv() {
+            Ok(result) => result,
+            Err(mpsc::TryRecvError::Empty) => return,
+            Err(mpsc::TryRecvError::Disconnected) => None,
+        };
+        let path = path.clone();
+        self.counting = None;
+        match result {
+            Some(count) => {
+                self.frame_counts.insert(path, count);
+                self.notify(Msg::CountDone.fill(&[&count]));
+            }
+            None => self.notify(Msg::CountFailed.text()),
+        }
+    }
+
+    /// Starts finding the selected file's scene changes in the background for its filmstrip.
+    fn find_scenes(&mut self) {
+        let Some(path) = self.selected_file() else { return };
+        if self.filmstrips.contains_key(&path) {
+            return;
+        }
+        if self.filmstrip_job.is_some() {
+            self.notify(Msg::ScenesOther.text());
+            return;
+        }
+        let (tx, rx) = mpsc::channel();
+        let target = path.clone();
+        thread::spawn(move || {
+            let _ = tx.send(extract_filmstrip(&target).ok());
+        });
+        self.filmstrip_job = Some((path, rx));
+        self.notify(Msg::ScenesStarted.text());
+    }
+
+    fn poll_filmstrip(&mut self) {
+        let Some((path, rx)) = &self.filmstrip_job else { return };
+        let result = match rx.try_recv() {
+            Ok(result) => result,
+            Err(mpsc::TryRecvError::Empty) => return,
+            Err(mpsc::TryRecvError::Disconnected) => None,
+        };
+        let path = path.clone();
+        self.filmstrip_job = None;
+        match result {
+            Some(strip) if !strip.is_empty() => {
+                self.notify(Msg::ScenesDone.fill(&[&strip.len()]));
+                self.filmstrips.insert(path, strip);
+            }
+            _ => self.notify(Msg::ScenesFailed.text()),
+        }
+    }
+
+    /// Collects the progress and result of the background probe of a URL.
+    fn poll_remote_probe(&mut self) {
+        let Some((path, rx)) = &self.remote_probe else { return };
+        loop {
+            match rx.try_recv() {
+                Ok(ProbeEvent::Status(line)) => {
+                    self.connect_status.push(line);
+                    let excess = self.connect_status.len().saturating_sub(CONNECT_STATUS_LINES);
+                    self.connect_status.drain(..excess);
+                }
+                Ok(ProbeEvent::Done(info)) => {
+                    self.remote_infos.insert(path.clone(), info);
+                    break;
+                }
+                Err(mpsc::TryRecvError::Disconnected) => {
+                    self.remote_infos.insert(path.clone(), None);
+                    break;
+                }
+                Err(mpsc::TryRecvError::Empty) => return,
+            }
+        }
+        self.remote_probe = None;
+        self.connect_status.clear();
+    }
+
+    /// Probes `path` in the background, as a URL can take a while to connect and buffer.
+    fn start_remote_probe(&mut self, path: &Path) {
+        if self.remote_probe.is_some() {
+            return;
+        }
+        let (tx, rx) = mpsc::channel();
+        let target = path.to_path_buf();
+        thread::spawn(move || {
+            let status = tx.clone();
+            let info = probe_video_reporting(&target, &mut |line| {
+                let _ = status.send(ProbeEvent::Status(line));
+            });
+            let _ = tx.send(ProbeEvent::Done(info.ok()));
+        });
+        self.remote_probe = Some((path.to_path_buf(), rx));
+    }
+
+    /// Collects a finished poster and starts on the selected file's if it isn't cached yet.
+    fn poll_poster(&mut self) {
+        if let Some((path, rx)) = &self.poster_job {
+            match rx.try_recv() {
+                Ok(poster) => {
+                    self.posters.insert(path.clone(), poster);
+                    self.poster_job = None;
+                }
+                Err(mpsc::TryRecvError::Disconnected) => {
+                    self.posters.insert(path.clone(), None);
+                    self.poster_job = None;
+                }
+                Err(mpsc::TryRecvError::Empty) => return,
+            }
+        }
+        // The selected file first; during a slideshow, then the ones coming up after it.
+        let files = self.visible_files();
+        let selected = self.list_state.selected().unwrap_or(0);
+        let lookahead = if self.slideshow.is_some() { files.len() } else { files.len().min(1) };
+        let Some(path) = (0..lookahead)
+            .map(|i| &files[(selected + i) % files.len()])
+            .find(|p| !self.posters.contains_key(*p))
+            .cloned()
+        else {
+            return;
+        };
+        let (tx, rx) = mpsc::channel();
+        let target = path.clone();
+        thread::spawn(move || {
+            let _ = tx.send(extract_poster(&target).ok());
+        });
+        self.poster_job = Some((path, rx));
+    }
+
+    fn start_slideshow(&mut self) {
+        if self.visible_files().is_empty() {
+            self.notify(Msg::NothingToPlay.text());
+            return;
+        }
+        let interval = slideshow_interval(self.settings.get(SLIDESHOW_SECS_SETTING));
+        self.slideshow = Some(Slideshow { interval, next_at: Instant::now() + interval });
+        self.clamp_selection();
+    }
+
+    /// Moves the slideshow on once its interval is up and the next poster is ready, so it never
+    /// lands on a blank slide.
+    fn advance_slideshow(&mut self) {
+        let Some(show) = &self.slideshow else { return };
+        if Instant::now() < show.next_at {
+            return;
+        }
+        let files = self.visible_files();
+        let next = self.list_state.selected().map_or(0, |i| (i + 1) % files.len().max(1));
+        if files.get(next).is_some_and(|p| self.posters.contains_key(p)) {
+            self.step_slideshow(true);
+        }
+    }
+
+    /// Shows the next (or previous) file and restarts the slide timer.
+    fn step_slideshow(&mut self, forward: bool) {
+        if forward { self.next_item() } else { self.previous_item() }
+        if let Some(show) = &mut self.slideshow {
+            show.next_at = Instant::now() + show.interval;
+        }
+    }
+
+    /// The duration line of the details pane: the container's figure, and the counted one beside
+    /// it once `D` has been pressed for this file.
+    fn duration_line(&self, path: &Path, info: &VideoInfo, duration_str: &str) -> String {
+        if let Some(&count) = self.frame_counts.get(path) {
+            match counted_duration(count, info.fps) {
+                Some(exact) => Msg::DurationCounted.fill(&[&duration_str, &format_precise(exact), &count]),
+                None => Msg::DurationFrames.fill(&[&duration_str, &count]),
+            }
+        } else if self.counting.as_ref().is_some_and(|(p, _)| p == path) {
+            Msg::DurationCounting.fill(&[&duration_str])
+        } else {
+            Msg::DurationCountHint.fill(&[&duration_str])
+        }
+    }
+
+    fn cycle_view(&mut self) {
+        self.view = match self.view {
+            ListView::All => ListView::Favorites,
+            ListView::Favorites => ListView::Recent,
+            ListView::Recent => ListView::Library,
+            ListView::Library => ListView::All,
+        };
+        self.notify(match self.view {
+            ListView::All => Msg::ViewAll,
+            ListView::Favorites => Msg::ViewFavorites,
+            ListView::Recent => Msg::ViewRecent,
+            ListView::Library => Msg::ViewLibrary,
+        }.text());
+        self.list_state.select(Some(0));
+        self.clamp_selection();
+        if self.view == ListView::Library {
+            self.refresh_library(false);
+        }
+    }
+
+    /// Switches to the library view, for the keys that only make sense there.
+    fn show_library(&mut self) {
+        if self.view != ListView::Library {
+            self.view = ListView::Library;
+            self.list_state.select(Some(0));
+            self.clamp_selection();
+            self.refresh_library(false);
+        }
+    }
+
+    fn cycle_library_sort(&mut self) {
+        if self.view == ListView::Library {
+            self.library_sort = self.library_sort.next();
+        }
+        self.show_library();
+        let selected = self.selected_file();
+        let saved = self.settings.set(LIBRARY_SORT_SETTING, self.library_sort.key());
+        // Keep the selection on the same file after it moves.
+        let position = selected.and_then(|sel| self.visible_files().iter().position(|p| *p == sel));
+        self.list_state.select(position.or(Some(0)));
+        self.clamp_selection();
+        self.notify(match saved {
+            Ok(()) => Msg::ListTitleLibrary.fill(&[&self.library_sort.label()]).trim().to_string(),
+            Err(e) => Msg::SettingsSaveFailed.fill(&[&format!("{:#}", e)]),
+        });
+    }
+
+    fn edit_library_filter(&mut self) {
+        self.show_library();
+        self.editing_filter = true;
+    }
+
+    /// Probes the scanned files the library has no up-to-date metadata for in the background, or
+    /// every one of them when `rebuild` is set.
+    fn refresh_library(&mut self, rebuild: bool) {
+        if self.library_job.is_some() {
+            if rebuild {
+                self.notify(Msg::LibraryBusy.text());
+            }
+            return;
+        }
+        let stale: Vec<PathBuf> = self
+            .files
+            .iter()
+            .filter(|p| library::Entry::stamp(p).is_some() && (rebuild || self.library.fresh(p).is_none()))
+            .cloned()
+            .collect();
+        if rebuild {
+            self.notify(Msg::LibraryRebuilding.fill(&[&stale.len()]));
+        }
+        if stale.is_empty() {
+            return;
+        }
+        let (tx, rx) = mpsc::channel();
+        let total = stale.len();
+        thread::spawn(move || {
+            for path in stale {
+                let Some(stamp) = library::Entry::stamp(&path) else { continue };
+                let entry = library_entry(&path, stamp);
+                if tx.send((path, entry)).is_err() {
+                    break;
+                }
+            }
+        });
+        self.library_job = Some(LibraryJob { total, done: 0, results: rx });
+    }
+
+    /// Marks the selected file for comparison; with another file already marked, opens the compare
+    /// popup on the two. Marking the marked file again unmarks it.
+    fn mark_for_compare(&mut self) {
+        let Some(path) = self.selected_file() else { return };
+        match self.compare_mark.take() {
+            Some(marked) if marked == path => self.notify(Msg::CompareUnmarked.text()),
+            Some(marked) => {
+                let first = self.compare_entry(&marked);
+                let second = self.compare_entry(&path);
+                self.comparison = Some([(marked, first), (path, second)]);
+            }
+            None => {
+                self.notify(Msg::CompareMarked.fill(&[&sequence::display_name(&path)]));
+                self.compare_mark = Some(path);
+            }
+        }
+    }
+
+    /// `path`'s library entry, probed and cached first unless the cached one is up to date.
+    fn compare_entry(&mut self, path: &Path) -> library::Entry {
+        if let Some(entry) = self.library.fresh(path) {
+            return entry.clone();
+        }
+        // Sequence patterns and URLs have nothing on disk to stamp, so they aren't cached.
+        let Some(stamp) = library::Entry::stamp(path) else { return library_entry(path, (0, 0)) };
+        let entry = library_entry(path, stamp);
+        self.library.insert(path.to_path_buf(), entry.clone());
+        if let Err(e) = self.library.save() {
+            self.notify(Msg::LibrarySaveFailed.fill(&[&format!("{:#}", e)]));
+        }
+        entry
+    }
+
+    /// Takes in the library entries probed since the last tick, saving the library once all are in.
+    fn poll_library(&mut self) {
+        if self.library_job.is_none() {
+            return;
+        }
+        let selected = self.selected_file();
+        let Some(job) = &mut self.library_job else { return };
+        loop {
+            match job.results.try_recv() {
+                Ok((path, entry)) => {
+                    job.done += 1;
+                    self.library.insert(path, entry);
+                }
+                Err(mpsc::TryRecvError::Empty) => break,
+                Err(mpsc::TryRecvError::Disconnected) => {
+                    let probed = job.done;
+                    self.library_job = None;
+                    match self.library.save() {
+                        Ok(()) => self.notify(Msg::LibraryUpdated.fill(&[&probed])),
+                        Err(e) => self.notify(Msg::LibrarySaveFailed.fill(&[&format!("{:#}", e)])),
+                    }
+                    break;
+                }
+            }
+        }
+        if self.view == ListView::Library {
+            // Entries arriving reorder the list; keep the selection on the same file.
+            let position = selected.and_then(|sel| self.visible_files().iter().position(|p| *p == sel));
+            if position.is_some() {
+                self.list_state.select(position);
+            }
+            self.clamp_selection();
+        }
+    }
+
+    /// Whether `path` is known to be audio only: by its extension, from the details pane or from
+    /// the library cache. Enter plays these with the spectrum visualizer.
+    fn is_audio(&self, path: &Path) -> bool {
+        has_extension(path, &AUDIO_EXTENSIONS)
+            || self.audio_only.contains(path)
+            || self.library.get(path).is_some_and(library::Entry::is_audio_only)
+    }
+
+    /// Keeps the selection inside the visible list after it shrinks or changes.
+    fn clamp_selection(&mut self) {
+        let selected = clamped_selection(self.list_state.selected(), self.visible_files().len());
+        self.list_state.select(selected);
+    }
+
     fn update_metadata(&mut self) {
-        if let Some(idx) = self.list_state.selected() {
-             if let Some(path) = self.files.get(idx) {
-                 match probe_video(path) {
+        // The list can change behind the selection (a rescan, the history after playback); without
+        // this the details would go stale on an index past the end.
+        self.clamp_selection();
+        if self.list_state.selected().is_some() {
+             if let Some(path) = self.selected_file() {
+                 let probed = match self.remote_infos.get(&path) {
+                     Some(info) => info.clone().context("probe failed"),
+                     None if remote::is_remote(&path) => {
+                         self.start_remote_probe(&path);
+                         self.video_metadata = Msg::ConnectingDetails.fill(&[&self.connect_status.join("\n")]);
+                         return;
+                     }
+                     None => match &self.selected_info {
+                         Some((probed, info)) if *probed == path => info.clone().context("probe failed"),
+                         _ => {
+                             let info = probe_video(&path).ok();
+                             self.selected_info = Some((path.clone(), info.clone()));
+                             info.context("probe failed")
+                         }
+                     },
+                 };
+                 match probed.map(|info| info.with_fps(self.sequence_fps)) {
                     Ok(info) => {
-                        let size_mb = std::fs::metadata(path).map(|m| m.len() as f64 / 1024.0 / 1024.0).unwrap_or(0.0);
-                        let duration_str = format!("{:02}:{:02}:{:02}", 
-                            (info.duration / 3600.0).floor(),
-                            ((info.duration % 3600.0) / 60.0).floor(),
-                            (info.duration % 60.0).floor()
-                        );
+                        let size_mb = std::fs::metadata(&path).map(|m| m.len() as f64 / 1024.0 / 1024.0).unwrap_or(0.0);
+                        let duration_str = info.known_duration().map_or_else(|| Msg::UnknownDuration.text().to_string(), |d| format!("{:02}:{:02}:{:02}",
+                            (d / 3600.0).floor(),
+                            ((d % 3600.0) / 60.0).floor(),
+                            (d % 60.0).floor()
+                        ));
                         let bitrate_str = if let Some(br) = info.bitrate {
                             format!("{:.2} Mbps", br as f64 / 1000.0 / 1000.0)
                         } else {
                             "N/A".to_string()
                         };
                         
-                        self.video_metadata = format!(
-                            "分辨率: {}x{}\n帧率: {:.2} FPS\n时长: {}\n大小: {:.2} MB\n码率: {}\n视频编码: {}\n音频编码: {}", 
-                            info.width, info.height, info.fps,
-                            duration_str,
-                            size_mb,
-                            bitrate_str,
-                            info.video_codec,
-                            info.audio_codec.as_deref().unwrap_or("无")
-                        );
+                        let tags = info.tag_lines();
+                        let video_duration = self.duration_line(&path, &info, &duration_str);
+                        if info.is_audio_only() {
+                            self.audio_only.insert(path.clone());
+                            self.video_metadata = Msg::AudioDetails.fill(&[
+                                &tags, &duration_str, &format!("{:.2}", size_mb), &bitrate_str,
+                                &info.audio_codec.as_deref().unwrap_or(Msg::NoAudio.text()),
+                                &info.audio_sample_rate.map(|r| r.to_string()).unwrap_or_else(|| "N/A".to_string()),
+                                &info.audio_channels.map(|c| c.to_string()).unwrap_or_else(|| "N/A".to_string()),
+                            ]);
+                            return;
+                        }
+                        if let Some(count) = info.frame_count {
+                            self.video_metadata = Msg::SequenceDetails.fill(&[
+                                &tags, &count, &info.width, &info.height, &self.sequence_fps, &duration_str, &info.video_codec,
+                            ]);
+                            return;
+                        }
+                        let fps = match info.vfr_average() {
+                            Some(avg) => Msg::VfrFps.fill(&[&format!("{:.2}", info.fps), &format!("{:.2}", avg)]),
+                            None => format!("{:.2}", info.fps),
+                        };
+                        let fps = match info.decode_cap(self.base.max_decode_fps) {
+                            Some(cap) => Msg::DecodeCappedFps.fill(&[&fps, &cap]),
+                            None => fps,
+                        };
+                        self.video_metadata = Msg::VideoDetails.fill(&[
+                            &tags, &info.width, &info.height, &fps,
+                            &info.scan_label(),
+                            &video_duration,
+                            &format!("{:.2}", size_mb),
+                            &bitrate_str,
+                            &info.hdr_transfer().map_or(info.video_codec.clone(), |t| Msg::HdrCodec.fill(&[&info.video_codec, &t])),
+                            &info.audio_codec.as_deref().unwrap_or(Msg::NoAudio.text()),
+                        ]);
                     },
                     Err(_) => {
-                        self.video_metadata = "无法解析视频元数据".to_string();
+                        self.video_metadata = Msg::MetadataFailed.text().to_string();
                     }
                  }
              }
         } else {
-            self.video_metadata = "未选择文件".to_string();
+            self.video_metadata = Msg::NoFileSelected.text().to_string();
         }
     }
 
     fn next_item(&mut self) {
         if self.show_mode_popup {
             let i = match self.mode_list_state.selected() {
-                Some(i) => if i >= 1 { 0 } else { i + 1 },
+                Some(i) => if i >= RenderMode::ALL.len() - 1 { 0 } else { i + 1 },
                 None => 0,
             };
             self.mode_list_state.select(Some(i));
         } else if !self.show_input_popup {
-            if self.files.is_empty() { return; }
+            let len = self.visible_files().len();
+            if len == 0 { return; }
             let i = match self.list_state.selected() {
                 Some(i) => {
-                    if i >= self.files.len() - 1 {
+                    if i >= len - 1 {
                         0
                     } else {
                         i + 1
@@ -161,16 +1048,17 @@ impl App {
     fn previous_item(&mut self) {
         if self.show_mode_popup {
             let i = match self.mode_list_state.selected() {
-                Some(i) => if i == 0 { 1 } else { i - 1 },
+                Some(i) => if i == 0 { RenderMode::ALL.len() - 1 } else { i - 1 },
                 None => 0,
             };
             self.mode_list_state.select(Some(i));
         } else if !self.show_input_popup {
-            if self.files.is_empty() { return; }
+            let len = self.visible_files().len();
+            if len == 0 { return; }
             let i = match self.list_state.selected() {
                 Some(i) => {
                     if i == 0 {
-                        self.files.len() - 1
+                        len - 1
                     } else {
                         i - 1
                     }
@@ -181,24 +1069,80 @@ impl App {
         }
     }
     
+    /// Takes the levels and ramp configured for the current render mode, unless the tune screen
+    /// has set them this session.
+    fn load_mode_defaults(&mut self) {
+        if self.tuned {
+            return;
+        }
+        self.levels = with_custom_levels(mode_levels(&self.settings, self.render_mode), &self.cli.levels);
+        self.ramp = effective_ramp(&self.cli, &self.settings, self.render_mode);
+    }
+
     fn select_mode(&mut self) {
         if let Some(idx) = self.mode_list_state.selected() {
-            self.render_mode = match idx {
-                0 => RenderMode::PixelArt,
-                1 => RenderMode::AsciiArt,
-                _ => RenderMode::PixelArt,
-            };
+            self.render_mode = RenderMode::ALL.get(idx).copied().unwrap_or(RenderMode::PixelArt);
+            self.load_mode_defaults();
+            self.notify(Msg::RenderModeSet.fill(&[&self.render_mode]));
         }
         self.show_mode_popup = false;
     }
     
+    /// Steps through previously entered paths: `older` moves back in time, otherwise forward.
+    fn browse_input_history(&mut self, older: bool) {
+        let len = self.input_history.entries().len();
+        let next = match (self.input_history_index, older) {
+            (None, true) if len > 0 => Some(0),
+            (None, _) => return,
+            (Some(i), true) => Some((i + 1).min(len - 1)),
+            (Some(0), false) => None,
+            (Some(i), false) => Some(i - 1),
+        };
+        if self.input_history_index.is_none() {
+            self.input_draft = self.input_buffer.clone();
+        }
+        self.input_history_index = next;
+        self.input_buffer = match next {
+            Some(i) => self.input_history.entries()[i].clone(),
+            None => std::mem::take(&mut self.input_draft),
+        };
+    }
+
+    fn complete_input(&mut self) {
+        match self.completer.complete(&self.input_buffer) {
+            Some(completed) => {
+                self.input_buffer = completed;
+                self.input_history_index = None;
+            }
+            None => self.notify(Msg::NoMatchingPath.text()),
+        }
+    }
+
     fn submit_input(&mut self) {
         let path_str = self.input_buffer.trim().trim_matches('"').trim_matches('\'').to_string();
+        self.input_history_index = None;
         if !path_str.is_empty() {
-             let path = PathBuf::from(&path_str);
-             if path.exists() {
-                 self.files.push(path);
+             let path = state::absolute(Path::new(&path_str));
+             // A directory or glob of numbered images is added as image sequences.
+             let added = if path.is_dir() {
+                 scan_sequences(&path.to_string_lossy())
+             } else if path_str.contains('*') {
+                 let images: Vec<PathBuf> = glob(&path_str).map(|p| p.flatten().collect()).unwrap_or_default();
+                 sequence::detect(&images).iter().map(|p| state::absolute(p)).collect()
+             } else if path.exists() || remote::is_remote(&path) {
+                 vec![path]
+             } else {
+                 Vec::new()
+             };
+             if added.is_empty() {
+                 self.notify(Msg::NoPlayableFound.text());
+             } else {
+                 self.notify(Msg::FilesAdded.fill(&[&added.len()]));
+                 let _ = self.input_history.record(&path_str);
+                 self.files.extend(added);
+                 self.view = ListView::All;
                  self.list_state.select(Some(self.files.len() - 1));
+                 self.clamp_selection();
              }
         }
         self.input_buffer.clear();
@@ -207,629 +1151,4255 @@ impl App {
 }
 
 fn main() -> Result<()> {
-    // Setup terminal
-    terminal::enable_raw_mode()?;
-    let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen)?;
-    let backend = CrosstermBackend::new(stdout);
-    let mut terminal = Terminal::new(backend)?;
-
-    // Create App
-    let mut app = App::new()?;
-
-    // Main Loop
-    let tick_rate = Duration::from_millis(250);
-    let mut last_tick = Instant::now();
-
-    loop {
-        terminal.draw(|f| ui(f, &mut app))?;
-
-        let timeout = tick_rate
-            .checked_sub(last_tick.elapsed())
-            .unwrap_or_else(|| Duration::from_secs(0));
+    let settings = Settings::load();
+    // A bad argument is reported with the usage, before `--lang` is known: the setting or the
+    // locale picks its language.
+    i18n::set(Lang::choose(None, settings.get(LANG_SETTING)));
+    let cli = cli::parse(std::env::args().skip(1))?;
+    i18n::set(Lang::choose(cli.lang, settings.get(LANG_SETTING)));
+    if cli.verbose {
+        trace::enable();
+    }
+    // A file, since the TUI owns the terminal; `RUST_LOG` is what users tend to try first.
+    if let Some(path) = cli.log.clone().or_else(|| std::env::var_os("RUST_LOG").map(|_| trace::default_log_path())) {
+        trace::log_to(&path).with_context(|| format!("Failed to create log file {}", path.display()))?;
+        trace::note(format!("args: {}", std::env::args().skip(1).collect::<Vec<_>>().join(" ")));
+        trace::note(format!("ffmpeg: {}, ffprobe: {}", get_command_path("ffmpeg"), get_command_path("ffprobe")));
+    }
+    let result = run(&cli);
+    if let Err(e) = &result {
+        trace::note(format!("error: {:#}", e));
+    }
+    result
+}
 
-        if crossterm::event::poll(timeout)? {
-            if let Event::Key(key) = crossterm::event::read()? {
-                if key.kind == KeyEventKind::Press {
-                    if app.show_input_popup {
-                        match key.code {
-                            KeyCode::Enter => app.submit_input(),
-                            KeyCode::Esc => {
-                                app.show_input_popup = false;
-                                app.input_buffer.clear();
-                            },
-                            KeyCode::Backspace => {
-                                app.input_buffer.pop();
-                            },
-                            KeyCode::Char(c) => {
-                                app.input_buffer.push(c);
-                            },
-                            _ => {}
-                        }
-                    } else {
-                        match key.code {
-                            KeyCode::Char('q') | KeyCode::Esc => {
-                                if app.show_mode_popup {
-                                    app.show_mode_popup = false;
-                                } else {
-                                    app.should_quit = true;
-                                }
-                            },
-                            KeyCode::Char('j') | KeyCode::Down => app.next_item(),
-                            KeyCode::Char('k') | KeyCode::Up => app.previous_item(),
-                            KeyCode::Char('m') | KeyCode::Char('M') | KeyCode::Char('s') | KeyCode::Char('S') | KeyCode::Tab | KeyCode::BackTab => {
-                                 app.show_mode_popup = !app.show_mode_popup;
-                                 let idx = match app.render_mode {
-                                     RenderMode::PixelArt => 0,
-                                     RenderMode::AsciiArt => 1,
-                                 };
-                                 app.mode_list_state.select(Some(idx));
-                            },
-                            KeyCode::Char('o') | KeyCode::Char('O') => {
-                                app.show_input_popup = true;
-                            },
-                            KeyCode::Enter => {
-                                if app.show_mode_popup {
-                                    app.select_mode();
-                                } else {
-                                    if let Some(idx) = app.list_state.selected() {
-                                        if let Some(path) = app.files.get(idx).cloned() {
-                                            terminal::disable_raw_mode()?;
-                                            execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
-                                            let _ = play_video(&path, app.render_mode);
-                                            terminal::enable_raw_mode()?;
-                                            execute!(terminal.backend_mut(), EnterAlternateScreen)?;
-                                            terminal.clear()?;
-                                        }
-                                    }
-                                }
-                            }
-                            _ => {}
-                        }
-                    }
-                }
-            }
+fn run(cli: &cli::Cli) -> Result<()> {
+    match &cli.command {
+        CliCommand::Tui => {}
+        CliCommand::DryRun(path) => return print_dry_run(path, cli),
+        CliCommand::Grid(paths) => return play_grid(paths, cli),
+        CliCommand::TestPattern(pattern) => return play_pattern(*pattern, cli),
+        CliCommand::Play(path) => {
+            return match (&cli.export, &cli.text_out) {
+                (Some(output), _) => export_frames(path, &play_options(cli, None), output, cli.export_stats),
+                (None, Some(output)) => export_text(path, cli, output),
+                (None, None) => play_file(path, cli),
+            };
         }
-
-        if last_tick.elapsed() >= tick_rate {
-            app.on_tick();
-            last_tick = Instant::now();
+        CliCommand::Help => {
+            println!("{}", Msg::Usage.text());
+            return Ok(());
         }
-
-        if app.should_quit {
-            break;
+        CliCommand::List => {
+            enter_scan_dir(cli)?;
+            print_file_table();
+            return Ok(());
+        }
+        CliCommand::Info(path) => {
+            let info = probe_video(path).with_context(|| format!("Failed to probe {}", path.display()))?;
+            println!("{}", info.to_json(&state::absolute(path)));
+            return Ok(());
         }
     }
 
-    // Restore terminal
-    terminal::disable_raw_mode()?;
-    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
-    
-    Ok(())
-}
+    // Read before `--dir` changes the directory relative paths are resolved against.
+    let piped = cli.stdin.then(read_stdin_paths).transpose()?;
+    enter_scan_dir(cli)?;
+    let stream = start_stream(cli)?;
+    let profile = start_profile(cli)?;
 
-// Custom widget for Gradient Gauge
-struct GradientGauge {
-    ratio: f64,
-    start_color: (u8, u8, u8),
-    end_color: (u8, u8, u8),
-    label: Option<String>,
+    // Logging to stderr would scribble over the menu, so it's printed once the TUI is gone.
+    trace::defer();
+    let result = run_tui(cli, stream, profile, piped);
+    trace::flush();
+    result
 }
 
-impl GradientGauge {
-    fn new(ratio: f64, start: (u8, u8, u8), end: (u8, u8, u8)) -> Self {
-        Self { ratio, start_color: start, end_color: end, label: None }
-    }
-    
-    fn label(mut self, label: String) -> Self {
-        self.label = Some(label);
-        self
+/// `--stdin`: the playable paths piped in and how many lines were skipped, or `None` when stdin
+/// is a terminal, where waiting for a list nobody is going to type would just hang.
+fn read_stdin_paths() -> Result<Option<(Vec<PathBuf>, usize)>> {
+    let mut stdin = io::stdin();
+    if stdin.is_terminal() {
+        return Ok(None);
     }
+    let mut input = Vec::new();
+    stdin.read_to_end(&mut input).context("Failed to read paths from stdin")?;
+    Ok(Some(playable_paths(&String::from_utf8_lossy(&input))))
 }
 
-impl Widget for GradientGauge {
-    fn render(self, area: Rect, buf: &mut Buffer) {
-        if area.width < 1 || area.height < 1 { return; }
-        
-        let width = area.width as usize;
-        let filled_width = (self.ratio * width as f64).round() as usize;
-        
-        for i in 0..width {
-            if i < filled_width {
-                // Interpolate color
-                let t = i as f32 / width.max(1) as f32;
-                let r = (self.start_color.0 as f32 * (1.0 - t) + self.end_color.0 as f32 * t) as u8;
-                let g = (self.start_color.1 as f32 * (1.0 - t) + self.end_color.1 as f32 * t) as u8;
-                let b = (self.start_color.2 as f32 * (1.0 - t) + self.end_color.2 as f32 * t) as u8;
-                
-                buf.get_mut(area.x + i as u16, area.y)
-                    .set_char('█') // Full block
-                    .set_fg(Color::Rgb(r, g, b));
-            } else {
-                buf.get_mut(area.x + i as u16, area.y)
-                    .set_char('░') // Light shade for empty
-                    .set_fg(Color::DarkGray);
-            }
-        }
-        
-        if let Some(label) = self.label {
-            let label_len = label.chars().count() as u16;
-             // Center label if possible, or left align
-            let x = area.x; // Just draw at start for simplicity or center
-            // Simple overlay would require calculating center and rendering spans again.
-            // For now, let's keep it simple: Just draw the bar. Label can be separate.
+/// The lines of `input` naming an existing video or audio file, or a URL, as absolute paths in
+/// order without duplicates, and the count of non-empty lines that didn't.
+fn playable_paths(input: &str) -> (Vec<PathBuf>, usize) {
+    let mut paths: Vec<PathBuf> = Vec::new();
+    let mut skipped = 0;
+    for line in input.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        let path = state::absolute(Path::new(line));
+        let playable = remote::is_remote(&path)
+            || (path.is_file() && (has_extension(&path, &VIDEO_EXTENSIONS) || has_extension(&path, &AUDIO_EXTENSIONS)));
+        if !playable {
+            skipped += 1;
+        } else if !paths.contains(&path) {
+            paths.push(path);
         }
     }
+    (paths, skipped)
 }
 
-// Helper to generate a gradient of colors for a span of text
-fn get_gradient_text(text: &str, start_color: (u8, u8, u8), end_color: (u8, u8, u8)) -> Line<'static> {
-    let mut spans = Vec::new();
-    let len = text.chars().count();
-    
-    for (i, c) in text.chars().enumerate() {
-        let t = i as f32 / len.max(1) as f32;
-        let r = (start_color.0 as f32 * (1.0 - t) + end_color.0 as f32 * t) as u8;
-        let g = (start_color.1 as f32 * (1.0 - t) + end_color.1 as f32 * t) as u8;
-        let b = (start_color.2 as f32 * (1.0 - t) + end_color.2 as f32 * t) as u8;
-        
-        spans.push(Span::styled(
-            c.to_string(),
-            Style::default().fg(Color::Rgb(r, g, b)),
-        ));
+/// Name shown for `path` in the file list: just the file name, or with `show_paths` the path
+/// relative to the scan root (absolute for files added from elsewhere).
+fn list_label(path: &Path, show_paths: bool) -> String {
+    let name = sequence::display_name(path);
+    if !show_paths {
+        return name;
+    }
+    let Some(dir) = state::absolute(path).parent().map(Path::to_path_buf) else { return name };
+    let root = std::env::current_dir().unwrap_or_default();
+    match dir.strip_prefix(&root) {
+        Ok(relative) => relative.join(name).display().to_string(),
+        Err(_) => dir.join(name).display().to_string(),
     }
-    Line::from(spans)
 }
 
-fn ui(f: &mut Frame, app: &mut App) {
-    let chunks = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints([
-            Constraint::Length(3), // Header
-            Constraint::Min(0),    // Content
-            Constraint::Length(3), // Footer
-        ])
-        .split(f.area());
-
-    // 1. Header with Gradient
-    let header_text = get_gradient_text(" 视频转字符画播放器 Vodeo2ASCII v0.1.0 ", (0, 255, 255), (255, 0, 255));
-    let time_str = Local::now().format("%H:%M:%S").to_string();
-    let header_content = Line::from(vec![
-        header_text.spans.into_iter().collect::<Vec<_>>(), 
-        vec![Span::raw(format!(" | {}", time_str)).style(Style::default().fg(Color::DarkGray))]
-    ].concat());
-    
-    let header = Paragraph::new(header_content)
-        .block(Block::default().borders(Borders::ALL).border_type(BorderType::Rounded).border_style(Style::default().fg(Color::Cyan)));
-    f.render_widget(header, chunks[0]);
+/// The cached metadata shown after a name in the library view: duration, resolution, codec and size.
+fn library_columns(entry: &library::Entry) -> String {
+    let mut columns = Vec::new();
+    if entry.duration > 0.0 {
+        columns.push(format_clock(entry.duration));
+    }
+    if entry.width > 0 {
+        columns.push(format!("{}x{}", entry.width, entry.height));
+    }
+    columns.push(entry.codec.clone());
+    columns.push(format!("{:.1} MB", entry.size as f64 / 1024.0 / 1024.0));
+    columns.join(" · ")
+}
 
-    // 2. Main Content
-    let main_chunks = Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints([
-            Constraint::Percentage(40), // File List
-            Constraint::Percentage(60), // Details & Stats
-        ])
-        .split(chunks[1]);
+/// `--dir`: makes the given directory the one the scanner and relative paths work from.
+fn enter_scan_dir(cli: &cli::Cli) -> Result<()> {
+    if let Some(dir) = &cli.dir {
+        std::env::set_current_dir(dir).with_context(|| format!("Failed to open directory {}", dir.display()))?;
+    }
+    Ok(())
+}
 
-    // Left: File List
-    let files: Vec<ListItem> = app
-        .files
-        .iter()
+/// `--fifo`: set up before the TUI takes over the screen, so errors are readable.
+fn start_stream(cli: &cli::Cli) -> Result<Option<FrameStream>> {
+    cli.fifo
+        .as_ref()
         .map(|path| {
-            let name = path.file_name().unwrap_or_default().to_string_lossy();
-            let icon = match path.extension().and_then(|e| e.to_str()) {
-                Some("mp4") | Some("MP4") => "🎥 ",
-                Some("mkv") => "🎞️ ",
-                Some("avi") => "📼 ",
-                _ => "📄 ",
-            };
-            // Style file items
-             ListItem::new(Line::from(vec![
-                 Span::styled(icon, Style::default().fg(Color::Blue)), 
-                 Span::raw(name)
-             ]))
+            FrameStream::start(path, cli.fps_cap.unwrap_or(stream::DEFAULT_FPS))
+                .with_context(|| format!("Failed to set up FIFO {}", path.display()))
         })
-        .collect();
+        .transpose()
+}
 
-    // highlight selection with gradient effect (simulated by bold + bright color)
-    let files_list = List::new(files)
-        .block(Block::default()
-            .borders(Borders::ALL)
-            .border_type(BorderType::Rounded)
-            .title(" 视频文件列表 ")
-            .border_style(Style::default().fg(Color::Blue))) // Blue border for active look
-        .highlight_style(Style::default().bg(Color::Rgb(30, 30, 60)).add_modifier(Modifier::BOLD))
-        .highlight_symbol(" ➤ ");
-        
-    f.render_stateful_widget(files_list, main_chunks[0], &mut app.list_state);
+/// `--profile-csv`: created up front, like the FIFO, so errors show before the TUI starts.
+fn start_profile(cli: &cli::Cli) -> Result<Option<FrameProfile>> {
+    cli.profile_csv.as_deref().map(FrameProfile::create).transpose()
+}
 
-    // Right: Details + Stats
-    let right_chunks = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints([
-            Constraint::Percentage(50), // Details
-            Constraint::Percentage(50), // Stats
-        ])
-        .split(main_chunks[1]);
+/// Playback settings straight from the command line, for runs without the menu.
+fn play_options(cli: &cli::Cli, stream: Option<FrameStream>) -> PlayOptions {
+    let settings = Settings::load();
+    let (border, border_color) = border_options(&settings);
+    let mode = cli.mode.unwrap_or(RenderMode::PixelArt);
+    PlayOptions {
+        mode,
+        sequence_fps: sequence::DEFAULT_FPS,
+        threads: cli.threads,
+        fps_cap: cli.fps_cap,
+        rewind_frames: rewind_frames_option(cli, &settings),
+        prebuffer: prebuffer_option(cli, &settings),
+        reconnects: reconnect_option(cli, &settings),
+        max_decode_fps: max_decode_fps_option(cli, &settings),
+        stream,
+        profile: None,
+        portrait_auto: !cli.no_portrait_auto,
+        cfr: !cli.no_cfr,
+        catch_up: !cli.no_catch_up,
+        deinterlace: !cli.no_deinterlace,
+        tonemap: !cli.no_tonemap,
+        skip_black: cli.skip_black,
+        ascii_color: cli.ascii_color.unwrap_or(AsciiColor::Original),
+        ramp: effective_ramp(cli, &settings, mode),
+        levels: with_custom_levels(mode_levels(&settings, mode), &cli.levels),
+        fit: cli.fit,
+        cells: (cli.cols, cli.rows),
+        smooth: cli.smooth,
+        border: cli.border.unwrap_or(border),
+        border_color,
+        auto_quality: cli.auto_quality.unwrap_or_else(|| settings.flag(AUTO_QUALITY_SETTING, false)),
+        black_indicator: settings.flag(BLACK_INDICATOR_SETTING, true),
+        scaling: scaling_options(cli, &settings),
+        crop: cli.crop,
+        burn_subs: cli.burn_subs.clone(),
+        auto_crop: cli.auto_crop || settings.flag(AUTO_CROP_SETTING, false),
+        backdrop: backdrop_option(cli, &settings),
+        depth: color_depth_option(cli, &settings),
+        ffmpeg_args: ffmpeg_args_option(cli, &settings),
+        sync_output: sync_output_option(cli, &settings),
+        deep_color: deep_color_option(cli, &settings),
+        reduce_motion: reduce_motion_option(cli, &settings),
+        exit_on_finish: cli.exit_on_finish,
+        debug_sync: cli.debug_sync,
+        manual_step: cli.manual_step,
+        show_lag: cli.show_lag,
+        adaptive_ramp: cli.adaptive_ramp,
+        hflip: cli.hflip,
+        vflip: cli.vflip,
+        mirror: cli.mirror,
+        saturation_steps: cli.saturation.map_or(0, saturation_steps),
+        auto_contrast: cli.auto_contrast,
+        browse: false,
+    }
+}
 
-    // Video Details (Dimmed logic if not active, but here we keep it clean)
-    let details_text = Text::from(app.video_metadata.as_str());
-    let details = Paragraph::new(details_text)
-        .block(Block::default()
-            .borders(Borders::ALL)
-            .border_type(BorderType::Rounded)
-            .title(" 视频详情 ")
-            .border_style(Style::default().fg(Color::Magenta))) // Different color
-        .style(Style::default().fg(Color::White)); // Bright text
-    f.render_widget(details, right_chunks[0]);
+/// Scaling algorithms from `--scaler` and `--upscale-scaler`, or else the settings.
+fn scaling_options(cli: &cli::Cli, settings: &Settings) -> Scaling {
+    let setting = |key| settings.get(key).and_then(Scaler::parse);
+    Scaling {
+        scaler: cli.scaler.or_else(|| setting(SCALER_SETTING)).unwrap_or_default(),
+        upscale: cli.upscale_scaler.or_else(|| setting(UPSCALE_SCALER_SETTING)),
+        source: (0, 0),
+        fill: false,
+    }
+}
 
-    // System Stats (Modern Gauges)
-    let stats_chunks = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints([
-            Constraint::Length(1), // Label CPU
-            Constraint::Length(1), // Gauge CPU
-            Constraint::Length(1), // Spacer
-            Constraint::Length(1), // Label Mem
-            Constraint::Length(1), // Gauge Mem
-        ])
-        .margin(1)
-        .split(right_chunks[1]);
-        
-    let stats_block = Block::default()
-        .borders(Borders::ALL)
-        .border_type(BorderType::Rounded)
-        .title(" 系统状态 ")
-        .border_style(Style::default().fg(Color::Green));
-    f.render_widget(stats_block, right_chunks[1]);
+/// Letterbox fill from `--backdrop`, or else the `backdrop` setting.
+fn backdrop_option(cli: &cli::Cli, settings: &Settings) -> Option<Backdrop> {
+    cli.backdrop.or_else(|| settings.get(BACKDROP_SETTING).and_then(Backdrop::parse))
+}
 
-    // CPU
-    let cpu_usage = app.system.global_cpu_usage();
-    f.render_widget(Paragraph::new(format!("CPU 使用率: {:.1}%", cpu_usage)).style(Style::default().fg(Color::LightCyan)), stats_chunks[0]);
-    
-    let cpu_gauge = GradientGauge::new(
-        cpu_usage as f64 / 100.0,
-        (0, 255, 0), // Green
-        (255, 0, 0)  // Red
-    );
-    f.render_widget(cpu_gauge, stats_chunks[1]);
+/// Terminal colors from `--color-mode`, or else the `color_depth` setting.
+fn color_depth_option(cli: &cli::Cli, settings: &Settings) -> ColorDepth {
+    cli.color_depth.or_else(|| settings.get(COLOR_DEPTH_SETTING).and_then(ColorDepth::parse)).unwrap_or_default()
+}
 
-    // Memory
-    let total_mem = app.system.total_memory() as f64 / 1024.0 / 1024.0 / 1024.0;
-    let used_mem = app.system.used_memory() as f64 / 1024.0 / 1024.0 / 1024.0;
-    f.render_widget(Paragraph::new(format!("内存使用率: {:.1} GB / {:.1} GB", used_mem, total_mem)).style(Style::default().fg(Color::LightMagenta)), stats_chunks[3]);
+/// Extra ffmpeg arguments from `--ffmpeg-args`, or else the `ffmpeg_args` setting; a setting with
+/// an open quote is ignored.
+fn ffmpeg_args_option(cli: &cli::Cli, settings: &Settings) -> Vec<String> {
+    cli.ffmpeg_args.clone().or_else(|| settings.get(FFMPEG_ARGS_SETTING).and_then(cli::split_args)).unwrap_or_default()
+}
 
-    let mem_gauge = GradientGauge::new(
-        used_mem / total_mem,
-        (0, 255, 255), // Cyan
-        (255, 0, 255)  // Magenta
-    );
-    f.render_widget(mem_gauge, stats_chunks[4]);
+/// Synchronized output from `--sync-output`, or else the `sync_output` setting, or else whether the
+/// terminal is known to support it.
+fn sync_output_option(cli: &cli::Cli, settings: &Settings) -> bool {
+    cli.sync_output.unwrap_or_else(|| settings.flag(SYNC_OUTPUT_SETTING, graphics::sync_supported()))
+}
 
-    // Footer
-    let footer_text = " [↑/↓]: 导航 | [回车]: 播放/确认 | [M/S/Tab]: 切换模式 | [O]: 打开文件 | [Q/Esc]: 退出/返回 ";
-    let footer = Paragraph::new(footer_text)
-        .block(Block::default().borders(Borders::ALL).border_type(BorderType::Rounded).border_style(Style::default().fg(Color::DarkGray)))
-        .style(Style::default().fg(Color::Gray));
-    f.render_widget(footer, chunks[2]);
+/// Whether `--pix-fmt`, or else the `pix_fmt` setting, asks for rgb48.
+fn deep_color_option(cli: &cli::Cli, settings: &Settings) -> bool {
+    cli.pix_fmt.or_else(|| settings.get(PIX_FMT_SETTING).and_then(PixelFormat::parse)) == Some(PixelFormat::Rgb48)
+}
 
-    // Popup for Mode Selection
-    if app.show_mode_popup {
-        let area = centered_rect(60, 20, f.area());
-        f.render_widget(Clear, area); // Clear background
-        
-        // Gradient border for popup
-        let block = Block::default()
-            .title(" 选择渲染模式 ")
-            .borders(Borders::ALL)
-            .border_type(BorderType::Thick)
-            .style(Style::default().bg(Color::Rgb(20, 20, 40)).fg(Color::Cyan)); // Dark blue bg
-        f.render_widget(block.clone(), area);
+fn rewind_frames_option(cli: &cli::Cli, settings: &Settings) -> usize {
+    cli.rewind_frames
+        .or_else(|| settings.get(REWIND_FRAMES_SETTING).and_then(|v| v.parse().ok()).filter(|&n| n <= rewind::MAX_FRAMES))
+        .unwrap_or(rewind::DEFAULT_FRAMES)
+}
 
-        let modes = vec![
-            ListItem::new(Line::from(vec![Span::styled(" 🎨 ", Style::default()), Span::raw("像素艺术 (半块字符 - 高保真)")])),
-            ListItem::new(Line::from(vec![Span::styled(" 🔢 ", Style::default()), Span::raw("ASCII 艺术 (经典字符模式)")])),
-        ];
-        
-        let list = List::new(modes)
-            .block(Block::default().borders(Borders::NONE))
-            .highlight_style(Style::default().bg(Color::Rgb(50, 50, 100)).add_modifier(Modifier::BOLD)) // Subtle highlighting
-            .highlight_symbol(" >> ");
-        
-        let inner_area = block.inner(area);
-        f.render_stateful_widget(list, inner_area, &mut app.mode_list_state);
+/// `--prebuffer`, or the setting; 0, reading frames as they're needed, without either.
+fn prebuffer_option(cli: &cli::Cli, settings: &Settings) -> usize {
+    cli.prebuffer.or_else(|| settings.get(PREBUFFER_SETTING).and_then(|v| v.parse().ok())).unwrap_or(0)
+}
+
+/// `--reconnect`, or the setting, or `DEFAULT_RECONNECTS`.
+fn reconnect_option(cli: &cli::Cli, settings: &Settings) -> u32 {
+    cli.reconnects.or_else(|| settings.get(RECONNECT_SETTING).and_then(|v| v.parse().ok())).unwrap_or(DEFAULT_RECONNECTS)
+}
+
+/// `--max-decode-fps`, or the setting, or `DEFAULT_MAX_DECODE_FPS`; `None` when set to 0.
+fn max_decode_fps_option(cli: &cli::Cli, settings: &Settings) -> Option<u32> {
+    let max = cli.max_decode_fps
+        .or_else(|| settings.get(MAX_DECODE_FPS_SETTING).and_then(|v| v.parse().ok()))
+        .unwrap_or(DEFAULT_MAX_DECODE_FPS);
+    (max > 0).then_some(max)
+}
+
+fn reduce_motion_option(cli: &cli::Cli, settings: &Settings) -> Option<u8> {
+    cli.reduce_motion.or_else(|| {
+        let threshold = settings.get(MOTION_THRESHOLD_SETTING).and_then(|v| v.parse().ok());
+        settings.flag(REDUCE_MOTION_SETTING, false).then(|| threshold.unwrap_or(cli::DEFAULT_MOTION_THRESHOLD))
+    })
+}
+
+/// Whether playback starts with a border, and its color, from `settings.txt`.
+fn border_options(settings: &Settings) -> (bool, (u8, u8, u8)) {
+    let color = match settings.get(BORDER_COLOR_SETTING).and_then(AsciiColor::parse) {
+        Some(AsciiColor::Mono(r, g, b)) => (r, g, b),
+        _ => render::DEFAULT_BORDER_COLOR,
+    };
+    (settings.flag(BORDER_SETTING, false), color)
+}
+
+/// Slide time from the `slideshow_secs` setting: whole seconds, at least one.
+fn slideshow_interval(setting: Option<&str>) -> Duration {
+    let secs = setting.and_then(|s| s.trim().parse().ok()).unwrap_or(DEFAULT_SLIDESHOW_SECS);
+    Duration::from_secs(secs.max(1))
+}
+
+/// The AsciiArt ramp for `mode` from `--ramp` or the mode's settings, reversed by `--invert-ramp`
+/// or the `invert_ramp` setting.
+fn effective_ramp(cli: &cli::Cli, settings: &Settings, mode: RenderMode) -> &'static [char] {
+    mode_ramp(cli.ramp, settings, mode, cli.invert_ramp || settings.flag(INVERT_RAMP_SETTING, false))
+}
+
+/// `custom` from `--ramp`, else `mode`'s `<mode>.ramp` setting, else the default; reversed when
+/// `invert` is set.
+fn mode_ramp(custom: Option<&'static [char]>, settings: &Settings, mode: RenderMode, invert: bool) -> &'static [char] {
+    let configured = || {
+        let ramp: Vec<char> = settings.get(&format!("{}.ramp", mode.key()))?.chars().collect();
+        (ramp.len() >= 2).then(|| render::intern_ramp(ramp))
+    };
+    render::resolve_ramp(custom.or_else(configured), invert)
+}
+
+/// `mode`'s default levels from its `<mode>.brightness`, `<mode>.contrast` and `<mode>.gamma`
+/// settings, in steps from neutral; missing or invalid ones stay neutral.
+fn mode_levels(settings: &Settings, mode: RenderMode) -> Levels {
+    let mut levels = Levels::default();
+    for level in Level::ALL {
+        if let Some(steps) = settings.get(&format!("{}.{}", mode.key(), level.key())).and_then(|v| v.trim().parse().ok()) {
+            levels.adjust(level, steps);
+        }
     }
-    
-    // Popup for File Input
-    if app.show_input_popup {
-        let area = centered_rect(60, 20, f.area());
-        f.render_widget(Clear, area);
-        
-        let block = Block::default()
-            .title(" 手动输入文件路径 ")
-            .borders(Borders::ALL)
-            .border_type(BorderType::Double)
-            .style(Style::default().bg(Color::Rgb(20, 20, 40)).fg(Color::Yellow));
-        f.render_widget(block.clone(), area);
-        
-        let inner_area = block.inner(area);
-        
-        let input_text = vec![
-            Line::from("请输入视频文件的完整路径 (支持拖拽):").style(Style::default().fg(Color::Gray)),
-            Line::from(""),
-            Line::from(app.input_buffer.as_str()).style(Style::default().fg(Color::White).add_modifier(Modifier::UNDERLINED)),
-        ];
-        
-        let p = Paragraph::new(input_text).wrap(Wrap { trim: false }); 
-        f.render_widget(p, inner_area);
+    levels
+}
+
+/// `levels` with the steps given on the command line put in place of their own.
+fn with_custom_levels(mut levels: Levels, custom: &[(Level, i32)]) -> Levels {
+    for &(level, steps) in custom {
+        levels.adjust(level, steps - levels.steps(level));
+    }
+    levels
+}
+
+/// `SATURATION_STEP`s from 1.0 closest to a `--saturation` factor.
+fn saturation_steps(factor: f32) -> i32 {
+    (((factor - 1.0) / SATURATION_STEP).round() as i32).clamp(*SATURATION_STEPS.start(), *SATURATION_STEPS.end())
+}
+
+/// A positional file: plays it once without the menu.
+fn play_file(path: &Path, cli: &cli::Cli) -> Result<()> {
+    // Playing one file from the command line is a one-shot: nothing is left to return to.
+    let opts = PlayOptions { profile: start_profile(cli)?, exit_on_finish: true, ..play_options(cli, start_stream(cli)?) };
+    let mut system = System::new();
+    trace::defer();
+    let result = play_video(path, &opts, &mut system, 0.0, None, &mut |_| {});
+    trace::flush();
+    result.map(|_| ())
+}
+
+/// `--grid`: plays `paths` side by side, each tile decoded by its own ffmpeg at its own frame rate,
+/// until every one has ended or the user quits. There's no audio, seeking or overlay.
+fn play_grid(paths: &[PathBuf], cli: &cli::Cli) -> Result<()> {
+    let mut opts = play_options(cli, None);
+    // Images are drawn by the terminal at the cursor and can't be placed cell by cell here.
+    if opts.mode == RenderMode::Image {
+        opts.mode = RenderMode::PixelArt;
+    }
+    opts.border = false;
+    let (term_w, term_h) = terminal::size()?;
+    trace::defer();
+
+    struct GridTile {
+        tile: grid::Tile,
+        label: String,
+        render_opts: RenderOptions,
+        state: PlaybackState,
+        contrast: AutoContrast,
+        size: (u32, u32),
+        frames: mpsc::Receiver<Vec<u8>>,
+        drawn: String,
+        ended: bool,
+    }
+    let mut tiles = Vec::new();
+    for (path, tile) in paths.iter().zip(grid::layout(paths.len(), term_w, term_h)) {
+        let info = probe_video(path).with_context(|| format!("Failed to probe {}", path.display()))?;
+        if info.is_audio_only() {
+            anyhow::bail!("{} has no video stream to show in the grid", path.display());
+        }
+        let plan = DecodePlan::new(path, &info, &opts, tile.w, tile.video_rows())?;
+        let fits = |w, h| render::fits(plan.mode, w, h, tile.w, tile.video_rows());
+        let filters = plan.filters(&opts);
+        let mut decoder = Decoder::spawn(path, &plan, 0.0, &filters, true, fits, &mut |_| {})?;
+        let size = (decoder.width, decoder.height);
+        // One frame in flight: the reader waits for the screen, and ffmpeg's `-re` keeps the pace.
+        let (tx, frames) = mpsc::sync_channel(1);
+        thread::spawn(move || loop {
+            let mut buffer = vec![0u8; decoder.frame_size()];
+            if decoder.read_frame(&mut buffer).is_err() || tx.send(buffer).is_err() {
+                break;
+            }
+        });
+        let render_opts = RenderOptions { ascii_color: opts.ascii_color, ramp: opts.ramp, format: plan.format, depth: opts.depth, ..RenderOptions::new(plan.mode, tile.w, tile.video_rows()) };
+        let label = sequence::display_name(path);
+        let state = plan.playback_state(&opts);
+        tiles.push(GridTile { tile, label, render_opts, state, contrast: AutoContrast::default(), size, frames, drawn: String::new(), ended: false });
+    }
+
+    let guard = TerminalGuard::new()?;
+    let mut stdout = io::stdout();
+    let mut rendered = String::new();
+    let result = (|| -> Result<()> {
+        let mut dirty = true;
+        while tiles.iter().any(|t| !t.ended) {
+            if crossterm::event::poll(Duration::from_millis(5))? {
+                if let Event::Key(key) = crossterm::event::read()? {
+                    if key.kind == KeyEventKind::Press && matches!(key.code, KeyCode::Char('q') | KeyCode::Esc) {
+                        break;
+                    }
+                }
+            }
+            for t in &mut tiles {
+                let mut latest = None;
+                loop {
+                    match t.frames.try_recv() {
+                        Ok(frame) => latest = Some(frame),
+                        Err(mpsc::TryRecvError::Empty) => break,
+                        Err(mpsc::TryRecvError::Disconnected) => {
+                            t.ended = true;
+                            dirty = true;
+                            break;
+                        }
+                    }
+                }
+                if let Some(mut frame) = latest {
+                    t.state.apply_effects(&mut frame, t.size, t.render_opts.format, &opts.levels, Some(&mut t.contrast));
+                    if render::render_frame(&mut rendered, &frame, t.size.0, t.size.1, &t.render_opts).is_some() {
+                        t.drawn.clear();
+                        render::push_at(&mut t.drawn, &rendered, t.tile.x, t.tile.y + 1);
+                        dirty = true;
+                    }
+                }
+            }
+            if dirty {
+                let mut out = String::new();
+                for t in &tiles {
+                    let label = if t.ended { format!("{} ({})", t.label, Msg::GridEnded.text()) } else { t.label.clone() };
+                    let label = wrap_to_width(&label, t.tile.w as usize).swap_remove(0);
+                    out.push_str(&format!("\x1b[{};{}H\x1b[0m\x1b[1m{}\x1b[0m", t.tile.y + 1, t.tile.x + 1, label));
+                    out.push_str(&t.drawn);
+                }
+                write_frame(&mut stdout, &out, opts.sync_output)?;
+                dirty = false;
+            }
+        }
+        Ok(())
+    })();
+    drop(guard);
+    trace::flush();
+    result
+}
+
+/// Shows synthetic test patterns through the normal renderer, without ffmpeg, until the user quits.
+fn play_pattern(first: Pattern, cli: &cli::Cli) -> Result<()> {
+    let mut opts = play_options(cli, None);
+    let settings = Settings::load();
+    // The image protocols need the cell size in pixels, which only the player works out.
+    let modes: Vec<RenderMode> = RenderMode::ALL.into_iter().filter(|&m| m != RenderMode::Image).collect();
+    let mut mode = if opts.mode == RenderMode::Image { RenderMode::PixelArt } else { opts.mode };
+    let mut pattern = first;
+    let state = PlaybackState::new(&opts);
+    let mut contrast = AutoContrast::default();
+
+    let guard = TerminalGuard::new()?;
+    let mut stdout = io::stdout();
+    let mut rendered = String::new();
+    let result = (|| -> Result<()> {
+        let mut dirty = true;
+        loop {
+            if dirty {
+                let (term_w, term_h) = terminal::size()?;
+                // The last row is left for the status line.
+                let rows = term_h.saturating_sub(1).max(1);
+                let (w, h) = render::target_size(mode, opts.fit, pattern::SOURCE_SIZE.0, pattern::SOURCE_SIZE.1, term_w, rows);
+                let format = PixelFormat::for_output(mode, opts.ascii_color);
+                let mut frame = pattern.frame(w, h, format);
+                state.apply_effects(&mut frame, (w, h), format, &opts.levels, Some(&mut contrast));
+                let render_opts = RenderOptions { ascii_color: opts.ascii_color, ramp: opts.ramp, format, backdrop: opts.backdrop, depth: opts.depth, ..RenderOptions::new(mode, term_w, rows) };
+                let mut out = String::from("\x1b[0m\x1b[2J");
+                if render::render_frame(&mut rendered, &frame, w, h, &render_opts).is_some() {
+                    out.push_str(&rendered);
+                }
+                render::push_overlay(&mut out, &Msg::PatternStatus.fill(&[&pattern, &mode]), term_h);
+                write_frame(&mut stdout, &out, opts.sync_output)?;
+                dirty = false;
+            }
+            match crossterm::event::read()? {
+                Event::Resize(..) => dirty = true,
+                Event::Key(key) if key.kind == KeyEventKind::Press => match key.code {
+                    KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                    KeyCode::Char(' ') | KeyCode::Tab | KeyCode::Right => {
+                        pattern = pattern.next();
+                        dirty = true;
+                    }
+                    KeyCode::Char('m') | KeyCode::Char('M') => {
+                        let idx = modes.iter().position(|&m| m == mode).map_or(0, |i| (i + 1) % modes.len());
+                        mode = modes[idx];
+                        opts.ramp = effective_ramp(cli, &settings, mode);
+                        opts.levels = with_custom_levels(mode_levels(&settings, mode), &cli.levels);
+                        dirty = true;
+                    }
+                    _ => {}
+                },
+                _ => {}
+            }
+        }
+    })();
+    drop(guard);
+    result
+}
+
+/// The interactive menu, until the user quits. Without `--stdin`, `piped` is `None` and the list
+/// is the scanned directory; with it, `piped` holds the paths read and how many lines were skipped,
+/// or is `Some(None)` when stdin turned out to be a terminal.
+fn run_tui(cli: &cli::Cli, stream: Option<FrameStream>, profile: Option<FrameProfile>, piped: Option<Option<(Vec<PathBuf>, usize)>>) -> Result<()> {
+    let guard = TerminalGuard::new()?;
+    let backend = CrosstermBackend::new(io::stdout());
+    let mut terminal = Terminal::new(backend)?;
+
+    // Create App
+    let mut app = App::new(cli, PlayOptions { profile, ..play_options(cli, stream) })?;
+    // A seed implies shuffling; it's only worth giving to get a repeatable order.
+    app.shuffle |= cli.shuffle || cli.shuffle_seed.is_some();
+    app.shuffle_seed = cli.shuffle_seed;
+    match piped {
+        Some(Some((files, skipped))) => {
+            app.notify(Msg::StdinFiles.fill(&[&files.len(), &skipped]));
+            app.files = files;
+            app.list_state.select(None);
+            app.clamp_selection();
+        }
+        Some(None) => app.notify(Msg::StdinIsTerminal.text()),
+        None => {}
+    }
+    // Piped lists aren't the scan root's contents, so there's nothing to watch.
+    if (cli.watch || app.settings.flag(WATCH_SETTING, false)) && !cli.stdin {
+        app.watcher = Some(DirWatcher::new(app.files.clone()));
+    }
+
+    // Main Loop
+    let mut last_tick = Instant::now();
+
+    loop {
+        terminal.draw(|f| ui(f, &mut app))?;
+
+        let timeout = TICK_RATE
+            .checked_sub(last_tick.elapsed())
+            .unwrap_or_else(|| Duration::from_secs(0));
+
+        if crossterm::event::poll(timeout)? {
+            if let Event::Key(key) = crossterm::event::read()? {
+                if key.kind == KeyEventKind::Press {
+                    if let Some((path, pos)) = app.resume_prompt.clone() {
+                        match key.code {
+                            KeyCode::Enter | KeyCode::Char('y') | KeyCode::Char('Y') => {
+                                app.resume_prompt = None;
+                                run_playback(&mut terminal, &mut app, &path, pos)?;
+                            },
+                            KeyCode::Char('n') | KeyCode::Char('N') => {
+                                app.resume_prompt = None;
+                                run_playback(&mut terminal, &mut app, &path, 0.0)?;
+                            },
+                            KeyCode::Esc | KeyCode::Char('q') => app.resume_prompt = None,
+                            _ => {}
+                        }
+                    } else if app.comparison.is_some() {
+                        if matches!(key.code, KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('x') | KeyCode::Char('X')) {
+                            app.comparison = None;
+                        }
+                    } else if let Some((_, note)) = &mut app.note_editor {
+                        match key.code {
+                            KeyCode::Enter => app.save_note(),
+                            KeyCode::Esc => app.note_editor = None,
+                            KeyCode::Backspace => {
+                                note.pop();
+                            },
+                            KeyCode::Char(c) => note.push(c),
+                            _ => {}
+                        }
+                    } else if app.editing_filter {
+                        match key.code {
+                            KeyCode::Enter => app.editing_filter = false,
+                            KeyCode::Esc => {
+                                app.editing_filter = false;
+                                app.library_filter.clear();
+                            },
+                            KeyCode::Backspace => {
+                                app.library_filter.pop();
+                            },
+                            KeyCode::Char(c) => app.library_filter.push(c),
+                            _ => {}
+                        }
+                        app.clamp_selection();
+                    } else if app.slideshow.is_some() {
+                        match key.code {
+                            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('w') | KeyCode::Char('W') => app.slideshow = None,
+                            KeyCode::Enter => {
+                                play_selected(&mut terminal, &mut app)?;
+                                if let Some(show) = &mut app.slideshow {
+                                    show.next_at = Instant::now() + show.interval;
+                                }
+                            }
+                            KeyCode::Right | KeyCode::Char('l') | KeyCode::Down | KeyCode::Char('j') => app.step_slideshow(true),
+                            KeyCode::Left | KeyCode::Char('h') | KeyCode::Up | KeyCode::Char('k') => app.step_slideshow(false),
+                            _ => {}
+                        }
+                    } else if app.show_input_popup {
+                        match key.code {
+                            KeyCode::Enter => app.submit_input(),
+                            KeyCode::Esc => {
+                                app.show_input_popup = false;
+                                app.input_buffer.clear();
+                                app.input_history_index = None;
+                            },
+                            KeyCode::Tab => app.complete_input(),
+                            KeyCode::Up => app.browse_input_history(true),
+                            KeyCode::Down => app.browse_input_history(false),
+                            KeyCode::Backspace => {
+                                app.input_buffer.pop();
+                            },
+                            KeyCode::Char(c) => {
+                                app.input_buffer.push(c);
+                            },
+                            _ => {}
+                        }
+                    } else {
+                        match key.code {
+                            KeyCode::Char('q') | KeyCode::Esc => {
+                                if app.show_mode_popup {
+                                    app.show_mode_popup = false;
+                                } else {
+                                    app.should_quit = true;
+                                }
+                            },
+                            KeyCode::Char('j') | KeyCode::Down => app.next_item(),
+                            KeyCode::Char('k') | KeyCode::Up => app.previous_item(),
+                            KeyCode::Char('m') | KeyCode::Char('M') | KeyCode::Char('s') | KeyCode::Char('S') | KeyCode::Tab | KeyCode::BackTab => {
+                                 app.show_mode_popup = !app.show_mode_popup;
+                                 let idx = RenderMode::ALL.iter().position(|&m| m == app.render_mode);
+                                 app.mode_list_state.select(idx);
+                            },
+                            KeyCode::Char('o') | KeyCode::Char('O') => {
+                                app.show_input_popup = true;
+                            },
+                            KeyCode::Char('f') | KeyCode::Char('F') => app.toggle_favorite(),
+                            KeyCode::Char('n') | KeyCode::Char('N') => app.edit_note(),
+                            KeyCode::Char('l') | KeyCode::Char('L') => app.toggle_show_paths(),
+                            KeyCode::Char('t') | KeyCode::Char('T') => app.toggle_stats(),
+                            KeyCode::Char('z') | KeyCode::Char('Z') => app.toggle_focus(),
+                            KeyCode::Char('h') | KeyCode::Char('H') => app.toggle_shuffle(),
+                            KeyCode::Char('c') | KeyCode::Char('C') => app.copy_selected_path(),
+                            KeyCode::Char('e') | KeyCode::Char('E') => app.open_externally(),
+                            KeyCode::Char('d') | KeyCode::Char('D') => app.count_frames(),
+                            KeyCode::Char('p') | KeyCode::Char('P') => app.cycle_ascii_color(),
+                            KeyCode::Char('[') => app.sequence_fps = app.sequence_fps.saturating_sub(1).max(1),
+                            KeyCode::Char(']') => app.sequence_fps = (app.sequence_fps + 1).min(120),
+                            KeyCode::Char('v') | KeyCode::Char('V') => app.cycle_view(),
+                            KeyCode::Char('w') | KeyCode::Char('W') => app.start_slideshow(),
+                            KeyCode::Char('g') | KeyCode::Char('G') => app.find_scenes(),
+                            KeyCode::Char('b') | KeyCode::Char('B') => tune_selected(&mut terminal, &mut app)?,
+                            KeyCode::Char('x') | KeyCode::Char('X') => app.mark_for_compare(),
+                            KeyCode::Char('a') | KeyCode::Char('A') => app.cycle_library_sort(),
+                            KeyCode::Char('/') => app.edit_library_filter(),
+                            KeyCode::Char('u') | KeyCode::Char('U') => {
+                                app.show_library();
+                                app.refresh_library(true);
+                            },
+                            KeyCode::Char('r') | KeyCode::Char('R') | KeyCode::Backspace => replay_last(&mut terminal, &mut app)?,
+                            KeyCode::Enter => {
+                                if app.show_mode_popup {
+                                    app.select_mode();
+                                } else {
+                                    play_selected(&mut terminal, &mut app)?;
+                                }
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+            }
+        }
+
+        if last_tick.elapsed() >= TICK_RATE {
+            app.on_tick();
+            last_tick = Instant::now();
+        }
+
+        if app.should_quit {
+            break;
+        }
+    }
+
+    drop(guard);
+
+    for path in &app.print_on_exit {
+        println!("{}", path);
+    }
+    
+    Ok(())
+}
+
+/// `selected` moved back inside a list of `len` entries, or `None` when the list is empty.
+fn clamped_selection(selected: Option<usize>, len: usize) -> Option<usize> {
+    (len > 0).then(|| selected.unwrap_or(0).min(len - 1))
+}
+
+/// Plays the selected file, first asking whether to resume if there's a saved position.
+fn play_selected(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, app: &mut App) -> Result<()> {
+    app.clamp_selection();
+    let Some(path) = app.selected_file() else {
+        app.notify(Msg::NothingToPlay.text());
+        return Ok(());
+    };
+    let saved = app.resume_points.get(&path)
+        .or_else(|| (app.view == ListView::Recent).then(|| app.history.position(&path)).flatten())
+        .filter(|&p| p >= MIN_RESUME_SECS);
+    match saved {
+        Some(pos) => app.resume_prompt = Some((path, pos)),
+        None => run_playback(terminal, app, &path, 0.0)?,
+    }
+    Ok(())
+}
+
+/// Plays the last file again from the start with the options it last played with; with nothing
+/// played yet this session, the most recent file in the history with the current options.
+fn replay_last(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, app: &mut App) -> Result<()> {
+    let last = app.last_played.clone().or_else(|| app.history.entries().first().map(|e| (e.path.clone(), app.play_options())));
+    match last {
+        Some((path, opts)) => run_playback_with(terminal, app, &path, 0.0, opts),
+        None => {
+            app.notify(Msg::NothingToReplay.text());
+            Ok(())
+        }
+    }
+}
+
+/// Where the tune screen takes its frame from, as a fraction of the duration; the very start is
+/// often a fade in from black.
+const TUNE_FRAME_AT: f64 = 0.1;
+
+/// Opens the tune screen on the selected file; choices confirmed there replace the menu's levels
+/// and ramp, and the file plays with them.
+fn tune_selected(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, app: &mut App) -> Result<()> {
+    app.clamp_selection();
+    let Some(path) = app.selected_file() else {
+        app.notify(Msg::NothingToPlay.text());
+        return Ok(());
+    };
+    let tuned = tune_frame(&path, &app.play_options());
+    // The tune screen drew over the whole screen behind ratatui's back.
+    terminal.clear()?;
+    match tuned {
+        Ok(Some((levels, ramp))) => {
+            app.levels = levels;
+            app.ramp = ramp;
+            app.tuned = true;
+            play_selected(terminal, app)?;
+        }
+        Ok(None) => {}
+        Err(e) => app.notify(Msg::TuneFailed.fill(&[&format!("{:#}", e)])),
+    }
+    Ok(())
+}
+
+/// Decodes one frame of `path` as playback would and redraws it as the user adjusts brightness,
+/// contrast, gamma and the ramp. Returns the choices on Enter, or `None` when cancelled.
+fn tune_frame(path: &Path, opts: &PlayOptions) -> Result<Option<(Levels, &'static [char])>> {
+    let info = probe_video(path)?.with_fps(opts.sequence_fps);
+    if info.is_audio_only() {
+        anyhow::bail!("{} has no video stream to tune", path.display());
+    }
+    let (term_w, term_h) = output_size(opts.cells, terminal::size()?);
+    let plan = DecodePlan::new(path, &info, opts, term_w, term_h)?;
+    let filters = plan.filters(opts);
+    let fits = |w, h| render::fits(plan.mode, w, h, term_w, term_h);
+    let at = info.known_duration().map_or(0.0, |d| d * TUNE_FRAME_AT);
+    let mut decoder = Decoder::spawn(path, &plan, at, &filters, false, fits, &mut |_| {})?;
+    let mut frame = vec![0u8; decoder.frame_size()];
+    decoder.read_frame(&mut frame).context("ffmpeg produced no frame")?;
+    let (width, height) = (decoder.width, decoder.height);
+    drop(decoder);
+
+    let base = RenderOptions {
+        ascii_color: opts.ascii_color,
+        image_protocol: plan.image_protocol,
+        format: plan.format,
+        backdrop: opts.backdrop,
+        depth: opts.depth,
+        ..RenderOptions::new(plan.mode, term_w, term_h)
+    };
+    // The ramp in use first, so Backspace and a full cycle come back to it.
+    let mut ramps = vec![opts.ramp];
+    ramps.extend(render::RAMP_PRESETS.into_iter().filter(|&r| r != opts.ramp));
+    let (mut levels, mut ramp, mut row) = (opts.levels, 0, 0);
+
+    let guard = TerminalGuard::new()?;
+    let mut out = io::stdout();
+    let (mut shown, mut screen) = (Vec::new(), String::new());
+    let result = (|| -> Result<Option<(Levels, &'static [char])>> {
+        loop {
+            shown.clone_from(&frame);
+            levels.apply(&mut shown);
+            if render::render_frame(&mut screen, &shown, width, height, &RenderOptions { ramp: ramps[ramp], ..base }).is_none() {
+                screen.clear();
+            }
+            let mut items: Vec<String> = Level::ALL.iter().map(|&level| tune_slider(&levels, level)).collect();
+            items.push(Msg::TuneRamp.fill(&[&ramps[ramp].iter().collect::<String>()]));
+            render::push_corner_menu(&mut screen, &items, row, term_w);
+            render::push_overlay(&mut screen, Msg::TuneHint.text(), term_h);
+            write!(out, "\x1b[0m\x1b[2J")?;
+            out.write_all(screen.as_bytes())?;
+            out.flush()?;
+
+            let Event::Key(key) = crossterm::event::read()? else { continue };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            let delta = match key.code {
+                KeyCode::Left => -1,
+                KeyCode::Right => 1,
+                KeyCode::Up => {
+                    row = row.saturating_sub(1);
+                    continue;
+                }
+                KeyCode::Down => {
+                    row = (row + 1).min(items.len() - 1);
+                    continue;
+                }
+                KeyCode::Backspace => {
+                    (levels, ramp) = (Levels::default(), 0);
+                    continue;
+                }
+                KeyCode::Enter => return Ok(Some((levels, ramps[ramp]))),
+                KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('Q') => return Ok(None),
+                _ => continue,
+            };
+            match Level::ALL.get(row) {
+                Some(&level) => levels.adjust(level, delta),
+                None => ramp = (ramp as i32 + delta).rem_euclid(ramps.len() as i32) as usize,
+            }
+        }
+    })();
+    drop(guard);
+    result
+}
+
+/// A tune screen row: the level's label and value, then a track with a knob where it stands.
+fn tune_slider(levels: &Levels, level: Level) -> String {
+    let value = levels.value(level);
+    let (msg, value) = match level {
+        Level::Brightness => (Msg::TuneBrightness, format!("{:+.2}", value)),
+        Level::Contrast => (Msg::TuneContrast, format!("{:.1}x ", value)),
+        Level::Gamma => (Msg::TuneGamma, format!("{:.1}  ", value)),
+    };
+    let steps = levels.steps(level);
+    let track: String = level.range().map(|s| if s == steps { '●' } else { '─' }).collect();
+    msg.fill(&[&format!("{} {}", value, track)])
+}
+
+/// Hands the terminal over to `play_video` with the menu's current options.
+fn run_playback(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, app: &mut App, path: &Path, start: f64) -> Result<()> {
+    let opts = app.play_options();
+    run_playback_with(terminal, app, path, start, opts)
+}
+
+/// Hands the terminal over to `play_video` and records the outcome in the history, moving on to
+/// the previous or next file in the list, with the same options, when the user asks.
+fn run_playback_with(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, app: &mut App, path: &Path, start: f64, opts: PlayOptions) -> Result<()> {
+    // The list as it was when playback began; the Recent view reorders as files get played.
+    let files = app.visible_files();
+    let rng = app.shuffle.then(|| app.shuffle_seed.map_or_else(shuffle::Rng::from_time, shuffle::Rng::new));
+    let mut order = PlayOrder::new(files.len(), files.iter().position(|p| p == path), rng);
+    let (mut path, mut start, mut banner) = (path.to_path_buf(), start, None);
+    loop {
+        app.last_played = Some((path.clone(), opts.clone()));
+        let history = &mut app.history;
+        let mut record = |pos: f64| {
+            let _ = history.record(&path, pos);
+        };
+        record(start);
+        let result = play_video(&path, &opts, &mut app.system, start, banner.take(), &mut record);
+        let step = result.as_ref().ok().and_then(|end| end.step);
+        let finished = result.as_ref().is_ok_and(|end| end.finished);
+        app.last_error = match result {
+            Ok(end) => {
+                let saved = if end.finished {
+                    app.resume_points.clear(&path)
+                } else {
+                    app.resume_points.set(&path, end.position)
+                };
+                // A finished file starts over next time, so don't keep its end position either.
+                let position = if end.finished { 0.0 } else { end.position };
+                saved
+                    .and_then(|_| app.history.record(&path, position))
+                    .err()
+                    .map(|e| Msg::HistorySaveFailed.fill(&[&format!("{:#}", e)]))
+            }
+            Err(e) => {
+                trace::note(format!("playback of {} failed: {:#}", path.display(), e));
+                Some(Msg::PlaybackFailed.fill(&[&format!("{:#}", e)]))
+            }
+        };
+        if finished && opts.exit_on_finish {
+            app.should_quit = true;
+            break;
+        }
+        let Some(step) = step.filter(|_| !files.is_empty()) else { break };
+        let (index, position) = order.step(step);
+        path = files[index].clone();
+        start = 0.0;
+        // Shuffled, the count is how far through this pass rather than where the file is listed.
+        let number = if app.shuffle { position } else { index };
+        banner = Some(Msg::NowPlaying.fill(&[&(number + 1), &files.len(), &sequence::display_name(&path)]));
+    }
+
+    // Back in the list on the file played last, wherever the Recent view has moved it.
+    let position = app.visible_files().iter().position(|p| *p == path);
+    if position.is_some() {
+        app.list_state.select(position);
+    }
+    app.clamp_selection();
+    // Playback drew over the whole screen behind ratatui's back.
+    terminal.clear()?;
+    Ok(())
+}
+
+/// Container extensions picked up by the scanner, matched case-insensitively.
+const VIDEO_EXTENSIONS: [&str; 13] = ["mp4", "mkv", "avi", "mov", "flv", "webm", "ts", "m4v", "wmv", "mpg", "mpeg", "3gp", "ogv"];
+const AUDIO_EXTENSIONS: [&str; 5] = ["mp3", "flac", "wav", "ogg", "m4a"];
+
+/// Lowercased extension of `path`, if it has one.
+fn extension_of(path: &Path) -> Option<String> {
+    path.extension().map(|e| e.to_string_lossy().to_ascii_lowercase())
+}
+
+/// Whether `path`'s extension, ignoring case, is one of `extensions`.
+fn has_extension(path: &Path, extensions: &[&str]) -> bool {
+    extension_of(path).is_some_and(|ext| extensions.contains(&ext.as_str()))
+}
+
+/// Regular files directly inside `dir`, in no particular order.
+fn dir_files(dir: &Path) -> Vec<PathBuf> {
+    std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter(|e| e.file_type().is_ok_and(|t| t.is_file()))
+                .map(|e| e.path())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Playable files in the current directory: videos, audio and image sequences, sorted.
+fn scan_files() -> Vec<PathBuf> {
+    let entries = dir_files(Path::new("."));
+    let mut files: Vec<PathBuf> = entries
+        .iter()
+        .filter(|p| has_extension(p, &VIDEO_EXTENSIONS) || has_extension(p, &AUDIO_EXTENSIONS))
+        .map(|p| state::absolute(p))
+        .collect();
+    files.extend(sequences_among(&entries));
+    files.sort();
+    files
+}
+
+/// `--dry-run`: probes `path` and prints the ffmpeg command playback would run, without running it.
+fn print_dry_run(path: &Path, cli: &cli::Cli) -> Result<()> {
+    trace::enable();
+    let opts = play_options(cli, None);
+    let info = probe_video(path).with_context(|| format!("Failed to probe {}", path.display()))?;
+    if info.is_audio_only() {
+        println!("# {}", Msg::DryRunAudioOnly.text());
+        return Ok(());
+    }
+    let terminal = terminal::size().unwrap_or((80, 24));
+    let (term_w, term_h) = output_size(opts.cells, terminal);
+    if let Some(warning) = oversize_warning((term_w, term_h), terminal) {
+        println!("# {}", warning);
+    }
+    let plan = DecodePlan::new(path, &info, &opts, term_w, term_h)?;
+    let filters = plan.filters(&opts);
+    println!("# {}", Msg::DryRunMode.fill(&[&plan.mode]));
+    if let Some(protocol) = plan.image_protocol {
+        println!("# {}", Msg::DryRunProtocol.fill(&[&protocol]));
+    }
+    println!("# {}", Msg::DryRunSizes.fill(&[&term_w, &term_h, &info.width, &info.height, &plan.target.0, &plan.target.1]));
+    if let Some(crop) = plan.crop {
+        println!("# {}", Msg::DryRunCrop.fill(&[&crop]));
+    }
+    if let (Some(from), Some(to)) = (plan.decimated_from, plan.cfr_fps) {
+        println!("# {}", Msg::DryRunDecimate.fill(&[&format!("{:.2}", from), &to]));
+    }
+    if let Some(transfer) = info.hdr_transfer() {
+        println!("# {}", if plan.tonemap { Msg::DryRunTonemap } else { Msg::DryRunNoTonemap }.fill(&[&transfer]));
+    }
+    println!("# {}", Msg::DryRunFilters.fill(&[&filters.video_arg().unwrap_or_default()]));
+    println!("{}", trace::format_command(&Decoder::command(path, &plan, 0.0, &filters, true)));
+    Ok(())
+}
+
+/// The character grid to draw in: `--cols`/`--rows` where given, the terminal's size otherwise.
+fn output_size(cells: (Option<u16>, Option<u16>), terminal: (u16, u16)) -> (u16, u16) {
+    (cells.0.unwrap_or(terminal.0), cells.1.unwrap_or(terminal.1))
+}
+
+/// Says the output won't fit when `size` is larger than the terminal either way.
+fn oversize_warning(size: (u16, u16), terminal: (u16, u16)) -> Option<String> {
+    (size.0 > terminal.0 || size.1 > terminal.1).then(|| {
+        Msg::CellsExceedTerminal.fill(&[&size.0.to_string(), &size.1.to_string(), &terminal.0.to_string(), &terminal.1.to_string()])
+    })
+}
+
+/// `--export`: decodes `path` as fast as ffmpeg allows and writes every rendered frame to `output`.
+///
+/// Frames are sized for `--cols`/`--rows` or the current terminal (80x24 when there is none) and
+/// each starts with a cursor-home sequence, so `cat`-ing the file replays them in place. With
+/// `stats`, the CPU and memory use while exporting is drawn in the top-right corner of each frame,
+/// as `s` does in playback.
+fn export_frames(path: &Path, opts: &PlayOptions, output: &Path, stats: bool) -> Result<()> {
+    let info = probe_video(path).with_context(|| format!("Failed to probe {}", path.display()))?;
+    if info.is_audio_only() {
+        anyhow::bail!("{} has no video stream to export", path.display());
+    }
+    let (term_w, term_h) = output_size(opts.cells, terminal::size().unwrap_or((80, 24)));
+    let plan = DecodePlan::new(path, &info, opts, term_w, term_h)?;
+    let state = plan.playback_state(opts);
+    let filters = state.filters(plan.target.0, plan.target.1);
+    let fits = |w, h| render::fits(plan.mode, w, h, term_w, term_h);
+    let mut decoder = Decoder::spawn(path, &plan, 0.0, &filters, false, fits, &mut |_| {})?;
+    let render_opts = RenderOptions {
+        ascii_color: opts.ascii_color,
+        ramp: opts.ramp,
+        image_protocol: plan.image_protocol,
+        format: plan.format,
+        backdrop: opts.backdrop,
+        depth: opts.depth,
+        ..RenderOptions::new(plan.mode, term_w, term_h)
+    };
+
+    let file = std::fs::File::create(output).with_context(|| format!("Failed to create {}", output.display()))?;
+    let mut writer = io::BufWriter::new(file);
+    let mut buffer = vec![0u8; decoder.frame_size()];
+    let mut rendered = String::new();
+    let mut frames = 0;
+    let mut smoothing = TemporalSmoothing::default();
+    let mut contrast = AutoContrast::default();
+    let mut system = stats.then(System::new);
+    let mut stats_lines = Vec::new();
+    let mut last_stats: Option<Instant> = None;
+    while decoder.read_frame(&mut buffer).is_ok() {
+        if let Some(factor) = opts.smooth {
+            smoothing.apply(&mut buffer, factor);
+        }
+        state.apply_effects(&mut buffer, (decoder.width, decoder.height), plan.format, &opts.levels, Some(&mut contrast));
+        if render::render_frame(&mut rendered, &buffer, decoder.width, decoder.height, &render_opts).is_some() {
+            if opts.border {
+                render::push_border(&mut rendered, decoder.width, decoder.height, &render_opts, opts.border_color);
+            }
+            if let Some(system) = &mut system {
+                // CPU use is measured between refreshes, so refresh at the playback overlay's rate.
+                if last_stats.is_none_or(|t| t.elapsed() >= TICK_RATE) {
+                    stats_lines = system_stats(system);
+                    last_stats = Some(Instant::now());
+                }
+                render::push_corner(&mut rendered, &stats_lines, term_w);
+            }
+            writer.write_all(rendered.as_bytes())?;
+            frames += 1;
+        }
+    }
+    writer.flush()?;
+    if let Some(failure) = decoder.failure() {
+        anyhow::bail!("ffmpeg failed after {} frames: {}", frames, failure);
+    }
+    println!("{}", Msg::ExportDone.fill(&[&frames, &output.display()]));
+    Ok(())
+}
+
+/// `--text-out`: writes the frame at `--at` as plain ASCII text, `--width` columns wide.
+fn export_text(path: &Path, cli: &cli::Cli, output: &Path) -> Result<()> {
+    let info = probe_video(path).with_context(|| format!("Failed to probe {}", path.display()))?;
+    if info.is_audio_only() {
+        anyhow::bail!("{} has no video stream to export", path.display());
+    }
+    let columns = cli.width.unwrap_or(cli::DEFAULT_TEXT_WIDTH) as u16;
+    // Decoded like a Plain playback as tall as the picture needs, so crops, deinterlacing,
+    // tonemapping and the scaler match what playing the file shows. The ramp and levels are those
+    // of `--mode`, or of Plain without it.
+    let mode_cli = cli::Cli { mode: Some(cli.mode.unwrap_or(RenderMode::Plain)), ..cli.clone() };
+    let opts = PlayOptions { mode: RenderMode::Plain, fit: Fit::Contain, border: false, ..play_options(&mode_cli, None) };
+    let plan = DecodePlan::new(path, &info, &opts, columns, u16::MAX)?;
+    let fits = |w, h| render::fits(plan.mode, w, h, columns, u16::MAX);
+    let at = cli.at.unwrap_or(0.0);
+    let mut decoder = Decoder::spawn(path, &plan, at, &plan.filters(&opts), false, fits, &mut |_| {})?;
+    let mut frame = vec![0u8; decoder.frame_size()];
+    decoder.read_frame(&mut frame).with_context(|| format!("ffmpeg produced no frame at {}", format_clock(at)))?;
+    let (width, height) = (decoder.width, decoder.height);
+    drop(decoder);
+    plan.playback_state(&opts).apply_effects(&mut frame, (width, height), plan.format, &opts.levels, Some(&mut AutoContrast::default()));
+    let text = render::plain_text(&frame, width, height, opts.ramp);
+    std::fs::write(output, text).with_context(|| format!("Failed to write {}", output.display()))?;
+    println!("{}", Msg::TextOutDone.fill(&[&width, &height, &output.display()]));
+    Ok(())
+}
+
+/// Library metadata for `path`, whose modification time and size are `stamp`. A file that fails to
+/// probe gets an entry without a codec, so it isn't probed again until it changes.
+fn library_entry(path: &Path, (mtime, size): (u64, u64)) -> library::Entry {
+    let entry = library::Entry { mtime, size, width: 0, height: 0, duration: 0.0, fps: 0.0, bitrate: 0, codec: String::new() };
+    match probe_video(path) {
+        Ok(info) => library::Entry {
+            width: info.width,
+            height: info.height,
+            duration: info.known_duration().unwrap_or(0.0),
+            fps: if info.is_audio_only() { 0.0 } else { info.frame_rate() },
+            bitrate: info.bitrate.unwrap_or(0),
+            codec: if info.is_audio_only() { info.audio_codec.clone().unwrap_or_default() } else { info.video_codec.clone() },
+            ..entry
+        },
+        Err(_) => entry,
+    }
+}
+
+/// `--list`: prints one table row per scanned file, probing only the files the library has no
+/// up-to-date metadata for.
+fn print_file_table() {
+    let headers = [Msg::ListName, Msg::ListResolution, Msg::ListDuration, Msg::ListCodec, Msg::ListSize].map(Msg::text);
+    let mut library = Library::load();
+    let mut probed = false;
+    let rows: Vec<[String; 5]> = scan_files()
+        .iter()
+        .map(|path| {
+            let name = sequence::display_name(path);
+            let size = std::fs::metadata(path)
+                .map(|m| format!("{:.2} MB", m.len() as f64 / 1024.0 / 1024.0))
+                .unwrap_or_else(|_| "—".to_string());
+            let entry = match (library.fresh(path).cloned(), library::Entry::stamp(path)) {
+                (Some(entry), _) => entry,
+                (None, Some(stamp)) => {
+                    let entry = library_entry(path, stamp);
+                    library.insert(path.clone(), entry.clone());
+                    probed = true;
+                    entry
+                }
+                // Sequence patterns aren't files of their own to cache.
+                (None, None) => library_entry(path, (0, 0)),
+            };
+            if entry.codec.is_empty() {
+                return [name, "—".to_string(), "—".to_string(), "—".to_string(), size];
+            }
+            [
+                name,
+                if entry.width == 0 { Msg::ListAudio.text().to_string() } else { format!("{}x{}", entry.width, entry.height) },
+                if entry.duration > 0.0 { format_clock(entry.duration) } else { Msg::UnknownDuration.text().to_string() },
+                entry.codec,
+                size,
+            ]
+        })
+        .collect();
+    if probed {
+        if let Err(e) = library.save() {
+            eprintln!("{}", Msg::LibrarySaveFailed.fill(&[&format!("{:#}", e)]));
+        }
+    }
+
+    let mut widths = headers.map(UnicodeWidthStr::width);
+    for row in &rows {
+        for (w, cell) in widths.iter_mut().zip(row) {
+            *w = (*w).max(cell.width());
+        }
+    }
+    let print_row = |cells: &[&str]| {
+        let line: Vec<String> = cells
+            .iter()
+            .zip(widths)
+            .map(|(cell, w)| format!("{}{}", cell, " ".repeat(w - cell.width())))
+            .collect();
+        println!("{}", line.join("  ").trim_end());
+    };
+
+    print_row(&headers);
+    print_row(&widths.map(|w| "-".repeat(w)).iter().map(String::as_str).collect::<Vec<_>>());
+    for row in &rows {
+        print_row(&row.iter().map(String::as_str).collect::<Vec<_>>());
+    }
+    if rows.is_empty() {
+        println!("{}", Msg::ListEmpty.text());
+    }
+}
+
+/// Numbered image sequences directly inside `dir`, as absolute pattern paths.
+fn scan_sequences(dir: &str) -> Vec<PathBuf> {
+    sequences_among(&dir_files(Path::new(dir)))
+}
+
+/// Image sequences formed by the numbered images in `files`.
+fn sequences_among(files: &[PathBuf]) -> Vec<PathBuf> {
+    let images: Vec<PathBuf> = files.iter().filter(|p| has_extension(p, &sequence::IMAGE_EXTENSIONS)).cloned().collect();
+    sequence::detect(&images).iter().map(|p| state::absolute(p)).collect()
+}
+
+/// `HH:MM:SS.mmm`, for durations worked out to the frame.
+fn format_precise(secs: f64) -> String {
+    let millis = (secs.max(0.0) * 1000.0).round() as u64;
+    format!("{:02}:{:02}:{:02}.{:03}", millis / 3_600_000, millis / 60_000 % 60, millis / 1000 % 60, millis % 1000)
+}
+
+/// Duration of `frames` frames at `fps`, if the frame rate is known.
+fn counted_duration(frames: u64, fps: f32) -> Option<f64> {
+    (fps > 0.0).then(|| frames as f64 / fps as f64)
+}
+
+/// Where to restart decoding for `position`: files without a known duration can't seek, and a
+/// position past the end (e.g. after a still image's only frame) would decode nothing.
+fn seek_target(duration: Option<f64>, position: f64) -> f64 {
+    match duration {
+        Some(duration) if position < duration => position,
+        _ => 0.0,
+    }
+}
+
+/// How far before the end `End` lands, so there's still something left to decode.
+const END_JUMP_MARGIN: f64 = 1.0;
+
+/// Where a jump to `fraction` of the file (0.0 start, 1.0 end) lands; `None` when the duration
+/// isn't known, which makes percentages meaningless.
+fn jump_target(duration: Option<f64>, fraction: f64) -> Option<f64> {
+    let duration = duration?;
+    Some((duration * fraction).min(duration - END_JUMP_MARGIN).max(0.0))
+}
+
+fn format_clock(secs: f64) -> String {
+    let secs = secs.max(0.0) as u64;
+    format!("{:02}:{:02}", secs / 60, secs % 60)
+}
+
+// Helper to generate a gradient of colors for a span of text
+fn get_gradient_text(text: &str, start_color: (u8, u8, u8), end_color: (u8, u8, u8)) -> Line<'static> {
+    let mut spans = Vec::new();
+    let len = text.chars().count();
+    
+    for (i, c) in text.chars().enumerate() {
+        let t = i as f32 / len.max(1) as f32;
+        let r = (start_color.0 as f32 * (1.0 - t) + end_color.0 as f32 * t) as u8;
+        let g = (start_color.1 as f32 * (1.0 - t) + end_color.1 as f32 * t) as u8;
+        let b = (start_color.2 as f32 * (1.0 - t) + end_color.2 as f32 * t) as u8;
+        
+        spans.push(Span::styled(
+            c.to_string(),
+            Style::default().fg(Color::Rgb(r, g, b)),
+        ));
+    }
+    Line::from(spans)
+}
+
+/// The menu's system stats pane: CPU and memory gauges plus the decode thread setting.
+fn render_stats(f: &mut Frame, app: &App, area: Rect) {
+    // System Stats (Modern Gauges)
+    let stats_chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(1), // Label CPU
+            Constraint::Length(1), // Gauge CPU
+            Constraint::Length(1), // Spacer
+            Constraint::Length(1), // Label Mem
+            Constraint::Length(1), // Gauge Mem
+            Constraint::Length(1), // Spacer
+            Constraint::Length(1), // Decode threads
+        ])
+        .margin(1)
+        .split(area);
+        
+    let stats_block = Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .title(Msg::StatsTitle.text())
+        .border_style(Style::default().fg(Color::Green));
+    f.render_widget(stats_block, area);
+
+    // CPU
+    let cpu_usage = app.system.global_cpu_usage();
+    f.render_widget(Paragraph::new(Msg::CpuUsage.fill(&[&format!("{:.1}", cpu_usage)])).style(Style::default().fg(Color::LightCyan)), stats_chunks[0]);
+    
+    let cpu_gauge = Gauge::new(
+        app.gauge_style,
+        cpu_usage as f64 / 100.0,
+        (0, 255, 0), // Green
+        (255, 0, 0)  // Red
+    );
+    f.render_widget(cpu_gauge, stats_chunks[1]);
+
+    // Memory
+    let total_mem = app.system.total_memory() as f64 / 1024.0 / 1024.0 / 1024.0;
+    let used_mem = app.system.used_memory() as f64 / 1024.0 / 1024.0 / 1024.0;
+    f.render_widget(Paragraph::new(Msg::MemoryUsage.fill(&[&format!("{:.1}", used_mem), &format!("{:.1}", total_mem)])).style(Style::default().fg(Color::LightMagenta)), stats_chunks[3]);
+
+    let mem_gauge = Gauge::new(
+        app.gauge_style,
+        used_mem / total_mem,
+        (0, 255, 255), // Cyan
+        (255, 0, 255)  // Magenta
+    );
+    f.render_widget(mem_gauge, stats_chunks[4]);
+
+    let threads_str = match app.base.threads {
+        Some(t) => Msg::ThreadsFromFlag.fill(&[&t]),
+        None => Msg::ThreadsAuto.text().to_string(),
+    };
+    f.render_widget(Paragraph::new(threads_str).style(Style::default().fg(Color::Gray)), stats_chunks[6]);
+}
+
+/// Where each part of the menu screen goes. Hidden parts get an empty area.
+struct Panes {
+    header: Rect,
+    list: Rect,
+    details: Rect,
+    stats: Rect,
+    footer: Rect,
+}
+
+impl Panes {
+    /// Whether `area` has room for the layout: the header and footer, plus a bordered row of list
+    /// between them, at a width the panes stay legible at.
+    fn fit(area: Rect, focus: bool) -> bool {
+        let bars = if focus { 0 } else { 6 };
+        area.width >= MIN_UI_WIDTH && area.height >= bars + 3
+    }
+
+    /// The normal layout, or just the file list in focus mode.
+    fn new(area: Rect, focus: bool, show_stats: bool) -> Self {
+        let bar = if focus { 0 } else { 3 };
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(bar), // Header
+                Constraint::Min(0),      // Content
+                Constraint::Length(bar), // Footer
+            ])
+            .split(area);
+
+        let list_share = if focus { 100 } else { 40 };
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([
+                Constraint::Percentage(list_share),       // File List
+                Constraint::Percentage(100 - list_share), // Details & Stats
+            ])
+            .split(rows[1]);
+
+        let stats_share = if show_stats { 50 } else { 0 };
+        let side = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Percentage(100 - stats_share), // Details
+                Constraint::Percentage(stats_share), // Stats
+            ])
+            .split(columns[1]);
+
+        Self { header: rows[0], list: columns[0], details: side[0], stats: side[1], footer: rows[2] }
+    }
+}
+
+fn ui(f: &mut Frame, app: &mut App) {
+    if !Panes::fit(f.area(), app.focus) {
+        render_too_small(f);
+        return;
+    }
+    let panes = Panes::new(f.area(), app.focus, app.show_stats);
+
+    // 1. Header with Gradient
+    let header_text = get_gradient_text(Msg::AppTitle.text(), (0, 255, 255), (255, 0, 255));
+    let time_str = Local::now().format("%H:%M:%S").to_string();
+    let header_content = Line::from([
+        header_text.spans.into_iter().collect::<Vec<_>>(), 
+        vec![Span::raw(format!(" | {}", time_str)).style(Style::default().fg(Color::DarkGray))]
+    ].concat());
+    
+    let header = Paragraph::new(header_content)
+        .block(Block::default().borders(Borders::ALL).border_type(BorderType::Rounded).border_style(Style::default().fg(Color::Cyan)));
+    if !app.focus {
+        f.render_widget(header, panes.header);
+    }
+
+    // 2. Main Content
+    // Left: File List
+    let visible = app.visible_files();
+    let files: Vec<ListItem> = visible
+        .iter()
+        .map(|path| {
+            let star = if app.favorites.contains(path) { "★ " } else { "  " };
+            let name = list_label(path, app.show_paths);
+            let icon = match extension_of(path).as_deref() {
+                _ if sequence::is_sequence(path) => "🖼️ ",
+                _ if app.is_audio(path) => "🎵 ",
+                Some("mp4" | "m4v") => "🎥 ",
+                Some("mkv" | "webm" | "ogv") => "🎞️ ",
+                Some("avi" | "wmv" | "mpg" | "mpeg") => "📼 ",
+                Some("ts" | "3gp") => "📺 ",
+                _ => "📄 ",
+            };
+            let mut spans = vec![
+                 Span::styled(star, Style::default().fg(Color::Yellow)),
+                 Span::styled(icon, Style::default().fg(Color::Blue)),
+                 Span::raw(name),
+            ];
+            if app.compare_mark.as_ref() == Some(path) {
+                spans.insert(2, Span::styled("⇄ ", Style::default().fg(Color::Magenta)));
+            }
+            if app.is_audio(path) {
+                spans.push(Span::styled(format!("  {}", Msg::AudioFile.text()), Style::default().fg(Color::Magenta)));
+            }
+            if app.view == ListView::Library {
+                if let Some(entry) = app.library.get(path).filter(|e| !e.codec.is_empty()) {
+                    spans.push(Span::styled(format!("  {}", library_columns(entry)), Style::default().fg(Color::DarkGray)));
+                }
+            }
+            ListItem::new(Line::from(spans))
+        })
+        .collect();
+    let title = match app.view {
+        ListView::All => Msg::ListTitleAll.text().to_string(),
+        ListView::Favorites => Msg::ListTitleFavorites.text().to_string(),
+        ListView::Recent => Msg::ListTitleRecent.text().to_string(),
+        ListView::Library => {
+            let mut title = Msg::ListTitleLibrary.fill(&[&app.library_sort.label()]);
+            if app.editing_filter || !app.library_filter.is_empty() {
+                let cursor = if app.editing_filter { "▏" } else { "" };
+                title.push_str(&Msg::LibraryFilter.fill(&[&format!("{}{}", app.library_filter, cursor)]));
+            }
+            if let Some(job) = &app.library_job {
+                title.push_str(&Msg::LibraryProbing.fill(&[&job.done, &job.total]));
+            }
+            title
+        }
+    };
+
+    // highlight selection with gradient effect (simulated by bold + bright color)
+    let files_list = List::new(files)
+        .block(Block::default()
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .title(title)
+            .border_style(Style::default().fg(Color::Blue))) // Blue border for active look
+        .highlight_style(Style::default().bg(Color::Rgb(30, 30, 60)).add_modifier(Modifier::BOLD))
+        .highlight_symbol(" ➤ ");
+        
+    f.render_stateful_widget(files_list, panes.list, &mut app.list_state);
+
+    // Right: Details + Stats
+    if !app.focus {
+        render_side_panes(f, app, &panes);
+
+        // Footer
+        let footer = Paragraph::new(Msg::Footer.text())
+            .block(Block::default().borders(Borders::ALL).border_type(BorderType::Rounded).border_style(Style::default().fg(Color::DarkGray)))
+            .style(Style::default().fg(Color::Gray));
+        f.render_widget(footer, panes.footer);
+    }
+
+    // Toast just above the footer
+    if let Some((msg, _)) = &app.toast {
+        let width = (Line::from(msg.as_str()).width() as u16 + 4).min(panes.footer.width);
+        let area = Rect {
+            x: panes.footer.x + panes.footer.width.saturating_sub(width + 1),
+            y: panes.footer.y.saturating_sub(3),
+            width,
+            height: 3.min(panes.footer.y),
+        };
+        f.render_widget(Clear, area);
+        f.render_widget(
+            Paragraph::new(msg.as_str())
+                .block(Block::default().borders(Borders::ALL).border_type(BorderType::Rounded).border_style(Style::default().fg(Color::Yellow)))
+                .style(Style::default().fg(Color::White).bg(Color::Rgb(20, 20, 40))),
+            area,
+        );
+    }
+
+    // Popup for Mode Selection
+    if app.show_mode_popup {
+        let labels: Vec<String> = RenderMode::ALL.iter().map(|m| format!(" {} {}", m.icon(), m)).collect();
+        // Room for the " >> " highlight symbol in front of each label.
+        let width = labels.iter().map(|l| l.width()).max().unwrap_or(0) + 4;
+        let area = popup_rect(width as u16, labels.len() as u16, f.area());
+        f.render_widget(Clear, area); // Clear background
+        
+        // Gradient border for popup
+        let block = Block::default()
+            .title(Msg::ModePopupTitle.text())
+            .borders(Borders::ALL)
+            .border_type(BorderType::Thick)
+            .style(Style::default().bg(Color::Rgb(20, 20, 40)).fg(Color::Cyan)); // Dark blue bg
+        f.render_widget(block.clone(), area);
+
+        let modes: Vec<ListItem> = RenderMode::ALL
+            .iter()
+            .map(|m| ListItem::new(Line::from(vec![Span::styled(format!(" {} ", m.icon()), Style::default()), Span::raw(m.to_string())])))
+            .collect();
+        
+        let list = List::new(modes)
+            .block(Block::default().borders(Borders::NONE))
+            .highlight_style(Style::default().bg(Color::Rgb(50, 50, 100)).add_modifier(Modifier::BOLD)) // Subtle highlighting
+            .highlight_symbol(" >> ");
+        
+        let inner_area = block.inner(area);
+        // Taller than the screen allows: the list scrolls to keep the selection visible.
+        f.render_stateful_widget(list, inner_area, &mut app.mode_list_state);
+    }
+    
+    if app.slideshow.is_some() {
+        render_slideshow(f, app);
+    }
+
+    // Popup for resuming a recent item
+    if let Some((path, pos)) = &app.resume_prompt {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        let keys = Msg::ResumeKeys.text();
+        let width = name.width().max(keys.width()) as u16;
+        let inner_w = popup_rect(width, 0, f.area()).width.saturating_sub(2);
+        let name_lines = wrap_to_width(&name, inner_w as usize).len().max(1);
+        let area = popup_rect(width, name_lines as u16 + 3, f.area());
+        f.render_widget(Clear, area);
+
+        let block = Block::default()
+            .title(Msg::ResumeTitle.text())
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .style(Style::default().bg(Color::Rgb(20, 20, 40)).fg(Color::Green));
+        let text = vec![
+            Line::from(name.to_string()).style(Style::default().fg(Color::White)),
+            Line::from(""),
+            Line::from(Msg::ResumeQuestion.fill(&[&format_clock(*pos)])),
+            Line::from(keys).style(Style::default().fg(Color::Gray)),
+        ];
+        f.render_widget(Paragraph::new(text).block(block).wrap(Wrap { trim: false }), area);
+    }
+
+    if let Some(pair) = &app.comparison {
+        render_comparison(f, pair);
+    }
+
+    // Popup for editing the selected file's note
+    if let Some((path, note)) = &app.note_editor {
+        let header = Msg::NoteHeader.text();
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        let width = header.width().max(name.width()).max(note.width() + 1) as u16;
+        let inner_w = popup_rect(width, 0, f.area()).width.saturating_sub(2);
+        let note_lines = wrap_to_width(note, inner_w as usize);
+        let area = popup_rect(width, note_lines.len() as u16 + 3, f.area());
+        f.render_widget(Clear, area);
+
+        let block = Block::default()
+            .title(Msg::NoteTitle.text())
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .style(Style::default().bg(Color::Rgb(20, 20, 40)).fg(Color::Yellow));
+        let mut text = vec![
+            Line::from(header).style(Style::default().fg(Color::Gray)),
+            Line::from(name.to_string()).style(Style::default().fg(Color::White)),
+            Line::from(""),
+        ];
+        let note_style = Style::default().fg(Color::White).add_modifier(Modifier::UNDERLINED);
+        text.extend(note_lines.into_iter().map(|l| Line::from(l).style(note_style)));
+        let inner_area = block.inner(area);
+        let overflow = (text.len() as u16).saturating_sub(inner_area.height);
+        f.render_widget(block, area);
+        f.render_widget(Paragraph::new(text).scroll((overflow, 0)), inner_area);
+    }
+
+    // Popup for File Input
+    if app.show_input_popup {
+        let header = Msg::InputHeader.text();
+        // One extra column so the end of the input never sits flush against the border.
+        let width = header.width().max(app.input_buffer.width() + 1) as u16;
+        let inner_w = popup_rect(width, 0, f.area()).width.saturating_sub(2);
+        let input_lines = wrap_to_width(&app.input_buffer, inner_w as usize);
+        let area = popup_rect(width, input_lines.len() as u16 + 2, f.area());
+        f.render_widget(Clear, area);
+        
+        let block = Block::default()
+            .title(Msg::InputTitle.text())
+            .borders(Borders::ALL)
+            .border_type(BorderType::Double)
+            .style(Style::default().bg(Color::Rgb(20, 20, 40)).fg(Color::Yellow));
+        f.render_widget(block.clone(), area);
+        
+        let inner_area = block.inner(area);
+        
+        let mut input_text = vec![
+            Line::from(header).style(Style::default().fg(Color::Gray)),
+            Line::from(""),
+        ];
+        let input_style = Style::default().fg(Color::White).add_modifier(Modifier::UNDERLINED);
+        input_text.extend(input_lines.into_iter().map(|l| Line::from(l).style(input_style)));
+
+        // On a short screen keep the end of the input, where typing happens, in view.
+        let overflow = (input_text.len() as u16).saturating_sub(inner_area.height);
+        let p = Paragraph::new(input_text).scroll((overflow, 0));
+        f.render_widget(p, inner_area);
+    }
+}
+
+/// The details pane, with the selected file's poster and filmstrip, and the stats pane under it.
+fn render_side_panes(f: &mut Frame, app: &App, panes: &Panes) {
+    // Video Details (Dimmed logic if not active, but here we keep it clean)
+    let mut details_text = Text::default();
+    if let Some(note) = app.selected_file().and_then(|p| app.notes.get(&p).map(str::to_string)) {
+        details_text.push_line(Line::from(Msg::NoteLine.fill(&[&note])).style(Style::default().fg(Color::Yellow)));
+        details_text.push_line(Line::from(""));
+    }
+    details_text.extend(Text::from(app.video_metadata.as_str()));
+    if let Some(err) = &app.last_error {
+        details_text.push_line(Line::from(""));
+        // ffmpeg failures carry the end of its stderr on lines of their own.
+        for line in err.lines() {
+            details_text.push_line(Line::from(line).style(Style::default().fg(Color::Red)));
+        }
+    }
+    let details_block = Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .title(Msg::DetailsTitle.text())
+        .border_style(Style::default().fg(Color::Magenta)); // Different color
+    let mut details_area = details_block.inner(panes.details);
+    f.render_widget(details_block, panes.details);
+
+    // Poster on top, taking at most half the pane so the metadata stays readable.
+    if let Some(poster) = app.selected_file().and_then(|p| app.posters.get(&p).cloned().flatten()) {
+        let (cols, rows) = poster::fit(details_area.width, details_area.height / 2);
+        if rows > 0 {
+            let poster_area = Rect { x: details_area.x + (details_area.width - cols) / 2, width: cols, height: rows, ..details_area };
+            f.render_widget(poster.widget(), poster_area);
+            details_area.y += rows + 1;
+            details_area.height = details_area.height.saturating_sub(rows + 1);
+        }
+    }
+    // Scene filmstrip in one row under it, at most a quarter of what's left.
+    if let Some(strip) = app.selected_file().and_then(|p| app.filmstrips.get(&p)) {
+        let slot = details_area.width / strip.len() as u16;
+        let (cols, rows) = poster::fit(slot.saturating_sub(1), details_area.height / 4);
+        if rows > 0 {
+            let left = details_area.x + (details_area.width - slot * strip.len() as u16) / 2;
+            for (i, scene) in strip.iter().enumerate() {
+                let area = Rect { x: left + i as u16 * slot + (slot - cols) / 2, width: cols, height: rows, ..details_area };
+                f.render_widget(scene.widget(), area);
+            }
+            details_area.y += rows + 1;
+            details_area.height = details_area.height.saturating_sub(rows + 1);
+        }
+    }
+    let details = Paragraph::new(details_text)
+        .style(Style::default().fg(Color::White)); // Bright text
+    f.render_widget(details, details_area);
+
+    if app.show_stats {
+        render_stats(f, app, panes.stats);
+    }
+}
+
+/// The compare popup: each field of two library entries side by side, differing ones highlighted.
+fn render_comparison(f: &mut Frame, [(first, a), (second, b)]: &[(PathBuf, library::Entry); 2]) {
+    let mut rows = vec![(Msg::CompareName.text(), sequence::display_name(first), sequence::display_name(second))];
+    rows.extend(comparison_rows(a, b));
+    let label_w = rows.iter().map(|r| r.0.width()).max().unwrap_or(0);
+    let value_w = rows.iter().map(|r| r.1.width().max(r.2.width())).max().unwrap_or(0);
+    let hint = Msg::CompareHint.text();
+    let area = popup_rect((label_w + 2 * value_w + 4).max(hint.width()) as u16, rows.len() as u16 + 2, f.area());
+    f.render_widget(Clear, area);
+
+    let block = Block::default()
+        .title(Msg::CompareTitle.text())
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .style(Style::default().bg(Color::Rgb(20, 20, 40)).fg(Color::Cyan));
+    // Values too wide for half the room left by the labels are cut off.
+    let column = (block.inner(area).width as usize).saturating_sub(label_w + 4) / 2;
+    let cell = |text: &str| {
+        let text = wrap_to_width(text, column.max(1)).swap_remove(0);
+        format!("{}{}", text, " ".repeat(column.saturating_sub(text.width())))
+    };
+    let mut text: Vec<Line> = rows
+        .iter()
+        .map(|(label, left, right)| {
+            let style = if left != right { Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD) } else { Style::default().fg(Color::White) };
+            Line::from(vec![
+                Span::styled(format!("{}{}  ", label, " ".repeat(label_w - label.width())), Style::default().fg(Color::Gray)),
+                Span::styled(cell(left), style),
+                Span::raw("  "),
+                Span::styled(cell(right), style),
+            ])
+        })
+        .collect();
+    text.push(Line::from(""));
+    text.push(Line::from(hint).style(Style::default().fg(Color::Gray)));
+    f.render_widget(Paragraph::new(text).block(block), area);
+}
+
+/// Label and both files' values for every compared field but the name; values are formatted the
+/// same way, so differing strings mean differing metadata.
+fn comparison_rows(a: &library::Entry, b: &library::Entry) -> Vec<(&'static str, String, String)> {
+    let fields = [Msg::CompareResolution, Msg::CompareFps, Msg::CompareDuration, Msg::CompareBitrate, Msg::CompareCodec, Msg::CompareSize];
+    fields.into_iter().map(|field| (field.text(), compared_value(field, a), compared_value(field, b))).collect()
+}
+
+/// `entry`'s value for the compare popup row `field`, or `N/A` when probing didn't find it.
+fn compared_value(field: Msg, e: &library::Entry) -> String {
+    let value = match field {
+        Msg::CompareResolution => (e.width > 0).then(|| format!("{}x{}", e.width, e.height)),
+        Msg::CompareFps => (e.fps > 0.0).then(|| format!("{:.2} FPS", e.fps)),
+        Msg::CompareDuration => (e.duration > 0.0).then(|| format_clock(e.duration)),
+        Msg::CompareBitrate => (e.bitrate > 0).then(|| format!("{:.2} Mbps", e.bitrate as f64 / 1000.0 / 1000.0)),
+        Msg::CompareCodec => (!e.codec.is_empty()).then(|| e.codec.clone()),
+        Msg::CompareSize => (e.size > 0).then(|| format!("{:.2} MB", e.size as f64 / 1024.0 / 1024.0)),
+        _ => None,
+    };
+    value.unwrap_or_else(|| "N/A".to_string())
+}
+
+/// Popups never get narrower than this, so titles and key hints stay readable.
+const POPUP_MIN_WIDTH: u16 = 30;
+/// Widest a popup gets, as a percentage of the screen width.
+const POPUP_MAX_WIDTH_PERCENT: u16 = 80;
+
+/// Narrowest terminal the menu is laid out in; below it the panes and popups would be cut to
+/// fragments.
+const MIN_UI_WIDTH: u16 = 30;
+
+/// Stands in for the whole menu while the terminal is too small for it, until it grows again.
+fn render_too_small(f: &mut Frame) {
+    let area = f.area();
+    f.render_widget(Clear, area);
+    let middle = Rect { y: area.y + area.height.saturating_sub(1) / 2, height: area.height.min(1), ..area };
+    let text = Paragraph::new(Msg::TerminalTooSmall.text()).alignment(Alignment::Center).style(Style::default().fg(Color::Yellow));
+    f.render_widget(text, middle);
+}
+
+/// Full-screen slideshow: the selected file's poster as large as fits, with its name below.
+fn render_slideshow(f: &mut Frame, app: &App) {
+    let area = f.area();
+    f.render_widget(Clear, area);
+    let Some(path) = app.selected_file() else { return };
+    let caption_rows = 2;
+    let (cols, rows) = poster::fit(area.width, area.height.saturating_sub(caption_rows));
+    let top = area.y + area.height.saturating_sub(rows + caption_rows) / 2;
+    match app.posters.get(&path) {
+        Some(Some(poster)) => {
+            let poster_area = Rect { x: area.x + (area.width - cols) / 2, y: top, width: cols, height: rows };
+            f.render_widget(poster.widget(), poster_area);
+        }
+        placeholder => {
+            let text = if placeholder.is_some() { Msg::NoPreview } else { Msg::PreviewLoading }.text();
+            let middle = Rect { y: top + rows / 2, height: 1, ..area };
+            f.render_widget(Paragraph::new(text).alignment(Alignment::Center).style(Style::default().fg(Color::DarkGray)), middle);
+        }
+    }
+    let position = app.list_state.selected().map_or(0, |i| i + 1);
+    let caption = vec![
+        Line::from(format!("{}  ({}/{})", sequence::display_name(&path), position, app.visible_files().len())).style(Style::default().fg(Color::White).add_modifier(Modifier::BOLD)),
+        Line::from(Msg::SlideshowKeys.text()).style(Style::default().fg(Color::DarkGray)),
+    ];
+    let caption_area = Rect { y: (top + rows).min(area.bottom().saturating_sub(caption_rows)), height: caption_rows, ..area };
+    f.render_widget(Paragraph::new(caption).alignment(Alignment::Center), caption_area);
+}
+
+/// Centers a popup sized for `content_w`x`content_h` cells of content plus its border within `r`,
+/// between `POPUP_MIN_WIDTH` and `POPUP_MAX_WIDTH_PERCENT` wide and no taller than `r`.
+fn popup_rect(content_w: u16, content_h: u16, r: Rect) -> Rect {
+    let max_w = (r.width as u32 * POPUP_MAX_WIDTH_PERCENT as u32 / 100) as u16;
+    let width = content_w.saturating_add(2).clamp(POPUP_MIN_WIDTH.min(max_w), max_w.max(1)).min(r.width);
+    let height = content_h.saturating_add(2).min(r.height);
+    Rect {
+        x: r.x + r.width.saturating_sub(width) / 2,
+        y: r.y + r.height.saturating_sub(height) / 2,
+        width,
+        height,
+    }
+}
+
+/// Breaks `text` into lines at most `width` columns wide, splitting anywhere (paths rarely have spaces).
+fn wrap_to_width(text: &str, width: usize) -> Vec<String> {
+    let mut lines = vec![String::new()];
+    let mut used = 0;
+    for c in text.chars() {
+        let w = c.width().unwrap_or(0);
+        if used + w > width.max(1) && used > 0 {
+            lines.push(String::new());
+            used = 0;
+        }
+        lines.last_mut().unwrap().push(c);
+        used += w;
+    }
+    lines
+}
+
+/// How far into a video the black-intro scan looks; intros longer than this are left alone.
+const BLACK_SCAN_SECS: f64 = 60.0;
+
+/// Shortest dark lead-in worth skipping, so a brief fade from black still plays.
+const MIN_BLACK_SECS: f64 = 1.0;
+
+/// Consecutive undecodable frames tolerated before playback gives up.
+const MAX_CONSECUTIVE_BAD_FRAMES: u32 = 30;
+
+/// How often `play_video` reports the current position back to its caller.
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Smallest decode size `-` goes down to, in percent of the terminal-fit size.
+const MIN_SCALE_PERCENT: u32 = 30;
+/// Step of the `-`/`+` resolution keys, in percent.
+const SCALE_STEP_PERCENT: u32 = 10;
+/// Steps from the terminal-fit size down to `MIN_SCALE_PERCENT`.
+const MAX_SCALE_STEPS: u32 = (100 - MIN_SCALE_PERCENT) / SCALE_STEP_PERCENT;
+
+/// Largest decode size `P` panning asks for, as a multiple of the terminal-fit size; the source's
+/// own resolution when that's smaller.
+const MAX_PAN_ZOOM: f64 = 4.0;
+/// Below this much larger than the terminal-fit size there's too little to pan across.
+const MIN_PAN_ZOOM: f64 = 1.25;
+/// Arrow keys pan by this fraction of the viewport.
+const PAN_STEP_DIVISOR: u32 = 8;
+
+/// Gap between two frames, during playback, past which the stall is reported.
+const STALL_THRESHOLD: Duration = Duration::from_secs(2);
+
+/// Render caps `F` steps through during playback, after "uncapped".
+const FPS_CAP_STEPS: [u32; 4] = [30, 24, 15, 10];
+
+/// Which side held up a frame that arrived late.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Stall {
+    /// ffmpeg didn't deliver the frame: a slow source, network or decoder.
+    Decode,
+    /// The last frame took that long to draw: the terminal isn't keeping up or is suspended.
+    Render,
+}
+
+impl Stall {
+    /// Classifies the gap between two frames from the time spent waiting on ffmpeg (`read`) and the
+    /// time spent drawing and handling input since the previous frame (`render`). Gaps a few frames
+    /// long are normal for very low frame rates, so only a gap past both that and
+    /// `STALL_THRESHOLD` counts.
+    fn detect(read: Duration, render: Duration, frame_interval: Duration) -> Option<Stall> {
+        if read + render <= STALL_THRESHOLD.max(frame_interval * 4) {
+            return None;
+        }
+        Some(if read >= render { Stall::Decode } else { Stall::Render })
+    }
+
+    fn notice(self, gap: Duration) -> String {
+        match self {
+            Stall::Decode => Msg::StallDecode,
+            Stall::Render => Msg::StallRender,
+        }
+        .fill(&[&format!("{:.1}", gap.as_secs_f64())])
+    }
+}
+
+/// Timing of the menu's poster slideshow.
+#[derive(Debug, Clone, Copy)]
+struct Slideshow {
+    /// How long each poster stays up, from `slideshow_secs` in settings.txt.
+    interval: Duration,
+    next_at: Instant,
+}
+
+/// How a playback session ended.
+#[derive(Debug, Clone, Copy)]
+struct PlaybackEnd {
+    /// Position in seconds when playback stopped.
+    position: f64,
+    /// `true` when the stream ran out rather than the user quitting.
+    finished: bool,
+    /// The file the user asked to switch to instead of quitting, when `PlayOptions::browse` allows.
+    step: Option<FileStep>,
+}
+
+/// A neighbouring file in the list, relative to the one playing.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum FileStep {
+    Previous,
+    Next,
+}
+
+impl FileStep {
+    /// `<`/`,`/PageUp for the previous file and `>`/`.`/PageDown for the next.
+    fn from_key(code: KeyCode) -> Option<Self> {
+        match code {
+            KeyCode::Char('<' | ',') | KeyCode::PageUp => Some(FileStep::Previous),
+            KeyCode::Char('>' | '.') | KeyCode::PageDown => Some(FileStep::Next),
+            _ => None,
+        }
+    }
+
+    /// The index after (or before) `current` in a list of `len`, wrapping around at the ends.
+    fn apply(self, current: usize, len: usize) -> usize {
+        match self {
+            FileStep::Next => (current + 1) % len.max(1),
+            FileStep::Previous => current.checked_sub(1).unwrap_or(len.saturating_sub(1)),
+        }
+    }
+}
+
+/// The order `<`/`>` step through a list of files in: as listed, or shuffled with a fresh order
+/// for every pass through the list.
+struct PlayOrder {
+    /// Indices into the list, in playing order.
+    order: Vec<usize>,
+    /// Where in `order` the playing file is; `None` while it's a file that isn't in the list.
+    at: Option<usize>,
+    rng: Option<shuffle::Rng>,
+}
+
+impl PlayOrder {
+    /// `current` is the playing file's index in the list; a shuffled order starts with it.
+    fn new(len: usize, current: Option<usize>, mut rng: Option<shuffle::Rng>) -> Self {
+        let mut order: Vec<usize> = (0..len).collect();
+        let mut at = current;
+        if let Some(rng) = &mut rng {
+            rng.shuffle(&mut order);
+            at = current.and_then(|c| order.iter().position(|&i| i == c)).map(|i| {
+                order.swap(0, i);
+                0
+            });
+        }
+        Self { order, at, rng }
+    }
+
+    /// The list index to play after `step`, and its position in this pass.
+    fn step(&mut self, step: FileStep) -> (usize, usize) {
+        let len = self.order.len();
+        let at = self.at.map_or(0, |at| step.apply(at, len));
+        if let (Some(rng), Some(last), FileStep::Next, 0) = (&mut self.rng, self.at, step, at) {
+            // Past the end: a new pass in a new order, which doesn't repeat the file just played.
+            let last = self.order[last];
+            rng.shuffle(&mut self.order);
+            if len > 1 && self.order[0] == last {
+                self.order.swap(0, 1);
+            }
+        }
+        self.at = Some(at);
+        (self.order[at], at)
+    }
+}
+
+/// Settings chosen in the menu that apply to a whole playback session.
+#[derive(Debug, Clone)]
+struct PlayOptions {
+    mode: RenderMode,
+    /// Frame rate for image sequences, which have no container timing of their own.
+    sequence_fps: u32,
+    /// ffmpeg `-threads` for decoding; `None` lets ffmpeg decide.
+    threads: Option<u32>,
+    /// Most frames drawn per second; decoding still runs at the source rate.
+    fps_cap: Option<u32>,
+    /// Drawn frames kept so the inspector can step back without restarting ffmpeg; each takes the
+    /// decode size's frame in memory.
+    rewind_frames: usize,
+    /// Frames a reader thread decodes ahead, and playback waits for before its clock starts;
+    /// 0 reads each frame as it's needed.
+    prebuffer: usize,
+    /// Times a network source whose ffmpeg fails mid-stream is respawned, after a growing wait,
+    /// before playback gives up. Local files fail at once.
+    reconnects: u32,
+    /// Sources faster than this are decimated by ffmpeg rather than decoded in full only for most
+    /// frames to be dropped; `None` decodes every frame.
+    max_decode_fps: Option<u32>,
+    /// Receives every drawn frame, minus the status line, for `--fifo`.
+    stream: Option<FrameStream>,
+    /// Receives every frame's timings, for `--profile-csv`.
+    profile: Option<FrameProfile>,
+    /// Switch portrait videos to PixelArt, whose half blocks double the vertical resolution.
+    portrait_auto: bool,
+    /// Decode variable frame rate videos at their average rate with ffmpeg's `fps` filter. Frames
+    /// get duplicated or dropped to fit, so motion can judder slightly, but the playback clock (and
+    /// with it subtitles, chapters and seeking) counts frames at a steady rate and can't drift.
+    /// Without it ffmpeg paces frames by their own timestamps: smoother, but the clock drifts.
+    cfr: bool,
+    /// When a frame is read a frame interval or more after it was due, drop the frames queued
+    /// behind the newest and draw that, so a slow terminal shows what's current instead of
+    /// falling further behind. The dropped frames still count towards the position.
+    catch_up: bool,
+    /// Start interlaced videos with `yadif` deinterlacing on (`d` toggles it).
+    deinterlace: bool,
+    /// Start HDR videos with tonemapping to SDR on (`e` toggles it). It's costly, but PQ and HLG
+    /// pictures look washed out or dark without it.
+    tonemap: bool,
+    /// When starting from the beginning, skip leading frames darker than this average luma.
+    skip_black: Option<u8>,
+    ascii_color: AsciiColor,
+    /// AsciiArt glyphs from darkest to brightest.
+    ramp: &'static [char],
+    /// Brightness, contrast and gamma applied to every frame before anything else.
+    levels: Levels,
+    fit: Fit,
+    /// Render into this many columns and rows instead of the terminal's, where given.
+    cells: (Option<u16>, Option<u16>),
+    /// Start with temporal smoothing on, giving the previous frame this weight.
+    smooth: Option<f32>,
+    /// Start with a border around the video, which leaves a one-cell margin for it.
+    border: bool,
+    border_color: (u8, u8, u8),
+    /// Start with the resolution adapting to how fast frames render.
+    auto_quality: bool,
+    /// Mark stretches of all-black frames on the status line, so playback doesn't look frozen.
+    black_indicator: bool,
+    /// Scaling algorithms; the source size is filled in per file by `DecodePlan`.
+    scaling: Scaling,
+    /// Region of the source to render; the decode size is fitted to it instead of the whole frame.
+    crop: Option<Crop>,
+    /// Subtitles for ffmpeg to draw into the source frames, ahead of every other filter.
+    burn_subs: Option<Burn>,
+    /// Without `crop`, detect black bars in the source and crop to the picture inside them.
+    auto_crop: bool,
+    /// Fill for the margins around the video.
+    backdrop: Option<Backdrop>,
+    /// Colors the terminal can show; every escape is reduced to them.
+    depth: ColorDepth,
+    /// Unvalidated ffmpeg arguments for the decoder, from `--ffmpeg-args`.
+    ffmpeg_args: Vec<String>,
+    /// Wrap each frame in a synchronized update.
+    sync_output: bool,
+    /// Decode color output as rgb48 and dither it to rgb24, for smoother gradients from sources
+    /// with more than 8 bits per channel.
+    deep_color: bool,
+    /// Hold frames that change by at most this much per byte on average from the one on screen,
+    /// for viewers bothered by flicker.
+    reduce_motion: Option<u8>,
+    /// Finishing a video returns at once instead of waiting on the summary or a single frame, and
+    /// the menu quits after it.
+    exit_on_finish: bool,
+    /// Show the frame number, its timestamp, the wall clock and the drift between them, to tell
+    /// whether playback keeps real-time pace.
+    debug_sync: bool,
+    /// Don't advance by the clock: each frame, every one drawn, stays up until space or the right
+    /// arrow asks for the next.
+    manual_step: bool,
+    /// Show the wall-clock time played, the media time and the lag between them in the lower-left
+    /// corner, to tell how far a slow terminal has fallen behind in all.
+    show_lag: bool,
+    /// Start with the ramp following each scene's brightness, toggled with `l`.
+    adaptive_ramp: bool,
+    /// Picture adjustments playback starts with; the playback keys change them from there.
+    hflip: bool,
+    vflip: bool,
+    mirror: Mirror,
+    saturation_steps: i32,
+    auto_contrast: bool,
+    /// `<`/`>` end playback asking for the previous or next file, for playback started from the list.
+    browse: bool,
+}
+
+/// Transforms the user can toggle while a video is playing.
+#[derive(Debug, Clone, Default)]
+struct PlaybackState {
+    /// Run `yadif` first, for interlaced sources.
+    deinterlace: bool,
+    /// Tonemap HDR to SDR ahead of the scaling.
+    tonemap: bool,
+    hflip: bool,
+    vflip: bool,
+    auto_contrast: bool,
+    /// Use `AdaptiveRamp`'s version of the ramp for the scene instead of the ramp as given.
+    adaptive_ramp: bool,
+    /// Kaleidoscope symmetry, applied to each frame before it's rendered.
+    mirror: Mirror,
+    /// `SATURATION_STEP`s the color saturation is above (or below) 1.0.
+    saturation_steps: i32,
+    /// Blend each frame with the previous one to calm color noise.
+    smoothing: bool,
+    guides: bool,
+    /// Decode thread cap, shown so a lower CPU reading has an explanation.
+    threads: Option<u32>,
+    fps_cap: Option<u32>,
+    /// Frame rate of the source, to show next to the capped rate.
+    source_fps: f64,
+    /// Live CPU/memory readout in the top-right corner.
+    show_stats: bool,
+    /// Live color histogram and dominant colors of the frame in the top-left corner.
+    histogram: bool,
+    /// Portrait video played in PixelArt instead of the selected mode.
+    portrait_switch: bool,
+    /// Image mode was selected but the terminal has no image protocol, so PixelArt is used.
+    image_fallback: bool,
+    /// Short-lived message for the status line, with when the playback loop drops it.
+    notice: Option<(String, Instant)>,
+    /// Title of the chapter being played, for files with chapters.
+    chapter: Option<String>,
+    /// Label of the embedded subtitle track being overlaid.
+    subtitle: Option<String>,
+    /// Frames dropped because they couldn't be read or rendered.
+    bad_frames: u64,
+    /// Position of the current frame while frames have been black for a while.
+    black: Option<f64>,
+    /// The viewport into a frame decoded larger than the terminal, while panning.
+    pan: Option<Pan>,
+    /// Cells the frame has been moved right and down from the center with the arrow keys.
+    shift: (i32, i32),
+    /// What ffmpeg is asked to output.
+    format: PixelFormat,
+    /// `SCALE_STEP_PERCENT` steps the decode size is below the terminal-fit size.
+    scale_steps: u32,
+    /// Draw a border around the video.
+    border: bool,
+    /// How the video is sized to the terminal, toggled with `w`.
+    fit: Fit,
+    /// Lower the resolution when rendering falls behind the frame rate, and restore it after.
+    auto_quality: bool,
+    /// Steps the adaptive resolution has taken below `scale_steps`.
+    auto_steps: u32,
+    /// How ffmpeg resizes the source to the decode size.
+    scaling: Scaling,
+    /// Source region cut out ahead of scaling, from `DecodePlan`.
+    crop: Option<Crop>,
+    /// `crop` was detected around black bars rather than asked for, so the status line shows it.
+    auto_crop: bool,
+    /// Constant output rate for a variable frame rate source, from `DecodePlan`.
+    cfr_fps: Option<f64>,
+    /// The source's rate, when `cfr_fps` is a cap it was decimated to.
+    decimated_from: Option<f64>,
+}
+
+impl PlaybackState {
+    /// The transforms `opts` starts playback with; `DecodePlan::playback_state` adds the ones that
+    /// depend on the source.
+    fn new(opts: &PlayOptions) -> Self {
+        Self {
+            threads: opts.threads,
+            fps_cap: opts.fps_cap,
+            smoothing: opts.smooth.is_some(),
+            border: opts.border,
+            fit: opts.fit,
+            hflip: opts.hflip,
+            vflip: opts.vflip,
+            mirror: opts.mirror,
+            saturation_steps: opts.saturation_steps,
+            auto_contrast: opts.auto_contrast,
+            auto_quality: opts.auto_quality,
+            ..Default::default()
+        }
+    }
+
+    /// Applies the effects done in-process rather than by ffmpeg to a `width` by `height` frame,
+    /// in playback's order: `levels`, auto contrast, the mirror and saturation. Auto contrast
+    /// smooths across frames, so it's skipped without a `contrast` to carry that over.
+    fn apply_effects(&self, frame: &mut [u8], (width, height): (u32, u32), format: PixelFormat, levels: &Levels, contrast: Option<&mut AutoContrast>) {
+        if !levels.is_neutral() {
+            levels.apply(frame);
+        }
+        if let Some(contrast) = contrast.filter(|_| self.auto_contrast) {
+            contrast.apply(frame, format.bytes_per_pixel());
+        }
+        self.mirror.apply(frame, width as usize, height as usize, format.bytes_per_pixel());
+        if self.saturation_steps != 0 && format == PixelFormat::Rgb24 {
+            effects::saturate(frame, self.saturation());
+        }
+    }
+
+    fn filters(&self, width: u32, height: u32) -> FilterChain {
+        FilterChain::new()
+            .video_if(self.deinterlace, "yadif")
+            .video_if(self.crop.is_some(), self.crop.map(|c| c.filter()).unwrap_or_default())
+            .video_if(self.cfr_fps.is_some(), format!("fps={:.3}", self.cfr_fps.unwrap_or_default()))
+            .video_if(self.tonemap, TONEMAP_FILTER)
+            .video(self.scaling.filter(width, height))
+            .video_if(self.hflip, "hflip")
+            .video_if(self.vflip, "vflip")
+            .video(format!("format={}", self.format.name()))
+    }
+
+    /// Text for the status overlay, or `None` when nothing non-default is active.
+    fn status(&self) -> Option<String> {
+        let mut parts = Vec::new();
+        if let Some((notice, _)) = &self.notice { parts.push(notice.as_str()); }
+        if self.portrait_switch { parts.push(Msg::StatusPortrait.text()); }
+        if self.image_fallback { parts.push(Msg::StatusImageFallback.text()); }
+        let chapter = self.chapter.as_ref().map(|c| Msg::StatusChapter.fill(&[c]));
+        if let Some(c) = &chapter { parts.push(c.as_str()); }
+        let subtitle = self.subtitle.as_ref().map(|s| Msg::StatusSubtitle.fill(&[s]));
+        if let Some(s) = &subtitle { parts.push(s.as_str()); }
+        let threads = self.threads.map(|t| Msg::StatusThreads.fill(&[&t]));
+        if let Some(t) = &threads { parts.push(t.as_str()); }
+        let fps = self.fps_cap.map(|cap| Msg::StatusFps.fill(&[&format!("{:.0}", (cap as f64).min(self.source_fps)), &format!("{:.0}", self.source_fps)]));
+        if let Some(f) = &fps { parts.push(f.as_str()); }
+        let decimated = self.decimated_from.zip(self.cfr_fps).map(|(from, to)| Msg::StatusDecodeCap.fill(&[&format!("{:.0}", from), &format!("{:.0}", to)]));
+        if let Some(d) = &decimated { parts.push(d.as_str()); }
+        if self.deinterlace { parts.push(Msg::StatusDeinterlace.text()); }
+        if self.tonemap { parts.push(Msg::StatusTonemap.text()); }
+        let auto_crop = self.crop.filter(|_| self.auto_crop).map(|c| Msg::StatusAutoCrop.fill(&[&c]));
+        if let Some(c) = &auto_crop { parts.push(c.as_str()); }
+        if self.hflip { parts.push(Msg::StatusHflip.text()); }
+        if self.vflip { parts.push(Msg::StatusVflip.text()); }
+        if self.auto_contrast { parts.push(Msg::StatusAutoContrast.text()); }
+        if self.adaptive_ramp { parts.push(Msg::StatusAdaptiveRamp.text()); }
+        let mirror = match self.mirror {
+            Mirror::Off => None,
+            Mirror::Horizontal => Some(Msg::MirrorHorizontal),
+            Mirror::Vertical => Some(Msg::MirrorVertical),
+            Mirror::Quad => Some(Msg::MirrorQuad),
+        }
+        .map(|m| Msg::StatusMirror.fill(&[&m.text()]));
+        if let Some(m) = &mirror { parts.push(m.as_str()); }
+        let saturation = (self.saturation_steps != 0).then(|| Msg::StatusSaturation.fill(&[&format!("{:.1}", self.saturation())]));
+        if let Some(s) = &saturation { parts.push(s.as_str()); }
+        let pan = self.pan.map(|p| p.percent()).map(|(x, y)| Msg::StatusPan.fill(&[&x, &y]));
+        if let Some(p) = &pan { parts.push(p.as_str()); }
+        if self.smoothing { parts.push(Msg::StatusSmoothing.text()); }
+        if self.guides { parts.push(Msg::StatusGuides.text()); }
+        let shift = (self.shift != (0, 0)).then(|| Msg::StatusShift.fill(&[&format!("{:+}", self.shift.0), &format!("{:+}", self.shift.1)]));
+        if let Some(s) = &shift { parts.push(s.as_str()); }
+        let auto = self.auto_quality.then(|| match self.auto_steps {
+            0 => Msg::StatusAutoQuality.text().to_string(),
+            steps => Msg::StatusAutoQualityLowered.fill(&[&(steps * SCALE_STEP_PERCENT)]),
+        });
+        if let Some(a) = &auto { parts.push(a.as_str()); }
+        let scale = (self.scale_steps + self.auto_steps > 0).then(|| Msg::StatusScale.fill(&[&self.scale_percent()]));
+        if let Some(s) = &scale { parts.push(s.as_str()); }
+        let bad = (self.bad_frames > 0).then(|| Msg::StatusBadFrames.fill(&[&self.bad_frames]));
+        if let Some(b) = &bad { parts.push(b.as_str()); }
+        let black = self.black.map(|position| Msg::StatusBlack.fill(&[&format_clock(position)]));
+        if let Some(b) = &black { parts.push(b.as_str()); }
+        if parts.is_empty() { None } else { Some(parts.join(" | ")) }
+    }
+
+    fn saturation(&self) -> f32 {
+        1.0 + self.saturation_steps as f32 * SATURATION_STEP
+    }
+
+    fn scale_percent(&self) -> u32 {
+        100 - (self.scale_steps + self.auto_steps).min(MAX_SCALE_STEPS) * SCALE_STEP_PERCENT
+    }
+}
+
+/// Where the terminal sits in a frame decoded larger than it, for `P` panning.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct Pan {
+    /// Top-left pixel of the viewport.
+    offset: (u32, u32),
+    /// Size of the viewport: everything the terminal can show in the current mode.
+    view: (u32, u32),
+    /// Largest offset that still fills the viewport, from the frame size.
+    max: (u32, u32),
+}
+
+impl Pan {
+    /// A viewport of `view` pixels in the middle of a `frame`-sized frame.
+    fn centered(frame: (u32, u32), view: (u32, u32)) -> Self {
+        let mut pan = Self { view, ..Default::default() };
+        pan.fit(frame);
+        pan.offset = (pan.max.0 / 2, pan.max.1 / 2);
+        pan
+    }
+
+    /// Follows the size ffmpeg actually decodes at, keeping the offset inside it.
+    fn fit(&mut self, frame: (u32, u32)) {
+        self.max = (frame.0.saturating_sub(self.view.0), frame.1.saturating_sub(self.view.1));
+        self.offset = (self.offset.0.min(self.max.0), self.offset.1.min(self.max.1));
+    }
+
+    /// Moves a `PAN_STEP_DIVISOR`th of the viewport per unit of `dx` and `dy`.
+    fn scroll(&mut self, dx: i32, dy: i32) {
+        let step = |offset: u32, view: u32, d: i32, max: u32| {
+            let moved = offset as i64 + d as i64 * (view / PAN_STEP_DIVISOR).max(1) as i64;
+            moved.clamp(0, max as i64) as u32
+        };
+        self.offset = (step(self.offset.0, self.view.0, dx, self.max.0), step(self.offset.1, self.view.1, dy, self.max.1));
+    }
+
+    /// How far across and down the viewport is, in percent.
+    fn percent(&self) -> (u32, u32) {
+        let percent = |offset: u32, max: u32| (offset * 100).checked_div(max).unwrap_or(0);
+        (percent(self.offset.0, self.max.0), percent(self.offset.1, self.max.1))
+    }
+
+    /// Whether there's more of the frame to the left, right, top and bottom.
+    fn marks(&self) -> (bool, bool, bool, bool) {
+        (self.offset.0 > 0, self.offset.0 < self.max.0, self.offset.1 > 0, self.offset.1 < self.max.1)
+    }
+}
+
+/// Decode size for panning across a `source`-sized video whose terminal-fit size is `full`, or
+/// `None` when it wouldn't be noticeably larger than `full`.
+fn pan_target(full: (u32, u32), source: (u32, u32)) -> Option<(u32, u32)> {
+    let zoom = (source.0 as f64 / full.0.max(1) as f64).min(MAX_PAN_ZOOM);
+    let scale = |v: u32| ((v as f64 * zoom) as u32 / 2 * 2).max(2);
+    (zoom >= MIN_PAN_ZOOM).then(|| (scale(full.0), scale(full.1)))
+}
+
+/// `full` scaled to `percent`, kept even and at least 2x2 like `render::target_size`.
+fn scaled_target(full: (u32, u32), percent: u32) -> (u32, u32) {
+    let scale = |v: u32| (v * percent / 100 / 2 * 2).max(2);
+    (scale(full.0), scale(full.1))
+}
+
+/// Next cap after `cap` in the `F` cycle: uncapped, then each of `FPS_CAP_STEPS`, then uncapped again.
+fn next_fps_cap(cap: Option<u32>) -> Option<u32> {
+    match cap {
+        None => Some(FPS_CAP_STEPS[0]),
+        Some(c) => FPS_CAP_STEPS.iter().copied().find(|&step| step < c),
+    }
+}
+
+/// Whether the `frame`th decoded frame (1-based) should be drawn under `cap`.
+///
+/// Frames are picked evenly in video time, so a 24 fps source capped to 10 draws frames 3, 5, 8, ...
+fn should_render(frame: u64, source_fps: f64, cap: Option<u32>) -> bool {
+    let Some(cap) = cap else { return true };
+    if cap as f64 >= source_fps || frame == 0 {
+        return true;
+    }
+    let slot = |n: u64| (n as f64 * cap as f64 / source_fps).floor();
+    slot(frame) != slot(frame - 1)
+}
+
+/// A running ffmpeg process producing raw frames of a known size and format.
+struct Decoder {
+    child: ChildGuard,
+    /// The stderr reader, which hands back the last lines once ffmpeg exits.
+    stderr_tail: Option<thread::JoinHandle<VecDeque<String>>>,
+    pipe: FramePipe,
+    width: u32,
+    height: u32,
+    /// Layout of the frames `read_frame` hands out.
+    format: PixelFormat,
+    /// Frames as ffmpeg sends them, when they're rgb48 to be dithered down to `format`.
+    deep: Option<Vec<u8>>,
+}
+
+/// Where a `Decoder`'s frames come from. Always ffmpeg's stdout: even a 1920x1080 rgb24 frame,
+/// larger than any terminal decodes, reads off a pipe in about 2.5 ms. The reader thread takes
+/// that off the render path. A memory-mapped rawvideo file would cut the copy, but it grows
+/// without bound on disk and has to be remapped as ffmpeg appends to it.
+enum FramePipe {
+    /// Read from ffmpeg's stdout as they're asked for.
+    Direct(ChildStdout),
+    /// Read ahead on a thread, so decoding overlaps rendering.
+    Threaded(FrameReader),
+}
+
+/// A thread reading ffmpeg's frames into a bounded queue. It ends at the end of the pipe, which
+/// killing ffmpeg brings about, or when the queue's receiver goes; dropping the reader waits for it.
+struct FrameReader {
+    /// Frames taken off the queue ahead of time by `Decoder::prebuffer`.
+    ready: VecDeque<io::Result<Vec<u8>>>,
+    queue: Option<mpsc::Receiver<io::Result<Vec<u8>>>>,
+    /// Frames already copied out, handed back so the thread needn't allocate more.
+    spare: mpsc::Sender<Vec<u8>>,
+    thread: Option<thread::JoinHandle<()>>,
+    /// The queue's bound, and so the most frames there can be to skip.
+    capacity: usize,
+}
+
+impl FrameReader {
+    fn start(mut stdout: ChildStdout, mut deep: Option<Vec<u8>>, (width, height): (u32, u32), frame_size: usize, capacity: usize) -> Self {
+        let (tx, queue) = mpsc::sync_channel(capacity);
+        let (spare, spares) = mpsc::channel::<Vec<u8>>();
+        let thread = thread::spawn(move || loop {
+            let mut frame = spares.try_recv().unwrap_or_else(|_| vec![0u8; frame_size]);
+            let read = read_raw(&mut stdout, &mut deep, (width, height), &mut frame).map(|()| frame);
+            let ended = matches!(&read, Err(e) if e.kind() == io::ErrorKind::UnexpectedEof);
+            if tx.send(read).is_err() || ended {
+                break;
+            }
+        });
+        FrameReader { ready: VecDeque::new(), queue: Some(queue), spare, thread: Some(thread), capacity }
+    }
+
+    /// Drops every decoded frame but the newest, which `read_frame` hands out next, and returns
+    /// how many went. An error or the end of the pipe stays queued behind the frames before it.
+    fn skip_to_latest(&mut self) -> usize {
+        while self.ready.len() <= self.capacity && self.ready.back().is_none_or(|read| read.is_ok()) {
+            let Some(read) = self.queue.as_ref().and_then(|queue| queue.try_recv().ok()) else { break };
+            self.ready.push_back(read);
+        }
+        let mut skipped = 0;
+        while self.ready.len() > 1 && self.ready.get(1).is_some_and(|read| read.is_ok()) {
+            if let Some(Ok(frame)) = self.ready.pop_front() {
+                let _ = self.spare.send(frame);
+            }
+            skipped += 1;
+        }
+        skipped
+    }
+
+    fn next(&mut self, timeout: Option<Duration>) -> Option<io::Result<Vec<u8>>> {
+        let queue = self.queue.as_ref()?;
+        match timeout {
+            Some(timeout) => queue.recv_timeout(timeout).ok(),
+            None => queue.recv().ok(),
+        }
+    }
+
+    fn read_frame(&mut self, buffer: &mut [u8]) -> io::Result<()> {
+        match self.ready.pop_front().or_else(|| self.next(None)) {
+            Some(Ok(frame)) => {
+                buffer.copy_from_slice(&frame);
+                let _ = self.spare.send(frame);
+                Ok(())
+            }
+            Some(Err(e)) => Err(e),
+            None => Err(io::ErrorKind::UnexpectedEof.into()),
+        }
+    }
+}
+
+impl Drop for FrameReader {
+    fn drop(&mut self) {
+        // Unblocks a thread waiting on a full queue; one mid-read ends with ffmpeg, which the
+        // `Decoder` kills before dropping its pipe.
+        drop(self.queue.take());
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Reads one frame of `width`x`height` from ffmpeg into `buffer`, through `deep` when it's rgb48
+/// to be dithered down.
+fn read_raw(stdout: &mut ChildStdout, deep: &mut Option<Vec<u8>>, (width, height): (u32, u32), buffer: &mut [u8]) -> io::Result<()> {
+    match deep {
+        Some(deep) => {
+            deep.resize((width * height) as usize * PixelFormat::Rgb48.bytes_per_pixel(), 0);
+            stdout.read_exact(deep)?;
+            effects::dither_rgb48(deep, width as usize, buffer);
+            Ok(())
+        }
+        None => stdout.read_exact(buffer),
+    }
+}
+
+/// The `-vf` chain followed by the user's `--ffmpeg-args`. ffmpeg keeps only the last `-vf`, so
+/// one among the user's arguments is run ahead of `filters` instead of replacing the scaling.
+/// The rest goes through untouched, before the rawvideo output options that must win.
+///
+/// `burn` goes first of all. When it starts with input labels, as the bitmap subtitle overlay
+/// does, the chain reads two streams and is passed as `-filter_complex` instead.
+fn output_args(extra: &[String], burn: Option<&str>, filters: &FilterChain) -> Vec<String> {
+    let mut user_filters = Vec::new();
+    let mut rest = Vec::new();
+    let mut extra = extra.iter();
+    while let Some(arg) = extra.next() {
+        match arg.as_str() {
+            "-vf" | "-filter:v" => user_filters.extend(extra.next().cloned()),
+            _ => rest.push(arg.clone()),
+        }
+    }
+    let complex = burn.is_some_and(|b| b.starts_with('['));
+    let chain = burn.map(str::to_string).into_iter().chain(user_filters).chain(filters.video_arg()).collect::<Vec<_>>().join(",");
+    let flag = if complex { "-filter_complex" } else { "-vf" };
+    let mut args = if chain.is_empty() { Vec::new() } else { vec![flag.to_string(), chain] };
+    args.extend(rest);
+    args
+}
+
+impl Decoder {
+    /// The ffmpeg invocation behind `spawn`, also printed by `--dry-run`.
+    ///
+    /// `realtime` paces decoding to the source frame rate (`-re`) for playback.
+    fn command(video_path: &Path, plan: &DecodePlan, start: f64, filters: &FilterChain, realtime: bool) -> Command {
+        let mut cmd = Command::new(get_command_path("ffmpeg"));
+        cmd.arg("-hide_banner");
+        if remote::is_remote(video_path) {
+            cmd.args(["-v", "verbose"]);
+        }
+        if realtime {
+            cmd.arg("-re");
+        }
+        if plan.burn_subs.as_ref().is_some_and(Burn::needs_source_timestamps) {
+            cmd.arg("-copyts");
+        }
+        let burn = plan.burn_subs.as_ref().map(|b| b.filter(video_path));
+        cmd.arg("-ss")
+            .arg(format!("{:.3}", start))
+            .args(remote::reconnect_args(video_path))
+            .args(&plan.input_args)
+            .arg("-i")
+            .arg(video_path)
+            // Frames go to a video-only pipe, so any audio filters are not applicable here.
+            .args(output_args(&plan.extra_args, burn.as_deref(), filters))
+            .arg("-vcodec")
+            .arg("rawvideo")
+            .arg("-pix_fmt")
+            .arg(plan.decode_format.name())
+            .arg("-f")
+            .arg("image2pipe")
+            .arg("-");
+        cmd
+    }
+
+    /// Starts decoding at `plan.target` from `start` seconds in. Until the output stream is known,
+    /// a URL's connection progress goes to `on_status`.
+    fn spawn(
+        video_path: &Path,
+        plan: &DecodePlan,
+        start: f64,
+        filters: &FilterChain,
+        realtime: bool,
+        fits: impl Fn(u32, u32) -> bool,
+        on_status: &mut dyn FnMut(String),
+    ) -> Result<Self> {
+        let (width, height) = plan.target;
+        let mut cmd = Self::command(video_path, plan, start, filters, realtime);
+        let mut child = ChildGuard::spawn(cmd.stdout(Stdio::piped()).stderr(Stdio::piped()))?;
+
+        let stdout = child.0.stdout.take().context("Failed to open stdout")?;
+        let stderr = child.0.stderr.take().context("Failed to open stderr")?;
+        let deep = (plan.decode_format != plan.format).then(Vec::new);
+        let (events, stderr_tail) = watch_stderr(stderr);
+        let mut decoder = Decoder { child, stderr_tail: Some(stderr_tail), pipe: FramePipe::Direct(stdout), width, height, format: plan.format, deep };
+
+        // ffmpeg may round the requested size; trust what it reports for the output stream.
+        let remote = remote::is_remote(video_path);
+        let deadline = Instant::now() + if remote { REMOTE_STARTUP_TIMEOUT } else { STARTUP_TIMEOUT };
+        let event = loop {
+            match events.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+                Ok(StderrEvent::Progress(line)) => {
+                    if remote {
+                        on_status(line);
+                    }
+                }
+                other => break other,
+            }
+        };
+        match event {
+            Ok(StderrEvent::MissingDecoder(codec)) => {
+                let codec = codec.unwrap_or_else(|| plan.codec.clone());
+                anyhow::bail!(
+                    "this ffmpeg build has no decoder for the {} codec; install an ffmpeg with {} decoding enabled",
+                    codec, codec
+                );
+            }
+            Ok(StderrEvent::OutputSize(w, h)) if (w, h) == (width, height) => {}
+            Ok(StderrEvent::OutputSize(w, h)) => {
+                trace::note(format!("ffmpeg rounded the output to {}x{} (requested {}x{})", w, h, width, height));
+                if !fits(w, h) {
+                    anyhow::bail!(
+                        "ffmpeg output size {}x{} does not match the requested {}x{}",
+                        w, h, width, height
+                    );
+                }
+                decoder.width = w;
+                decoder.height = h;
+            }
+            Ok(StderrEvent::Progress(_)) | Err(_) => {}
+        }
+        Ok(decoder)
+    }
+
+    fn frame_size(&self) -> usize {
+        (self.width * self.height) as usize * self.format.bytes_per_pixel()
+    }
+
+    /// Reads the next frame into `buffer`, `frame_size` bytes long, dithering rgb48 down first.
+    fn read_frame(&mut self, buffer: &mut [u8]) -> io::Result<()> {
+        match &mut self.pipe {
+            FramePipe::Direct(stdout) => read_raw(stdout, &mut self.deep, (self.width, self.height), buffer),
+            FramePipe::Threaded(reader) => reader.read_frame(buffer),
+        }
+    }
+
+    /// Hands reading to a thread that keeps up to `capacity` decoded frames queued, so ffmpeg's
+    /// output is read while the previous frame renders.
+    fn threaded(self, capacity: usize) -> Self {
+        let Decoder { child, stderr_tail, pipe, width, height, format, deep } = self;
+        let pipe = match pipe {
+            FramePipe::Direct(stdout) => {
+                let frame_size = (width * height) as usize * format.bytes_per_pixel();
+                FramePipe::Threaded(FrameReader::start(stdout, deep, (width, height), frame_size, capacity))
+            }
+            threaded => threaded,
+        };
+        Decoder { child, stderr_tail, pipe, width, height, format, deep: None }
+    }
+
+    /// With a threaded pipe, waits until `frames` frames are decoded (or ffmpeg ends, or
+    /// `STARTUP_TIMEOUT` passes), so an uneven start doesn't stutter the first frames.
+    /// `on_fill` hears how many are in.
+    fn prebuffer(&mut self, frames: usize, on_fill: &mut dyn FnMut(usize)) {
+        let FramePipe::Threaded(reader) = &mut self.pipe else { return };
+        let deadline = Instant::now() + STARTUP_TIMEOUT;
+        while reader.ready.len() < frames {
+            let Some(read) = reader.next(Some(deadline.saturating_duration_since(Instant::now()))) else { break };
+            let ended = read.is_err();
+            reader.ready.push_back(read);
+            on_fill(reader.ready.len());
+            if ended {
+                break;
+            }
+        }
+    }
+
+    /// With a threaded pipe, skips the frames queued behind the newest one; see
+    /// `FrameReader::skip_to_latest`.
+    fn skip_to_latest(&mut self) -> usize {
+        match &mut self.pipe {
+            FramePipe::Threaded(reader) => reader.skip_to_latest(),
+            FramePipe::Direct(_) => 0,
+        }
+    }
+
+    /// Once the frames run out: how ffmpeg failed, with the end of its stderr, or `None` if it
+    /// exited cleanly.
+    fn failure(&mut self) -> Option<String> {
+        let status = self.child.0.wait().ok()?;
+        if status.success() {
+            return None;
+        }
+        let tail = self.stderr_tail.take().and_then(|t| t.join().ok()).unwrap_or_default();
+        Some(tail.iter().fold(status.to_string(), |message, line| format!("{}\n  {}", message, line)))
+    }
+}
+
+/// A spawned ffmpeg that is killed and reaped when dropped, so no exit path (finishing, quitting,
+/// an error part-way through setup) can leave it running.
+struct ChildGuard(Child);
+
+impl ChildGuard {
+    fn spawn(cmd: &mut Command) -> Result<Self> {
+        trace::command(cmd);
+        cmd.spawn().map(Self).context("Failed to spawn ffmpeg")
+    }
+}
+
+impl Drop for ChildGuard {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
+
+/// How a video will be decoded for the current terminal, shared by playback and `--dry-run`.
+struct DecodePlan {
+    mode: RenderMode,
+    /// `mode` was switched to PixelArt because the video is portrait.
+    portrait_switch: bool,
+    /// How Image frames are sent, when the terminal supports one.
+    image_protocol: Option<ImageProtocol>,
+    /// `mode` was switched to PixelArt because Image was selected without a supported protocol.
+    image_fallback: bool,
+    /// ffmpeg options placed before `-i`.
+    input_args: Vec<String>,
+    /// Decode resolution that fits the terminal.
+    target: (u32, u32),
+    /// `gray` for the monochrome ASCII modes, a third of the bytes of `rgb24`.
+    format: PixelFormat,
+    /// What ffmpeg outputs: `format`, or `rgb48` that the decoder dithers down to it.
+    decode_format: PixelFormat,
+    /// Source video codec as probed, named when ffmpeg turns out not to have a decoder for it.
+    codec: String,
+    scaling: Scaling,
+    /// `PlayOptions::crop`, or the picture inside detected black bars, checked against the source size.
+    crop: Option<Crop>,
+    /// `crop` came from `cropdetect`.
+    auto_crop: bool,
+    /// Constant rate ffmpeg outputs frames at: the average of a variable frame rate source, when
+    /// `PlayOptions::cfr` asks, or `PlayOptions::max_decode_fps` for a source faster than that.
+    cfr_fps: Option<f64>,
+    /// The source's rate, when `cfr_fps` is the cap it's decimated to.
+    decimated_from: Option<f64>,
+    /// `--ffmpeg-args`, placed between the input and the raw video output.
+    extra_args: Vec<String>,
+    /// Deinterlace an interlaced source, when `PlayOptions::deinterlace` asks.
+    deinterlace: bool,
+    /// Tonemap an HDR source to SDR, when `PlayOptions::tonemap` asks.
+    tonemap: bool,
+    /// `PlayOptions::burn_subs`, with the track checked and its kind known.
+    burn_subs: Option<Burn>,
+}
+
+impl DecodePlan {
+    fn new(video_path: &Path, info: &VideoInfo, opts: &PlayOptions, term_w: u16, term_h: u16) -> Result<Self> {
+        let mut input_args = sequence::input_args(video_path, opts.sequence_fps);
+        if let Some(threads) = opts.threads {
+            input_args.extend(["-threads".to_string(), threads.to_string()]);
+        }
+        // An explicit `--crop` says what to render, so there's nothing to detect.
+        let detected = if opts.auto_crop && opts.crop.is_none() { detect_bars(video_path, &input_args, info) } else { None };
+        let crop = opts.crop.or(detected);
+        // Unknown sizes can't be checked; ffmpeg reports a crop that doesn't fit.
+        if let Some(crop) = crop.filter(|c| info.width > 0 && info.height > 0 && !c.fits(info.width, info.height)) {
+            anyhow::bail!("Crop {} doesn't fit the {}x{} source of {}", crop, info.width, info.height, video_path.display());
+        }
+        let (src_w, src_h) = crop.map_or((info.width, info.height), |c| (c.w, c.h));
+        // Anamorphic video is sized by the shape it's shown at; ffmpeg scales it to that.
+        let (shown_w, shown_h) = info.display_size(src_w, src_h);
+        let burn_subs = match opts.burn_subs.clone() {
+            Some(Burn::File(path)) if !path.exists() => anyhow::bail!("Subtitle file not found: {}", path.display()),
+            Some(Burn::Text(index) | Burn::Bitmap(index)) => Some(
+                Burn::Text(index)
+                    .resolve(&probe_subtitle_tracks(video_path))
+                    .with_context(|| format!("{} has no subtitle track #{}", video_path.display(), index + 1))?,
+            ),
+            other => other,
+        };
+        let image_protocol = if opts.mode == RenderMode::Image { graphics::detect() } else { None };
+        let image_fallback = opts.mode == RenderMode::Image && image_protocol.is_none();
+        // Images are scaled by the terminal, so only the one-pixel-per-cell modes gain from switching.
+        let portrait_switch = opts.portrait_auto
+            && matches!(opts.mode, RenderMode::AsciiArt | RenderMode::Blocks)
+            && render::is_portrait(shown_w, shown_h);
+        let mode = if portrait_switch || image_fallback { RenderMode::PixelArt } else { opts.mode };
+        // A border needs a cell of margin on every side.
+        let (fit_w, fit_h) = if opts.border { (term_w.saturating_sub(2), term_h.saturating_sub(2)) } else { (term_w, term_h) };
+        let mut target = render::target_size(mode, opts.fit, shown_w, shown_h, fit_w, fit_h);
+        if mode == RenderMode::Image {
+            target = graphics::decode_size((target.0, target.1 / 2), graphics::cell_size_px());
+        }
+        let format = PixelFormat::for_output(mode, opts.ascii_color);
+        let decode_format = if opts.deep_color && format == PixelFormat::Rgb24 { PixelFormat::Rgb48 } else { format };
+        trace::note(format!(
+            "{}x{} source{} -> {}x{} {} decode for a {}x{} terminal, mode {:?}{}{}",
+            info.width, info.height, crop.map(|c| format!(" cropped to {}", c)).unwrap_or_default(),
+            target.0, target.1, decode_format.name(), term_w, term_h, mode,
+            image_protocol.map(|p| format!(" via {}", p)).unwrap_or_default(),
+            if portrait_switch { " (switched for portrait)" } else if image_fallback { " (no image protocol)" } else { "" }
+        ));
+        let scaling = Scaling { source: (src_w, src_h), fill: opts.fit.crops(), ..opts.scaling };
+        let cfr_fps = info.vfr_average().filter(|_| opts.cfr).map(f64::from);
+        if let Some(avg) = cfr_fps {
+            trace::note(format!("variable frame rate ({:.3} base, {:.3} average): converting to constant", info.fps, avg));
+        }
+        let decode_cap = info.decode_cap(opts.max_decode_fps);
+        let decimated_from = decode_cap.map(|_| info.frame_rate());
+        if let (Some(cap), Some(rate)) = (decode_cap, decimated_from) {
+            trace::note(format!("{:.3} fps source: decimating to {} fps", rate, cap));
+        }
+        let cfr_fps = decode_cap.or(cfr_fps);
+        Ok(Self {
+            mode,
+            portrait_switch,
+            image_protocol,
+            image_fallback,
+            input_args,
+            target,
+            format,
+            decode_format,
+            codec: info.video_codec.clone(),
+            scaling,
+            cfr_fps,
+            decimated_from,
+            extra_args: opts.ffmpeg_args.clone(),
+            deinterlace: opts.deinterlace && info.is_interlaced(),
+            tonemap: opts.tonemap && info.is_hdr(),
+            crop,
+            auto_crop: detected.is_some(),
+            burn_subs,
+        })
+    }
+
+    /// What a playback of this plan with `opts` starts with, before any key changes it.
+    fn playback_state(&self, opts: &PlayOptions) -> PlaybackState {
+        PlaybackState {
+            portrait_switch: self.portrait_switch,
+            image_fallback: self.image_fallback,
+            format: self.decode_format,
+            adaptive_ramp: opts.adaptive_ramp && self.mode.uses_ramp(),
+            // Monochrome output has no color to saturate.
+            saturation_steps: if self.decode_format == PixelFormat::Gray { 0 } else { opts.saturation_steps },
+            scaling: self.scaling,
+            crop: self.crop,
+            auto_crop: self.auto_crop,
+            cfr_fps: self.cfr_fps,
+            decimated_from: self.decimated_from,
+            deinterlace: self.deinterlace,
+            tonemap: self.tonemap,
+            ..PlaybackState::new(opts)
+        }
+    }
+
+    /// The filters a playback of this plan with `opts` decodes with, before any key changes them.
+    fn filters(&self, opts: &PlayOptions) -> FilterChain {
+        self.playback_state(opts).filters(self.target.0, self.target.1)
+    }
+}
+
+/// Plays `video_path` from `start` seconds in, showing `banner` on the status line for a moment
+/// as it starts and handing the position to `on_progress` now and then. Returns where playback
+/// stopped and whether it finished, or which file the user stepped to.
+fn play_video(video_path: &Path, opts: &PlayOptions, system: &mut System, start: f64, banner: Option<String>, on_progress: &mut dyn FnMut(f64)) -> Result<PlaybackEnd> {
+    // Taken before probing, so a URL's connection progress has a screen to go on.
+    let guard = TerminalGuard::new()?;
+    let mut stdout_term = std::io::stdout();
+    let mut connect_status = Vec::new();
+    let mut show_status = |line: String| {
+        connect_status.push(line);
+        let excess = connect_status.len().saturating_sub(CONNECT_STATUS_LINES);
+        connect_status.drain(..excess);
+        show_connecting(&mut std::io::stdout(), video_path, &connect_status);
+    };
+    let info = probe_video_reporting(video_path, &mut show_status)?.with_fps(opts.sequence_fps);
+    if info.is_audio_only() {
+        return play_spectrum(video_path, start, opts, on_progress);
+    }
+    let terminal = terminal::size()?;
+    let (term_w, term_h) = output_size(opts.cells, terminal);
+    let mut plan = DecodePlan::new(video_path, &info, opts, term_w, term_h)?;
+    let DecodePlan { mode, image_protocol, format, .. } = plan;
+    // The terminal-fit size, which `-`/`+` scale down from and back up to.
+    let mut full_target = plan.target;
+    // Frames come out at the converted rate for VFR sources, which is what the clock has to count.
+    let fps = plan.cfr_fps.unwrap_or(if info.fps > 0.0 { info.fps as f64 } else { 30.0 });
+    let duration = info.known_duration();
+
+    // Only a fresh start skips the intro; resuming or jumping in means the user picked the spot.
+    let mut start = seek_target(duration, start);
+    let mut notice = oversize_warning((term_w, term_h), terminal).or(banner).map(|n| (n, Instant::now() + TOAST_DURATION));
+    if let (Some(threshold), true, true) = (opts.skip_black, start == 0.0, duration.is_some()) {
+        if let Some(skip_to) = find_black_intro(video_path, &plan.input_args, fps, threshold).ok().flatten() {
+            start = skip_to;
+            notice = Some((Msg::BlackSkipped.fill(&[&format_clock(skip_to)]), Instant::now() + TOAST_DURATION));
+        }
+    }
+
+    let fits = |w, h| render::fits(mode, w, h, term_w, term_h);
+    let mut render_opts = RenderOptions {
+        ascii_color: opts.ascii_color,
+        ramp: opts.ramp,
+        image_protocol,
+        format,
+        backdrop: opts.backdrop,
+        depth: opts.depth,
+        ..RenderOptions::new(mode, term_w, term_h)
+    };
+
+    let mut state = PlaybackState { source_fps: fps, notice, ..plan.playback_state(opts) };
+    let smooth_factor = opts.smooth.unwrap_or(effects::DEFAULT_SMOOTHING);
+    trace::note(format!("filters: {}", state.filters(plan.target.0, plan.target.1).video_arg().unwrap_or_default()));
+    let read_ahead = opts.prebuffer.max(READ_AHEAD_FRAMES);
+    let mut decoder = Decoder::spawn(video_path, &plan, start, &state.filters(plan.target.0, plan.target.1), true, fits, &mut show_status)?.threaded(read_ahead);
+    decoder.prebuffer(opts.prebuffer, &mut |n| show_buffering(&mut std::io::stdout(), n, opts.prebuffer, term_h));
+    // When the current decoder's first frame came out; later ones are held to their slots from it.
+    let mut paced_from = None;
+    // Network sources that fail mid-stream are respawned; this counts the attempts since the
+    // source last played for a while.
+    let remote = remote::is_remote(video_path);
+    let mut reconnects = 0;
+    let mut buffer = vec![0u8; decoder.frame_size()];
+    if !connect_status.is_empty() {
+        write!(stdout_term, "\x1b[0m\x1b[2J")?;
+    }
+
+    let mut render_buffer = String::with_capacity((plan.target.0 * plan.target.1 * 30) as usize);
+    // The part of the frame on screen while panning.
+    let mut window = Vec::new();
+    // Position of the current decoder's first frame, and frames read from it since.
+    let mut segment_start = start;
+    let mut frames_read: u64 = 0;
+    let mut last_progress = Instant::now();
+    let mut contrast = AutoContrast::default();
+    let mut adaptive = AdaptiveRamp::new(opts.ramp);
+    let mut smoothing = TemporalSmoothing::default();
+    let mut auto_quality = AutoQuality::default();
+    let mut motion = MotionHold::default();
+    let mut recent = RecentFrames::new(opts.rewind_frames);
+    let max_held = (fps * MAX_MOTION_HOLD).round() as u32;
+
+    // Embedded subtitle tracks, probed the first time `U` is pressed, and the cues of the chosen one.
+    let mut subtitle_tracks: Option<Vec<Track>> = None;
+    let mut subtitle_choice = 0;
+    let mut cues: Vec<Cue> = Vec::new();
+
+    let mut consecutive_bad: u32 = 0;
+    // Position of the first of the black frames just rendered.
+    let mut black_since: Option<f64> = None;
+    let mut stats_lines = Vec::new();
+    let mut last_stats: Option<Instant> = None;
+
+    // When the previous frame finished reading, for the stall watchdog. Everything that pauses
+    // playback (inspecting, the chapter and subtitle menus) stops reading on purpose and then
+    // restarts ffmpeg, which resets this, so pauses never count as stalls.
+    let mut last_read = Instant::now();
+    let frame_interval = Duration::from_secs_f64(1.0 / fps);
+    let mut stats = PlaybackStats::new(Instant::now());
+    let mut lag = LagClock::default();
+    if let Some(profile) = &opts.profile {
+        profile.start_playback();
+    }
+    let mut step = None;
+
+    let result = (|| -> Result<bool> {
+        loop {
+            // A single bad frame is skipped; only a sustained run of them ends playback.
+            let mut skip_frame = |state: &mut PlaybackState| -> Result<()> {
+                state.bad_frames += 1;
+                consecutive_bad += 1;
+                if consecutive_bad > MAX_CONSECUTIVE_BAD_FRAMES {
+                    anyhow::bail!("{} consecutive frames could not be decoded", consecutive_bad);
+                }
+                Ok(())
+            };
+
+            // A frame or more late already, what's queued is stale too: go straight to the newest
+            // rather than drawing each of them further and further behind.
+            if let Some(paced) = paced_from.filter(|_| opts.catch_up && !opts.manual_step) {
+                if Instant::now() > paced + frame_interval.mul_f64((frames_read + 1) as f64) {
+                    let skipped = decoder.skip_to_latest() as u64;
+                    frames_read += skipped;
+                    stats.skipped += skipped;
+                }
+            }
+            let read_started = Instant::now();
+            let read = decoder.read_frame(&mut buffer);
+            let (render_gap, read_gap) = (read_started - last_read, read_started.elapsed());
+            last_read = Instant::now();
+            // A fresh decoder's first frame includes ffmpeg starting up and seeking.
+            if let Some(stall) = Stall::detect(read_gap, render_gap, frame_interval).filter(|_| frames_read > 0) {
+                let message = stall.notice(read_gap + render_gap);
+                trace::note(&message);
+                state.notice = Some((message, Instant::now() + TOAST_DURATION));
+            }
+            match read {
+                Ok(()) => {}
+                Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => {
+                    let reached = segment_start + frames_read as f64 / fps;
+                    if let Some(failure) = decoder.failure().filter(|_| duration.is_none_or(|d| reached < d - DECODE_END_SLACK)) {
+                        if frames_read as f64 >= RECONNECT_STABLE_SECS * fps {
+                            reconnects = 0;
+                        }
+                        if !remote || reconnects >= opts.reconnects {
+                            anyhow::bail!("ffmpeg failed after {}: {}", format_clock(reached), failure);
+                        }
+                        reconnects += 1;
+                        trace::note(format!("reconnecting ({}/{}) after {}: {}", reconnects, opts.reconnects, format_clock(reached), failure));
+                        if !wait_to_reconnect(&mut stdout_term, reconnects, opts.reconnects, term_h)? {
+                            return Ok(false);
+                        }
+                        // A live stream has no position to seek to; it picks up wherever it is now.
+                        let seek = if duration.is_some() { seek_target(duration, reached) } else { 0.0 };
+                        let fits = |w, h| state.pan.is_some() || fits(w, h);
+                        drop(std::mem::replace(&mut decoder, Decoder::spawn(video_path, &plan, seek, &state.filters(plan.target.0, plan.target.1), true, fits, &mut |_| {})?.threaded(read_ahead)));
+                        decoder.prebuffer(opts.prebuffer, &mut |n| show_buffering(&mut stdout_term, n, opts.prebuffer, term_h));
+                        buffer.resize(decoder.frame_size(), 0);
+                        segment_start = if duration.is_some() { seek } else { reached };
+                        frames_read = 0;
+                        paced_from = None;
+                        last_read = Instant::now();
+                        write!(stdout_term, "\x1b[0m\x1b[2J")?;
+                        continue;
+                    }
+                    // Single-frame content stays on screen instead of flashing by, even with
+                    // `exit_on_finish`: that's implied for `Vodeo2ASCII photo.jpg` too.
+                    if segment_start == 0.0 && frames_read == 1 {
+                        hold_until_quit(&mut stdout_term, term_h)?;
+                    }
+                    return Ok(true);
+                }
+                Err(_) => {
+                    skip_frame(&mut state)?;
+                    continue;
+                }
+            }
+            frames_read += 1;
+            // Queued frames come out as fast as they're asked for; a late one isn't held at all.
+            let paced = *paced_from.get_or_insert_with(Instant::now);
+            if !opts.manual_step {
+                thread::sleep((paced + frame_interval.mul_f64((frames_read - 1) as f64)).saturating_duration_since(Instant::now()));
+            }
+            // Frames from the start of the video, and where the wall clock says playback should be.
+            let frame_number = (segment_start * fps).round() as u64 + frames_read - 1;
+            let wall = paced.elapsed();
+            let clock = segment_start + wall.as_secs_f64();
+            lag.tick(wall, frame_interval.mul_f64((frames_read - 1) as f64));
+            let mut position = segment_start + frames_read as f64 / fps;
+            let chapter = chapter_at(&info.chapters, position).map(|i| &info.chapters[i].title);
+            if state.chapter.as_ref() != chapter {
+                state.chapter = chapter.cloned();
+            }
+            if state.notice.as_ref().is_some_and(|(_, expiry)| Instant::now() >= *expiry) {
+                // The status line shrinks; clear what the longer one left behind.
+                state.notice = None;
+                write!(stdout_term, "\x1b[0m\x1b[2J")?;
+                motion.reset();
+            }
+            if last_progress.elapsed() >= PROGRESS_INTERVAL {
+                on_progress(position);
+                last_progress = Instant::now();
+            }
+
+            // Dropped frames are still read, so ffmpeg's real-time pacing is unaffected.
+            let mut render_time = None;
+            let mut timing = FrameTiming { decode: read_gap, ..Default::default() };
+            let render = opts.manual_step || should_render(frames_read, fps, state.fps_cap);
+            // A frame that barely differs from the one on screen leaves that one up.
+            let held = render && !opts.manual_step && opts.reduce_motion.is_some_and(|threshold| motion.hold(&buffer, threshold, max_held));
+            if render && !held {
+                let render_started = Instant::now();
+                if state.smoothing {
+                    smoothing.apply(&mut buffer, smooth_factor);
+                }
+                state.apply_effects(&mut buffer, (decoder.width, decoder.height), format, &opts.levels, Some(&mut contrast));
+                if opts.black_indicator {
+                    // A while of nothing but black looks like a hang; say what's going on.
+                    let black = effects::is_black(&buffer, format.bytes_per_pixel(), BLACK_SCREEN_LUMA);
+                    black_since = if black { black_since.or(Some(position)) } else { None };
+                    let was_black = state.black.is_some();
+                    state.black = black_since.filter(|since| position - since >= BLACK_INDICATOR_DELAY).map(|_| position);
+                    if was_black && state.black.is_none() {
+                        // The status line goes away; clear what it left behind.
+                        write!(stdout_term, "\x1b[0m\x1b[2J")?;
+                        motion.reset();
+                    }
+                }
+                recent.push(position, &buffer);
+                let (frame, width, height) = match &mut state.pan {
+                    Some(pan) => {
+                        pan.fit((decoder.width, decoder.height));
+                        render::viewport(&buffer, (decoder.width, decoder.height), format.bytes_per_pixel(), pan.offset, pan.view, &mut window);
+                        (&window[..], pan.view.0.min(decoder.width), pan.view.1.min(decoder.height))
+                    }
+                    None => (&buffer[..], decoder.width, decoder.height),
+                };
+                render_opts.ramp = if state.adaptive_ramp { adaptive.ramp(frame, format.bytes_per_pixel()) } else { opts.ramp };
+                if render::render_frame(&mut render_buffer, frame, width, height, &render_opts).is_none() {
+                    skip_frame(&mut state)?;
+                    continue;
+                }
+                consecutive_bad = 0;
+                if state.border {
+                    render::push_border(&mut render_buffer, width, height, &render_opts, opts.border_color);
+                }
+                if state.guides {
+                    render::push_guides(&mut render_buffer, width, height, &render_opts);
+                }
+                if let Some(pan) = &state.pan {
+                    render::push_scroll_marks(&mut render_buffer, term_w, term_h, pan.marks());
+                }
+                if let Some(cue) = subtitles::cue_at(&cues, position) {
+                    render::push_subtitle(&mut render_buffer, &cue.text, term_w, term_h.saturating_sub(1));
+                }
+                if let Some(stream) = &opts.stream {
+                    stream.publish(&render_buffer);
+                }
+                if state.histogram {
+                    render::push_histogram(&mut render_buffer, &ColorStats::sample(frame, format.bytes_per_pixel()));
+                }
+                if state.show_stats {
+                    if last_stats.is_none_or(|t| t.elapsed() >= TICK_RATE) {
+                        stats_lines = system_stats(system);
+                        stats_lines.extend(memory_stats(system, buffer.len(), read_ahead + 1, render_buffer.capacity()));
+                        last_stats = Some(Instant::now());
+                    }
+                    render::push_corner(&mut render_buffer, &stats_lines, term_w);
+                }
+                // One box when both are on, the sync lines first.
+                let mut corner = if opts.debug_sync { sync_lines(frame_number, fps, clock) } else { Vec::new() };
+                if opts.show_lag && !opts.manual_step {
+                    corner.extend(lag_lines(&lag));
+                }
+                if !corner.is_empty() {
+                    render::push_lower_corner(&mut render_buffer, &corner, term_h);
+                }
+                let step_hint = opts.manual_step.then(|| Msg::ManualStepHint.fill(&[&frame_number]));
+                if let Some(status) = state.status().into_iter().chain(step_hint).reduce(|a, b| format!("{}  {}", a, b)) {
+                    render::push_overlay(&mut render_buffer, &status, term_h);
+                }
+
+                let write_started = Instant::now();
+                write_frame(&mut stdout_term, &render_buffer, opts.sync_output)?;
+                stats.drawn(render_buffer.len(), Instant::now());
+                render_time = Some(render_started.elapsed());
+                timing = FrameTiming { render: write_started - render_started, write: write_started.elapsed(), drawn: true, ..timing };
+            } else if !render {
+                stats.capped += 1;
+            }
+            if let Some(profile) = &opts.profile {
+                profile.record(FrameTiming { total: read_started.elapsed(), ..timing });
+            }
+            if opts.manual_step && timing.drawn {
+                if !wait_for_step()? {
+                    return Ok(false);
+                }
+                // The wait isn't a stall.
+                last_read = Instant::now();
+            }
+
+            let mut restart = false;
+            if crossterm::event::poll(Duration::from_millis(0))? {
+                if let Event::Key(key) = crossterm::event::read()? {
+                    if key.kind != KeyEventKind::Press {
+                        continue;
+                    }
+                    // Most keys change what's drawn or clear the screen; draw the next frame either way.
+                    motion.reset();
+                    match key.code {
+                        KeyCode::Char('q') | KeyCode::Esc => return Ok(false),
+                        code if opts.browse && FileStep::from_key(code).is_some() => {
+                            step = FileStep::from_key(code);
+                            return Ok(false);
+                        }
+                        KeyCode::Char('h') | KeyCode::Char('H') => state.hflip = !state.hflip,
+                        KeyCode::Char('v') | KeyCode::Char('V') => state.vflip = !state.vflip,
+                        KeyCode::Char('d') | KeyCode::Char('D') => state.deinterlace = !state.deinterlace,
+                        KeyCode::Char('e') | KeyCode::Char('E') if info.is_hdr() => state.tonemap = !state.tonemap,
+                        KeyCode::Char('f') | KeyCode::Char('F') => {
+                            state.fps_cap = next_fps_cap(state.fps_cap);
+                            // The status line may get shorter; clear what the old one left behind.
+                            write!(stdout_term, "\x1b[0m\x1b[2J")?;
+                            continue;
+                        }
+                        KeyCode::Char('i') | KeyCode::Char('I') => {
+                            let size = (decoder.width, decoder.height);
+                            let frame_len = buffer.len();
+                            // Frames older than the buffered ones are decoded one at a time, with the
+                            // same filters and in-process effects; temporal ones can't be reproduced.
+                            let mut fetch = |at: f64| -> Option<Vec<u8>> {
+                                let filters = state.filters(plan.target.0, plan.target.1);
+                                let mut single = Decoder::spawn(video_path, &plan, at, &filters, false, |_, _| true, &mut |_| {}).ok()?;
+                                let mut frame = vec![0u8; single.frame_size()];
+                                single.read_frame(&mut frame).ok()?;
+                                if frame.len() != frame_len {
+                                    return None;
+                                }
+                                state.apply_effects(&mut frame, size, format, &opts.levels, None);
+                                Some(frame)
+                            };
+                            let stepping = Stepping { position, step: 1.0 / fps, recent: &recent, fetch: &mut fetch };
+                            match inspect_frame(&mut stdout_term, &buffer, stepping, size, &render_opts, state.guides)? {
+                                Some(at) => position = at,
+                                None => return Ok(false),
+                            }
+                            // ffmpeg's real-time clock kept running while paused; restart it from here.
+                        }
+                        KeyCode::Char('c') | KeyCode::Char('C') if !info.chapters.is_empty() && duration.is_some() => {
+                            let size = (decoder.width, decoder.height);
+                            let current = chapter_at(&info.chapters, position).unwrap_or(0);
+                            let items: Vec<String> = info.chapters.iter().map(|c| format!("{}  {}", format_clock(c.start), c.title)).collect();
+                            let hint = Msg::ChapterMenuHint.text();
+                            if let Some(i) = choose_item(&mut stdout_term, &buffer, size, &render_opts, &items, current, hint, 'c')? {
+                                position = info.chapters[i].start;
+                            }
+                        }
+                        KeyCode::Char('0'..='9') | KeyCode::Home | KeyCode::End => {
+                            let fraction = match key.code {
+                                KeyCode::Char(digit) => digit.to_digit(10).unwrap_or(0) as f64 / 10.0,
+                                KeyCode::Home => 0.0,
+                                _ => 1.0,
+                            };
+                            let Some(target) = jump_target(duration, fraction) else {
+                                state.notice = Some((Msg::SeekUnknownDuration.text().to_string(), Instant::now() + TOAST_DURATION));
+                                continue;
+                            };
+                            position = target;
+                            state.notice = Some((Msg::JumpedTo.fill(&[&format_clock(position)]), Instant::now() + TOAST_DURATION));
+                        }
+                        KeyCode::Char('u') | KeyCode::Char('U') => {
+                            let tracks = subtitle_tracks.get_or_insert_with(|| probe_subtitle_tracks(video_path));
+                            if tracks.is_empty() {
+                                state.notice = Some((Msg::NoSubtitles.text().to_string(), Instant::now() + TOAST_DURATION));
+                                continue;
+                            }
+                            let size = (decoder.width, decoder.height);
+                            let items: Vec<String> = std::iter::once(Msg::SubtitlesOff.text().to_string()).chain(tracks.iter().map(Track::label)).collect();
+                            let hint = Msg::SubtitleMenuHint.text();
+                            if let Some(i) = choose_item(&mut stdout_term, &buffer, size, &render_opts, &items, subtitle_choice, hint, 'u')? {
+                                let notice = match i.checked_sub(1).map(|t| &tracks[t]) {
+                                    None => {
+                                        cues.clear();
+                                        state.subtitle = None;
+                                        subtitle_choice = 0;
+                                        None
+                                    }
+                                    Some(track) if !track.is_text() => Some(Msg::BitmapSubtitles.text().to_string()),
+                                    Some(track) => match extract_subtitles(video_path, track) {
+                                        Ok(extracted) if extracted.is_empty() => Some(Msg::EmptySubtitles.text().to_string()),
+                                        Ok(extracted) => {
+                                            cues = extracted;
+                                            state.subtitle = Some(track.label());
+                                            subtitle_choice = i;
+                                            None
+                                        }
+                                        Err(e) => Some(Msg::SubtitleFailed.fill(&[&format!("{:#}", e)])),
+                                    },
+                                };
+                                state.notice = notice.map(|n| (n, Instant::now() + TOAST_DURATION));
+                            }
+                            // The menu paused playback; restart ffmpeg's clock from here.
+                        }
+                        KeyCode::Char('s') | KeyCode::Char('S') => {
+                            state.show_stats = !state.show_stats;
+                            last_stats = None;
+                            write!(stdout_term, "\x1b[0m\x1b[2J")?;
+                            continue;
+                        }
+                        KeyCode::Char('t') | KeyCode::Char('T') => {
+                            state.smoothing = !state.smoothing;
+                            smoothing.reset();
+                            write!(stdout_term, "\x1b[0m\x1b[2J")?;
+                            continue;
+                        }
+                        KeyCode::Char('g') | KeyCode::Char('G') => {
+                            state.guides = !state.guides;
+                            continue;
+                        }
+                        KeyCode::Char('o') | KeyCode::Char('O') => {
+                            state.histogram = !state.histogram;
+                            write!(stdout_term, "\x1b[0m\x1b[2J")?;
+                            continue;
+                        }
+                        KeyCode::Char(c @ ('-' | '=' | '+')) => {
+                            // Never above the terminal-fit size: there's no detail to gain past it.
+                            let steps = if c == '-' { (state.scale_steps + 1).min(MAX_SCALE_STEPS) } else { state.scale_steps.saturating_sub(1) };
+                            if steps == state.scale_steps {
+                                continue;
+                            }
+                            state.scale_steps = steps;
+                            state.pan = None;
+                            plan.target = scaled_target(full_target, state.scale_percent());
+                        }
+                        KeyCode::Char('b') | KeyCode::Char('B') => {
+                            // The fit size changes with the margin the border needs.
+                            state.border = !state.border;
+                            state.pan = None;
+                            plan = DecodePlan::new(video_path, &info, &PlayOptions { border: state.border, fit: state.fit, ..opts.clone() }, term_w, term_h)?;
+                            full_target = plan.target;
+                            plan.target = scaled_target(full_target, state.scale_percent());
+                        }
+                        KeyCode::Char('w') | KeyCode::Char('W') => {
+                            // Filling the width or height may overflow the terminal; ffmpeg crops the rest.
+                            state.fit = state.fit.next();
+                            state.pan = None;
+                            plan = DecodePlan::new(video_path, &info, &PlayOptions { border: state.border, fit: state.fit, ..opts.clone() }, term_w, term_h)?;
+                            state.scaling = plan.scaling;
+                            full_target = plan.target;
+                            plan.target = scaled_target(full_target, state.scale_percent());
+                            state.notice = Some((Msg::FitSet.fill(&[&fit_name(state.fit)]), Instant::now() + TOAST_DURATION));
+                        }
+                        KeyCode::Char('r') | KeyCode::Char('R') => {
+                            state.auto_quality = !state.auto_quality;
+                            auto_quality.reset();
+                            write!(stdout_term, "\x1b[0m\x1b[2J")?;
+                            if state.auto_steps == 0 {
+                                continue;
+                            }
+                            // Turned off while lowered: go back to the chosen resolution.
+                            state.auto_steps = 0;
+                            plan.target = scaled_target(full_target, state.scale_percent());
+                        }
+                        KeyCode::Char(c @ ('[' | ']')) => {
+                            if format == PixelFormat::Gray {
+                                state.notice = Some((Msg::SaturationMono.text().to_string(), Instant::now() + TOAST_DURATION));
+                                continue;
+                            }
+                            let steps = state.saturation_steps + if c == '[' { -1 } else { 1 };
+                            if !SATURATION_STEPS.contains(&steps) {
+                                continue;
+                            }
+                            state.saturation_steps = steps;
+                            write!(stdout_term, "\x1b[0m\x1b[2J")?;
+                            continue;
+                        }
+                        KeyCode::Char('k') | KeyCode::Char('K') => {
+                            state.mirror = state.mirror.next();
+                            write!(stdout_term, "\x1b[0m\x1b[2J")?;
+                            continue;
+                        }
+                        KeyCode::Char('p') | KeyCode::Char('P') => {
+                            if state.pan.take().is_some() {
+                                plan.target = scaled_target(full_target, state.scale_percent());
+                            } else if mode == RenderMode::Image {
+                                state.notice = Some((Msg::PanImage.text().to_string(), Instant::now() + TOAST_DURATION));
+                                continue;
+                            } else if let Some(target) = pan_target(full_target, plan.scaling.source) {
+                                // The whole terminal, less the border's margin, whatever the video's aspect.
+                                let margin = if state.border { 2 } else { 0 };
+                                let view = render::target_size(mode, Fit::Stretch, 1, 1, term_w.saturating_sub(margin), term_h.saturating_sub(margin));
+                                state.pan = Some(Pan::centered(target, view));
+                                plan.target = target;
+                            } else {
+                                state.notice = Some((Msg::PanNotNeeded.text().to_string(), Instant::now() + TOAST_DURATION));
+                                continue;
+                            }
+                        }
+                        KeyCode::Left | KeyCode::Right | KeyCode::Up | KeyCode::Down if state.pan.is_some() => {
+                            if let Some(pan) = &mut state.pan {
+                                let marks = pan.marks();
+                                match key.code {
+                                    KeyCode::Left => pan.scroll(-1, 0),
+                                    KeyCode::Right => pan.scroll(1, 0),
+                                    KeyCode::Up => pan.scroll(0, -1),
+                                    _ => pan.scroll(0, 1),
+                                }
+                                // An edge arrow that goes away would be left behind.
+                                if pan.marks() != marks {
+                                    write!(stdout_term, "\x1b[0m\x1b[2J")?;
+                                }
+                            }
+                            continue;
+                        }
+                        KeyCode::Left | KeyCode::Right | KeyCode::Up | KeyCode::Down => {
+                            let delta = match key.code {
+                                KeyCode::Left => (-1, 0),
+                                KeyCode::Right => (1, 0),
+                                KeyCode::Up => (0, -1),
+                                _ => (0, 1),
+                            };
+                            state.shift = render::nudge(&render_opts, decoder.width, decoder.height, delta);
+                            render_opts.shift = state.shift;
+                            write!(stdout_term, "\x1b[0m\x1b[2J")?;
+                            continue;
+                        }
+                        KeyCode::Char('x') | KeyCode::Char('X') => {
+                            state.shift = (0, 0);
+                            render_opts.shift = state.shift;
+                            write!(stdout_term, "\x1b[0m\x1b[2J")?;
+                            continue;
+                        }
+                        KeyCode::Char('a') | KeyCode::Char('A') => {
+                            state.auto_contrast = !state.auto_contrast;
+                            contrast.reset();
+                            write!(stdout_term, "\x1b[0m\x1b[2J")?;
+                            continue;
+                        }
+                        KeyCode::Char(c @ ('z' | 'Z')) => {
+                            let notice = copy_frame(&buffer, (decoder.width, decoder.height), &render_opts, c == 'Z');
+                            state.notice = Some((notice, Instant::now() + TOAST_DURATION));
+                            continue;
+                        }
+                        KeyCode::Char('y') | KeyCode::Char('Y') => {
+                            let command = command_line(video_path, opts, &state);
+                            let copied = clipboard::copy(&command).is_ok();
+                            show_command_line(&mut stdout_term, &command, copied)?;
+                            write!(stdout_term, "\x1b[0m\x1b[2J")?;
+                            // The command stayed up while paused; restart ffmpeg's clock from here.
+                        }
+                        KeyCode::Char('l') | KeyCode::Char('L') => {
+                            if !mode.uses_ramp() {
+                                state.notice = Some((Msg::AdaptiveRampUnused.text().to_string(), Instant::now() + TOAST_DURATION));
+                                continue;
+                            }
+                            state.adaptive_ramp = !state.adaptive_ramp;
+                            adaptive.reset();
+                            write!(stdout_term, "\x1b[0m\x1b[2J")?;
+                            continue;
+                        }
+                        _ => continue,
+                    }
+                    restart = true;
+                }
+            }
+
+            // Panning decodes at a fixed size on purpose.
+            if let (true, None, Some(elapsed)) = (state.auto_quality, state.pan, render_time) {
+                let budget = Duration::from_secs_f64(1.0 / state.fps_cap.map_or(fps, |cap| fps.min(cap as f64)));
+                let steps = match auto_quality.record(elapsed, budget) {
+                    Some(Adjust::Lower) => (state.auto_steps + 1).min(MAX_SCALE_STEPS.saturating_sub(state.scale_steps)),
+                    Some(Adjust::Raise) => state.auto_steps.saturating_sub(1),
+                    None => state.auto_steps,
+                };
+                if steps != state.auto_steps {
+                    trace::note(format!("auto quality: {} -> {} steps down", state.auto_steps, steps));
+                    state.auto_steps = steps;
+                    plan.target = scaled_target(full_target, state.scale_percent());
+                    restart = true;
+                }
+            }
+
+            if restart {
+                // Filters, size or position changed: restart ffmpeg so the new settings take effect.
+                black_since = None;
+                let position = seek_target(duration, position);
+                // A panned frame is meant to be larger than the terminal.
+                let panning = state.pan.is_some();
+                let fits = |w, h| panning || fits(w, h);
+                // Dropping the old decoder kills its ffmpeg and joins its reader thread.
+                drop(std::mem::replace(&mut decoder, Decoder::spawn(video_path, &plan, position, &state.filters(plan.target.0, plan.target.1), true, fits, &mut |_| {})?.threaded(read_ahead)));
+                decoder.prebuffer(opts.prebuffer, &mut |n| show_buffering(&mut stdout_term, n, opts.prebuffer, term_h));
+                paced_from = None;
+                buffer.resize(decoder.frame_size(), 0);
+                segment_start = position;
+                frames_read = 0;
+                contrast.reset();
+                adaptive.reset();
+                smoothing.reset();
+                auto_quality.reset();
+                motion.reset();
+                recent.clear();
+                last_read = Instant::now();
+                // Record jumps right away rather than at the next progress interval.
+                on_progress(position);
+                last_progress = Instant::now();
+                write!(stdout_term, "\x1b[0m\x1b[2J")?;
+            }
+        }
+    })();
+
+    drop(decoder);
+    // A user who quit may want the numbers; a script that let the video end doesn't.
+    if stats.rendered > 0 && result.as_ref().is_ok_and(|&finished| !(finished && opts.exit_on_finish)) {
+        show_summary(&mut stdout_term, &stats, state.bad_frames, term_w, term_h)?;
+    }
+    drop(guard);
+    if let Some(stream) = &opts.stream {
+        stream.clear();
+    }
+    if let Some(profile) = &opts.profile {
+        profile.flush();
+    }
+
+    result.map(|finished| PlaybackEnd { position: segment_start + frames_read as f64 / fps, finished, step })
+}
+
+/// Keeps the last frame up with a hint until `q` or `Esc`.
+fn hold_until_quit(out: &mut impl Write, term_h: u16) -> Result<()> {
+    let mut hint = String::new();
+    render::push_overlay(&mut hint, Msg::SingleFrameHint.text(), term_h);
+    out.write_all(hint.as_bytes())?;
+    out.flush()?;
+    loop {
+        if let Event::Key(key) = crossterm::event::read()? {
+            if key.kind == KeyEventKind::Press && matches!(key.code, KeyCode::Char('q') | KeyCode::Esc) {
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// Shows what the playback session drew until a key is pressed.
+fn show_summary(out: &mut impl Write, stats: &PlaybackStats, bad_frames: u64, term_w: u16, term_h: u16) -> Result<()> {
+    let now = Instant::now();
+    let rate = |fps: Option<f64>| fps.map_or_else(|| "—".to_string(), |f| format!("{:.1}", f));
+    let lines = [
+        Msg::SummaryTitle.text().to_string(),
+        String::new(),
+        Msg::SummaryDuration.fill(&[&format_clock(stats.elapsed(now).as_secs_f64())]),
+        Msg::SummaryFrames.fill(&[&stats.rendered]),
+        Msg::SummaryFps.fill(&[&format!("{:.1}", stats.average_fps(now)), &rate(stats.min_fps), &rate(stats.max_fps)]),
+        Msg::SummaryDropped.fill(&[&(stats.capped + stats.skipped + bad_frames), &stats.capped, &stats.skipped, &bad_frames]),
+        Msg::SummaryBytes.fill(&[&format!("{:.1}", stats.bytes as f64 / 1024.0 / 1024.0)]),
+        String::new(),
+        Msg::SummaryHint.text().to_string(),
+    ];
+    let mut panel = String::from("\x1b[0m\x1b[2J");
+    render::push_panel(&mut panel, &lines, term_w, term_h);
+    out.write_all(panel.as_bytes())?;
+    out.flush()?;
+    loop {
+        if let Event::Key(key) = crossterm::event::read()? {
+            if key.kind == KeyEventKind::Press {
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// Decodes up to `BLACK_SCAN_SECS` of tiny grayscale frames as fast as possible and returns where a
+/// black intro ends, if there is one worth skipping (see `effects::black_intro_frames`).
+fn find_black_intro(video_path: &Path, input_args: &[String], fps: f64, threshold: u8) -> Result<Option<f64>> {
+    const SCAN_W: usize = 32;
+    const SCAN_H: usize = 18;
+    let mut child = ChildGuard::spawn(Command::new(get_command_path("ffmpeg"))
+        .arg("-hide_banner")
+        .arg("-v").arg("error")
+        .args(input_args)
+        .arg("-t").arg(BLACK_SCAN_SECS.to_string())
+        .arg("-i").arg(video_path)
+        .arg("-vf").arg(format!("scale={}:{},format=gray", SCAN_W, SCAN_H))
+        .arg("-f").arg("rawvideo")
+        .arg("-pix_fmt").arg("gray")
+        .arg("-")
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null()))?;
+    let mut stdout = child.0.stdout.take().context("Failed to open stdout")?;
+
+    let mut frame = vec![0u8; SCAN_W * SCAN_H];
+    let mut lumas = Vec::new();
+    // Stop at the first bright frame; nothing after it can change the answer.
+    while stdout.read_exact(&mut frame).is_ok() {
+        let luma = effects::mean_luminance(&frame);
+        lumas.push(luma);
+        if luma >= threshold as f32 {
+            break;
+        }
+    }
+    drop(child);
+
+    let min_frames = (MIN_BLACK_SECS * fps).ceil() as usize;
+    Ok(effects::black_intro_frames(&lumas, threshold as f32, min_frames).map(|n| n as f64 / fps))
+}
+
+/// Runs `cropdetect` over a short sample of the video and returns the picture inside baked-in
+/// black bars, or `None` when there are none or the sample is too dark to tell.
+fn detect_bars(video_path: &Path, input_args: &[String], info: &VideoInfo) -> Option<Crop> {
+    let start = info.known_duration().map_or(0.0, |d| (d / 10.0).min(CROP_SCAN_MAX_START));
+    let output = traced_output(Command::new(get_command_path("ffmpeg"))
+        .arg("-hide_banner")
+        .arg("-ss").arg(format!("{:.3}", start))
+        .args(input_args)
+        .arg("-t").arg(CROP_SCAN_SECS.to_string())
+        .arg("-i").arg(video_path)
+        .args(["-an", "-vf", "cropdetect=limit=24:round=2", "-f", "null", "-"]))
+        .ok()?;
+    let crop = parse_cropdetect(&String::from_utf8_lossy(&output.stderr), (info.width, info.height));
+    trace::note(format!("cropdetect: {}", crop.map_or("no bars".to_string(), |c| c.to_string())));
+    crop
+}
+
+/// The last `crop=W:H:X:Y` that `cropdetect` logged, if it cuts something off `source` and still
+/// leaves at least a quarter of each side, as a mostly black sample can suggest a sliver.
+fn parse_cropdetect(stderr: &str, (src_w, src_h): (u32, u32)) -> Option<Crop> {
+    let value = stderr.rsplit("crop=").next().filter(|_| stderr.contains("crop="))?;
+    let crop = Crop::parse(value.split_whitespace().next()?)?;
+    let useful = crop.fits(src_w, src_h) && (crop.w, crop.h) != (src_w, src_h) && crop.w >= src_w / 4 && crop.h >= src_h / 4;
+    useful.then_some(crop)
+}
+
+/// Refreshes `system` and formats a compact CPU/memory readout for the playback corner box.
+fn system_stats(system: &mut System) -> Vec<String> {
+    system.refresh_cpu_all();
+    system.refresh_memory();
+    let gib = |bytes: u64| bytes as f64 / 1024.0 / 1024.0 / 1024.0;
+    vec![
+        format!("CPU  {:5.1}%", system.global_cpu_usage()),
+        Msg::StatsMemory.fill(&[&format!("{:.1}", gib(system.used_memory())), &format!("{:.1}", gib(system.total_memory()))]),
+    ]
+}
+
+/// The app's own resident memory, and what playback's buffers take of it: `frames` frames of
+/// `frame` bytes (the one being drawn and the read-ahead queue) and `output` bytes of escapes.
+fn memory_stats(system: &mut System, frame: usize, frames: usize, output: usize) -> Vec<String> {
+    let mib = |bytes: u64| format!("{:.1}", bytes as f64 / 1024.0 / 1024.0);
+    let own = sysinfo::get_current_pid().ok().and_then(|pid| {
+        system.refresh_processes_specifics(ProcessesToUpdate::Some(&[pid]), false, ProcessRefreshKind::nothing().with_memory());
+        system.process(pid).map(|p| p.memory())
+    });
+    vec![
+        Msg::StatsProcess.fill(&[&own.map_or_else(|| "N/A".to_string(), mib)]),
+        Msg::StatsBuffers.fill(&[&mib(frame as u64), &frames, &mib(output as u64)]),
+    ]
+}
+
+/// Where the inspector paused and how it gets at the frames around it.
+struct Stepping<'a> {
+    /// Position of the paused frame.
+    position: f64,
+    /// Seconds between frames, for decoding ones older than `recent` holds.
+    step: f64,
+    recent: &'a RecentFrames,
+    /// Decodes the frame at a position with ffmpeg, past the start of `recent`.
+    fetch: &'a mut dyn FnMut(f64) -> Option<Vec<u8>>,
+}
+
+/// Pauses on `frame` and lets the arrow keys move a cursor over it, describing the pixels under the
+/// cursor. `,` and `.` step back and forward through the frames kept in `stepping.recent`, and
+/// further back by decoding single frames with ffmpeg.
+///
+/// Returns where to resume from, or `None` if the user quit playback from inside the inspector.
+fn inspect_frame(out: &mut impl Write, frame: &[u8], stepping: Stepping, (width, height): (u32, u32), opts: &RenderOptions, guides: bool) -> Result<Option<f64>> {
+    let Stepping { position, step, recent, fetch } = stepping;
+    let (cols, rows) = render::frame_cells(opts.mode, width, height, opts.term_w, opts.term_h);
+    if cols == 0 || rows == 0 {
+        return Ok(Some(position));
+    }
+    let mut cursor = (cols / 2, rows / 2);
+    let mut screen = String::new();
+    let (mut at, mut shown) = (position, frame.to_vec());
+    // Shown in place of the hint until the next key.
+    let mut notice = None;
+    loop {
+        if render::render_frame(&mut screen, &shown, width, height, opts).is_none() {
+            return Ok(Some(at));
+        }
+        if guides {
+            render::push_guides(&mut screen, width, height, opts);
+        }
+        render::push_inspector(&mut screen, &shown, width, height, opts, cursor);
+        render::push_overlay(&mut screen, notice.take().as_deref().unwrap_or(Msg::InspectHint.text()), opts.term_h);
+        write!(out, "\x1b[0m\x1b[2J")?;
+        out.write_all(screen.as_bytes())?;
+        out.flush()?;
+
+        let Event::Key(key) = crossterm::event::read()? else { continue };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        match key.code {
+            KeyCode::Left => cursor.0 = cursor.0.saturating_sub(1),
+            KeyCode::Right => cursor.0 = (cursor.0 + 1).min(cols - 1),
+            KeyCode::Up => cursor.1 = cursor.1.saturating_sub(1),
+            KeyCode::Down => cursor.1 = (cursor.1 + 1).min(rows - 1),
+            KeyCode::Char(',') => {
+                let previous = match recent.before(at) {
+                    Some((p, f)) => Some((p, f.to_vec())),
+                    None if at >= step => fetch(at - step).map(|f| (at - step, f)),
+                    None => None,
+                };
+                if let Some((p, f)) = previous {
+                    (at, shown) = (p, f);
+                }
+            }
+            KeyCode::Char('.') => match recent.after(at) {
+                Some((p, f)) => (at, shown) = (p, f.to_vec()),
+                None => (at, shown) = (position, frame.to_vec()),
+            },
+            KeyCode::Char(c @ ('z' | 'Z')) => notice = Some(copy_frame(&shown, (width, height), opts, c == 'Z')),
+            KeyCode::Char('i') | KeyCode::Char('I') | KeyCode::Char(' ') | KeyCode::Esc => return Ok(Some(at)),
+            KeyCode::Char('q') | KeyCode::Char('Q') => return Ok(None),
+            _ => {}
+        }
+    }
+}
+
+/// Pauses on `frame` with `items` listed over it and `hint` in the status line. Returns the chosen
+/// index, or `None` if cancelled with `close`, `q` or `Esc`.
+#[allow(clippy::too_many_arguments)]
+fn choose_item(out: &mut impl Write, frame: &[u8], (width, height): (u32, u32), opts: &RenderOptions, items: &[String], current: usize, hint: &str, close: char) -> Result<Option<usize>> {
+    let mut selected = current.min(items.len().saturating_sub(1));
+    let mut screen = String::new();
+    loop {
+        if render::render_frame(&mut screen, frame, width, height, opts).is_none() {
+            screen.clear();
+        }
+        render::push_menu(&mut screen, items, selected, opts.term_w, opts.term_h);
+        render::push_overlay(&mut screen, hint, opts.term_h);
+        write!(out, "\x1b[0m\x1b[2J")?;
+        out.write_all(screen.as_bytes())?;
+        out.flush()?;
+
+        let Event::Key(key) = crossterm::event::read()? else { continue };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        match key.code {
+            KeyCode::Up => selected = selected.saturating_sub(1),
+            KeyCode::Down => selected = (selected + 1).min(items.len() - 1),
+            KeyCode::Enter => return Ok(Some(selected)),
+            KeyCode::Char(c) if c.to_ascii_lowercase() == close || c == 'q' => return Ok(None),
+            KeyCode::Esc => return Ok(None),
+            _ => {}
+        }
+    }
+}
+
+/// Writes one frame to the terminal. With `sync`, it goes inside a synchronized update
+/// (`?2026h` … `?2026l`) and the terminal shows it in one go, without tearing halfway through.
+fn write_frame(out: &mut impl Write, frame: &str, sync: bool) -> io::Result<()> {
+    if sync {
+        out.write_all(b"\x1b[?2026h")?;
+    }
+    out.write_all(frame.as_bytes())?;
+    if sync {
+        out.write_all(b"\x1b[?2026l")?;
+    }
+    out.flush()
+}
+
+/// Visualizer for audio-only files: decodes mono PCM in real time and draws a live spectrum.
+fn play_spectrum(audio_path: &Path, start: f64, opts: &PlayOptions, on_progress: &mut dyn FnMut(f64)) -> Result<PlaybackEnd> {
+    let (term_w, term_h) = output_size(opts.cells, terminal::size()?);
+    let ffmpeg_cmd = get_command_path("ffmpeg");
+    let mut child = ChildGuard::spawn(Command::new(&ffmpeg_cmd)
+        .arg("-hide_banner")
+        .arg("-re")
+        .arg("-ss")
+        .arg(format!("{:.3}", start))
+        .arg("-i")
+        .arg(audio_path)
+        .arg("-vn")
+        .arg("-ac")
+        .arg("1")
+        .arg("-ar")
+        .arg(spectrum::SAMPLE_RATE.to_string())
+        .arg("-f")
+        .arg("s16le")
+        .arg("-")
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null()))?;
+    let mut stdout = child.0.stdout.take().context("Failed to open stdout")?;
+
+    let guard = TerminalGuard::new()?;
+    let mut stdout_term = std::io::stdout();
+
+    // Redraw at roughly 30 FPS worth of samples.
+    let chunk_samples = (spectrum::SAMPLE_RATE / 30) as usize;
+    let mut raw = vec![0u8; chunk_samples * 2];
+    let mut samples = vec![0i16; chunk_samples];
+    let mut analyser = Spectrum::new(term_w as usize / 2);
+    let mut render_buffer = String::new();
+    let mut samples_read: u64 = 0;
+    let mut last_progress = Instant::now();
+    let mut step = None;
+
+    let result = (|| -> Result<bool> {
+        loop {
+            if stdout.read_exact(&mut raw).is_err() {
+                return Ok(true);
+            }
+            for (s, b) in samples.iter_mut().zip(raw.chunks_exact(2)) {
+                *s = i16::from_le_bytes([b[0], b[1]]);
+            }
+            samples_read += chunk_samples as u64;
+            if last_progress.elapsed() >= PROGRESS_INTERVAL {
+                on_progress(start + samples_read as f64 / spectrum::SAMPLE_RATE as f64);
+                last_progress = Instant::now();
+            }
+
+            analyser.push(&samples);
+            spectrum::render_bars(&mut render_buffer, analyser.bars(), term_w, term_h);
+            write_frame(&mut stdout_term, &render_buffer, opts.sync_output)?;
+
+            if crossterm::event::poll(Duration::from_millis(0))? {
+                if let Event::Key(key) = crossterm::event::read()? {
+                    if key.kind != KeyEventKind::Press {
+                        continue;
+                    }
+                    if matches!(key.code, KeyCode::Char('q') | KeyCode::Esc) {
+                        return Ok(false);
+                    }
+                    if opts.browse && FileStep::from_key(key.code).is_some() {
+                        step = FileStep::from_key(key.code);
+                        return Ok(false);
+                    }
+                }
+            }
+        }
+    })();
+
+    drop(guard);
+    drop(child);
+
+    result.map(|finished| PlaybackEnd { position: start + samples_read as f64 / spectrum::SAMPLE_RATE as f64, finished, step })
+}
+
+/// Replaces the screen with the latest of ffmpeg's progress lines while `path` connects.
+fn show_connecting(out: &mut impl Write, path: &Path, status: &[String]) {
+    let _ = write!(out, "\x1b[H\x1b[2J\x1b[0m{}\r\n", Msg::Connecting.fill(&[&path.display()]));
+    for line in status {
+        let _ = write!(out, "\r\n\x1b[2m{}\x1b[0m", line);
+    }
+    let _ = out.flush();
+}
+
+/// The `--debug-sync` overlay: frame `number`, its timestamp at `fps`, the wall `clock` position and
+/// how far the frame is ahead of it (negative when playback lags).
+fn sync_lines(number: u64, fps: f64, clock: f64) -> Vec<String> {
+    let pts = number as f64 / fps;
+    vec![
+        Msg::SyncFrame.fill(&[&number]),
+        Msg::SyncPts.fill(&[&format!("{:.3}", pts)]),
+        Msg::SyncClock.fill(&[&format!("{:.3}", clock)]),
+        Msg::SyncDrift.fill(&[&format!("{:+.0}", (pts - clock) * 1000.0)]),
+    ]
+}
+
+/// The `--show-lag` readout: wall-clock and media time played, and how far the first is ahead.
+fn lag_lines(lag: &LagClock) -> Vec<String> {
+    let secs = |d: Duration| format!("{:.2}", d.as_secs_f64());
+    vec![Msg::LagWall.fill(&[&secs(lag.wall())]), Msg::LagMedia.fill(&[&secs(lag.media())]), Msg::LagBehind.fill(&[&secs(lag.lag())])]
+}
+
+fn fit_name(fit: Fit) -> &'static str {
+    match fit {
+        Fit::Contain => Msg::FitContain,
+        Fit::Stretch => Msg::FitStretch,
+        Fit::Width => Msg::FitWidth,
+        Fit::Height => Msg::FitHeight,
+    }
+    .text()
+}
+
+/// A command that plays `path` again the way it's playing now: the options and every setting
+/// changed with the playback keys, as flags. Those at their defaults are left out.
+fn command_line(path: &Path, opts: &PlayOptions, state: &PlaybackState) -> String {
+    let mut args: Vec<String> = vec![env!("CARGO_PKG_NAME").to_string()];
+    let mut flag = |name: &str, value: Option<String>| {
+        args.push(name.to_string());
+        args.extend(value);
+    };
+    if opts.mode != RenderMode::PixelArt {
+        flag("--mode", Some(opts.mode.key().to_string()));
+    }
+    if opts.mode.uses_ramp() && opts.ramp != render::DEFAULT_RAMP {
+        flag("--ramp", Some(opts.ramp.iter().collect()));
+    }
+    if state.adaptive_ramp {
+        flag("--adaptive-ramp", None);
+    }
+    if let AsciiColor::Mono(r, g, b) = opts.ascii_color {
+        flag("--ascii-color", Some(format!("#{:02X}{:02X}{:02X}", r, g, b)));
+    }
+    if opts.depth != ColorDepth::TrueColor {
+        flag("--color-mode", Some(opts.depth.key().to_string()));
+    }
+    for level in Level::ALL {
+        let steps = opts.levels.steps(level);
+        if steps != 0 {
+            flag(&format!("--{}", level.key()), Some(steps.to_string()));
+        }
+    }
+    if state.hflip {
+        flag("--hflip", None);
+    }
+    if state.vflip {
+        flag("--vflip", None);
+    }
+    if state.mirror != Mirror::Off {
+        flag("--mirror", Some(state.mirror.key().to_string()));
+    }
+    if state.saturation_steps != 0 {
+        flag("--saturation", Some(format!("{:.1}", state.saturation())));
+    }
+    if state.auto_contrast {
+        flag("--auto-contrast", None);
+    }
+    if state.smoothing {
+        flag("--smooth", Some(opts.smooth.unwrap_or(effects::DEFAULT_SMOOTHING).to_string()));
+    }
+    if state.fit != Fit::Contain {
+        flag("--fit", Some(state.fit.key().to_string()));
+    }
+    if let Some(cols) = opts.cells.0 {
+        flag("--cols", Some(cols.to_string()));
+    }
+    if let Some(rows) = opts.cells.1 {
+        flag("--rows", Some(rows.to_string()));
+    }
+    if state.border || opts.border {
+        flag("--border", Some(if state.border { "on" } else { "off" }.to_string()));
+    }
+    if state.auto_quality || opts.auto_quality {
+        flag("--auto-quality", Some(if state.auto_quality { "on" } else { "off" }.to_string()));
+    }
+    if let Some(crop) = opts.crop {
+        flag("--crop", Some(crop.arg()));
+    } else if state.auto_crop {
+        flag("--auto-crop", None);
+    }
+    if let Some(backdrop) = opts.backdrop {
+        let hex = |(r, g, b): (u8, u8, u8)| format!("#{:02X}{:02X}{:02X}", r, g, b);
+        let value = if backdrop.top == backdrop.bottom { hex(backdrop.top) } else { format!("{}:{}", hex(backdrop.top), hex(backdrop.bottom)) };
+        flag("--backdrop", Some(value));
+    }
+    if opts.scaling.scaler != Scaler::default() {
+        flag("--scaler", Some(opts.scaling.scaler.flag().to_string()));
+    }
+    if let Some(upscale) = opts.scaling.upscale {
+        flag("--upscale-scaler", Some(upscale.flag().to_string()));
+    }
+    if opts.deep_color {
+        flag("--pix-fmt", Some("rgb48".to_string()));
+    }
+    if let Some(burn) = &opts.burn_subs {
+        flag("--burn-subs", Some(match burn {
+            Burn::Text(index) | Burn::Bitmap(index) => (index + 1).to_string(),
+            Burn::File(file) => file.display().to_string(),
+        }));
+    }
+    if opts.deinterlace && !state.deinterlace {
+        flag("--no-deinterlace", None);
+    }
+    if opts.tonemap && !state.tonemap {
+        flag("--no-tonemap", None);
+    }
+    if !opts.cfr {
+        flag("--no-cfr", None);
+    }
+    if !opts.catch_up {
+        flag("--no-catch-up", None);
+    }
+    if let Some(cap) = state.fps_cap {
+        flag("--fps-cap", Some(cap.to_string()));
+    }
+    if let Some(threads) = opts.threads {
+        flag("--threads", Some(threads.to_string()));
+    }
+    if let Some(threshold) = opts.reduce_motion {
+        flag("--motion-threshold", Some(threshold.to_string()));
+    }
+    args.push(path.display().to_string());
+    args.iter().map(|arg| trace::shell_quote(arg)).collect::<Vec<_>>().join(" ")
+}
+
+/// Shows `command` over the paused video, saying whether it reached the clipboard, until a key is
+/// pressed.
+fn show_command_line(out: &mut impl Write, command: &str, copied: bool) -> Result<()> {
+    let title = if copied { Msg::CommandLineCopied } else { Msg::CommandLineNotCopied };
+    // Left to the terminal to wrap, so the command copies as one line.
+    write!(out, "\x1b[0m\x1b[2J\x1b[H{}\r\n\r\n{}\r\n\r\n{}", title.text(), command, Msg::CommandLineHint.text())?;
+    out.flush()?;
+    loop {
+        if let Event::Key(key) = crossterm::event::read()? {
+            if key.kind == KeyEventKind::Press {
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// Copies `frame` to the clipboard as text, `colored` with its escape codes, and says how that went.
+fn copy_frame(frame: &[u8], (width, height): (u32, u32), opts: &RenderOptions, colored: bool) -> String {
+    let copied = render::clipboard_text(frame, width, height, opts, colored)
+        .context("The frame can't be drawn as text")
+        .and_then(|text| clipboard::copy(&text));
+    match copied {
+        Ok(()) if colored => Msg::FrameCopiedAnsi.text().to_string(),
+        Ok(()) => Msg::FrameCopiedPlain.text().to_string(),
+        Err(e) => Msg::FrameCopyFailed.fill(&[&format!("{:#}", e)]),
+    }
+}
+
+/// Blocks until space or the right arrow asks for the next frame (`true`), or `q`/`Esc` quits.
+fn wait_for_step() -> Result<bool> {
+    loop {
+        if let Event::Key(key) = crossterm::event::read()? {
+            match key.code {
+                _ if key.kind != KeyEventKind::Press => {}
+                KeyCode::Char(' ') | KeyCode::Right => return Ok(true),
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(false),
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Shows that a dropped network source is being reconnected and waits out `attempt`'s backoff.
+/// `false` when the user quit meanwhile.
+fn wait_to_reconnect(out: &mut impl Write, attempt: u32, attempts: u32, term_h: u16) -> Result<bool> {
+    let delay = remote::retry_delay(attempt);
+    write!(out, "\x1b[{};1H\x1b[0m\x1b[2K{}", term_h, Msg::Reconnecting.fill(&[&attempt, &attempts, &delay.as_secs()]))?;
+    out.flush()?;
+    let deadline = Instant::now() + delay;
+    while crossterm::event::poll(deadline.saturating_duration_since(Instant::now()))? {
+        if let Event::Key(key) = crossterm::event::read()? {
+            if key.kind == KeyEventKind::Press && matches!(key.code, KeyCode::Char('q') | KeyCode::Esc) {
+                return Ok(false);
+            }
+        }
+    }
+    Ok(true)
+}
+
+/// Shows pre-buffering progress on the bottom line, cleared again once `filled` reaches `frames`.
+fn show_buffering(out: &mut impl Write, filled: usize, frames: usize, term_h: u16) {
+    let _ = write!(out, "\x1b[{};1H\x1b[0m\x1b[2K", term_h);
+    if filled < frames {
+        let _ = write!(out, "{}", Msg::Buffering.fill(&[&filled, &frames]));
+    }
+    let _ = out.flush();
+}
+
+/// What the decoder needs to know from ffmpeg's stderr.
+#[derive(Debug, PartialEq)]
+enum StderrEvent {
+    /// Frame size of the output stream.
+    OutputSize(u32, u32),
+    /// ffmpeg can't decode the input; the codec's name, when the message gives it.
+    MissingDecoder(Option<String>),
+    /// A line showing connection or download progress, before the output stream is known.
+    Progress(String),
+}
+
+/// Drains ffmpeg's stderr on a background thread and reports the output stream's frame size once
+/// seen, or that the input's codec can't be decoded, along with any progress lines before that.
+/// The thread ends with stderr, returning its last lines.
+fn watch_stderr(stderr: ChildStderr) -> (mpsc::Receiver<StderrEvent>, thread::JoinHandle<VecDeque<String>>) {
+    let (tx, rx) = mpsc::channel();
+    let reader = thread::spawn(move || {
+        let mut in_output = false;
+        let mut sent = false;
+        let mut tail = VecDeque::new();
+        for line in BufReader::new(stderr).lines().map_while(Result::ok) {
+            push_stderr_line(&mut tail, &line);
+            if let (false, Some(codec)) = (sent, parse_missing_decoder(&line)) {
+                let _ = tx.send(StderrEvent::MissingDecoder(codec));
+                sent = true;
+            } else if line.starts_with("Output #0") {
+                in_output = true;
+            } else if let (false, false, Some(status)) = (sent, in_output, remote::progress_line(&line)) {
+                let _ = tx.send(StderrEvent::Progress(status));
+            } else if in_output && !sent && line.contains("Video:") {
+                if let Some((w, h)) = parse_stream_size(&line) {
+                    let _ = tx.send(StderrEvent::OutputSize(w, h));
+                    sent = true;
+                }
+            }
+        }
+        tail
+    });
+    (rx, reader)
+}
+
+/// Keeps the last `STDERR_TAIL_LINES` of what ffmpeg said, leaving out the `frame=` statistics
+/// it rewrites in place with carriage returns.
+fn push_stderr_line(tail: &mut VecDeque<String>, line: &str) {
+    for part in line.split('\r').map(str::trim).filter(|p| !p.is_empty() && !p.starts_with("frame=") && !p.starts_with("size=")) {
+        if tail.len() == STDERR_TAIL_LINES {
+            tail.pop_front();
+        }
+        tail.push_back(part.to_string());
     }
 }
 
-// Helper to center the popup
-fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
-    let popup_layout = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints([
-            Constraint::Percentage((100 - percent_y) / 2),
-            Constraint::Percentage(percent_y),
-            Constraint::Percentage((100 - percent_y) / 2),
-        ])
-        .split(r);
-
-    Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints([
-            Constraint::Percentage((100 - percent_x) / 2),
-            Constraint::Percentage(percent_x),
-            Constraint::Percentage((100 - percent_x) / 2),
-        ])
-        .split(popup_layout[1])[1]
+/// Recognizes ffmpeg's complaints about a codec it can't decode, such as
+/// `Decoder (codec av1) not found for input stream #0:0`. `Some(None)` when the line doesn't name it.
+fn parse_missing_decoder(line: &str) -> Option<Option<String>> {
+    let lower = line.to_ascii_lowercase();
+    let missing = ["decoder not found", ") not found for input stream", "no decoder found", "unknown codec", "unsupported codec"];
+    if !missing.iter().any(|m| lower.contains(m)) {
+        return None;
+    }
+    let re = Regex::new(r"(?i)codec (?:id )?'?([a-z0-9_]+)'?|found for: ([a-z0-9_]+)").ok()?;
+    let codec = re.captures(line).and_then(|c| c.get(1).or(c.get(2))).map(|m| m.as_str().to_string());
+    Some(codec.filter(|c| c.parse::<u32>().is_err() && c != "with"))
 }
 
-// Reuse existing logic, slightly adapted to not fail on missing inquiry
-fn play_video(video_path: &Path, mode: RenderMode) -> Result<()> {
-    let info = probe_video(video_path)?;
-    let (orig_w, orig_h) = (info.width, info.height);
-    let (term_w, term_h) = terminal::size()?;
-    
-    // Determine processing resolution
-    let (target_width, target_height) = match mode {
-        RenderMode::PixelArt => {
-             // STRATEGY: Half-Block Rendering (▀)
-            let effective_term_w = term_w as u32;
-            let effective_term_h = (term_h as u32) * 2; 
-            
-            let video_aspect = orig_w as f32 / orig_h as f32;
-            let term_aspect = effective_term_w as f32 / effective_term_h as f32;
-
-            let (mut w, mut h) = if video_aspect > term_aspect {
-                let h = effective_term_w as f32 / video_aspect;
-                (effective_term_w, h as u32)
-            } else {
-                let w = effective_term_h as f32 * video_aspect;
-                (w as u32, effective_term_h)
-            };
-            
-             // Ensure even and non-zero
-            w = (w / 2) * 2;
-            h = (h / 2) * 2;
-            if w == 0 { w = 2; }
-            if h == 0 { h = 2; }
-            (w, h)
-        },
-        RenderMode::AsciiArt => {
-            let char_aspect = 0.5; 
-            let video_aspect = orig_w as f32 / orig_h as f32;
-            
-            let mut w = term_w as u32;
-            let mut h = (w as f32 / video_aspect * char_aspect) as u32;
-
-            if h > term_h as u32 {
-                h = term_h as u32;
-                w = (h as f32 * video_aspect / char_aspect) as u32;
-            }
-            
-            // Ensure even and non-zero
-            w = (w / 2) * 2;
-            h = (h / 2) * 2;
-            if w == 0 { w = 2; }
-            if h == 0 { h = 2; }
-            (w, h)
-        }
-    };
+/// Extracts `WxH` from an ffmpeg stream description such as
+/// `Stream #0:0: Video: rawvideo (RGB[24] / 0x18424752), rgb24, 320x180, q=2-31`.
+fn parse_stream_size(line: &str) -> Option<(u32, u32)> {
+    let re = Regex::new(r", (\d+)x(\d+)").ok()?;
+    let caps = re.captures(line)?;
+    Some((caps[1].parse().ok()?, caps[2].parse().ok()?))
+}
 
-    let frame_size = (target_width * target_height * 3) as usize;
+#[derive(Debug, Clone)]
+struct VideoInfo {
+    width: u32,
+    height: u32,
+    /// Width over height of one stored pixel, from `pixel_aspect`: 1 for square pixels, other
+    /// values for anamorphic video, which is stored narrower or wider than it's meant to be shown.
+    pixel_aspect: f32,
+    /// `r_frame_rate`: the base rate, which for variable frame rate video is above the real one.
+    fps: f32,
+    /// `avg_frame_rate`, when ffprobe knows it.
+    avg_fps: Option<f32>,
+    /// `field_order`: `progressive`, `tt`/`bb`/`tb`/`bt` for interlaced video, or `unknown`.
+    field_order: Option<String>,
+    /// `color_transfer`: `smpte2084` (PQ) or `arib-std-b67` (HLG) for HDR video, `bt709` and the
+    /// like for SDR.
+    color_transfer: Option<String>,
+    duration: f64,
+    video_codec: String,
+    audio_codec: Option<String>,
+    audio_sample_rate: Option<u32>,
+    audio_channels: Option<u32>,
+    bitrate: Option<u64>,
+    /// Known for image sequences, where the duration follows from the chosen frame rate.
+    frame_count: Option<u32>,
+    /// Container tags from `TAG_LABELS`, keyed by lowercased tag name.
+    tags: BTreeMap<String, String>,
+    /// Chapter markers in start order; empty for files without any.
+    chapters: Vec<Chapter>,
+}
 
-    let ffmpeg_cmd = get_command_path("ffmpeg");
-    let mut child = Command::new(&ffmpeg_cmd)
-        .arg("-re") 
-        .arg("-i")
-        .arg(video_path)
-        .arg("-vf")
-        .arg(format!("scale={}:{}", target_width, target_height))
-        .arg("-vcodec")
-        .arg("rawvideo")
-        .arg("-pix_fmt")
-        .arg("rgb24")
-        .arg("-f")
-        .arg("image2pipe")
-        .arg("-") 
-        .stdout(Stdio::piped())
-        .stderr(Stdio::null()) 
-        .spawn()
-        .context("Failed to spawn ffmpeg")?;
+#[derive(Debug, Clone, PartialEq)]
+struct Chapter {
+    /// Start position in seconds.
+    start: f64,
+    title: String,
+}
 
-    let mut stdout = child.stdout.take().context("Failed to open stdout")?;
-    let mut buffer = vec![0u8; frame_size];
+/// Index of the chapter playing at `position`, if any has started yet.
+fn chapter_at(chapters: &[Chapter], position: f64) -> Option<usize> {
+    chapters.iter().rposition(|c| c.start <= position)
+}
 
-    terminal::enable_raw_mode()?;
-    let mut stdout_term = std::io::stdout();
-    execute!(stdout_term, EnterAlternateScreen, crossterm::cursor::Hide)?;
+/// Container tags shown in the details pane, with their labels, in display order.
+const TAG_LABELS: [(&str, Msg); 4] = [("title", Msg::TagTitle), ("artist", Msg::TagArtist), ("date", Msg::TagDate), ("comment", Msg::TagComment)];
 
-    let mut render_buffer = String::with_capacity((target_width * target_height * 30) as usize);
-    let ascii_chars = b" .:-=+*#%@";
+impl VideoInfo {
+    fn with_sequence_frames(mut self, path: &Path) -> Self {
+        if sequence::is_sequence(path) {
+            self.frame_count = sequence::frames(path).map(|(_, count)| count);
+            self = self.with_fps(sequence::DEFAULT_FPS);
+        }
+        self
+    }
 
-    let result = (|| -> Result<()> {
-        loop {
-            if let Err(_) = stdout.read_exact(&mut buffer) {
-                break; 
-            }
+    fn to_json(&self, path: &Path) -> String {
+        let audio = self.audio_codec.as_deref().map(|codec| {
+            JsonObject::new()
+                .str("codec", codec)
+                .opt_num("sample_rate", self.audio_sample_rate)
+                .opt_num("channels", self.audio_channels)
+                .finish()
+        });
+        JsonObject::new()
+            .str("path", &path.to_string_lossy())
+            .num("width", self.width)
+            .num("height", self.height)
+            .num("fps", self.fps)
+            .opt_num("duration", self.known_duration())
+            .str("video_codec", &self.video_codec)
+            .opt_num("bitrate", self.bitrate)
+            .opt_num("frame_count", self.frame_count)
+            .raw("tags", self.tags.iter().fold(JsonObject::new(), |obj, (k, v)| obj.str(k, v)).finish())
+            .raw("audio", audio.unwrap_or_else(|| "null".to_string()))
+            .finish()
+    }
 
-            let img = image::RgbImage::from_raw(target_width, target_height, buffer.clone())
-                .context("Failed to create image from buffer")?;
+    /// One "label: value" line per known tag, ready to put above the technical details.
+    fn tag_lines(&self) -> String {
+        TAG_LABELS
+            .iter()
+            .filter_map(|(key, label)| self.tags.get(*key).map(|v| format!("{}: {}\n", label.text(), v)))
+            .collect()
+    }
 
-            render_buffer.clear();
-            render_buffer.push_str("\x1b[H"); 
-            
-            let mut last_fg: Option<(u8, u8, u8)> = None;
-            let mut last_bg: Option<(u8, u8, u8)> = None;
+    /// `width`x`height` of stored pixels as they're meant to be shown: the width stretched or
+    /// squeezed by the pixel aspect, for sizing the picture on screen.
+    fn display_size(&self, width: u32, height: u32) -> (u32, u32) {
+        (((width as f32 * self.pixel_aspect).round() as u32).max(1), height)
+    }
 
-            // Centering logic
-            let display_height = match mode {
-                RenderMode::PixelArt => target_height / 2,
-                RenderMode::AsciiArt => target_height,
-            };
-            
-            let offset_y = (term_h as u32).saturating_sub(display_height) / 2;
-            let offset_x = (term_w as u32).saturating_sub(target_width) / 2;
+    /// The duration, unless the file reports none (still images, broken metadata).
+    fn known_duration(&self) -> Option<f64> {
+        (self.duration.is_finite() && self.duration > 0.0).then_some(self.duration)
+    }
 
-            for _ in 0..offset_y {
-                render_buffer.push_str("\r\n");
-            }
+    /// Files with an audio stream but no video, played through the spectrum visualizer.
+    fn is_audio_only(&self) -> bool {
+        (self.width == 0 || self.height == 0) && self.audio_codec.is_some()
+    }
 
-            match mode {
-                RenderMode::PixelArt => {
-                    for y in 0..(target_height / 2) {
-                        if offset_x > 0 {
-                            write!(render_buffer, "\x1b[0m{:width$}", "", width=offset_x as usize).unwrap();
-                            last_fg = None; last_bg = None;
-                        }
+    /// The average frame rate, if it differs from the base rate by more than `VFR_TOLERANCE`: the
+    /// sign of a variable frame rate video, whose frames aren't evenly spaced at `fps`.
+    fn vfr_average(&self) -> Option<f32> {
+        let avg = self.avg_fps.filter(|avg| *avg > 0.0 && self.frame_count.is_none())?;
+        (self.fps > 0.0 && (self.fps - avg).abs() / self.fps > VFR_TOLERANCE).then_some(avg)
+    }
 
-                        for x in 0..target_width {
-                            let p1 = img.get_pixel(x, y * 2);
-                            let [r1, g1, b1] = p1.0;
-                            let p2 = img.get_pixel(x, y * 2 + 1);
-                            let [r2, g2, b2] = p2.0;
+    /// Frames per second the source really has: the average for variable frame rate video.
+    fn frame_rate(&self) -> f64 {
+        self.vfr_average().unwrap_or(self.fps) as f64
+    }
 
-                            let curr_fg = (r1, g1, b1);
-                            if last_fg != Some(curr_fg) {
-                                write!(render_buffer, "\x1b[38;2;{};{};{}m", r1, g1, b1).unwrap();
-                                last_fg = Some(curr_fg);
-                            }
+    /// The rate `max_decode_fps` decimates this video to, when its frames come faster than that.
+    /// Image sequences play at the rate picked for them.
+    fn decode_cap(&self, max_decode_fps: Option<u32>) -> Option<f64> {
+        let cap = max_decode_fps? as f64;
+        (self.frame_count.is_none() && self.frame_rate() > cap * (1.0 + VFR_TOLERANCE as f64)).then_some(cap)
+    }
 
-                            let curr_bg = (r2, g2, b2);
-                            if last_bg != Some(curr_bg) {
-                                write!(render_buffer, "\x1b[48;2;{};{};{}m", r2, g2, b2).unwrap();
-                                last_bg = Some(curr_bg);
-                            }
+    /// Whether ffprobe reports interlaced fields, in either order.
+    fn is_interlaced(&self) -> bool {
+        matches!(self.field_order.as_deref(), Some("tt" | "bb" | "tb" | "bt"))
+    }
 
-                            render_buffer.push('▀');
-                        }
-                        render_buffer.push_str("\x1b[0m\r\n");
-                        last_fg = None; last_bg = None;
-                    }
-                },
-                RenderMode::AsciiArt => {
-                    for y in 0..target_height {
-                        if offset_x > 0 {
-                            write!(render_buffer, "\x1b[0m{:width$}", "", width=offset_x as usize).unwrap();
-                            last_fg = None; 
-                        }
+    /// The HDR transfer, `PQ` or `HLG`, or `None` for SDR video.
+    fn hdr_transfer(&self) -> Option<&'static str> {
+        match self.color_transfer.as_deref() {
+            Some("smpte2084") => Some("PQ"),
+            Some("arib-std-b67") => Some("HLG"),
+            _ => None,
+        }
+    }
 
-                        for x in 0..target_width {
-                            let pixel = img.get_pixel(x, y);
-                            let [r, g, b] = pixel.0;
+    fn is_hdr(&self) -> bool {
+        self.hdr_transfer().is_some()
+    }
 
-                            let brightness = ((r as u16 * 77 + g as u16 * 150 + b as u16 * 29) >> 8) as u8;
-                            let char_idx = (brightness as usize * (ascii_chars.len() - 1)) / 255;
-                            let ascii = ascii_chars[char_idx] as char;
+    /// The scan type for the details pane.
+    fn scan_label(&self) -> String {
+        match self.field_order.as_deref() {
+            Some(order) if self.is_interlaced() => Msg::ScanInterlaced.fill(&[&order.to_ascii_uppercase()]),
+            Some("progressive") => Msg::ScanProgressive.text().to_string(),
+            _ => Msg::ScanUnknown.text().to_string(),
+        }
+    }
 
-                            let curr_fg = (r, g, b);
-                            if last_fg != Some(curr_fg) {
-                                write!(render_buffer, "\x1b[38;2;{};{};{}m", r, g, b).unwrap();
-                                last_fg = Some(curr_fg);
-                            }
-                            render_buffer.push(ascii);
-                        }
-                        render_buffer.push_str("\x1b[0m\r\n");
-                        last_fg = None;
-                    }
-                }
-            }
-            
-            stdout_term.write_all(render_buffer.as_bytes())?;
-            stdout_term.flush()?;
-            
-            if crossterm::event::poll(Duration::from_millis(0))? {
-                if let crossterm::event::Event::Key(key) = crossterm::event::read()? {
-                    if key.code == crossterm::event::KeyCode::Char('q') || key.code == crossterm::event::KeyCode::Esc {
-                        break;
-                    }
-                }
-            }
+    /// Overrides the frame rate, recomputing the duration when the frame count is known.
+    fn with_fps(mut self, fps: u32) -> Self {
+        if let Some(count) = self.frame_count {
+            self.fps = fps as f32;
+            self.duration = count as f64 / fps.max(1) as f64;
         }
-        Ok(())
-    })();
+        self
+    }
+}
 
-    let _ = stdout_term.write(b"\x1b[0m"); 
-    execute!(stdout_term, crossterm::cursor::Show, LeaveAlternateScreen)?;
-    terminal::disable_raw_mode()?;
-    let _ = child.kill();
+/// What the background probe of a URL reports to the menu.
+enum ProbeEvent {
+    /// A progress line from ffprobe's stderr.
+    Status(String),
+    /// The probe finished; `None` if it failed.
+    Done(Option<VideoInfo>),
+}
 
-    result
+/// An ffprobe frame rate, `30000/1001` or `25`; `None` for `0/0` and other unknowns.
+fn parse_rate(value: &str) -> Option<f32> {
+    let value = value.trim();
+    let rate = match value.split_once('/') {
+        Some((num, den)) => num.parse::<f32>().ok()? / den.parse::<f32>().ok()?,
+        None => value.parse().ok()?,
+    };
+    (rate.is_finite() && rate > 0.0).then_some(rate)
 }
 
-struct VideoInfo {
-    width: u32,
-    height: u32,
-    fps: f32,
-    duration: f64,
-    video_codec: String,
-    audio_codec: Option<String>,
-    bitrate: Option<u64>,
+/// Format tags some containers carry a display aspect ratio in, instead of on the video stream.
+const ASPECT_TAGS: [&str; 3] = ["display_aspect_ratio", "aspect_ratio", "aspect"];
+
+/// An aspect ratio as ffprobe or a tag writes it, `16:9`, `4/3` or `1.85`; `None` for `N/A`,
+/// `0:1` (ffprobe's unknown) and anything else unreadable.
+fn parse_aspect(value: &str) -> Option<f32> {
+    parse_rate(&value.trim().replace(':', "/"))
+}
+
+/// The width over height of one stored pixel of a `width`x`height` video. The stream's
+/// `sample_aspect_ratio` says so directly; failing that its `display_aspect_ratio`, and failing
+/// that a display aspect ratio from the format's tags, are the shape the whole picture should have.
+/// Square when none of them is known.
+fn pixel_aspect(width: u32, height: u32, sar: Option<&str>, dar: Option<&str>, format_dar: Option<&str>) -> f32 {
+    if let Some(sar) = sar.and_then(parse_aspect) {
+        return sar;
+    }
+    if width == 0 || height == 0 {
+        return 1.0;
+    }
+    dar.and_then(parse_aspect)
+        .or_else(|| format_dar.and_then(parse_aspect))
+        .map_or(1.0, |dar| dar * height as f32 / width as f32)
 }
 
 fn probe_video(path: &Path) -> Result<VideoInfo> {
+    probe_video_reporting(path, &mut |_| {})
+}
+
+/// `probe_video`, handing the connection and download progress of a URL to `on_status`.
+fn probe_video_reporting(path: &Path, on_status: &mut dyn FnMut(String)) -> Result<VideoInfo> {
     let ffprobe_cmd = get_command_path("ffprobe");
-    
-    // 1. Probe Video Stream
-    let output = Command::new(&ffprobe_cmd)
-        .arg("-v").arg("error")
+    let loglevel = remote::loglevel(path, "error");
+    let mut run = |cmd: &mut Command| {
+        trace::command(cmd);
+        if remote::is_remote(path) { remote::output_reporting(cmd, on_status) } else { cmd.output() }
+    };
+
+    // 1. Probe the video stream, the container tags (with the aspect ratio tags for when the
+    // stream has none) and the chapters in one run
+    let format_tags = TAG_LABELS.map(|(key, _)| key).iter().chain(&ASPECT_TAGS).copied().collect::<Vec<_>>().join(",");
+    let output = run(Command::new(&ffprobe_cmd)
+        .arg("-v").arg(loglevel)
         .arg("-select_streams").arg("v:0")
-        .arg("-show_entries").arg("stream=width,height,r_frame_rate,duration,codec_name,bit_rate")
-        .arg("-of").arg("default=noprint_wrappers=1")
-        .arg(path)
-        .output()
+        .arg("-show_entries").arg(format!("stream=width,height,sample_aspect_ratio,display_aspect_ratio,r_frame_rate,avg_frame_rate,field_order,color_transfer,duration,codec_name,bit_rate:format_tags={}", format_tags))
+        .arg("-show_chapters")
+        .arg("-of").arg("default")
+        .args(sequence::input_args(path, sequence::DEFAULT_FPS))
+        .arg(path))
         .context("Failed to run ffprobe for video stream")?;
 
     let output_str = String::from_utf8_lossy(&output.stdout);
     
     let mut width = 0;
     let mut height = 0;
+    let (mut sar, mut dar) = (None, None);
     let mut fps = 30.0;
+    let mut avg_fps = None;
+    let mut field_order = None;
+    let mut color_transfer = None;
     let mut duration = 0.0;
     let mut video_codec = String::from("Unknown");
     let mut bitrate = None;
 
-    for line in output_str.lines() {
+    for line in probe_section(&output_str, "STREAM").lines() {
         if let Some((key, value)) = line.split_once('=') {
             match key.trim() {
                 "width" => width = value.trim().parse().unwrap_or(0),
                 "height" => height = value.trim().parse().unwrap_or(0),
-                "r_frame_rate" => {
-                    let fps_str = value.trim();
-                    if fps_str.contains('/') {
-                        let parts: Vec<&str> = fps_str.split('/').collect();
-                        if parts.len() == 2 {
-                            let num: f32 = parts[0].parse().unwrap_or(0.0);
-                            let den: f32 = parts[1].parse().unwrap_or(1.0);
-                            if den != 0.0 { fps = num / den; }
-                        }
-                    } else {
-                        fps = fps_str.parse().unwrap_or(30.0);
-                    }
-                },
+                "sample_aspect_ratio" => sar = Some(value.trim().to_string()),
+                "display_aspect_ratio" => dar = Some(value.trim().to_string()),
+                "r_frame_rate" => fps = parse_rate(value).unwrap_or(fps),
+                "avg_frame_rate" => avg_fps = parse_rate(value),
+                "field_order" => field_order = Some(value.trim().to_string()),
+                "color_transfer" => color_transfer = Some(value.trim().to_string()),
                 "duration" => duration = value.trim().parse().unwrap_or(0.0),
                 "codec_name" => video_codec = value.trim().to_string(),
                 "bit_rate" => {
@@ -842,54 +5412,778 @@ fn probe_video(path: &Path) -> Result<VideoInfo> {
         }
     }
 
-    if width == 0 || height == 0 {
-        anyhow::bail!("Failed to parse essential video metadata.");
-    }
-
     // 2. Probe Audio Stream
-    let audio_output = Command::new(&ffprobe_cmd)
-        .arg("-v").arg("error")
+    let audio_output = run(Command::new(&ffprobe_cmd)
+        .arg("-v").arg(loglevel)
         .arg("-select_streams").arg("a:0")
-        .arg("-show_entries").arg("stream=codec_name")
+        .arg("-show_entries").arg("stream=codec_name,sample_rate,channels,duration,bit_rate")
         .arg("-of").arg("default=noprint_wrappers=1")
-        .arg(path)
-        .output()
+        .args(sequence::input_args(path, sequence::DEFAULT_FPS))
+        .arg(path))
         .ok(); // Optional
 
     let mut audio_codec = None;
+    let mut audio_sample_rate = None;
+    let mut audio_channels = None;
     if let Some(out) = audio_output {
         let out_str = String::from_utf8_lossy(&out.stdout);
         for line in out_str.lines() {
              if let Some((key, value)) = line.split_once('=') {
-                 if key.trim() == "codec_name" {
-                     audio_codec = Some(value.trim().to_string());
+                 match key.trim() {
+                     "codec_name" => audio_codec = Some(value.trim().to_string()),
+                     "sample_rate" => audio_sample_rate = value.trim().parse().ok(),
+                     "channels" => audio_channels = value.trim().parse().ok(),
+                     // Audio-only files have no video stream to take these from.
+                     "duration" if duration == 0.0 => duration = value.trim().parse().unwrap_or(0.0),
+                     "bit_rate" if bitrate.is_none() => bitrate = value.trim().parse().ok(),
+                     _ => {}
                  }
              }
         }
     }
 
+    let mut tags = parse_tags(&probe_section(&output_str, "FORMAT"));
+    let format_dars: Vec<String> = ASPECT_TAGS.iter().filter_map(|key| tags.remove(*key)).collect();
+    let pixel_aspect = pixel_aspect(width, height, sar.as_deref(), dar.as_deref(), format_dars.first().map(String::as_str));
+    let chapters = parse_chapters(&output_str);
+
+    if (width == 0 || height == 0) && audio_codec.is_none() {
+        anyhow::bail!("{} has no video or audio stream that ffprobe can read", path.display());
+    }
+    trace::note(format!(
+        "probed {}: {}x{} (pixel aspect {:.3}), {:.3} fps (average {:?}), field order {:?}, transfer {:?}, {:.3}s, video {}, audio {:?}, bitrate {:?}",
+        path.display(), width, height, pixel_aspect, fps, avg_fps, field_order, color_transfer, duration, video_codec, audio_codec, bitrate
+    ));
+
     Ok(VideoInfo {
         width,
         height,
+        pixel_aspect,
         fps,
+        avg_fps,
+        field_order,
+        color_transfer,
         duration,
         video_codec,
         audio_codec,
+        audio_sample_rate,
+        audio_channels,
         bitrate,
-    })
+        frame_count: None,
+        tags,
+        chapters,
+    }
+    .with_sequence_frames(path))
+}
+
+/// Picks a representative frame of `path` with ffmpeg's `thumbnail` filter, decoded at poster size.
+fn extract_poster(path: &Path) -> Result<Poster> {
+    let output = traced_output(Command::new(get_command_path("ffmpeg"))
+        .args(["-hide_banner", "-v", "error"])
+        .args(sequence::input_args(path, sequence::DEFAULT_FPS))
+        .arg("-i").arg(path)
+        .args(["-vf", &poster::filter(), "-frames:v", "1", "-f", "rawvideo", "-"]))
+        .context("Failed to run ffmpeg for a poster frame")?;
+    Poster::from_rgb(output.stdout).context("ffmpeg produced no poster frame")
+}
+
+/// The first frame and the frames where scenes change, spread into a filmstrip; slow, as it decodes
+/// the whole file.
+fn extract_filmstrip(path: &Path) -> Result<Vec<Poster>> {
+    let output = traced_output(Command::new(get_command_path("ffmpeg"))
+        .args(["-hide_banner", "-v", "error"])
+        .args(sequence::input_args(path, sequence::DEFAULT_FPS))
+        .arg("-i").arg(path)
+        .args(["-an", "-vf", &poster::scene_filter(), "-vsync", "vfr", "-f", "rawvideo", "-"]))
+        .context("Failed to run ffmpeg for scene changes")?;
+    Ok(poster::filmstrip(&output.stdout))
+}
+
+/// Decodes the first video stream with `ffprobe -count_frames`; slow, as it reads the whole file.
+fn count_video_frames(path: &Path) -> Result<u64> {
+    let output = traced_output(Command::new(get_command_path("ffprobe"))
+        .args(["-v", "error", "-select_streams", "v:0", "-count_frames"])
+        .args(["-show_entries", "stream=nb_read_frames", "-of", "default=noprint_wrappers=1:nokey=1"])
+        .arg(path))
+        .context("Failed to run ffprobe to count frames")?;
+    parse_frame_count(&String::from_utf8_lossy(&output.stdout)).context("ffprobe reported no frame count")
+}
+
+fn parse_frame_count(output: &str) -> Option<u64> {
+    output.lines().find_map(|line| line.trim().parse().ok())
+}
+
+/// Subtitle streams embedded in `path`; none when there are none or ffprobe fails.
+fn probe_subtitle_tracks(path: &Path) -> Vec<Track> {
+    traced_output(Command::new(get_command_path("ffprobe"))
+        .args(["-v", "error", "-select_streams", "s"])
+        .args(["-show_entries", "stream=codec_name:stream_tags=language,title"])
+        .arg(path))
+        .map(|out| subtitles::parse_tracks(&String::from_utf8_lossy(&out.stdout)))
+        .unwrap_or_default()
+}
+
+/// Converts a text subtitle track to SRT with ffmpeg and parses its cues.
+fn extract_subtitles(path: &Path, track: &Track) -> Result<Vec<Cue>> {
+    let output = traced_output(Command::new(get_command_path("ffmpeg"))
+        .args(["-hide_banner", "-v", "error", "-i"])
+        .arg(path)
+        .args(["-map", &format!("0:s:{}", track.index), "-f", "srt", "-"]))
+        .context("Failed to run ffmpeg to extract subtitles")?;
+    if !output.status.success() {
+        anyhow::bail!("{}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(subtitles::parse_srt(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Reads the `[CHAPTER]` blocks of `ffprobe -show_chapters`, ignoring the other sections around
+/// them; untitled chapters are numbered.
+fn parse_chapters(output: &str) -> Vec<Chapter> {
+    let mut chapters: Vec<Chapter> = Vec::new();
+    let mut inside = false;
+    for line in output.lines() {
+        let line = line.trim();
+        if line == "[CHAPTER]" {
+            chapters.push(Chapter { start: 0.0, title: String::new() });
+            inside = true;
+            continue;
+        }
+        if line == "[/CHAPTER]" {
+            inside = false;
+        }
+        let Some(chapter) = chapters.last_mut().filter(|_| inside) else { continue };
+        match line.split_once('=') {
+            Some(("start_time", value)) => chapter.start = value.parse().unwrap_or(0.0),
+            Some((key, value)) if key.eq_ignore_ascii_case("TAG:title") => chapter.title = value.to_string(),
+            _ => {}
+        }
+    }
+    for (i, chapter) in chapters.iter_mut().enumerate() {
+        if chapter.title.is_empty() {
+            chapter.title = Msg::ChapterFallback.fill(&[&(i + 1)]);
+        }
+    }
+    chapters.sort_by(|a, b| a.start.total_cmp(&b.start));
+    chapters
+}
+
+/// The `key=value` lines of the `[NAME]` blocks of ffprobe's default output.
+fn probe_section(output: &str, name: &str) -> String {
+    let (open, close) = (format!("[{}]", name), format!("[/{}]", name));
+    let mut inside = false;
+    let mut lines = Vec::new();
+    for line in output.lines().map(str::trim) {
+        if line == open {
+            inside = true;
+        } else if line == close {
+            inside = false;
+        } else if inside {
+            lines.push(line);
+        }
+    }
+    lines.join("\n")
+}
+
+/// Reads `TAG:key=value` lines from ffprobe's default output, skipping empty values.
+fn parse_tags(output: &str) -> BTreeMap<String, String> {
+    output
+        .lines()
+        .filter_map(|line| line.strip_prefix("TAG:")?.split_once('='))
+        .filter(|(_, value)| !value.trim().is_empty())
+        .map(|(key, value)| (key.trim().to_ascii_lowercase(), value.trim().to_string()))
+        .collect()
+}
+
+/// `Command::output` that shows up in `--verbose` logs first.
+fn traced_output(cmd: &mut Command) -> io::Result<std::process::Output> {
+    trace::command(cmd);
+    cmd.output()
 }
 
 fn get_command_path(cmd: &str) -> String {
-    let exe_name = if cfg!(target_os = "windows") {
-        format!("{}.exe", cmd)
-    } else {
-        cmd.to_string()
-    };
+    locate::command_path(cmd)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn child_guard_kills_and_reaps_on_drop() {
+        let guard = ChildGuard::spawn(Command::new("sleep").arg("30")).unwrap();
+        let pid = guard.0.id().to_string();
+        drop(guard);
+        let alive = Command::new("kill").arg("-0").arg(&pid).stderr(Stdio::null()).status().unwrap();
+        assert!(!alive.success());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn threaded_decoder_prebuffers_and_hands_out_frames_in_order() {
+        let mut child = ChildGuard::spawn(Command::new("printf").arg("abcdefghijklmnopqr").stdout(Stdio::piped())).unwrap();
+        let stdout = child.0.stdout.take().unwrap();
+        let decoder = Decoder { child, stderr_tail: None, pipe: FramePipe::Direct(stdout), width: 2, height: 1, format: PixelFormat::Rgb24, deep: None };
+        let mut filled = Vec::new();
+        let mut decoder = decoder.threaded(2);
+        decoder.prebuffer(2, &mut |n| filled.push(n));
+        assert_eq!(filled, [1, 2]);
+
+        let mut frame = [0u8; 6];
+        for expected in [b"abcdef", b"ghijkl", b"mnopqr"] {
+            decoder.read_frame(&mut frame).unwrap();
+            assert_eq!(&frame, expected);
+        }
+        assert_eq!(decoder.read_frame(&mut frame).unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+        assert_eq!(decoder.failure(), None);
+
+        // An endless source: dropping kills it and joins the reader blocked on the full queue.
+        let mut child = ChildGuard::spawn(Command::new("cat").arg("/dev/zero").stdout(Stdio::piped())).unwrap();
+        let stdout = child.0.stdout.take().unwrap();
+        let mut endless = Decoder { child, stderr_tail: None, pipe: FramePipe::Direct(stdout), width: 2, height: 1, format: PixelFormat::Rgb24, deep: None }.threaded(1);
+        endless.read_frame(&mut frame).unwrap();
+        drop(endless);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn catching_up_skips_to_the_newest_queued_frame() {
+        let mut child = ChildGuard::spawn(Command::new("printf").arg("abcdefghijklmnopqr").stdout(Stdio::piped())).unwrap();
+        let stdout = child.0.stdout.take().unwrap();
+        let mut decoder = Decoder { child, stderr_tail: None, pipe: FramePipe::Direct(stdout), width: 2, height: 1, format: PixelFormat::Rgb24, deep: None }.threaded(4);
+        // All three frames and the end of the pipe queued up, as behind a slow terminal.
+        decoder.prebuffer(4, &mut |_| {});
+        assert_eq!(decoder.skip_to_latest(), 2);
+        let mut frame = [0u8; 6];
+        decoder.read_frame(&mut frame).unwrap();
+        assert_eq!(&frame, b"mnopqr");
+        // Nothing's left to skip, and the end of the pipe wasn't skipped over.
+        assert_eq!(decoder.skip_to_latest(), 0);
+        assert_eq!(decoder.read_frame(&mut frame).unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn memory_stats_show_the_process_and_buffer_sizes() {
+        let lines = memory_stats(&mut System::new(), 3 * 1024 * 1024, 5, 512 * 1024);
+        assert!(lines[0].starts_with("本进程 ") && lines[0].ends_with(" MB"));
+        assert_ne!(lines[0], "本进程 N/A MB");
+        assert_eq!(lines[1], "缓冲 帧 3.0 MB ×5 · 输出 0.5 MB");
+    }
+
+    #[test]
+    fn sync_overlay_shows_the_frame_ahead_of_or_behind_the_clock() {
+        let lines = sync_lines(250, 25.0, 10.04);
+        assert_eq!(lines[0], "帧号  250");
+        assert_eq!(lines[1], "PTS   10.000 s");
+        assert_eq!(lines[3], "偏差  -40 ms");
+        assert_eq!(sync_lines(30, 30.0, 0.99)[3], "偏差  +10 ms");
+    }
+
+    #[test]
+    fn command_line_plays_back_with_the_same_settings() {
+        let args = ["--mode", "ascii", "--brightness", "2", "--hflip", "--fit", "width", "--crop", "320:360:0:0", "my clip.mp4"];
+        let cli = cli::parse(args.iter().map(|a| a.to_string())).unwrap();
+        let opts = play_options(&cli, None);
+        // Changed during playback.
+        let state = PlaybackState { hflip: true, mirror: Mirror::Quad, saturation_steps: 5, fps_cap: Some(15), fit: Fit::Width, ..Default::default() };
+        let command = command_line(Path::new("my clip.mp4"), &opts, &state);
+        assert!(command.ends_with(" 'my clip.mp4'"));
+        let again = cli::parse(cli::split_args(&command).unwrap().into_iter().skip(1)).unwrap();
+        assert_eq!(again.command, CliCommand::Play(PathBuf::from("my clip.mp4")));
+        assert_eq!((again.mode, again.fit, again.crop), (Some(RenderMode::AsciiArt), Fit::Width, cli.crop));
+        assert_eq!(again.levels, [(Level::Brightness, 2)]);
+        assert!(again.hflip && !again.vflip);
+        assert_eq!((again.mirror, again.saturation, again.fps_cap), (Mirror::Quad, Some(1.5), Some(15)));
+    }
+
+    #[test]
+    fn popups_fit_content_within_bounds() {
+        let screen = Rect::new(0, 0, 100, 30);
+        // Small content still gets the minimum width, centered.
+        assert_eq!(popup_rect(10, 3, screen), Rect::new(35, 12, 30, 5));
+        // Wide content is capped at 80% of the screen, tall content at its height.
+        assert_eq!(popup_rect(200, 100, screen), Rect::new(10, 0, 80, 30));
+        // A tiny screen never gets a popup wider than the screen.
+        assert!(popup_rect(10, 3, Rect::new(0, 0, 20, 4)).width <= 20);
+        assert_eq!(popup_rect(10, 3, Rect::new(0, 0, 0, 0)), Rect::new(0, 0, 0, 0));
+    }
+
+    #[test]
+    fn menu_needs_room_for_its_bars_and_a_row_of_list() {
+        assert!(Panes::fit(Rect::new(0, 0, 80, 9), false));
+        assert!(!Panes::fit(Rect::new(0, 0, 80, 8), false));
+        // Focus mode has no header or footer to make room for.
+        assert!(Panes::fit(Rect::new(0, 0, 80, 3), true));
+        assert!(!Panes::fit(Rect::new(0, 0, 29, 40), false));
+    }
+
+    #[test]
+    fn long_input_wraps_to_popup_width() {
+        assert_eq!(wrap_to_width("/videos/clip.mp4", 6), vec!["/video", "s/clip", ".mp4"]);
+        assert_eq!(wrap_to_width("视频/a.mp4", 5), vec!["视频/", "a.mp4"]);
+        assert_eq!(wrap_to_width("", 10), vec![""]);
+    }
+
+    #[test]
+    fn list_label_is_relative_to_the_scan_root() {
+        let root = std::env::current_dir().unwrap();
+        assert_eq!(list_label(Path::new("./clips/a.mp4"), false), "a.mp4");
+        assert_eq!(list_label(Path::new("./clips/a.mp4"), true), Path::new("clips").join("a.mp4").display().to_string());
+        let outside = root.parent().unwrap().join("b.mkv");
+        assert_eq!(list_label(&outside, true), outside.display().to_string());
+    }
+
+    #[test]
+    fn extensions_match_regardless_of_case() {
+        assert!(has_extension(Path::new("clip.Mov"), &VIDEO_EXTENSIONS));
+        assert!(has_extension(Path::new("dir/VIDEO.MKV"), &VIDEO_EXTENSIONS));
+        assert!(has_extension(Path::new("song.FLAC"), &AUDIO_EXTENSIONS));
+        assert!(!has_extension(Path::new("notes.txt"), &VIDEO_EXTENSIONS));
+        assert!(!has_extension(Path::new("mp4"), &VIDEO_EXTENSIONS));
+    }
+
+    #[test]
+    fn fps_cap_draws_evenly_spaced_frames() {
+        let drawn: Vec<u64> = (1..=24).filter(|&n| should_render(n, 24.0, Some(10))).collect();
+        assert_eq!(drawn.len(), 10);
+        assert_eq!(&drawn[..3], &[3, 5, 8]);
+        assert!((1..=24).all(|n| should_render(n, 24.0, Some(30))));
+        assert!((1..=24).all(|n| should_render(n, 24.0, None)));
+    }
+
+    #[test]
+    fn scaled_target_stays_even_and_nonzero() {
+        assert_eq!(scaled_target((200, 100), 100), (200, 100));
+        assert_eq!(scaled_target((200, 100), 90), (180, 90));
+        assert_eq!(scaled_target((158, 90), 70), (110, 62));
+        assert_eq!(scaled_target((4, 2), 30), (2, 2));
+        let state = PlaybackState { scale_steps: 3, ..Default::default() };
+        assert_eq!(state.scale_percent(), 70);
+        assert!(state.status().unwrap().contains("分辨率: 70%"));
+        let state = PlaybackState { scale_steps: 6, auto_quality: true, auto_steps: 2, ..Default::default() };
+        assert_eq!(state.scale_percent(), MIN_SCALE_PERCENT);
+        assert!(state.status().unwrap().contains("自动画质: -20%"));
+    }
+
+    #[test]
+    fn stalls_are_blamed_on_the_slower_side() {
+        let ms = Duration::from_millis;
+        let frame = ms(40);
+        assert_eq!(Stall::detect(ms(40), ms(5), frame), None);
+        assert_eq!(Stall::detect(ms(2500), ms(5), frame), Some(Stall::Decode));
+        assert_eq!(Stall::detect(ms(10), ms(3000), frame), Some(Stall::Render));
+        // At 0.25 fps a 3 s wait is just the next frame.
+        assert_eq!(Stall::detect(ms(3000), ms(5), ms(4000)), None);
+    }
+
+    #[test]
+    fn slideshow_interval_defaults_and_clamps() {
+        assert_eq!(slideshow_interval(None), Duration::from_secs(DEFAULT_SLIDESHOW_SECS));
+        assert_eq!(slideshow_interval(Some(" 5")), Duration::from_secs(5));
+        assert_eq!(slideshow_interval(Some("0")), Duration::from_secs(1));
+        assert_eq!(slideshow_interval(Some("soon")), Duration::from_secs(DEFAULT_SLIDESHOW_SECS));
+    }
+
+    #[test]
+    fn fps_cap_cycles_back_to_uncapped() {
+        let mut cap = None;
+        let mut seen = Vec::new();
+        for _ in 0..=FPS_CAP_STEPS.len() {
+            cap = next_fps_cap(cap);
+            seen.push(cap);
+        }
+        assert_eq!(seen, vec![Some(30), Some(24), Some(15), Some(10), None]);
+    }
+
+    #[test]
+    fn missing_decoder_messages_name_the_codec() {
+        assert_eq!(parse_missing_decoder("[vist#0:0/av1 @ 0x5] Decoder (codec av1) not found for input stream #0:0"), Some(Some("av1".to_string())));
+        assert_eq!(parse_missing_decoder("Decoding requested, but no decoder found for: hevc"), Some(Some("hevc".to_string())));
+        assert_eq!(parse_missing_decoder("Unsupported codec with id 0 for input stream 0"), Some(None));
+        assert_eq!(parse_missing_decoder("  Stream #0:0: Video: av1 (libdav1d), yuv420p, 1920x1080"), None);
+    }
+
+    #[test]
+    fn stderr_tail_keeps_the_last_messages_without_statistics() {
+        let mut tail = VecDeque::new();
+        for i in 0..7 {
+            push_stderr_line(&mut tail, &format!("line {}", i));
+        }
+        push_stderr_line(&mut tail, "frame=  10 fps=0.0 q=-0.0 size=N/A\rframe=  20 fps=20 q=-0.0 size=N/A\r");
+        push_stderr_line(&mut tail, "[h264 @ 0x5] error while decoding MB 3 4");
+        assert_eq!(tail, ["line 3", "line 4", "line 5", "line 6", "[h264 @ 0x5] error while decoding MB 3 4"]);
+    }
+
+    #[test]
+    fn stream_size_from_ffmpeg_output_line() {
+        let line = "  Stream #0:0: Video: rawvideo (RGB[24] / 0x18424752), rgb24(pc, gbr/unknown/unknown, progressive), 318x180 [SAR 1:1 DAR 53:30], q=2-31, 34344 kb/s, 25 fps, 25 tbn";
+        assert_eq!(parse_stream_size(line), Some((318, 180)));
+        assert_eq!(parse_stream_size("  Stream #0:1: Audio: aac, 44100 Hz, stereo"), None);
+    }
+
+    /// A video-only probe result with every optional field missing.
+    fn probe_fixture() -> VideoInfo {
+        VideoInfo {
+            width: 640,
+            height: 360,
+            pixel_aspect: 1.0,
+            fps: 25.0,
+            avg_fps: Some(25.0),
+            field_order: None,
+            color_transfer: None,
+            duration: 12.5,
+            video_codec: "h264".to_string(),
+            audio_codec: None,
+            audio_sample_rate: None,
+            audio_channels: None,
+            bitrate: None,
+            frame_count: None,
+            tags: BTreeMap::new(),
+            chapters: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn video_info_json_uses_null_for_missing_fields() {
+        let json = probe_fixture().to_json(Path::new("/v/a.mp4"));
+        assert!(json.contains("\"bitrate\": null,"));
+        assert!(json.contains("\"audio\": null\n}"));
+        assert!(json.contains("\"width\": 640,"));
+        assert!(json.contains("\"tags\": {},"));
+    }
+
+    #[test]
+    fn counted_frames_give_a_precise_duration() {
+        assert_eq!(parse_frame_count("1554\n"), Some(1554));
+        assert_eq!(parse_frame_count("N/A\n"), None);
+        assert_eq!(counted_duration(1554, 25.0), Some(62.16));
+        assert_eq!(counted_duration(10, 0.0), None);
+        assert_eq!(format_precise(3723.04), "01:02:03.040");
+    }
+
+    #[test]
+    fn pixel_aspect_falls_back_from_the_stream_to_the_format() {
+        let round = |aspect: f32| (aspect * 1000.0).round() / 1000.0;
+        // DVD widescreen: 720x480 stored, 16:9 shown, said by the stream's SAR.
+        assert_eq!(round(pixel_aspect(720, 480, Some("32:27"), Some("16:9"), None)), 1.185);
+        // SAR unknown, DAR on the stream.
+        assert_eq!(round(pixel_aspect(1440, 1080, Some("N/A"), Some("16:9"), None)), 1.333);
+        // ffprobe's 0:1 for unknown on both; the format's tag still says 16:9.
+        assert_eq!(round(pixel_aspect(1440, 1080, Some("0:1"), Some("0:1"), Some("1.7778"))), 1.333);
+        assert_eq!(round(pixel_aspect(720, 576, None, None, Some("4/3"))), 1.067);
+        // Nothing known, or no size to relate a DAR to: square pixels.
+        assert_eq!(pixel_aspect(640, 360, Some("N/A"), Some("N/A"), None), 1.0);
+        assert_eq!(pixel_aspect(0, 0, None, Some("16:9"), None), 1.0);
+
+        let anamorphic = VideoInfo { width: 1440, height: 1080, pixel_aspect: 4.0 / 3.0, ..probe_fixture() };
+        assert_eq!(anamorphic.display_size(1440, 1080), (1920, 1080));
+        assert_eq!(probe_fixture().display_size(640, 360), (640, 360));
+    }
 
-    if std::path::Path::new(&exe_name).exists() {
-        if let Ok(path) = std::env::current_dir() {
-            return path.join(exe_name).to_string_lossy().to_string();
+    #[test]
+    fn variable_frame_rate_is_told_apart_by_the_average_rate() {
+        assert_eq!(parse_rate("30000/1001").map(|r| (r * 100.0).round()), Some(2997.0));
+        assert_eq!(parse_rate("25"), Some(25.0));
+        assert_eq!(parse_rate("0/0"), None);
+
+        assert_eq!(probe_fixture().vfr_average(), None);
+        assert_eq!(VideoInfo { fps: 30000.0 / 1001.0, avg_fps: Some(29.97), ..probe_fixture() }.vfr_average(), None);
+        let vfr = VideoInfo { fps: 30.0, avg_fps: Some(24.5), ..probe_fixture() };
+        assert_eq!(vfr.vfr_average(), Some(24.5));
+        assert_eq!(VideoInfo { avg_fps: None, ..vfr.clone() }.vfr_average(), None);
+
+        let state = PlaybackState { cfr_fps: Some(24.5), ..Default::default() };
+        assert!(state.filters(320, 180).video_arg().unwrap().starts_with("fps=24.500,scale=320:180"));
+    }
+
+    #[test]
+    fn piped_paths_are_checked_and_deduplicated() {
+        let dir = std::env::temp_dir().join(format!("vodeo2ascii-stdin-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for name in ["a.mp4", "notes.txt"] {
+            std::fs::write(dir.join(name), "").unwrap();
         }
+        let video = dir.join("a.mp4");
+        let input = format!(
+            "  {}  \n\n{}\n{}\n{}\nhttps://example.com/live.m3u8\n",
+            video.display(), dir.join("notes.txt").display(), dir.join("missing.mkv").display(), video.display()
+        );
+        let (paths, skipped) = playable_paths(&input);
+        assert_eq!(paths, [video, PathBuf::from("https://example.com/live.m3u8")]);
+        assert_eq!(skipped, 2);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn only_sources_faster_than_the_cap_are_decimated() {
+        let capture = VideoInfo { fps: 240.0, avg_fps: Some(240.0), ..probe_fixture() };
+        assert_eq!(capture.decode_cap(Some(60)), Some(60.0));
+        assert_eq!(capture.decode_cap(None), None);
+        assert_eq!(VideoInfo { fps: 60.0, avg_fps: Some(59.94), ..probe_fixture() }.decode_cap(Some(60)), None);
+        // A variable rate source is judged by how fast its frames really come.
+        assert_eq!(VideoInfo { fps: 120.0, avg_fps: Some(50.0), ..probe_fixture() }.decode_cap(Some(60)), None);
+        assert_eq!(VideoInfo { frame_count: Some(500), ..capture }.decode_cap(Some(60)), None);
+    }
+
+    #[test]
+    fn per_mode_settings_give_levels_and_ramp() {
+        let lines = ["ascii.contrast=3", "ascii.gamma=99", "ascii.ramp= .oO@", "pixel.brightness=oops", "plain.ramp=#"];
+        let settings = Settings::from_lines(lines.map(String::from));
+        assert_eq!(mode_levels(&settings, RenderMode::AsciiArt), Levels { brightness: 0, contrast: 3, gamma: 10 });
+        assert!(mode_levels(&settings, RenderMode::PixelArt).is_neutral());
+        assert_eq!(mode_ramp(None, &settings, RenderMode::AsciiArt, false), [' ', '.', 'o', 'O', '@']);
+        assert_eq!(mode_ramp(None, &settings, RenderMode::AsciiArt, true), ['@', 'O', 'o', '.', ' ']);
+        assert_eq!(mode_ramp(Some(&['a', 'b']), &settings, RenderMode::AsciiArt, false), ['a', 'b']);
+        // A one-glyph ramp can't render anything, so the default stands.
+        assert_eq!(mode_ramp(None, &settings, RenderMode::Plain, false), render::DEFAULT_RAMP);
+    }
+
+    #[test]
+    fn extra_ffmpeg_filters_run_ahead_of_the_scaling() {
+        let filters = FilterChain::new().video("scale=320:180");
+        let extra: Vec<String> = ["-vf", "yadif", "-an", "-filter:v", "hqdn3d"].map(String::from).to_vec();
+        assert_eq!(output_args(&extra, None, &filters), ["-vf", "yadif,hqdn3d,scale=320:180", "-an"]);
+        assert_eq!(output_args(&[], None, &filters), ["-vf", "scale=320:180"]);
+        assert!(output_args(&[], None, &FilterChain::new()).is_empty());
+
+        // Burned subtitles come first, and the bitmap overlay needs a complex graph.
+        let burn = Burn::Bitmap(0).filter(Path::new("movie.mkv"));
+        assert_eq!(output_args(&extra, Some(&burn), &filters), ["-filter_complex", "[0:v:0][0:s:0]overlay,yadif,hqdn3d,scale=320:180", "-an"]);
+    }
+
+    #[test]
+    fn interlaced_sources_get_yadif_first() {
+        let tff = VideoInfo { field_order: Some("tt".to_string()), ..probe_fixture() };
+        assert!(tff.is_interlaced());
+        assert!(tff.scan_label().contains("TT"));
+        assert!(!VideoInfo { field_order: Some("progressive".to_string()), ..probe_fixture() }.is_interlaced());
+        assert!(!probe_fixture().is_interlaced());
+
+        let state = PlaybackState { deinterlace: true, cfr_fps: Some(25.0), ..Default::default() };
+        assert!(state.filters(320, 180).video_arg().unwrap().starts_with("yadif,fps=25.000,scale="));
+        assert!(state.status().unwrap().contains(Msg::StatusDeinterlace.text()));
+    }
+
+    #[test]
+    fn hdr_sources_get_tonemapped_ahead_of_the_scaling() {
+        let pq = VideoInfo { color_transfer: Some("smpte2084".to_string()), ..probe_fixture() };
+        assert_eq!(pq.hdr_transfer(), Some("PQ"));
+        assert_eq!(VideoInfo { color_transfer: Some("arib-std-b67".to_string()), ..probe_fixture() }.hdr_transfer(), Some("HLG"));
+        assert!(!VideoInfo { color_transfer: Some("bt709".to_string()), ..probe_fixture() }.is_hdr());
+        assert!(!probe_fixture().is_hdr());
+
+        let state = PlaybackState { tonemap: true, deinterlace: true, ..Default::default() };
+        let chain = state.filters(320, 180).video_arg().unwrap();
+        assert!(chain.starts_with(&format!("yadif,{},scale=", TONEMAP_FILTER)));
+        assert!(state.status().unwrap().contains(Msg::StatusTonemap.text()));
+    }
+
+    #[test]
+    fn cropdetect_output_gives_the_picture_inside_the_bars() {
+        let stderr = "[Parsed_cropdetect_0 @ 0x5] x1:0 x2:1919 y1:138 y2:941 w:1920 h:800 x:0 y:140 pts:1 t:0.04 crop=1920:800:0:140\n\
+                      [Parsed_cropdetect_0 @ 0x5] x1:0 x2:1919 y1:136 y2:943 w:1920 h:808 x:0 y:136 pts:2 t:0.08 crop=1920:808:0:136\n";
+        assert_eq!(parse_cropdetect(stderr, (1920, 1080)), Some(Crop { w: 1920, h: 808, offset: Some((0, 136)) }));
+        // No bars, a sliver from a dark sample, or nothing detected at all.
+        assert_eq!(parse_cropdetect("crop=1920:1080:0:0", (1920, 1080)), None);
+        assert_eq!(parse_cropdetect("crop=1920:16:0:532", (1920, 1080)), None);
+        assert_eq!(parse_cropdetect("crop=-1920:-1072:1920:1080", (1920, 1080)), None);
+        assert_eq!(parse_cropdetect("", (1920, 1080)), None);
+    }
+
+    #[test]
+    fn crops_are_checked_and_fitted_instead_of_the_source() {
+        let crop = |s| cli::Cli { crop: Crop::parse(s), mode: Some(RenderMode::PixelArt), ..Default::default() };
+        let opts = play_options(&crop("320:360:320:0"), None);
+        let plan = DecodePlan::new(Path::new("a.mp4"), &probe_fixture(), &opts, 80, 24).unwrap();
+        // A portrait half of a 640x360 source fits 80x48 pixels by height.
+        assert_eq!(plan.target, (42, 48));
+        let state = PlaybackState { crop: plan.crop, scaling: plan.scaling, ..Default::default() };
+        assert!(state.filters(42, 48).video_arg().unwrap().starts_with("crop=320:360:320:0,scale=42:48"));
+
+        let opts = play_options(&crop("320:360:400:0"), None);
+        assert!(DecodePlan::new(Path::new("a.mp4"), &probe_fixture(), &opts, 80, 24).is_err());
+    }
+
+    #[test]
+    fn panning_decodes_larger_and_scrolls_inside_the_frame() {
+        assert_eq!(pan_target((80, 44), (1920, 1080)), Some((320, 176)));
+        assert_eq!(pan_target((80, 44), (160, 90)), Some((160, 88)));
+        assert_eq!(pan_target((80, 44), (90, 50)), None);
+
+        let mut pan = Pan::centered((320, 176), (80, 48));
+        assert_eq!((pan.offset, pan.max), ((120, 64), (240, 128)));
+        assert_eq!(pan.percent(), (50, 50));
+        pan.scroll(-100, 0);
+        assert_eq!(pan.offset.0, 0);
+        assert_eq!(pan.marks(), (false, true, true, true));
+        pan.scroll(0, 1);
+        assert_eq!(pan.offset.1, 70);
+        // ffmpeg rounding the frame down pulls the offset back in.
+        pan.fit((300, 100));
+        assert_eq!((pan.offset, pan.max), ((0, 52), (220, 52)));
+        let state = PlaybackState { pan: Some(pan), ..Default::default() };
+        assert!(state.status().unwrap().contains("平移: 横向 0% 纵向 100%"));
+    }
+
+    #[test]
+    fn deep_color_decodes_rgb48_for_color_modes_only() {
+        let cli = |mode, ascii_color| cli::Cli { pix_fmt: Some(PixelFormat::Rgb48), mode: Some(mode), ascii_color, ..Default::default() };
+        let opts = play_options(&cli(RenderMode::PixelArt, None), None);
+        assert!(opts.deep_color);
+        let plan = DecodePlan::new(Path::new("a.mp4"), &probe_fixture(), &opts, 80, 24).unwrap();
+        assert_eq!((plan.format, plan.decode_format), (PixelFormat::Rgb24, PixelFormat::Rgb48));
+        let filters = PlaybackState { format: plan.decode_format, ..Default::default() }.filters(80, 44);
+        assert!(filters.video_arg().unwrap().ends_with(",format=rgb48le"));
+
+        let opts = play_options(&cli(RenderMode::AsciiArt, Some(AsciiColor::GREEN)), None);
+        let plan = DecodePlan::new(Path::new("a.mp4"), &probe_fixture(), &opts, 80, 24).unwrap();
+        assert_eq!(plan.decode_format, PixelFormat::Gray);
+    }
+
+    #[test]
+    fn plan_filters_and_effects_follow_the_command_line() {
+        let cli = cli::Cli { hflip: true, mirror: Mirror::Horizontal, saturation: Some(0.0), ..Default::default() };
+        let opts = play_options(&cli, None);
+        let plan = DecodePlan::new(Path::new("a.mp4"), &probe_fixture(), &opts, 80, 24).unwrap();
+        assert!(plan.filters(&opts).video_arg().unwrap().contains(",hflip,"));
+
+        // Left half red, right half blue: mirrored onto itself, then desaturated to gray.
+        let mut frame = vec![200, 0, 0, 0, 0, 200];
+        plan.playback_state(&opts).apply_effects(&mut frame, (2, 1), PixelFormat::Rgb24, &opts.levels, None);
+        assert_eq!(frame[..3], frame[3..]);
+        assert!(frame[0] == frame[1] && frame[1] == frame[2]);
+    }
+
+    #[test]
+    fn file_steps_wrap_around_the_list() {
+        assert_eq!(FileStep::from_key(KeyCode::Char('>')), Some(FileStep::Next));
+        assert_eq!(FileStep::from_key(KeyCode::PageUp), Some(FileStep::Previous));
+        assert_eq!(FileStep::from_key(KeyCode::Char('n')), None);
+        assert_eq!(FileStep::Next.apply(1, 3), 2);
+        assert_eq!(FileStep::Next.apply(2, 3), 0);
+        assert_eq!(FileStep::Previous.apply(0, 3), 2);
+        assert_eq!(FileStep::Previous.apply(2, 3), 1);
+    }
+
+    #[test]
+    fn shuffled_passes_cover_the_list_starting_from_the_playing_file() {
+        let pass = |order: &mut PlayOrder| -> Vec<usize> { (0..5).map(|_| order.step(FileStep::Next).0).collect() };
+        let mut order = PlayOrder::new(5, Some(3), Some(shuffle::Rng::new(9)));
+        let steps = pass(&mut order);
+        let mut first = vec![3];
+        first.extend(&steps[..4]);
+        let mut sorted = first.clone();
+        sorted.sort();
+        assert_eq!(sorted, [0, 1, 2, 3, 4]);
+        // The fifth step starts a new pass, which doesn't open with the file that closed the last.
+        assert_ne!(steps[4], steps[3]);
+        // The same seed plays the same order.
+        let mut again = PlayOrder::new(5, Some(3), Some(shuffle::Rng::new(9)));
+        assert_eq!(pass(&mut again)[..4], first[1..]);
+        // Unshuffled, it's just the list's order.
+        let mut listed = PlayOrder::new(5, Some(3), None);
+        assert_eq!(pass(&mut listed), [4, 0, 1, 2, 3]);
+        assert_eq!(listed.step(FileStep::Previous), (2, 2));
+    }
+
+    #[test]
+    fn selection_is_clamped_when_the_list_shrinks() {
+        assert_eq!(clamped_selection(Some(7), 3), Some(2));
+        assert_eq!(clamped_selection(Some(1), 3), Some(1));
+        assert_eq!(clamped_selection(None, 3), Some(0));
+        assert_eq!(clamped_selection(Some(0), 0), None);
+    }
+
+    #[test]
+    fn focus_mode_gives_the_file_list_the_whole_screen() {
+        let area = Rect::new(0, 0, 100, 40);
+        let normal = Panes::new(area, false, true);
+        assert_eq!((normal.header.height, normal.footer.height), (3, 3));
+        assert_eq!((normal.list.width, normal.list.height), (40, 34));
+        assert_eq!(normal.details.height + normal.stats.height, 34);
+
+        let focus = Panes::new(area, true, true);
+        assert_eq!(focus.list, area);
+        assert_eq!((focus.header.height, focus.footer.height, focus.details.width), (0, 0, 0));
+        assert_eq!(focus.footer.y, 40);
+    }
+
+    #[test]
+    fn synchronized_frames_are_wrapped_in_mode_2026() {
+        let mut out = Vec::new();
+        write_frame(&mut out, "frame", true).unwrap();
+        assert_eq!(out, b"\x1b[?2026hframe\x1b[?2026l");
+        out.clear();
+        write_frame(&mut out, "frame", false).unwrap();
+        assert_eq!(out, b"frame");
+    }
+
+    #[test]
+    fn zero_duration_is_unknown_and_unseekable() {
+        let still = VideoInfo { duration: 0.0, ..probe_fixture() };
+        assert_eq!(still.known_duration(), None);
+        assert!(still.to_json(Path::new("a.png")).contains("\"duration\": null,"));
+        assert_eq!(VideoInfo { duration: f64::NAN, ..probe_fixture() }.known_duration(), None);
+        assert_eq!(probe_fixture().known_duration(), Some(12.5));
+
+        assert_eq!(seek_target(None, 42.0), 0.0);
+        assert_eq!(seek_target(Some(0.04), 0.04), 0.0);
+        assert_eq!(seek_target(Some(12.5), 3.0), 3.0);
+        assert_eq!(jump_target(None, 0.5), None);
+        assert_eq!(jump_target(Some(100.0), 0.3), Some(30.0));
+        assert_eq!(jump_target(Some(100.0), 1.0), Some(100.0 - END_JUMP_MARGIN));
+        assert_eq!(jump_target(Some(0.5), 0.9), Some(0.0));
+    }
+
+    #[test]
+    fn chapters_are_parsed_and_located() {
+        let output = "[CHAPTER]\nid=0\nstart_time=0.000000\nTAG:title=Intro\n[/CHAPTER]\n\
+                      [CHAPTER]\nid=1\nstart_time=95.500000\n[/CHAPTER]\n\
+                      [FORMAT]\nTAG:title=Sunset\n[/FORMAT]\n";
+        let chapters = parse_chapters(output);
+        assert_eq!(chapters, vec![
+            Chapter { start: 0.0, title: "Intro".to_string() },
+            Chapter { start: 95.5, title: "章节 2".to_string() },
+        ]);
+        assert_eq!(chapter_at(&chapters, 10.0), Some(0));
+        assert_eq!(chapter_at(&chapters, 95.5), Some(1));
+        assert_eq!(chapter_at(&[], 10.0), None);
+    }
+
+    #[test]
+    fn tags_are_parsed_and_labelled_in_order() {
+        let tags = parse_tags("TAG:comment=shot on a phone\nTAG:TITLE=Sunset\nTAG:date= \nwidth=640\n");
+        assert_eq!(tags.len(), 2);
+        assert_eq!(tags["title"], "Sunset");
+        let info = VideoInfo { tags, ..probe_fixture() };
+        assert_eq!(info.tag_lines(), "标题: Sunset\n备注: shot on a phone\n");
+    }
+
+    #[test]
+    fn probe_sections_keep_stream_and_format_lines_apart() {
+        let output = "[STREAM]\nwidth=640\nduration=1.5\n[/STREAM]\n[FORMAT]\nTAG:title=Sunset\n[/FORMAT]\n";
+        assert_eq!(probe_section(output, "STREAM"), "width=640\nduration=1.5");
+        assert_eq!(parse_tags(&probe_section(output, "FORMAT"))["title"], "Sunset");
+        assert_eq!(probe_section(output, "CHAPTER"), "");
+    }
+
+    #[test]
+    fn cols_and_rows_override_the_terminal_size() {
+        assert_eq!(output_size((Some(120), None), (80, 24)), (120, 24));
+        assert_eq!(output_size((None, None), (80, 24)), (80, 24));
+        assert!(oversize_warning((80, 24), (80, 24)).is_none());
+        assert!(oversize_warning((60, 30), (80, 24)).is_some());
+    }
+
+    #[test]
+    fn comparison_formats_both_entries_alike() {
+        let a = library::Entry { mtime: 1, size: 3 << 20, width: 1920, height: 1080, duration: 90.0, fps: 23.976, bitrate: 4_500_000, codec: "h264".to_string() };
+        let b = library::Entry { bitrate: 0, codec: "hevc".to_string(), ..a.clone() };
+        let rows = comparison_rows(&a, &b);
+        let differing: Vec<&str> = rows.iter().filter(|r| r.1 != r.2).map(|r| r.0).collect();
+        assert_eq!(differing, [Msg::CompareBitrate.text(), Msg::CompareCodec.text()]);
+        assert_eq!(rows[0], (Msg::CompareResolution.text(), "1920x1080".to_string(), "1920x1080".to_string()));
+        assert_eq!(rows[3].2, "N/A");
+        assert_eq!(rows[5].1, "3.00 MB");
     }
-    cmd.to_string()
 }
@@ -0,0 +1,38 @@
+use anyhow::{Context, Result};
+use std::{
+    path::Path,
+    process::{Command, Stdio},
+};
+
+/// The OS's "open with the default application" command for `path`.
+fn command(path: &Path) -> Command {
+    let mut cmd = if cfg!(target_os = "windows") {
+        // `start` is a cmd builtin; its first quoted argument is the window title.
+        let mut cmd = Command::new("cmd");
+        cmd.args(["/C", "start", ""]);
+        cmd
+    } else if cfg!(target_os = "macos") {
+        Command::new("open")
+    } else {
+        Command::new("xdg-open")
+    };
+    cmd.arg(path);
+    cmd
+}
+
+/// Opens `path` in the default application without waiting for it to close.
+pub fn open(path: &Path) -> Result<()> {
+    let mut cmd = command(path);
+    let program = cmd.get_program().to_string_lossy().to_string();
+    let mut child = cmd
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .with_context(|| format!("Failed to run {}", program))?;
+    // The launcher usually exits right after handing off; reap it off the UI thread.
+    std::thread::spawn(move || {
+        let _ = child.wait();
+    });
+    Ok(())
+}
@@ -0,0 +1,206 @@
+use crate::effects::luminance;
+use crate::i18n::Msg;
+use crate::render::PixelFormat;
+
+/// Source size test patterns are laid out for, so they fit the terminal like a 1080p video would.
+pub const SOURCE_SIZE: (u32, u32) = (1920, 1080);
+
+/// The 75% bars of SMPTE ECR 1-1978: gray, yellow, cyan, green, magenta, red, blue.
+const BARS: [(u8, u8, u8); 7] = [
+    (191, 191, 191),
+    (191, 191, 0),
+    (0, 191, 191),
+    (0, 191, 0),
+    (191, 0, 191),
+    (191, 0, 0),
+    (0, 0, 191),
+];
+/// The strip under the bars, the same colors in reverse with black between them.
+const CASTELLATIONS: [(u8, u8, u8); 7] = [
+    (0, 0, 191),
+    (19, 19, 19),
+    (191, 0, 191),
+    (19, 19, 19),
+    (0, 191, 191),
+    (19, 19, 19),
+    (191, 191, 191),
+];
+/// Bottom row: -I, white, +Q, black, then the PLUGE's below-black, black and above-black bars.
+const PLUGE: [((u8, u8, u8), u32); 7] = [
+    ((0, 33, 76), 5),
+    ((255, 255, 255), 5),
+    ((50, 0, 106), 5),
+    ((19, 19, 19), 5),
+    ((9, 9, 9), 2),
+    ((19, 19, 19), 2),
+    ((29, 29, 29), 4),
+];
+
+/// Synthetic frames for `--test-pattern`, to check the terminal and the renderer without a video.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Pattern {
+    /// SMPTE color bars.
+    #[default]
+    Bars,
+    /// Every hue left to right, from white at the top through full color to black at the bottom.
+    Sweep,
+    /// A smooth gray ramp above a stepped one, for banding and the ASCII ramp.
+    Ramp,
+    /// A circle and crosshair, round only when the character aspect ratio is right.
+    Circle,
+}
+
+impl Pattern {
+    /// Accepts `bars`, `sweep`, `ramp` or `circle`, as given to `--test-pattern`.
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "bars" | "smpte" => Some(Pattern::Bars),
+            "sweep" | "gradient" => Some(Pattern::Sweep),
+            "ramp" | "gray" | "grey" => Some(Pattern::Ramp),
+            "circle" | "aspect" => Some(Pattern::Circle),
+            _ => None,
+        }
+    }
+
+    /// The next pattern, in the order the key cycles through them.
+    pub fn next(self) -> Self {
+        match self {
+            Pattern::Bars => Pattern::Sweep,
+            Pattern::Sweep => Pattern::Ramp,
+            Pattern::Ramp => Pattern::Circle,
+            Pattern::Circle => Pattern::Bars,
+        }
+    }
+
+    /// A `width`x`height` frame of this pattern in `format`.
+    pub fn frame(self, width: u32, height: u32, format: PixelFormat) -> Vec<u8> {
+        let (w, h) = (width.max(1), height.max(1));
+        let mut frame = Vec::with_capacity((w * h) as usize * format.bytes_per_pixel());
+        for y in 0..h {
+            for x in 0..w {
+                let (r, g, b) = self.pixel(x, y, w, h);
+                match format {
+                    PixelFormat::Rgb24 => frame.extend_from_slice(&[r, g, b]),
+                    PixelFormat::Gray => frame.push(luminance(r, g, b)),
+                    PixelFormat::Rgb48 => frame.extend([r, g, b].iter().flat_map(|&c| (c as u16 * 257).to_le_bytes())),
+                }
+            }
+        }
+        frame
+    }
+
+    fn pixel(self, x: u32, y: u32, w: u32, h: u32) -> (u8, u8, u8) {
+        match self {
+            Pattern::Bars => {
+                let bar = (x * 7 / w) as usize;
+                if y < h * 2 / 3 {
+                    BARS[bar]
+                } else if y < h * 3 / 4 {
+                    CASTELLATIONS[bar]
+                } else {
+                    // The PLUGE row is split in 28ths, so its bars line up with the ones above.
+                    let mut at = x * 28 / w;
+                    PLUGE
+                        .iter()
+                        .find(|&&(_, span)| {
+                            let inside = at < span;
+                            at = at.saturating_sub(span);
+                            inside
+                        })
+                        .map_or((19, 19, 19), |&(color, _)| color)
+                }
+            }
+            Pattern::Sweep => {
+                let hue = hue_color(x as f32 / w as f32);
+                // 0.0 at the top is white, 0.5 full color, 1.0 black.
+                let t = y as f32 / (h - 1).max(1) as f32;
+                let channel = |c: u8| {
+                    let c = c as f32;
+                    let v = if t < 0.5 { 255.0 + (c - 255.0) * t * 2.0 } else { c * (1.0 - t) * 2.0 };
+                    v.round() as u8
+                };
+                (channel(hue.0), channel(hue.1), channel(hue.2))
+            }
+            Pattern::Ramp => {
+                let level = if y < h / 2 {
+                    x * 255 / (w - 1).max(1)
+                } else {
+                    (x * 16 / w) * 255 / 15
+                };
+                (level as u8, level as u8, level as u8)
+            }
+            Pattern::Circle => {
+                let (cx, cy) = (w as f32 / 2.0, h as f32 / 2.0);
+                let (dx, dy) = (x as f32 + 0.5 - cx, y as f32 + 0.5 - cy);
+                let radius = h as f32 * 0.45;
+                // About 1.5% of the height thick, and never thinner than a pixel.
+                let line = (h as f32 * 0.015).max(0.75);
+                let on_circle = ((dx * dx + dy * dy).sqrt() - radius).abs() <= line;
+                let on_cross = dx.abs() <= line || dy.abs() <= line;
+                if on_circle || on_cross {
+                    (255, 255, 255)
+                } else {
+                    (0, 0, 0)
+                }
+            }
+        }
+    }
+}
+
+/// Fully saturated color for `position` (0.0-1.0) around the hue wheel, red first.
+fn hue_color(position: f32) -> (u8, u8, u8) {
+    let h = position.rem_euclid(1.0) * 6.0;
+    let rise = ((h % 1.0) * 255.0).round() as u8;
+    let fall = 255 - rise;
+    match h as u32 {
+        0 => (255, rise, 0),
+        1 => (fall, 255, 0),
+        2 => (0, 255, rise),
+        3 => (0, fall, 255),
+        4 => (rise, 0, 255),
+        _ => (255, 0, fall),
+    }
+}
+
+impl std::fmt::Display for Pattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Pattern::Bars => Msg::PatternBars,
+            Pattern::Sweep => Msg::PatternSweep,
+            Pattern::Ramp => Msg::PatternRamp,
+            Pattern::Circle => Msg::PatternCircle,
+        };
+        f.write_str(name.text())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn patterns_fill_the_frame() {
+        let bars = Pattern::Bars.frame(70, 40, PixelFormat::Rgb24);
+        assert_eq!(bars.len(), 70 * 40 * 3);
+        assert_eq!(&bars[..3], &[191, 191, 191]);
+        let last_bar = 69 * 3;
+        assert_eq!(&bars[last_bar..last_bar + 3], &[0, 0, 191]);
+
+        let ramp = Pattern::Ramp.frame(256, 2, PixelFormat::Gray);
+        assert_eq!(ramp.len(), 512);
+        assert_eq!((ramp[0], ramp[255]), (0, 255));
+
+        let sweep = Pattern::Sweep.frame(6, 3, PixelFormat::Rgb24);
+        assert_eq!(&sweep[..3], &[255, 255, 255]);
+        assert_eq!(&sweep[6 * 3..6 * 3 + 3], &[255, 0, 0]);
+        assert_eq!(&sweep[sweep.len() - 3..], &[0, 0, 0]);
+
+        let mut p = Pattern::default();
+        for _ in 0..4 {
+            p = p.next();
+        }
+        assert_eq!(p, Pattern::Bars);
+        assert_eq!(Pattern::parse("Circle"), Some(Pattern::Circle));
+        assert_eq!(Pattern::parse("noise"), None);
+    }
+}
@@ -0,0 +1,92 @@
+use crate::halfblock::HalfBlocks;
+
+/// Size every poster is decoded at, letterboxed to 16:9; previews are sampled down from this.
+pub const WIDTH: u32 = 64;
+pub const HEIGHT: u32 = 36;
+
+/// How many frames ffmpeg's `thumbnail` filter compares before picking the most typical one.
+const CANDIDATE_FRAMES: u32 = 100;
+
+/// ffmpeg scene score above which a frame starts a new scene.
+const SCENE_THRESHOLD: f32 = 0.4;
+/// Most scene frames kept for a filmstrip, picked evenly from all the scenes found.
+pub const MAX_SCENES: usize = 6;
+
+/// A representative frame of a file, in `rgb24` at `WIDTH`x`HEIGHT`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Poster {
+    pixels: Vec<u8>,
+}
+
+impl Poster {
+    /// Wraps ffmpeg's raw output; `None` unless it holds exactly one frame.
+    pub fn from_rgb(pixels: Vec<u8>) -> Option<Self> {
+        (pixels.len() == (WIDTH * HEIGHT * 3) as usize).then_some(Self { pixels })
+    }
+
+    /// The poster drawn with half blocks over whatever area it's rendered into.
+    pub fn widget(&self) -> HalfBlocks<'_> {
+        HalfBlocks::new(&self.pixels, WIDTH, HEIGHT)
+    }
+}
+
+/// The largest 16:9 preview, in cells, that fits `cols` x `rows`.
+pub fn fit(cols: u16, rows: u16) -> (u16, u16) {
+    // Two pixels per row, so 16:9 pixels take 16:4.5 cells.
+    let rows = rows.min((cols as u32 * HEIGHT / WIDTH / 2) as u16);
+    ((rows as u32 * 2 * WIDTH / HEIGHT) as u16, rows)
+}
+
+/// `-vf` chain picking a typical (rather than black or mid-transition) frame and letterboxing it.
+pub fn filter() -> String {
+    format!("thumbnail={},{}", CANDIDATE_FRAMES, letterbox())
+}
+
+/// `-vf` chain keeping the first frame and the first of every scene after it, letterboxed.
+pub fn scene_filter() -> String {
+    format!("select='eq(n,0)+gt(scene,{})',{}", SCENE_THRESHOLD, letterbox())
+}
+
+fn letterbox() -> String {
+    format!("scale={w}:{h}:force_original_aspect_ratio=decrease,pad={w}:{h}:(ow-iw)/2:(oh-ih)/2,format=rgb24", w = WIDTH, h = HEIGHT)
+}
+
+/// Up to `MAX_SCENES` posters spread evenly over the frames ffmpeg wrote back to back, so a long
+/// video's filmstrip spans all of it rather than just its first scenes.
+pub fn filmstrip(pixels: &[u8]) -> Vec<Poster> {
+    let frames: Vec<&[u8]> = pixels.chunks_exact((WIDTH * HEIGHT * 3) as usize).collect();
+    let picks = frames.len().min(MAX_SCENES);
+    (0..picks)
+        .filter_map(|i| Poster::from_rgb(frames[i * frames.len() / picks].to_vec()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::prelude::*;
+
+    #[test]
+    fn poster_needs_a_whole_frame_and_fits_16_9() {
+        assert!(Poster::from_rgb(vec![0; 10]).is_none());
+        let poster = Poster::from_rgb(vec![200; (WIDTH * HEIGHT * 3) as usize]).unwrap();
+        let area = Rect::new(0, 0, 8, 2);
+        let mut buf = Buffer::empty(area);
+        poster.widget().render(area, &mut buf);
+        assert_eq!((buf[(7, 1)].symbol(), buf[(7, 1)].fg), ("▀", Color::Rgb(200, 200, 200)));
+
+        assert_eq!(fit(64, 40), (64, 18));
+        assert_eq!(fit(64, 9), (32, 9));
+    }
+
+    #[test]
+    fn filmstrip_spreads_over_all_scenes() {
+        let frame = (WIDTH * HEIGHT * 3) as usize;
+        let pixels: Vec<u8> = (0..12u8).flat_map(|i| vec![i; frame]).collect();
+        let strip = filmstrip(&pixels);
+        let firsts: Vec<u8> = strip.iter().map(|p| p.pixels[0]).collect();
+        assert_eq!(firsts, [0, 2, 4, 6, 8, 10]);
+        assert_eq!(filmstrip(&pixels[..frame * 2 + 5]).len(), 2);
+        assert!(filmstrip(&[]).is_empty());
+    }
+}
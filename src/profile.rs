@@ -0,0 +1,120 @@
+use anyhow::{Context, Result};
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+/// Rows buffered between flushes, so profiling adds no per-frame system call.
+const FLUSH_EVERY: u32 = 120;
+
+const HEADER: &str = "playback,frame,decode_ms,render_ms,write_ms,total_ms,drawn";
+
+/// `--profile-csv`: one CSV row per frame read, saying where its time went.
+///
+/// Shared by every playback of a session; each playback gets its own number and counts frames
+/// from 1. Rows are buffered and flushed every `FLUSH_EVERY` rows, at the end of each playback and
+/// when the last copy is dropped.
+#[derive(Debug, Clone)]
+pub struct FrameProfile {
+    inner: Arc<Mutex<Inner>>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    writer: BufWriter<File>,
+    playback: u32,
+    frame: u64,
+    unflushed: u32,
+}
+
+/// Where one frame's time went. Frames skipped by the FPS cap have no render or write time.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FrameTiming {
+    pub decode: Duration,
+    pub render: Duration,
+    pub write: Duration,
+    pub total: Duration,
+    pub drawn: bool,
+}
+
+impl FrameProfile {
+    pub fn create(path: &Path) -> Result<Self> {
+        let file = File::create(path).with_context(|| format!("Failed to create {}", path.display()))?;
+        let mut writer = BufWriter::new(file);
+        writeln!(writer, "{}", HEADER)?;
+        Ok(Self { inner: Arc::new(Mutex::new(Inner { writer, playback: 0, frame: 0, unflushed: 0 })) })
+    }
+
+    /// Starts numbering the rows of a new playback.
+    pub fn start_playback(&self) {
+        if let Ok(mut inner) = self.inner.lock() {
+            inner.playback += 1;
+            inner.frame = 0;
+        }
+    }
+
+    /// Appends the next frame's row; a failed write is dropped rather than interrupting playback.
+    pub fn record(&self, timing: FrameTiming) {
+        let Ok(mut inner) = self.inner.lock() else { return };
+        inner.frame += 1;
+        let row = row(inner.playback, inner.frame, &timing);
+        let _ = writeln!(inner.writer, "{}", row);
+        inner.unflushed += 1;
+        if inner.unflushed >= FLUSH_EVERY {
+            inner.unflushed = 0;
+            let _ = inner.writer.flush();
+        }
+    }
+
+    /// Writes out the buffered rows, e.g. when playback returns to the menu.
+    pub fn flush(&self) {
+        if let Ok(mut inner) = self.inner.lock() {
+            inner.unflushed = 0;
+            let _ = inner.writer.flush();
+        }
+    }
+}
+
+fn row(playback: u32, frame: u64, timing: &FrameTiming) -> String {
+    let ms = |d: Duration| format!("{:.3}", d.as_secs_f64() * 1000.0);
+    format!(
+        "{},{},{},{},{},{},{}",
+        playback, frame, ms(timing.decode), ms(timing.render), ms(timing.write), ms(timing.total), timing.drawn as u8
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rows_are_numbered_per_playback_and_written_in_milliseconds() {
+        let path = std::env::temp_dir().join(format!("vodeo2ascii-profile-{}.csv", std::process::id()));
+        let profile = FrameProfile::create(&path).unwrap();
+        let timing = FrameTiming {
+            decode: Duration::from_micros(1500),
+            render: Duration::from_millis(4),
+            write: Duration::from_micros(250),
+            total: Duration::from_micros(5750),
+            drawn: true,
+        };
+        profile.start_playback();
+        profile.record(timing);
+        profile.record(FrameTiming { drawn: false, ..Default::default() });
+        profile.start_playback();
+        profile.record(timing);
+        drop(profile);
+
+        let csv = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(csv.lines().collect::<Vec<_>>(), [
+            HEADER,
+            "1,1,1.500,4.000,0.250,5.750,1",
+            "1,2,0.000,0.000,0.000,0.000,0",
+            "2,1,1.500,4.000,0.250,5.750,1",
+        ]);
+    }
+}
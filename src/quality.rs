@@ -0,0 +1,83 @@
+use std::time::Duration;
+
+/// Rendered frames averaged before each decision.
+const WINDOW: u32 = 30;
+/// Share of the frame budget rendering may take before the resolution is lowered.
+const OVER_BUDGET: f64 = 0.9;
+/// Share of the budget below which there's room to raise the resolution again.
+const HEADROOM: f64 = 0.5;
+/// Roomy windows in a row needed before raising, so quality doesn't flap between two levels.
+const ROOMY_WINDOWS_TO_RAISE: u32 = 3;
+
+/// A change the monitor asks for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Adjust {
+    Lower,
+    Raise,
+}
+
+/// Watches how long each frame takes to render against the time the frame rate allows, and asks
+/// for a lower decode resolution when rendering can't keep up, or a higher one once it easily can.
+#[derive(Debug, Default)]
+pub struct AutoQuality {
+    total: Duration,
+    frames: u32,
+    roomy_windows: u32,
+}
+
+impl AutoQuality {
+    /// Records one rendered frame; `budget` is the time between rendered frames.
+    pub fn record(&mut self, render: Duration, budget: Duration) -> Option<Adjust> {
+        self.total += render;
+        self.frames += 1;
+        if self.frames < WINDOW {
+            return None;
+        }
+        let load = self.total.as_secs_f64() / self.frames as f64 / budget.as_secs_f64().max(f64::EPSILON);
+        self.total = Duration::ZERO;
+        self.frames = 0;
+        if load > OVER_BUDGET {
+            self.roomy_windows = 0;
+            return Some(Adjust::Lower);
+        }
+        if load < HEADROOM {
+            self.roomy_windows += 1;
+            if self.roomy_windows >= ROOMY_WINDOWS_TO_RAISE {
+                self.roomy_windows = 0;
+                return Some(Adjust::Raise);
+            }
+        } else {
+            self.roomy_windows = 0;
+        }
+        None
+    }
+
+    /// Forgets the measurements, e.g. after the resolution changed.
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lowers_when_slow_and_raises_after_sustained_headroom() {
+        let budget = Duration::from_millis(40);
+        let mut quality = AutoQuality::default();
+        let window = |quality: &mut AutoQuality, ms| (0..WINDOW).filter_map(|_| quality.record(Duration::from_millis(ms), budget)).last();
+
+        assert_eq!(window(&mut quality, 30), None);
+        assert_eq!(window(&mut quality, 39), Some(Adjust::Lower));
+        for _ in 1..ROOMY_WINDOWS_TO_RAISE {
+            assert_eq!(window(&mut quality, 5), None);
+        }
+        assert_eq!(window(&mut quality, 5), Some(Adjust::Raise));
+
+        // A busy window in between starts the count over.
+        window(&mut quality, 5);
+        window(&mut quality, 30);
+        assert_eq!(window(&mut quality, 5), None);
+    }
+}
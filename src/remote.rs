@@ -0,0 +1,121 @@
+use std::{
+    io::{self, BufRead, BufReader, Read},
+    path::Path,
+    process::{Command, Output, Stdio},
+    thread,
+    time::Duration,
+};
+
+/// Longest status line kept, so a runaway URL or header can't flood the screen.
+const MAX_STATUS_LEN: usize = 120;
+/// Wait before the first reconnection attempt; each one after waits twice as long as the last.
+const FIRST_RETRY_DELAY: Duration = Duration::from_secs(1);
+/// Longest wait between reconnection attempts.
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(16);
+
+/// Whether `path` is a URL for one of ffmpeg's network protocols (`https://…`, `rtsp://…`) rather
+/// than a file.
+pub fn is_remote(path: &Path) -> bool {
+    let path = path.to_string_lossy();
+    path.split_once("://").is_some_and(|(scheme, _)| {
+        scheme.len() > 1 && scheme.chars().all(|c| c.is_ascii_alphanumeric() || c == '+') && scheme != "file"
+    })
+}
+
+/// ffmpeg input options that have its HTTP protocol reconnect by itself after a dropped connection,
+/// for `http(s)://` sources; other protocols don't know them.
+pub fn reconnect_args(path: &Path) -> &'static [&'static str] {
+    let path = path.to_string_lossy().to_ascii_lowercase();
+    if path.starts_with("http://") || path.starts_with("https://") {
+        &["-reconnect", "1", "-reconnect_streamed", "1", "-reconnect_delay_max", "5"]
+    } else {
+        &[]
+    }
+}
+
+/// How long to wait before reconnection `attempt` (from 1): exponential backoff, capped.
+pub fn retry_delay(attempt: u32) -> Duration {
+    FIRST_RETRY_DELAY.saturating_mul(1 << attempt.saturating_sub(1).min(16)).min(MAX_RETRY_DELAY)
+}
+
+/// ffmpeg/ffprobe log level for `path`: `verbose` for remote sources, which is where connection
+/// and download progress is logged, `fallback` otherwise.
+pub fn loglevel<'a>(path: &Path, fallback: &'a str) -> &'a str {
+    if is_remote(path) { "verbose" } else { fallback }
+}
+
+/// The part of an ffmpeg/ffprobe stderr line worth showing while a source connects or buffers,
+/// without the `[https @ 0x55d0c8] ` context prefix; `None` for everything else.
+pub fn progress_line(line: &str) -> Option<String> {
+    let line = line.trim();
+    let message = match line.strip_prefix('[').and_then(|rest| rest.split_once("] ")) {
+        Some((context, message)) if context.contains(" @ ") => message.trim(),
+        _ => line,
+    };
+    let lower = message.to_ascii_lowercase();
+    let markers = [
+        "opening", "connect", "resolv", "redirect", "http error", "server returned", "timed out", "input #", "duration:",
+        "stream #", "time=",
+    ];
+    if message.is_empty() || !markers.iter().any(|m| lower.contains(m)) {
+        return None;
+    }
+    Some(message.chars().take(MAX_STATUS_LEN).collect())
+}
+
+/// Runs `cmd` like `Command::output`, handing each progress line on stderr to `on_status` as it
+/// arrives instead of only once the command finishes.
+pub fn output_reporting(cmd: &mut Command, on_status: &mut dyn FnMut(String)) -> io::Result<Output> {
+    let mut child = cmd.stdout(Stdio::piped()).stderr(Stdio::piped()).spawn()?;
+    let mut stdout = child.stdout.take().expect("stdout is piped");
+    // Read on the side, so a full stdout pipe can't stall ffprobe while stderr is being read here.
+    let reader = thread::spawn(move || {
+        let mut bytes = Vec::new();
+        stdout.read_to_end(&mut bytes).map(|_| bytes)
+    });
+
+    let mut stderr = Vec::new();
+    for line in BufReader::new(child.stderr.take().expect("stderr is piped")).lines().map_while(Result::ok) {
+        if let Some(status) = progress_line(&line) {
+            on_status(status);
+        }
+        stderr.extend_from_slice(line.as_bytes());
+        stderr.push(b'\n');
+    }
+    let status = child.wait()?;
+    let stdout = reader.join().map_err(|_| io::Error::other("stdout reader panicked"))??;
+    Ok(Output { status, stdout, stderr })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn urls_are_remote_and_progress_lines_are_picked_out() {
+        assert!(is_remote(Path::new("https://example.com/a.mp4")));
+        assert!(is_remote(Path::new("rtsp://camera.local/stream")));
+        assert!(!is_remote(Path::new("/videos/a.mp4")));
+        assert!(!is_remote(Path::new("file:///videos/a.mp4")));
+        assert!(!is_remote(Path::new(r"C:\videos\a.mp4")));
+
+        assert_eq!(
+            progress_line("[tcp @ 0x55d0c8a1] Successfully connected to 93.184.216.34 port 443").as_deref(),
+            Some("Successfully connected to 93.184.216.34 port 443")
+        );
+        assert_eq!(progress_line("[https @ 0x1] Opening 'https://example.com/a.mp4' for reading").as_deref(), Some("Opening 'https://example.com/a.mp4' for reading"));
+        assert_eq!(progress_line("  Duration: 00:01:00.00, start: 0.000000, bitrate: 800 kb/s").as_deref(), Some("Duration: 00:01:00.00, start: 0.000000, bitrate: 800 kb/s"));
+        assert_eq!(progress_line("[h264 @ 0x2] Reinit context to 1920x1088, pix_fmt: yuv420p"), None);
+        assert_eq!(progress_line(&format!("time={}", "0".repeat(500))).map(|s| s.chars().count()), Some(MAX_STATUS_LEN));
+    }
+
+    #[test]
+    fn reconnects_back_off_and_only_http_gets_ffmpegs_options() {
+        let delays: Vec<u64> = (1..=7).map(|n| retry_delay(n).as_secs()).collect();
+        assert_eq!(delays, [1, 2, 4, 8, 16, 16, 16]);
+        assert_eq!(retry_delay(u32::MAX), MAX_RETRY_DELAY);
+        assert_eq!(reconnect_args(Path::new("HTTPS://example.com/live.m3u8"))[..2], ["-reconnect", "1"]);
+        assert!(reconnect_args(Path::new("rtsp://camera.local/stream")).is_empty());
+        assert!(reconnect_args(Path::new("/videos/a.mp4")).is_empty());
+    }
+}
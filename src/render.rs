@@ -0,0 +1,1676 @@
+use std::{collections::BTreeSet, fmt::Write, sync::Mutex};
+
+use image::{ImageBuffer, Rgb};
+use unicode_width::UnicodeWidthStr;
+
+use crate::effects::luminance;
+use crate::graphics::{self, ImageProtocol};
+use crate::histogram::{ColorStats, BINS};
+use crate::i18n::Msg;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RenderMode {
+    PixelArt,
+    AsciiArt,
+    /// Two vertical pixels per glyph, like PixelArt: the glyph follows their average brightness
+    /// and takes the top pixel's color, doubling AsciiArt's vertical resolution.
+    CompactAscii,
+    /// One space per pixel with only the background colored, for fonts where `▀` leaves gaps.
+    Blocks,
+    /// Real frames through the terminal's inline image protocol (kitty, iTerm2 or sixel).
+    Image,
+    /// AsciiArt's layout without any color codes: glyphs in the terminal's own colors, the
+    /// smallest output there is.
+    Plain,
+}
+
+impl RenderMode {
+    /// Every mode, in the order the mode popup lists them.
+    pub const ALL: [RenderMode; 6] = [RenderMode::PixelArt, RenderMode::AsciiArt, RenderMode::CompactAscii, RenderMode::Blocks, RenderMode::Image, RenderMode::Plain];
+
+    /// Whether frames are drawn with glyphs from the ramp.
+    pub fn uses_ramp(self) -> bool {
+        matches!(self, RenderMode::AsciiArt | RenderMode::CompactAscii | RenderMode::Plain)
+    }
+
+    /// Accepts `pixel`, `ascii`, `compact`, `blocks`, `image` or `plain`, as given to `--mode`.
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "pixel" | "pixelart" => Some(RenderMode::PixelArt),
+            "ascii" | "asciiart" => Some(RenderMode::AsciiArt),
+            "compact" | "compact-ascii" => Some(RenderMode::CompactAscii),
+            "blocks" | "block" => Some(RenderMode::Blocks),
+            "image" => Some(RenderMode::Image),
+            "plain" | "no-color" => Some(RenderMode::Plain),
+            _ => None,
+        }
+    }
+
+    /// The name `parse` takes, which also prefixes the mode's keys in settings.
+    pub fn key(&self) -> &'static str {
+        match self {
+            RenderMode::PixelArt => "pixel",
+            RenderMode::AsciiArt => "ascii",
+            RenderMode::CompactAscii => "compact",
+            RenderMode::Blocks => "blocks",
+            RenderMode::Image => "image",
+            RenderMode::Plain => "plain",
+        }
+    }
+
+    pub fn icon(&self) -> &'static str {
+        match self {
+            RenderMode::PixelArt => "🎨",
+            RenderMode::AsciiArt => "🔢",
+            RenderMode::CompactAscii => "🔡",
+            RenderMode::Blocks => "🟦",
+            RenderMode::Image => "🖼",
+            RenderMode::Plain => "📄",
+        }
+    }
+}
+
+impl std::fmt::Display for RenderMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            RenderMode::PixelArt => Msg::ModePixelArt,
+            RenderMode::AsciiArt => Msg::ModeAsciiArt,
+            RenderMode::CompactAscii => Msg::ModeCompactAscii,
+            RenderMode::Blocks => Msg::ModeBlocks,
+            RenderMode::Image => Msg::ModeImage,
+            RenderMode::Plain => Msg::ModePlain,
+        };
+        f.write_str(name.text())
+    }
+}
+
+/// Glyphs from darkest to brightest used by `AsciiArt` unless `--ramp` gives others.
+pub const DEFAULT_RAMP: &[char] = &[' ', '.', ':', '-', '=', '+', '*', '#', '%', '@'];
+
+/// `DEFAULT_RAMP` from brightest to darkest, for light terminal backgrounds.
+const INVERTED_DEFAULT_RAMP: &[char] = &['@', '%', '#', '*', '+', '=', '-', ':', '.', ' '];
+
+/// Ramps the tune screen cycles through besides the one in use, darkest glyph first.
+pub const RAMP_PRESETS: [&[char]; 4] = [
+    DEFAULT_RAMP,
+    &[' ', '.', 'o', 'O', '@'],
+    &[' ', '.', '\'', ',', ':', ';', 'i', 'l', '!', 'I', 'r', 'c', 'v', 'u', 'n', 'x', 'z', 'X', 'Y', 'U', 'J', 'C', 'L', 'Q', '0', 'O', 'Z', 'm', 'w', 'q', 'p', 'd', 'b', 'k', 'h', 'a', 'o', '*', '#', 'M', 'W', '&', '8', '%', 'B', '@', '$'],
+    &[' ', '░', '▒', '▓', '█'],
+];
+
+/// Ramps built at runtime, so switching modes back and forth doesn't leak a copy each time.
+static INTERNED_RAMPS: Mutex<BTreeSet<&'static [char]>> = Mutex::new(BTreeSet::new());
+
+/// `ramp` as a slice that lives for the program, as `RenderOptions` needs; leaked the first time
+/// these glyphs are seen and shared after that.
+pub fn intern_ramp(ramp: Vec<char>) -> &'static [char] {
+    let Ok(mut interned) = INTERNED_RAMPS.lock() else { return Box::leak(ramp.into_boxed_slice()) };
+    if let Some(&existing) = interned.get(ramp.as_slice()) {
+        return existing;
+    }
+    let leaked: &'static [char] = Box::leak(ramp.into_boxed_slice());
+    interned.insert(leaked);
+    leaked
+}
+
+/// The ramp to render with: `custom` or the default, reversed when `invert` is set so bright
+/// pixels get sparse glyphs on a light background.
+pub fn resolve_ramp(custom: Option<&'static [char]>, invert: bool) -> &'static [char] {
+    match (custom, invert) {
+        (None, false) => DEFAULT_RAMP,
+        (None, true) => INVERTED_DEFAULT_RAMP,
+        (Some(ramp), false) => ramp,
+        (Some(ramp), true) => intern_ramp(ramp.iter().rev().copied().collect()),
+    }
+}
+
+/// How the decoded frame is sized to the terminal.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Fit {
+    /// As large as possible while keeping the aspect ratio, centered.
+    #[default]
+    Contain,
+    /// Fills the whole terminal, distorting the aspect ratio if needed.
+    Stretch,
+    /// Always as wide as the terminal, keeping the aspect ratio; rows past the bottom are cropped.
+    Width,
+    /// Always as tall as the terminal, keeping the aspect ratio; columns past the sides are cropped.
+    Height,
+}
+
+impl Fit {
+    /// Accepts `contain`, `stretch`, `width` or `height`, as given to `--fit`.
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "contain" => Some(Fit::Contain),
+            "stretch" => Some(Fit::Stretch),
+            "width" => Some(Fit::Width),
+            "height" => Some(Fit::Height),
+            _ => None,
+        }
+    }
+
+    /// The name `parse` takes.
+    pub fn key(self) -> &'static str {
+        match self {
+            Fit::Contain => "contain",
+            Fit::Stretch => "stretch",
+            Fit::Width => "width",
+            Fit::Height => "height",
+        }
+    }
+
+    /// The fit after this one for the playback toggle: the automatic choice, then width, then height.
+    pub fn next(self) -> Self {
+        match self {
+            Fit::Contain | Fit::Stretch => Fit::Width,
+            Fit::Width => Fit::Height,
+            Fit::Height => Fit::Contain,
+        }
+    }
+
+    /// Whether the video can overflow the terminal, cropped to it rather than squeezed.
+    pub fn crops(self) -> bool {
+        matches!(self, Fit::Width | Fit::Height)
+    }
+}
+
+/// ffmpeg's algorithm for resizing frames to the decode size (`scale=...:flags=`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Scaler {
+    /// ffmpeg's own default.
+    #[default]
+    Bicubic,
+    Bilinear,
+    Area,
+    Lanczos,
+    /// Nearest neighbor: blocky but crisp when enlarging small sources.
+    Neighbor,
+}
+
+impl Scaler {
+    /// Accepts the ffmpeg flag names, plus `nearest` for `neighbor`.
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "bicubic" => Some(Scaler::Bicubic),
+            "bilinear" => Some(Scaler::Bilinear),
+            "area" => Some(Scaler::Area),
+            "lanczos" => Some(Scaler::Lanczos),
+            "neighbor" | "nearest" => Some(Scaler::Neighbor),
+            _ => None,
+        }
+    }
+
+    pub fn flag(self) -> &'static str {
+        match self {
+            Scaler::Bicubic => "bicubic",
+            Scaler::Bilinear => "bilinear",
+            Scaler::Area => "area",
+            Scaler::Lanczos => "lanczos",
+            Scaler::Neighbor => "neighbor",
+        }
+    }
+}
+
+/// Which `Scaler` resizes a `source`-sized video: `upscale`, when set, takes over whenever the
+/// decode size is larger than the source in either direction.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Scaling {
+    pub scaler: Scaler,
+    pub upscale: Option<Scaler>,
+    /// Source size in pixels; `(0, 0)` when unknown, which never counts as upscaling.
+    pub source: (u32, u32),
+    /// Scale to cover the decode size at the source's aspect and crop the rest, for `Fit::crops`.
+    pub fill: bool,
+}
+
+impl Scaling {
+    pub fn scaler_for(&self, width: u32, height: u32) -> Scaler {
+        let (src_w, src_h) = self.source;
+        let upscaling = src_w > 0 && src_h > 0 && (width > src_w || height > src_h);
+        self.upscale.filter(|_| upscaling).unwrap_or(self.scaler)
+    }
+
+    /// The `scale` filter for `width`x`height`, leaving out the flags for ffmpeg's default, and
+    /// the centered `crop` back to that size when filling.
+    pub fn filter(&self, width: u32, height: u32) -> String {
+        let cover = if self.fill { ":force_original_aspect_ratio=increase" } else { "" };
+        let scale = match self.scaler_for(width, height) {
+            Scaler::Bicubic => format!("scale={}:{}{}", width, height, cover),
+            scaler => format!("scale={}:{}{}:flags={}", width, height, cover, scaler.flag()),
+        };
+        if self.fill { format!("{},crop={}:{}", scale, width, height) } else { scale }
+    }
+}
+
+/// A `w`x`h` region of the source, cut out with ffmpeg's `crop` before scaling. `offset` is its
+/// top-left corner; without one the region is centered.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Crop {
+    pub w: u32,
+    pub h: u32,
+    pub offset: Option<(u32, u32)>,
+}
+
+impl Crop {
+    /// Accepts `W:H` or `W:H:X:Y` in source pixels, as given to `--crop`.
+    pub fn parse(s: &str) -> Option<Self> {
+        let parts = s.split(':').map(|p| p.trim().parse::<u32>().ok()).collect::<Option<Vec<_>>>()?;
+        let crop = match parts[..] {
+            [w, h] => Self { w, h, offset: None },
+            [w, h, x, y] => Self { w, h, offset: Some((x, y)) },
+            _ => return None,
+        };
+        (crop.w > 0 && crop.h > 0).then_some(crop)
+    }
+
+    /// The `W:H[:X:Y]` form `parse` takes.
+    pub fn arg(&self) -> String {
+        match self.offset {
+            Some((x, y)) => format!("{}:{}:{}:{}", self.w, self.h, x, y),
+            None => format!("{}:{}", self.w, self.h),
+        }
+    }
+
+    /// Whether the region lies within a `width`x`height` source.
+    pub fn fits(&self, width: u32, height: u32) -> bool {
+        let (x, y) = self.offset.unwrap_or_default();
+        x.saturating_add(self.w) <= width && y.saturating_add(self.h) <= height
+    }
+
+    pub fn filter(&self) -> String {
+        format!("crop={}", self.arg())
+    }
+}
+
+impl std::fmt::Display for Crop {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}x{}", self.w, self.h)?;
+        match self.offset {
+            Some((x, y)) => write!(f, "+{}+{}", x, y),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Fill for the letterbox margins around a centered frame: one color, or a vertical gradient from
+/// `top` to `bottom` across the terminal.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Backdrop {
+    pub top: (u8, u8, u8),
+    pub bottom: (u8, u8, u8),
+}
+
+impl Backdrop {
+    pub fn solid(color: (u8, u8, u8)) -> Self {
+        Self { top: color, bottom: color }
+    }
+
+    /// Accepts a color as for `--ascii-color` (but not `original`), or `top:bottom` for a gradient.
+    pub fn parse(s: &str) -> Option<Self> {
+        let color = |s: &str| match AsciiColor::parse(s.trim())? {
+            AsciiColor::Mono(r, g, b) => Some((r, g, b)),
+            AsciiColor::Original => None,
+        };
+        match s.split_once(':') {
+            Some((top, bottom)) => Some(Self { top: color(top)?, bottom: color(bottom)? }),
+            None => color(s).map(Self::solid),
+        }
+    }
+
+    /// The color of terminal row `row`, zero-based, out of `rows`.
+    pub fn color_at(&self, row: u32, rows: u32) -> (u8, u8, u8) {
+        let t = row as f32 / rows.saturating_sub(1).max(1) as f32;
+        let mix = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t.min(1.0)).round() as u8;
+        (mix(self.top.0, self.bottom.0), mix(self.top.1, self.bottom.1), mix(self.top.2, self.bottom.2))
+    }
+}
+
+/// Foreground coloring for `AsciiArt`: the source pixels, or one fixed color for the whole frame.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum AsciiColor {
+    #[default]
+    Original,
+    Mono(u8, u8, u8),
+}
+
+impl AsciiColor {
+    pub const GREEN: AsciiColor = AsciiColor::Mono(0, 255, 70);
+    pub const AMBER: AsciiColor = AsciiColor::Mono(255, 176, 0);
+    pub const WHITE: AsciiColor = AsciiColor::Mono(230, 230, 230);
+
+    /// Accepts `original`, `green`, `amber`, `white` or a `#RRGGBB` hex color.
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "original" | "color" => Some(AsciiColor::Original),
+            "green" => Some(AsciiColor::GREEN),
+            "amber" => Some(AsciiColor::AMBER),
+            "white" => Some(AsciiColor::WHITE),
+            hex => {
+                let hex = hex.strip_prefix('#').unwrap_or(hex);
+                // Byte slicing below needs ASCII; anything else would split a character.
+                if hex.len() != 6 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+                    return None;
+                }
+                let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+                Some(AsciiColor::Mono(channel(0)?, channel(2)?, channel(4)?))
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for AsciiColor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            AsciiColor::Original => f.write_str(Msg::ColorOriginal.text()),
+            AsciiColor::GREEN => f.write_str(Msg::ColorGreen.text()),
+            AsciiColor::AMBER => f.write_str(Msg::ColorAmber.text()),
+            AsciiColor::WHITE => f.write_str(Msg::ColorWhite.text()),
+            AsciiColor::Mono(r, g, b) => write!(f, "#{:02X}{:02X}{:02X}", r, g, b),
+        }
+    }
+}
+
+/// How many colors the terminal can show, which decides the escapes colors are written with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorDepth {
+    /// 24-bit `38;2;R;G;B` colors.
+    #[default]
+    TrueColor,
+    /// The xterm 256-color palette: its 6x6x6 cube and gray ramp.
+    Ansi256,
+    /// The 16 standard colors, `3N`/`9N` foregrounds and `4N`/`10N` backgrounds, for the most basic
+    /// terminals and CI logs.
+    Ansi16,
+}
+
+/// The standard 16 colors as xterm shows them, in escape order: 0-7 normal, 8-15 bright.
+const ANSI16: [(u8, u8, u8); 16] = [
+    (0, 0, 0), (205, 0, 0), (0, 205, 0), (205, 205, 0), (0, 0, 238), (205, 0, 205), (0, 205, 205), (229, 229, 229),
+    (127, 127, 127), (255, 0, 0), (0, 255, 0), (255, 255, 0), (92, 92, 255), (255, 0, 255), (0, 255, 255), (255, 255, 255),
+];
+
+/// Channel levels of the 256-color palette's 6x6x6 cube.
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+fn distance((r1, g1, b1): (u8, u8, u8), (r2, g2, b2): (u8, u8, u8)) -> u32 {
+    let d = |a: u8, b: u8| (a as i32 - b as i32).pow(2) as u32;
+    d(r1, r2) + d(g1, g2) + d(b1, b2)
+}
+
+/// Index of the nearest of the 16 standard colors to `rgb`.
+pub fn nearest_ansi16(rgb: (u8, u8, u8)) -> u8 {
+    (0..16).min_by_key(|&i| distance(rgb, ANSI16[i])).unwrap_or(0) as u8
+}
+
+/// Index of the nearest 256-palette color to `rgb`: the closest cube color or gray, whichever is nearer.
+pub fn nearest_ansi256((r, g, b): (u8, u8, u8)) -> u8 {
+    let level = |v: u8| (0..6).min_by_key(|&i| (CUBE_LEVELS[i] as i32 - v as i32).unsigned_abs()).unwrap_or(0);
+    let (ri, gi, bi) = (level(r), level(g), level(b));
+    let cube = (CUBE_LEVELS[ri], CUBE_LEVELS[gi], CUBE_LEVELS[bi]);
+    // Grays 232-255 run from 8 to 238 in steps of 10.
+    let average = (r as u32 + g as u32 + b as u32) / 3;
+    let gray_step = (average.saturating_sub(3) / 10).min(23) as u8;
+    let gray = 8 + gray_step * 10;
+    if distance((r, g, b), (gray, gray, gray)) < distance((r, g, b), cube) {
+        232 + gray_step
+    } else {
+        (16 + 36 * ri + 6 * gi + bi) as u8
+    }
+}
+
+impl ColorDepth {
+    /// Accepts `truecolor` (or `24bit`), `256` or `16`, as given to `--color-mode`.
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "truecolor" | "24bit" => Some(ColorDepth::TrueColor),
+            "256" => Some(ColorDepth::Ansi256),
+            "16" => Some(ColorDepth::Ansi16),
+            _ => None,
+        }
+    }
+
+    /// The name `parse` takes.
+    pub fn key(self) -> &'static str {
+        match self {
+            ColorDepth::TrueColor => "truecolor",
+            ColorDepth::Ansi256 => "256",
+            ColorDepth::Ansi16 => "16",
+        }
+    }
+
+    /// `rgb` as this depth shows it; equal paints need no new escape.
+    fn paint(self, rgb: (u8, u8, u8)) -> Paint {
+        match self {
+            ColorDepth::TrueColor => Paint::Rgb(rgb),
+            ColorDepth::Ansi256 => Paint::Indexed(nearest_ansi256(rgb)),
+            ColorDepth::Ansi16 => Paint::Basic(nearest_ansi16(rgb)),
+        }
+    }
+}
+
+/// A color reduced to what a `ColorDepth` can write.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Paint {
+    Rgb((u8, u8, u8)),
+    Indexed(u8),
+    Basic(u8),
+}
+
+impl Paint {
+    fn push_fg(self, out: &mut String) {
+        match self {
+            Paint::Rgb((r, g, b)) => write!(out, "\x1b[38;2;{};{};{}m", r, g, b),
+            Paint::Indexed(i) => write!(out, "\x1b[38;5;{}m", i),
+            Paint::Basic(i) if i < 8 => write!(out, "\x1b[{}m", 30 + i),
+            Paint::Basic(i) => write!(out, "\x1b[{}m", 90 + i - 8),
+        }
+        .unwrap();
+    }
+
+    fn push_bg(self, out: &mut String) {
+        match self {
+            Paint::Rgb((r, g, b)) => write!(out, "\x1b[48;2;{};{};{}m", r, g, b),
+            Paint::Indexed(i) => write!(out, "\x1b[48;5;{}m", i),
+            Paint::Basic(i) if i < 8 => write!(out, "\x1b[{}m", 40 + i),
+            Paint::Basic(i) => write!(out, "\x1b[{}m", 100 + i - 8),
+        }
+        .unwrap();
+    }
+}
+
+/// Layout of the raw frames ffmpeg sends.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum PixelFormat {
+    #[default]
+    Rgb24,
+    /// One luma byte per pixel, for modes that never show the source colors.
+    Gray,
+    /// 16 bits per channel, little-endian. Only ever decoded: it's dithered down to `Rgb24`
+    /// before anything renders it.
+    Rgb48,
+}
+
+impl PixelFormat {
+    /// Gray for Plain and the monochrome ASCII modes, which only look at brightness; rgb24 otherwise.
+    pub fn for_output(mode: RenderMode, ascii_color: AsciiColor) -> Self {
+        match (mode, ascii_color) {
+            (RenderMode::Plain, _) | (RenderMode::AsciiArt | RenderMode::CompactAscii, AsciiColor::Mono(..)) => PixelFormat::Gray,
+            _ => PixelFormat::Rgb24,
+        }
+    }
+
+    /// Accepts `rgb24` or `rgb48`, as given to `--pix-fmt`; gray follows from the mode instead.
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "rgb24" => Some(PixelFormat::Rgb24),
+            "rgb48" | "rgb48le" => Some(PixelFormat::Rgb48),
+            _ => None,
+        }
+    }
+
+    /// Name for ffmpeg's `-pix_fmt` and `format` filter.
+    pub fn name(self) -> &'static str {
+        match self {
+            PixelFormat::Rgb24 => "rgb24",
+            PixelFormat::Gray => "gray",
+            PixelFormat::Rgb48 => "rgb48le",
+        }
+    }
+
+    pub fn bytes_per_pixel(self) -> usize {
+        match self {
+            PixelFormat::Rgb24 => 3,
+            PixelFormat::Gray => 1,
+            PixelFormat::Rgb48 => 6,
+        }
+    }
+}
+
+/// Everything about the output that isn't the frame itself.
+#[derive(Debug, Clone, Copy)]
+pub struct RenderOptions {
+    pub mode: RenderMode,
+    pub term_w: u16,
+    pub term_h: u16,
+    pub ascii_color: AsciiColor,
+    /// `AsciiArt` glyphs from darkest to brightest; at least two.
+    pub ramp: &'static [char],
+    /// How `Image` frames reach the terminal; without one they can't be drawn.
+    pub image_protocol: Option<ImageProtocol>,
+    /// Layout of the frames passed to `render_frame`; `Gray` only suits Plain and the monochrome ASCII modes.
+    pub format: PixelFormat,
+    /// Fill for the margins around the frame; `None` leaves the terminal background. Plain ignores it.
+    pub backdrop: Option<Backdrop>,
+    /// Cells the frame is moved right and down from the center; `frame_offsets` keeps it on screen.
+    pub shift: (i32, i32),
+    /// Colors the terminal can show; frames and margins are reduced to them.
+    pub depth: ColorDepth,
+}
+
+impl RenderOptions {
+    pub fn new(mode: RenderMode, term_w: u16, term_h: u16) -> Self {
+        Self { mode, term_w, term_h, ascii_color: AsciiColor::Original, ramp: DEFAULT_RAMP, image_protocol: None, format: PixelFormat::Rgb24, backdrop: None, shift: (0, 0), depth: ColorDepth::TrueColor }
+    }
+}
+
+/// Picks the decode resolution for a video so it fits the terminal, keeping its aspect ratio
+/// unless `fit` says to stretch. `Fit::Width` and `Fit::Height` fill that side whatever the
+/// aspect, and clamp the other to the terminal for the decoder to crop (see `Scaling::fill`).
+///
+/// `Image` gets the PixelArt layout here, in cells; the caller scales it to the cell pixel size.
+pub fn target_size(mode: RenderMode, fit: Fit, orig_w: u32, orig_h: u32, term_w: u16, term_h: u16) -> (u32, u32) {
+    let (mut w, mut h) = match mode {
+        // Every cell, with PixelArt's two pixels per row.
+        _ if fit == Fit::Stretch => (term_w as u32, term_h as u32 * if matches!(mode, RenderMode::AsciiArt | RenderMode::Blocks | RenderMode::Plain) { 1 } else { 2 }),
+        _ if fit.crops() => {
+            let one_per_cell = matches!(mode, RenderMode::AsciiArt | RenderMode::Blocks | RenderMode::Plain);
+            let (max_w, max_h) = (term_w as u32, term_h as u32 * if one_per_cell { 1 } else { 2 });
+            // A one-pixel-per-cell row shows as tall as two pixels are wide.
+            let aspect = orig_w as f32 / orig_h as f32 / if one_per_cell { 0.5 } else { 1.0 };
+            if fit == Fit::Width {
+                (max_w, ((max_w as f32 / aspect) as u32).min(max_h))
+            } else {
+                (((max_h as f32 * aspect) as u32).min(max_w), max_h)
+            }
+        }
+        // Two pixels per row: a cell's 1:2 aspect holds two square pixels stacked.
+        RenderMode::PixelArt | RenderMode::CompactAscii | RenderMode::Image => {
+            // STRATEGY: Half-Block Rendering (▀)
+            let effective_term_w = term_w as u32;
+            let effective_term_h = (term_h as u32) * 2;
+
+            let video_aspect = orig_w as f32 / orig_h as f32;
+            let term_aspect = effective_term_w as f32 / effective_term_h as f32;
+
+            if video_aspect > term_aspect {
+                let h = effective_term_w as f32 / video_aspect;
+                (effective_term_w, h as u32)
+            } else {
+                let w = effective_term_h as f32 * video_aspect;
+                (w as u32, effective_term_h)
+            }
+        },
+        // One cell per pixel in all three, so they share the character aspect correction.
+        RenderMode::AsciiArt | RenderMode::Blocks | RenderMode::Plain => {
+            let char_aspect = 0.5;
+            let video_aspect = orig_w as f32 / orig_h as f32;
+
+            let mut w = term_w as u32;
+            let mut h = (w as f32 / video_aspect * char_aspect) as u32;
+
+            if h > term_h as u32 {
+                h = term_h as u32;
+                w = (h as f32 * video_aspect / char_aspect) as u32;
+            }
+            (w, h)
+        }
+    };
+
+    // Ensure even and non-zero
+    w = (w / 2) * 2;
+    h = (h / 2) * 2;
+    if w == 0 { w = 2; }
+    if h == 0 { h = 2; }
+    (w, h)
+}
+
+/// Whether a video is taller than it is wide, so half-block cells (two pixels each) fill the
+/// terminal height better than one-pixel-per-cell modes.
+pub fn is_portrait(orig_w: u32, orig_h: u32) -> bool {
+    orig_h > orig_w
+}
+
+/// Number of terminal columns/rows to skip so the frame ends up centered, or moved from there by
+/// `opts.shift` as far as the terminal allows.
+pub fn frame_offsets(opts: &RenderOptions, width: u32, height: u32) -> (u32, u32) {
+    let (cols, rows) = frame_cells(opts.mode, width, height, opts.term_w, opts.term_h);
+    let offset = |term: u16, cells: u32, shift: i32| {
+        let room = (term as u32).saturating_sub(cells);
+        (room as i64 / 2 + shift as i64).clamp(0, room as i64) as u32
+    };
+    (offset(opts.term_w, cols, opts.shift.0), offset(opts.term_h, rows, opts.shift.1))
+}
+
+/// `opts.shift` moved by `(dx, dy)` cells, stopping where the frame would leave the terminal.
+pub fn nudge(opts: &RenderOptions, width: u32, height: u32, (dx, dy): (i32, i32)) -> (i32, i32) {
+    let centered = frame_offsets(&RenderOptions { shift: (0, 0), ..*opts }, width, height);
+    let moved = frame_offsets(&RenderOptions { shift: (opts.shift.0 + dx, opts.shift.1 + dy), ..*opts }, width, height);
+    (moved.0 as i32 - centered.0 as i32, moved.1 as i32 - centered.1 as i32)
+}
+
+/// Whether a `width`x`height` frame can be drawn without overflowing the terminal.
+pub fn fits(mode: RenderMode, width: u32, height: u32, term_w: u16, term_h: u16) -> bool {
+    let (cols, rows) = frame_cells(mode, width, height, term_w, term_h);
+    width > 0 && height > 0 && cols <= term_w as u32 && rows <= term_h as u32
+}
+
+/// Renders one raw `rgb24` frame into `out` as ANSI escape sequences, replacing its contents.
+///
+/// This is the whole render pipeline minus ffmpeg, so tests can feed it synthetic buffers.
+pub fn render_frame(out: &mut String, frame: &[u8], width: u32, height: u32, opts: &RenderOptions) -> Option<()> {
+    if opts.format == PixelFormat::Gray {
+        render_gray(out, frame, width, height, opts)?;
+    } else {
+        render_rgb(out, frame, width, height, opts)?;
+    }
+    // Rows below the frame; the ones above and beside it are filled as the frame is written.
+    let (_, offset_y) = frame_offsets(opts, width, height);
+    let (_, rows) = frame_cells(opts.mode, width, height, opts.term_w, opts.term_h);
+    if backdrop(opts).is_some() {
+        for row in (offset_y + rows)..opts.term_h as u32 {
+            write!(out, "\x1b[{};1H", row + 1).unwrap();
+            push_fill(out, opts, row, opts.term_w as u32);
+        }
+    }
+    Some(())
+}
+
+fn render_rgb(out: &mut String, frame: &[u8], width: u32, height: u32, opts: &RenderOptions) -> Option<()> {
+    let RenderOptions { mode, term_w, term_h, ascii_color, ramp, image_protocol, depth, .. } = *opts;
+    let img = ImageBuffer::<Rgb<u8>, &[u8]>::from_raw(width, height, frame)?;
+
+    out.clear();
+    out.push_str("\x1b[H");
+
+    let mut last_fg: Option<Paint> = None;
+    let mut last_bg: Option<Paint> = None;
+
+    let (offset_x, offset_y) = frame_offsets(opts, width, height);
+    // Margin right of the frame, for the cell modes that are as many columns wide as the frame.
+    let right = (term_w as u32).saturating_sub(offset_x + width);
+
+    for row in 0..offset_y {
+        push_fill(out, opts, row, opts.term_w as u32);
+        out.push_str("\r\n");
+    }
+
+    match mode {
+        RenderMode::PixelArt => {
+            for y in 0..(height / 2) {
+                if offset_x > 0 {
+                    push_pad(out, opts, offset_y + y, offset_x, "\x1b[0m");
+                    last_fg = None; last_bg = None;
+                }
+
+                for x in 0..width {
+                    let [r1, g1, b1] = img.get_pixel(x, y * 2).0;
+                    let [r2, g2, b2] = img.get_pixel(x, y * 2 + 1).0;
+
+                    let curr_fg = depth.paint((r1, g1, b1));
+                    if last_fg != Some(curr_fg) {
+                        curr_fg.push_fg(out);
+                        last_fg = Some(curr_fg);
+                    }
+
+                    let curr_bg = depth.paint((r2, g2, b2));
+                    if last_bg != Some(curr_bg) {
+                        curr_bg.push_bg(out);
+                        last_bg = Some(curr_bg);
+                    }
+
+                    out.push('▀');
+                }
+                out.push_str("\x1b[0m");
+                push_fill(out, opts, offset_y + y, right);
+                out.push_str("\r\n");
+                last_fg = None; last_bg = None;
+            }
+        },
+        RenderMode::CompactAscii => {
+            if let AsciiColor::Mono(r, g, b) = ascii_color {
+                push_mono(out, opts, (r, g, b), width, height / 2, (offset_x, offset_y), |x, y| {
+                    let [r1, g1, b1] = img.get_pixel(x, y * 2).0;
+                    let [r2, g2, b2] = img.get_pixel(x, y * 2 + 1).0;
+                    pair_glyph(luminance(r1, g1, b1), luminance(r2, g2, b2), ramp)
+                });
+                return Some(());
+            }
+
+            // Like AsciiArt, rows start on the default background and only foregrounds change.
+            for y in 0..(height / 2) {
+                if offset_x > 0 {
+                    push_pad(out, opts, offset_y + y, offset_x, "\x1b[49m");
+                }
+                out.push_str("\x1b[49m");
+
+                for x in 0..width {
+                    let [r1, g1, b1] = img.get_pixel(x, y * 2).0;
+                    let [r2, g2, b2] = img.get_pixel(x, y * 2 + 1).0;
+
+                    let curr_fg = depth.paint((r1, g1, b1));
+                    if last_fg != Some(curr_fg) {
+                        curr_fg.push_fg(out);
+                        last_fg = Some(curr_fg);
+                    }
+                    out.push(pair_glyph(luminance(r1, g1, b1), luminance(r2, g2, b2), ramp));
+                }
+                out.push_str("\x1b[0m");
+                push_fill(out, opts, offset_y + y, right);
+                out.push_str("\r\n");
+                last_fg = None;
+            }
+        },
+        RenderMode::AsciiArt => {
+            // Only foregrounds are set per cell, so each row starts with an explicit default
+            // background; whatever an earlier frame or program left behind can't bleed through.
+            if let AsciiColor::Mono(r, g, b) = ascii_color {
+                push_mono(out, opts, (r, g, b), width, height, (offset_x, offset_y), |x, y| {
+                    let [r, g, b] = img.get_pixel(x, y).0;
+                    ascii_for(r, g, b, ramp)
+                });
+                return Some(());
+            }
+
+            for y in 0..height {
+                if offset_x > 0 {
+                    push_pad(out, opts, offset_y + y, offset_x, "\x1b[0m");
+                    last_fg = None;
+                }
+                out.push_str("\x1b[49m");
+
+                for x in 0..width {
+                    let [r, g, b] = img.get_pixel(x, y).0;
+
+                    let curr_fg = depth.paint((r, g, b));
+                    if last_fg != Some(curr_fg) {
+                        curr_fg.push_fg(out);
+                        last_fg = Some(curr_fg);
+                    }
+                    out.push(ascii_for(r, g, b, ramp));
+                }
+                out.push_str("\x1b[0m");
+                push_fill(out, opts, offset_y + y, right);
+                out.push_str("\r\n");
+                last_fg = None;
+            }
+        },
+        RenderMode::Blocks => {
+            for y in 0..height {
+                if offset_x > 0 {
+                    push_pad(out, opts, offset_y + y, offset_x, "\x1b[0m");
+                    last_bg = None;
+                }
+
+                for x in 0..width {
+                    let [r, g, b] = img.get_pixel(x, y).0;
+
+                    let curr_bg = depth.paint((r, g, b));
+                    if last_bg != Some(curr_bg) {
+                        curr_bg.push_bg(out);
+                        last_bg = Some(curr_bg);
+                    }
+                    out.push(' ');
+                }
+                out.push_str("\x1b[0m");
+                push_fill(out, opts, offset_y + y, right);
+                out.push_str("\r\n");
+                last_bg = None;
+            }
+        }
+        RenderMode::Plain => push_plain(out, width, height, offset_x, |x, y| {
+            let [r, g, b] = img.get_pixel(x, y).0;
+            ascii_for(r, g, b, ramp)
+        }),
+        RenderMode::Image => {
+            let cells = frame_cells(mode, width, height, term_w, term_h);
+            if backdrop(opts).is_some() {
+                let right = (term_w as u32).saturating_sub(offset_x + cells.0);
+                for row in offset_y..offset_y + cells.1 {
+                    write!(out, "\x1b[{};1H", row + 1).unwrap();
+                    push_fill(out, opts, row, offset_x);
+                    write!(out, "\x1b[{};{}H", row + 1, offset_x + cells.0 + 1).unwrap();
+                    push_fill(out, opts, row, right);
+                }
+            }
+            write!(out, "\x1b[{};{}H", offset_y + 1, offset_x + 1).unwrap();
+            graphics::push_image(out, image_protocol?, frame, width, height, cells)?;
+        }
+    }
+    Some(())
+}
+
+/// `render_frame` for `gray` frames: Plain and the monochrome ASCII modes, drawn straight from the luma bytes.
+fn render_gray(out: &mut String, frame: &[u8], width: u32, height: u32, opts: &RenderOptions) -> Option<()> {
+    if frame.len() < (width * height) as usize {
+        return None;
+    }
+    let color = match opts.ascii_color {
+        AsciiColor::Mono(r, g, b) => (r, g, b),
+        AsciiColor::Original => (230, 230, 230),
+    };
+    let (offset_x, offset_y) = frame_offsets(opts, width, height);
+    let luma = |x: u32, y: u32| frame[(y * width + x) as usize];
+
+    out.clear();
+    out.push_str("\x1b[H");
+    for row in 0..offset_y {
+        push_fill(out, opts, row, opts.term_w as u32);
+        out.push_str("\r\n");
+    }
+    match opts.mode {
+        RenderMode::CompactAscii => push_mono(out, opts, color, width, height / 2, (offset_x, offset_y), |x, y| pair_glyph(luma(x, y * 2), luma(x, y * 2 + 1), opts.ramp)),
+        RenderMode::Plain => push_plain(out, width, height, offset_x, |x, y| glyph_for(luma(x, y), opts.ramp)),
+        _ => push_mono(out, opts, color, width, height, (offset_x, offset_y), |x, y| glyph_for(luma(x, y), opts.ramp)),
+    }
+    Some(())
+}
+
+/// One color for the whole frame: set it once and only emit `glyph(col, row)` for each cell after that.
+fn push_mono(
+    out: &mut String,
+    opts: &RenderOptions,
+    color: (u8, u8, u8),
+    cols: u32,
+    rows: u32,
+    (offset_x, offset_y): (u32, u32),
+    glyph: impl Fn(u32, u32) -> char,
+) {
+    let right = (opts.term_w as u32).saturating_sub(offset_x + cols);
+    out.push_str("\x1b[0m");
+    opts.depth.paint(color).push_fg(out);
+    for y in 0..rows {
+        push_pad(out, opts, offset_y + y, offset_x, "\x1b[49m");
+        for x in 0..cols {
+            out.push(glyph(x, y));
+        }
+        push_fill(out, opts, offset_y + y, right);
+        out.push_str("\r\n");
+    }
+    out.push_str("\x1b[0m");
+}
+
+/// The backdrop `opts` asks for, unless the mode draws without escape sequences.
+fn backdrop(opts: &RenderOptions) -> Option<Backdrop> {
+    opts.backdrop.filter(|_| opts.mode != RenderMode::Plain)
+}
+
+/// `cols` cells of margin on terminal row `row`: the backdrop color, or `reset` and blanks without
+/// one. The backdrop is followed by a default background, which keeps the foreground the row set.
+fn push_pad(out: &mut String, opts: &RenderOptions, row: u32, cols: u32, reset: &str) {
+    match backdrop(opts) {
+        Some(_) => push_fill(out, opts, row, cols),
+        None => write!(out, "{}{:width$}", reset, "", width=cols as usize).unwrap(),
+    }
+}
+
+/// `cols` cells of backdrop on terminal row `row`; nothing at all without a backdrop.
+fn push_fill(out: &mut String, opts: &RenderOptions, row: u32, cols: u32) {
+    if let Some(backdrop) = backdrop(opts).filter(|_| cols > 0) {
+        opts.depth.paint(backdrop.color_at(row, opts.term_h as u32)).push_bg(out);
+        write!(out, "{:width$}\x1b[49m", "", width=cols as usize).unwrap();
+    }
+}
+
+/// Bare glyphs with no escape sequences at all, so the terminal's default colors show them.
+fn push_plain(out: &mut String, cols: u32, rows: u32, offset_x: u32, glyph: impl Fn(u32, u32) -> char) {
+    for y in 0..rows {
+        write!(out, "{:width$}", "", width=offset_x as usize).unwrap();
+        for x in 0..cols {
+            out.push(glyph(x, y));
+        }
+        out.push_str("\r\n");
+    }
+}
+
+/// Appends a frame rendered by `render_frame` for a smaller area so that area's top-left cell sits
+/// at `(col, row)` of the screen, both zero-based; each line gets its own cursor move.
+pub fn push_at(out: &mut String, frame: &str, col: u16, row: u16) {
+    let body = frame.strip_prefix("\x1b[H").unwrap_or(frame);
+    for (i, line) in body.split("\r\n").enumerate().filter(|(_, line)| !line.is_empty()) {
+        write!(out, "\x1b[{};{}H{}", row as usize + i + 1, col + 1, line).unwrap();
+    }
+    out.push_str("\x1b[0m");
+}
+
+/// A `gray` frame as lines of `ramp` glyphs for a text file: no escape codes, no centering and no
+/// trailing spaces.
+pub fn plain_text(frame: &[u8], width: u32, height: u32, ramp: &[char]) -> String {
+    let mut text = String::with_capacity(((width + 1) * height) as usize);
+    for row in frame.chunks_exact(width.max(1) as usize).take(height as usize) {
+        let line: String = row.iter().map(|&luma| glyph_for(luma, ramp)).collect();
+        text.push_str(line.trim_end());
+        text.push('\n');
+    }
+    text
+}
+
+/// A frame as text to paste elsewhere, rendered by `render_frame` at the frame's own size so there
+/// are no margins or cursor moves, one `\n`-ended line per row. Plain text keeps only the glyphs,
+/// so the modes that draw with colored cells instead are rendered as CompactAscii or Plain; colored
+/// text keeps the escapes, with Image drawn as PixelArt.
+pub fn clipboard_text(frame: &[u8], width: u32, height: u32, opts: &RenderOptions, colored: bool) -> Option<String> {
+    let mode = match opts.mode {
+        RenderMode::Image if colored => RenderMode::PixelArt,
+        RenderMode::PixelArt | RenderMode::Image if !colored => RenderMode::CompactAscii,
+        RenderMode::Blocks if !colored => RenderMode::Plain,
+        mode => mode,
+    };
+    let (cols, rows) = frame_cells(mode, width, height, opts.term_w, opts.term_h);
+    let opts = RenderOptions { mode, term_w: cols as u16, term_h: rows as u16, image_protocol: None, backdrop: None, shift: (0, 0), ..*opts };
+    let mut out = String::new();
+    render_frame(&mut out, frame, width, height, &opts)?;
+    let body = out.strip_prefix("\x1b[H").unwrap_or(&out);
+    let lines = body.split("\r\n").take(rows as usize);
+    let mut text = String::with_capacity(body.len());
+    if colored {
+        for line in lines {
+            text.push_str(line);
+            text.push_str("\x1b[0m\n");
+        }
+    } else {
+        for line in lines {
+            text.push_str(strip_escapes(line).trim_end());
+            text.push('\n');
+        }
+    }
+    Some(text)
+}
+
+/// `line` without its CSI escape sequences (`ESC [`, parameters, a final letter).
+fn strip_escapes(line: &str) -> String {
+    let mut plain = String::with_capacity(line.len());
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            // Up to and including the final letter; the `[` is in the final range too, so skip it first.
+            chars.next();
+            chars.find(|c| ('@'..='~').contains(c));
+        } else {
+            plain.push(c);
+        }
+    }
+    plain
+}
+
+/// Overlays rule-of-thirds lines and a center crosshair on a frame already in `out`.
+///
+/// Cells are overwritten in a dim gray, so the guides show exactly where the frame sits after centering.
+pub fn push_guides(out: &mut String, width: u32, height: u32, opts: &RenderOptions) {
+    let (cols, rows) = frame_cells(opts.mode, width, height, opts.term_w, opts.term_h);
+    if cols < 3 || rows < 3 {
+        return;
+    }
+    let (offset_x, offset_y) = frame_offsets(opts, width, height);
+    let thirds_x = [cols / 3, cols * 2 / 3];
+    let thirds_y = [rows / 3, rows * 2 / 3];
+    let center = (cols / 2, rows / 2);
+
+    out.push_str("\x1b[0m\x1b[38;5;240m");
+    for y in 0..rows {
+        for x in 0..cols {
+            let on_col = thirds_x.contains(&x);
+            let on_row = thirds_y.contains(&y);
+            let glyph = if (x, y) == center {
+                '+'
+            } else if on_col && on_row {
+                '┼'
+            } else if on_col {
+                '│'
+            } else if on_row {
+                '─'
+            } else {
+                continue;
+            };
+            write!(out, "\x1b[{};{}H{}", offset_y + y + 1, offset_x + x + 1, glyph).unwrap();
+        }
+    }
+    out.push_str("\x1b[0m");
+}
+
+/// Border color used unless `border_color` is set.
+pub const DEFAULT_BORDER_COLOR: (u8, u8, u8) = (0, 255, 255);
+
+/// Draws a rounded box around the centered frame, in the margin next to it. Nothing is drawn when
+/// the frame reaches an edge of the terminal and there's no room.
+pub fn push_border(out: &mut String, width: u32, height: u32, opts: &RenderOptions, color: (u8, u8, u8)) {
+    let (cols, rows) = frame_cells(opts.mode, width, height, opts.term_w, opts.term_h);
+    let (offset_x, offset_y) = frame_offsets(opts, width, height);
+    // 1-based edges: the cells just outside the frame.
+    let (left, top, right, bottom) = (offset_x, offset_y, offset_x + cols + 1, offset_y + rows + 1);
+    if left < 1 || top < 1 || right > opts.term_w as u32 || bottom > opts.term_h as u32 {
+        return;
+    }
+    let line = "─".repeat(cols as usize);
+    out.push_str("\x1b[0m");
+    opts.depth.paint(color).push_fg(out);
+    write!(out, "\x1b[{};{}H╭{}╮", top, left, line).unwrap();
+    for row in top + 1..bottom {
+        write!(out, "\x1b[{};{}H│\x1b[{};{}H│", row, left, row, right).unwrap();
+    }
+    write!(out, "\x1b[{};{}H╰{}╯\x1b[0m", bottom, left, line).unwrap();
+}
+
+/// Columns and rows of terminal cells a `width`x`height` frame occupies.
+pub fn frame_cells(mode: RenderMode, width: u32, height: u32, term_w: u16, term_h: u16) -> (u32, u32) {
+    match mode {
+        RenderMode::PixelArt | RenderMode::CompactAscii => (width, height / 2),
+        RenderMode::AsciiArt | RenderMode::Blocks | RenderMode::Plain => (width, height),
+        // The terminal scales the image into the cells PixelArt would use at the same aspect ratio.
+        RenderMode::Image => {
+            let (cols, rows) = target_size(RenderMode::PixelArt, Fit::Contain, width, height, term_w, term_h);
+            (cols, rows / 2)
+        }
+    }
+}
+
+/// Source pixels drawn in frame cell `(col, row)`: the top and bottom pixel in the two-pixel modes, the pixel at
+/// the cell's center for Image, one pixel otherwise.
+pub fn cell_pixels(opts: &RenderOptions, width: u32, height: u32, (col, row): (u32, u32)) -> Vec<(u32, u32)> {
+    match opts.mode {
+        RenderMode::PixelArt | RenderMode::CompactAscii => vec![(col, row * 2), (col, row * 2 + 1)],
+        RenderMode::AsciiArt | RenderMode::Blocks | RenderMode::Plain => vec![(col, row)],
+        RenderMode::Image => {
+            let (cols, rows) = frame_cells(opts.mode, width, height, opts.term_w, opts.term_h);
+            let center = |cell: u32, cells: u32, size: u32| ((2 * cell + 1) * size / (2 * cells.max(1))).min(size.saturating_sub(1));
+            vec![(center(col, cols, width), center(row, rows, height))]
+        }
+    }
+}
+
+/// Marks frame cell `cell` with a cursor and describes the pixels under it in a box in the top-right corner.
+pub fn push_inspector(out: &mut String, frame: &[u8], width: u32, height: u32, opts: &RenderOptions, cell: (u32, u32)) {
+    let expanded: Vec<u8>;
+    let frame = if opts.format == PixelFormat::Gray {
+        expanded = frame.iter().flat_map(|&v| [v, v, v]).collect();
+        &expanded
+    } else {
+        frame
+    };
+    let Some(img) = ImageBuffer::<Rgb<u8>, &[u8]>::from_raw(width, height, frame) else { return };
+    let pixels = cell_pixels(opts, width, height, cell);
+
+    let mut lines = vec![Msg::InspectCell.fill(&[&cell.0, &cell.1])];
+    for (i, &(x, y)) in pixels.iter().enumerate() {
+        if x >= width || y >= height {
+            return;
+        }
+        let [r, g, b] = img.get_pixel(x, y).0;
+        let label = match (opts.mode, i) {
+            (RenderMode::PixelArt | RenderMode::CompactAscii, 0) => Msg::InspectTop.text(),
+            (RenderMode::PixelArt | RenderMode::CompactAscii, _) => Msg::InspectBottom.text(),
+            _ => "",
+        };
+        lines.push(Msg::InspectPixel.fill(&[&label, &x, &y]));
+        lines.push(Msg::InspectColor.fill(&[&label, &r, &g, &b, &luminance(r, g, b)]));
+    }
+    let (x, y) = pixels[0];
+    let [r, g, b] = img.get_pixel(x, y).0;
+    lines.push(match opts.mode {
+        RenderMode::PixelArt => Msg::InspectHalfBlock.text().to_string(),
+        RenderMode::AsciiArt => Msg::InspectGlyph.fill(&[&ascii_for(r, g, b, opts.ramp)]),
+        RenderMode::Plain => Msg::InspectPlainGlyph.fill(&[&ascii_for(r, g, b, opts.ramp)]),
+        RenderMode::CompactAscii => {
+            let [r2, g2, b2] = pixels.get(1).map_or([r, g, b], |&(x, y)| img.get_pixel(x, y).0);
+            let (top, bottom) = (luminance(r, g, b), luminance(r2, g2, b2));
+            Msg::InspectPairGlyph.fill(&[&pair_glyph(top, bottom, opts.ramp), &((top as u16 + bottom as u16) / 2)])
+        }
+        RenderMode::Blocks => Msg::InspectBlock.text().to_string(),
+        RenderMode::Image => Msg::InspectImage.text().to_string(),
+    });
+
+    let (offset_x, offset_y) = frame_offsets(opts, width, height);
+    write!(out, "\x1b[{};{}H\x1b[0m\x1b[1;97;41m+\x1b[0m", offset_y + cell.1 + 1, offset_x + cell.0 + 1).unwrap();
+
+    push_corner(out, &lines, opts.term_w);
+}
+
+/// Copies the `view`-sized window at `offset` out of a `width` pixels wide frame into `out`, for
+/// panning across a frame larger than the terminal. The window is clamped to the frame.
+pub fn viewport(frame: &[u8], (width, height): (u32, u32), channels: usize, offset: (u32, u32), view: (u32, u32), out: &mut Vec<u8>) {
+    let (view_w, view_h) = (view.0.min(width) as usize, view.1.min(height) as usize);
+    let x = (offset.0 as usize).min(width as usize - view_w);
+    let y = (offset.1 as usize).min(height as usize - view_h);
+    let row_len = width as usize * channels;
+    out.clear();
+    for row in frame.chunks_exact(row_len).skip(y).take(view_h) {
+        out.extend_from_slice(&row[x * channels..(x + view_w) * channels]);
+    }
+}
+
+/// Arrows at the edges of the terminal pointing where more of a panned frame lies: `left`,
+/// `right`, `up` and `down`. The bottom one sits above the status line.
+pub fn push_scroll_marks(out: &mut String, term_w: u16, term_h: u16, (left, right, up, down): (bool, bool, bool, bool)) {
+    let (mid_col, mid_row) = (term_w / 2 + 1, term_h / 2 + 1);
+    let marks = [(left, mid_row, 1, '◀'), (right, mid_row, term_w, '▶'), (up, 1, mid_col, '▲'), (down, term_h.saturating_sub(1).max(1), mid_col, '▼')];
+    for (_, row, col, mark) in marks.into_iter().filter(|m| m.0) {
+        write!(out, "\x1b[{};{}H\x1b[0m\x1b[7m{}\x1b[0m", row, col, mark).unwrap();
+    }
+}
+
+/// Draws `lines` in a box in the top-right corner of a `term_w` columns wide terminal.
+pub fn push_corner(out: &mut String, lines: &[String], term_w: u16) {
+    let left = (term_w as usize).saturating_sub(box_width(lines)) + 1;
+    push_box(out, lines, (1, left), None);
+}
+
+/// Draws `lines` in a box in the bottom-left corner, above the status line.
+pub fn push_lower_corner(out: &mut String, lines: &[String], term_h: u16) {
+    let top = (term_h as usize).saturating_sub(lines.len() + 2).max(1);
+    push_box(out, lines, (top, 1), None);
+}
+
+/// Draws `items` in a box in the top-right corner, with `selected` in reverse video, leaving the
+/// middle of the frame uncovered.
+pub fn push_corner_menu(out: &mut String, items: &[String], selected: usize, term_w: u16) {
+    let left = (term_w as usize).saturating_sub(box_width(items)) + 1;
+    push_box(out, items, (1, left), Some(selected));
+}
+
+/// Draws `items` as a box centered on the terminal, with `selected` in reverse video.
+pub fn push_menu(out: &mut String, items: &[String], selected: usize, term_w: u16, term_h: u16) {
+    push_centered(out, items, Some(selected), term_w, term_h);
+}
+
+/// Draws `lines` as a box centered on the terminal.
+pub fn push_panel(out: &mut String, lines: &[String], term_w: u16, term_h: u16) {
+    push_centered(out, lines, None, term_w, term_h);
+}
+
+fn push_centered(out: &mut String, lines: &[String], highlight: Option<usize>, term_w: u16, term_h: u16) {
+    let top = (term_h as usize).saturating_sub(lines.len() + 2) / 2 + 1;
+    let left = (term_w as usize).saturating_sub(box_width(lines)) / 2 + 1;
+    push_box(out, lines, (top, left), highlight);
+}
+
+/// Columns taken by `push_box` for `lines`, borders included.
+fn box_width(lines: &[String]) -> usize {
+    lines.iter().map(|l| l.width()).max().unwrap_or(0) + 4
+}
+
+/// Draws `lines` in a bordered box whose top-left corner is at 1-based `(row, col)`.
+fn push_box(out: &mut String, lines: &[String], (top, left): (usize, usize), highlight: Option<usize>) {
+    let inner = box_width(lines) - 4;
+    write!(out, "\x1b[{};{}H\x1b[0m┌{}┐", top, left, "─".repeat(inner + 2)).unwrap();
+    for (i, line) in lines.iter().enumerate() {
+        let pad = " ".repeat(inner - line.width());
+        if highlight == Some(i) {
+            write!(out, "\x1b[{};{}H│\x1b[7m {}{} \x1b[0m│", top + i + 1, left, line, pad).unwrap();
+        } else {
+            write!(out, "\x1b[{};{}H│ {}{} │", top + i + 1, left, line, pad).unwrap();
+        }
+    }
+    write!(out, "\x1b[{};{}H└{}┘", top + lines.len() + 1, left, "─".repeat(inner + 2)).unwrap();
+}
+
+/// Bar heights for the histogram overlay, from empty to full.
+const BARS: [char; 9] = [' ', '▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Draws `stats` in a box in the top-left corner: a row of bars per channel, each scaled to its
+/// fullest bin, and the dominant colors as swatches underneath.
+pub fn push_histogram(out: &mut String, stats: &ColorStats) {
+    const CHANNELS: [(&str, u8); 3] = [("R", 91), ("G", 92), ("B", 94)];
+    // A label, a space and a column per bin.
+    let inner = BINS + 2;
+    write!(out, "\x1b[1;1H\x1b[0m┌{}┐", "─".repeat(inner + 2)).unwrap();
+    let mut row = 2;
+    for (i, bins) in stats.channels.iter().enumerate() {
+        let (label, color) = if stats.channels.len() == 1 { ("Y", 97) } else { CHANNELS[i] };
+        let max = bins.iter().copied().max().unwrap_or(0).max(1) as usize;
+        let bars: String = bins.iter().map(|&n| BARS[(n as usize * (BARS.len() - 1)).div_ceil(max)]).collect();
+        write!(out, "\x1b[{};1H│ {} \x1b[{}m{}\x1b[0m │", row, label, color, bars).unwrap();
+        row += 1;
+    }
+    if !stats.palette.is_empty() {
+        let swatches: Vec<String> = stats.palette.iter().map(|(r, g, b)| format!("\x1b[38;2;{};{};{}m██\x1b[0m", r, g, b)).collect();
+        let pad = inner.saturating_sub(stats.palette.len() * 3 - 1);
+        write!(out, "\x1b[{};1H│ {}{} │", row, swatches.join(" "), " ".repeat(pad)).unwrap();
+        row += 1;
+    }
+    write!(out, "\x1b[{};1H└{}┘", row, "─".repeat(inner + 2)).unwrap();
+}
+
+/// Draws `text` as a reverse-video status line on terminal row `row` (1-based), over the frame.
+pub fn push_overlay(out: &mut String, text: &str, row: u16) {
+    write!(out, "\x1b[{};1H\x1b[0m\x1b[7m {} \x1b[0m", row.max(1), text).unwrap();
+}
+
+/// Draws subtitle `lines` centered, white on black, with the last one on terminal row `bottom` (1-based).
+pub fn push_subtitle(out: &mut String, lines: &[String], term_w: u16, bottom: u16) {
+    let top = (bottom as usize + 1).saturating_sub(lines.len()).max(1);
+    for (i, line) in lines.iter().enumerate() {
+        let left = (term_w as usize).saturating_sub(line.width() + 2) / 2 + 1;
+        write!(out, "\x1b[{};{}H\x1b[0m\x1b[97;40m {} \x1b[0m", top + i, left, line).unwrap();
+    }
+}
+
+fn ascii_for(r: u8, g: u8, b: u8, ramp: &[char]) -> char {
+    glyph_for(luminance(r, g, b), ramp)
+}
+
+/// CompactAscii's glyph for a cell's two stacked pixels: their average brightness.
+fn pair_glyph(top: u8, bottom: u8, ramp: &[char]) -> char {
+    glyph_for(((top as u16 + bottom as u16) / 2) as u8, ramp)
+}
+
+fn glyph_for(brightness: u8, ramp: &[char]) -> char {
+    let char_idx = (brightness as usize * (ramp.len() - 1)) / 255;
+    ramp[char_idx]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Grayscale ramp from black (top-left) to white (bottom-right), in `rgb24` layout.
+    fn gradient_fixture(width: u32, height: u32) -> Vec<u8> {
+        let max = (width + height - 2).max(1);
+        let mut buf = Vec::with_capacity((width * height * 3) as usize);
+        for y in 0..height {
+            for x in 0..width {
+                let v = ((x + y) * 255 / max) as u8;
+                buf.extend_from_slice(&[v, v, v]);
+            }
+        }
+        buf
+    }
+
+    #[test]
+    fn pixel_art_4x4_gradient() {
+        let frame = gradient_fixture(4, 4);
+        let mut out = String::new();
+        render_frame(&mut out, &frame, 4, 4, &RenderOptions::new(RenderMode::PixelArt, 4, 2)).unwrap();
+
+        let expected = concat!(
+            "\x1b[H",
+            "\x1b[38;2;0;0;0m\x1b[48;2;42;42;42m▀",
+            "\x1b[38;2;42;42;42m\x1b[48;2;85;85;85m▀",
+            "\x1b[38;2;85;85;85m\x1b[48;2;127;127;127m▀",
+            "\x1b[38;2;127;127;127m\x1b[48;2;170;170;170m▀",
+            "\x1b[0m\r\n",
+            "\x1b[38;2;85;85;85m\x1b[48;2;127;127;127m▀",
+            "\x1b[38;2;127;127;127m\x1b[48;2;170;170;170m▀",
+            "\x1b[38;2;170;170;170m\x1b[48;2;212;212;212m▀",
+            "\x1b[38;2;212;212;212m\x1b[48;2;255;255;255m▀",
+            "\x1b[0m\r\n",
+        );
+        assert_eq!(out, expected);
+    }
+
+    #[test]
+    fn ascii_art_4x2_gradient() {
+        let frame = gradient_fixture(4, 2);
+        let mut out = String::new();
+        render_frame(&mut out, &frame, 4, 2, &RenderOptions::new(RenderMode::AsciiArt, 4, 2)).unwrap();
+
+        let expected = concat!(
+            "\x1b[H",
+            "\x1b[49m\x1b[38;2;0;0;0m ",
+            "\x1b[38;2;63;63;63m:",
+            "\x1b[38;2;127;127;127m=",
+            "\x1b[38;2;191;191;191m*",
+            "\x1b[0m\r\n",
+            "\x1b[49m\x1b[38;2;63;63;63m:",
+            "\x1b[38;2;127;127;127m=",
+            "\x1b[38;2;191;191;191m*",
+            "\x1b[38;2;255;255;255m@",
+            "\x1b[0m\r\n",
+        );
+        assert_eq!(out, expected);
+    }
+
+    #[test]
+    fn compact_ascii_averages_two_rows_per_glyph() {
+        let frame = gradient_fixture(4, 4);
+        let mut out = String::new();
+        render_frame(&mut out, &frame, 4, 4, &RenderOptions::new(RenderMode::CompactAscii, 4, 2)).unwrap();
+
+        // Glyphs from the mean of each stacked pair, colored by the top pixel.
+        let expected = concat!(
+            "\x1b[H",
+            "\x1b[49m\x1b[38;2;0;0;0m ",
+            "\x1b[38;2;42;42;42m:",
+            "\x1b[38;2;85;85;85m-",
+            "\x1b[38;2;127;127;127m+",
+            "\x1b[0m\r\n",
+            "\x1b[49m\x1b[38;2;85;85;85m-",
+            "\x1b[38;2;127;127;127m+",
+            "\x1b[38;2;170;170;170m*",
+            "\x1b[38;2;212;212;212m%",
+            "\x1b[0m\r\n",
+        );
+        assert_eq!(out, expected);
+        assert_eq!(target_size(RenderMode::CompactAscii, Fit::Contain, 1920, 1080, 80, 24), (80, 44));
+        assert_eq!(frame_cells(RenderMode::CompactAscii, 80, 44, 80, 24), (80, 22));
+    }
+
+    #[test]
+    fn blocks_4x2_gradient() {
+        let frame = gradient_fixture(4, 2);
+        let mut out = String::new();
+        render_frame(&mut out, &frame, 4, 2, &RenderOptions::new(RenderMode::Blocks, 4, 2)).unwrap();
+
+        let expected = concat!(
+            "\x1b[H",
+            "\x1b[48;2;0;0;0m ",
+            "\x1b[48;2;63;63;63m ",
+            "\x1b[48;2;127;127;127m ",
+            "\x1b[48;2;191;191;191m ",
+            "\x1b[0m\r\n",
+            "\x1b[48;2;63;63;63m ",
+            "\x1b[48;2;127;127;127m ",
+            "\x1b[48;2;191;191;191m ",
+            "\x1b[48;2;255;255;255m ",
+            "\x1b[0m\r\n",
+        );
+        assert_eq!(out, expected);
+    }
+
+    #[test]
+    fn mono_ascii_sets_color_once() {
+        let frame = gradient_fixture(4, 2);
+        let mut out = String::new();
+        let opts = RenderOptions { ascii_color: AsciiColor::GREEN, ..RenderOptions::new(RenderMode::AsciiArt, 4, 2) };
+        render_frame(&mut out, &frame, 4, 2, &opts).unwrap();
+        assert_eq!(out, "\x1b[H\x1b[0m\x1b[38;2;0;255;70m\x1b[49m :=*\r\n\x1b[49m:=*@\r\n\x1b[0m");
+    }
+
+    #[test]
+    fn plain_has_no_color_codes() {
+        let rgb = gradient_fixture(4, 2);
+        let gray: Vec<u8> = rgb.chunks_exact(3).map(|px| px[0]).collect();
+        let opts = RenderOptions::new(RenderMode::Plain, 6, 2);
+        assert_eq!(PixelFormat::for_output(RenderMode::Plain, AsciiColor::Original), PixelFormat::Gray);
+        let (mut from_rgb, mut from_gray) = (String::new(), String::new());
+        render_frame(&mut from_rgb, &rgb, 4, 2, &opts).unwrap();
+        render_frame(&mut from_gray, &gray, 4, 2, &RenderOptions { format: PixelFormat::Gray, ..opts }).unwrap();
+        // Centered by plain spaces, and nothing else around the glyphs.
+        assert_eq!(from_rgb, "\x1b[H  :=*\r\n :=*@\r\n");
+        assert_eq!(from_rgb, from_gray);
+    }
+
+    #[test]
+    fn upscale_scaler_only_applies_when_enlarging() {
+        let scaling = Scaling { scaler: Scaler::Area, upscale: Some(Scaler::Neighbor), source: (320, 240), fill: false };
+        assert_eq!(scaling.filter(160, 120), "scale=160:120:flags=area");
+        assert_eq!(scaling.filter(400, 200), "scale=400:200:flags=neighbor");
+        assert_eq!(Scaling::default().filter(640, 480), "scale=640:480");
+        assert_eq!(Scaling { upscale: Some(Scaler::Neighbor), ..Scaling::default() }.filter(640, 480), "scale=640:480");
+        assert_eq!(Scaler::parse("Nearest"), Some(Scaler::Neighbor));
+    }
+
+    #[test]
+    fn frames_can_be_moved_into_a_tile() {
+        let mut out = String::new();
+        push_at(&mut out, "\x1b[H\r\n ab\r\n cd\r\n", 10, 2);
+        assert_eq!(out, "\x1b[4;11H ab\x1b[5;11H cd\x1b[0m");
+    }
+
+    #[test]
+    fn clipboard_text_has_a_line_per_row_with_or_without_colors() {
+        // 2x4 pixels: black over white in each column.
+        let frame: Vec<u8> = [[0, 0, 0], [255, 255, 255], [0, 0, 0], [255, 255, 255]].iter().flat_map(|px| px.repeat(2)).collect();
+        let opts = RenderOptions { shift: (3, 1), ..RenderOptions::new(RenderMode::PixelArt, 80, 24) };
+        // Plain text can't show half blocks, so pairs of pixels become CompactAscii glyphs.
+        let plain = clipboard_text(&frame, 2, 4, &opts, false).unwrap();
+        assert_eq!(plain.lines().count(), 2);
+        assert!(!plain.contains('\x1b') && !plain.contains('▀'));
+        let colored = clipboard_text(&frame, 2, 4, &opts, true).unwrap();
+        assert_eq!(colored.lines().count(), 2);
+        assert!(colored.lines().all(|line| line.ends_with("\x1b[0m") && line.matches('▀').count() == 2));
+        // No centering or cursor moves: the art starts at the first column.
+        assert!(!colored.contains("\x1b[H") && !colored.starts_with(' '));
+        assert_eq!(strip_escapes("\x1b[38;2;1;2;3m#\x1b[0m ."), "# .");
+    }
+
+    #[test]
+    fn plain_text_is_bare_lines() {
+        let gray = [255, 0, 128, 0, 0, 0];
+        assert_eq!(plain_text(&gray, 3, 2, &[' ', '+', '@']), "@ +\n\n");
+    }
+
+    #[test]
+    fn gray_frames_render_like_mono_rgb() {
+        let rgb = gradient_fixture(4, 4);
+        let gray: Vec<u8> = rgb.chunks_exact(3).map(|px| px[0]).collect();
+        for mode in [RenderMode::AsciiArt, RenderMode::CompactAscii] {
+            let opts = RenderOptions { ascii_color: AsciiColor::AMBER, ..RenderOptions::new(mode, 6, 5) };
+            assert_eq!(PixelFormat::for_output(mode, opts.ascii_color), PixelFormat::Gray);
+            let (mut from_rgb, mut from_gray) = (String::new(), String::new());
+            render_frame(&mut from_rgb, &rgb, 4, 4, &opts).unwrap();
+            render_frame(&mut from_gray, &gray, 4, 4, &RenderOptions { format: PixelFormat::Gray, ..opts }).unwrap();
+            assert_eq!(from_rgb, from_gray);
+        }
+        assert_eq!(PixelFormat::for_output(RenderMode::AsciiArt, AsciiColor::Original), PixelFormat::Rgb24);
+        assert!(render_frame(&mut String::new(), &[0; 15], 4, 4, &RenderOptions { format: PixelFormat::Gray, ..RenderOptions::new(RenderMode::AsciiArt, 4, 4) }).is_none());
+    }
+
+    #[test]
+    fn ascii_rows_start_with_default_background() {
+        let frame = gradient_fixture(4, 3);
+        for ascii_color in [AsciiColor::Original, AsciiColor::WHITE] {
+            let mut out = String::new();
+            let opts = RenderOptions { ascii_color, ..RenderOptions::new(RenderMode::AsciiArt, 8, 3) };
+            render_frame(&mut out, &frame, 4, 3, &opts).unwrap();
+            let rows: Vec<&str> = out.trim_end_matches("\x1b[0m").split("\r\n").filter(|r| !r.is_empty()).collect();
+            assert_eq!(rows.len(), 3);
+            for row in rows {
+                assert!(row.contains("\x1b[49m"), "{:?}", row);
+                assert!(!row.contains("\x1b[48;"));
+            }
+        }
+    }
+
+    #[test]
+    fn ascii_color_parses_presets_and_hex() {
+        assert_eq!(AsciiColor::parse("Amber"), Some(AsciiColor::AMBER));
+        assert_eq!(AsciiColor::parse("#10a0FF"), Some(AsciiColor::Mono(0x10, 0xa0, 0xff)));
+        assert_eq!(AsciiColor::parse("#12345"), None);
+        assert_eq!(AsciiColor::parse("a€bc"), None);
+        assert_eq!(AsciiColor::parse("#+1+2+3"), None);
+    }
+
+    #[test]
+    fn guides_land_inside_the_centered_frame() {
+        let mut out = String::new();
+        push_guides(&mut out, 6, 6, &RenderOptions::new(RenderMode::AsciiArt, 10, 8));
+        // 6x6 frame centered in 10x8: offset (2, 1), center cell (3, 3) -> 1-based (5, 6).
+        assert!(out.contains("\x1b[5;6H+"));
+        assert!(out.contains("\x1b[2;5H│"));
+        assert!(out.contains("\x1b[4;5H┼"));
+    }
+
+    #[test]
+    fn border_surrounds_the_centered_frame_when_there_is_room() {
+        let mut out = String::new();
+        push_border(&mut out, 4, 2, &RenderOptions::new(RenderMode::AsciiArt, 8, 6), DEFAULT_BORDER_COLOR);
+        // 4x2 cells centered in 8x6: offset (2, 2), so the box spans columns 2-7 and rows 2-5.
+        assert!(out.contains("\x1b[2;2H╭────╮"));
+        assert!(out.contains("\x1b[3;2H│\x1b[3;7H│"));
+        assert!(out.contains("\x1b[5;2H╰────╯"));
+
+        let mut edge = String::new();
+        push_border(&mut edge, 8, 2, &RenderOptions::new(RenderMode::AsciiArt, 8, 6), DEFAULT_BORDER_COLOR);
+        assert!(edge.is_empty());
+    }
+
+    #[test]
+    fn crop_is_validated_against_the_source() {
+        let corner = Crop::parse("640:360:1280:720").unwrap();
+        assert_eq!(corner.filter(), "crop=640:360:1280:720");
+        assert!(corner.fits(1920, 1080));
+        assert!(!corner.fits(1280, 720));
+        let centered = Crop::parse("800:600").unwrap();
+        assert_eq!(centered.filter(), "crop=800:600");
+        assert!(!centered.fits(1920, 480));
+        assert_eq!(Crop::parse("0:100"), None);
+        assert_eq!(Crop::parse("640:360:10"), None);
+        assert_eq!(Crop::parse("640x360"), None);
+    }
+
+    #[test]
+    fn backdrop_fills_every_margin_cell_and_plain_ignores_it() {
+        let backdrop = Backdrop::parse("#000000:#0000FF").unwrap();
+        assert_eq!(backdrop.color_at(0, 6), (0, 0, 0));
+        assert_eq!(backdrop.color_at(5, 6), (0, 0, 255));
+        assert_eq!(Backdrop::parse("green"), Some(Backdrop::solid((0, 255, 70))));
+        assert_eq!(Backdrop::parse("original:green"), None);
+
+        // 4x2 cells centered in 8x6: two rows above and below, two columns either side.
+        let frame = vec![255u8; 4 * 2 * 3];
+        let opts = RenderOptions { backdrop: Some(Backdrop::solid((1, 2, 3))), ..RenderOptions::new(RenderMode::AsciiArt, 8, 6) };
+        let mut out = String::new();
+        render_frame(&mut out, &frame, 4, 2, &opts).unwrap();
+        let fill = |cols: usize| format!("\x1b[48;2;1;2;3m{}\x1b[49m", " ".repeat(cols));
+        assert_eq!(out.matches(&fill(8)).count(), 4);
+        assert_eq!(out.matches(&fill(2)).count(), 4);
+        assert!(out.contains("\x1b[6;1H"));
+
+        let plain = RenderOptions { mode: RenderMode::Plain, ..opts };
+        render_frame(&mut out, &frame, 4, 2, &plain).unwrap();
+        assert!(!out.contains("48;2;1;2;3"));
+    }
+
+    #[test]
+    fn color_depth_maps_pixels_to_the_nearest_palette_color() {
+        assert_eq!(nearest_ansi16((200, 10, 10)), 1);
+        assert_eq!(nearest_ansi16((250, 250, 90)), 11);
+        assert_eq!(nearest_ansi16((120, 130, 125)), 8);
+        assert_eq!(nearest_ansi256((255, 0, 0)), 196);
+        assert_eq!(nearest_ansi256((128, 128, 128)), 244);
+
+        // Top pixels red and bright white, bottoms blue and black.
+        let frame = [255, 0, 0, 255, 255, 255, 0, 0, 230, 0, 0, 0];
+        let mut out = String::new();
+        let opts = RenderOptions { depth: ColorDepth::Ansi16, ..RenderOptions::new(RenderMode::PixelArt, 2, 1) };
+        render_frame(&mut out, &frame, 2, 2, &opts).unwrap();
+        assert!(out.contains("\x1b[91m\x1b[44m▀\x1b[97m\x1b[40m▀"), "{:?}", out);
+        assert!(!out.contains("38;2") && !out.contains("48;2"));
+
+        render_frame(&mut out, &frame, 2, 2, &RenderOptions { depth: ColorDepth::Ansi256, ..opts }).unwrap();
+        assert!(out.contains("\x1b[38;5;196m\x1b[48;5;20m▀"), "{:?}", out);
+        assert_eq!(ColorDepth::parse("TrueColor"), Some(ColorDepth::TrueColor));
+        assert_eq!(ColorDepth::parse("8"), None);
+    }
+
+    #[test]
+    fn inspector_reports_both_pixels_of_a_half_block_cell() {
+        let frame = gradient_fixture(4, 4);
+        let mut out = String::new();
+        push_inspector(&mut out, &frame, 4, 4, &RenderOptions::new(RenderMode::PixelArt, 40, 10), (1, 1));
+        assert!(out.contains("上 像素 (1, 2)"));
+        assert!(out.contains("下 像素 (1, 3)"));
+        // Cursor lands on the centered cell: offsets (18, 4) plus the cell, 1-based.
+        assert!(out.contains("\x1b[6;20H"));
+    }
+
+    #[test]
+    fn repeated_colors_are_not_re_emitted() {
+        let frame = vec![10u8; 4 * 2 * 3];
+        let mut out = String::new();
+        render_frame(&mut out, &frame, 4, 2, &RenderOptions::new(RenderMode::AsciiArt, 4, 2)).unwrap();
+        assert_eq!(out.matches("\x1b[38;2;").count(), 2);
+    }
+
+    #[test]
+    fn frame_is_centered_in_larger_terminal() {
+        let frame = gradient_fixture(4, 4);
+        let mut out = String::new();
+        render_frame(&mut out, &frame, 4, 4, &RenderOptions::new(RenderMode::PixelArt, 10, 6)).unwrap();
+
+        // 3 columns of padding and 2 blank rows above a 4x2 cell frame.
+        assert!(out.starts_with("\x1b[H\r\n\r\n\x1b[0m   \x1b[38;2;"));
+        assert_eq!(out.matches("\x1b[0m   ").count(), 2);
+    }
+
+    #[test]
+    fn portrait_video_fills_terminal_height() {
+        assert!(is_portrait(1080, 1920));
+        // 9:16 in a wide 200x50 terminal: every row is used and the width keeps the aspect ratio.
+        let (w, h) = target_size(RenderMode::PixelArt, Fit::Contain, 1080, 1920, 200, 50);
+        assert_eq!((w, h), (56, 100));
+        assert_eq!(frame_cells(RenderMode::PixelArt, w, h, 200, 50), (56, 50));
+        assert_eq!(target_size(RenderMode::AsciiArt, Fit::Contain, 1080, 1920, 200, 50), (56, 50));
+    }
+
+    #[test]
+    fn target_size_keeps_aspect_and_stays_even() {
+        // 16:9 in a wide terminal: the width limits neither layout, so the height does.
+        assert_eq!(target_size(RenderMode::PixelArt, Fit::Contain, 1920, 1080, 200, 50), (176, 100));
+        assert_eq!(target_size(RenderMode::AsciiArt, Fit::Contain, 1920, 1080, 200, 50), (176, 50));
+        // 16:9 in a narrow terminal: full width, letterboxed.
+        assert_eq!(target_size(RenderMode::PixelArt, Fit::Contain, 1920, 1080, 80, 50), (80, 44));
+        assert_eq!(target_size(RenderMode::Blocks, Fit::Contain, 1920, 1080, 80, 50), (80, 22));
+        // Square: two pixels per cell row in PixelArt, half-height cells in AsciiArt.
+        assert_eq!(target_size(RenderMode::PixelArt, Fit::Contain, 1000, 1000, 80, 24), (48, 48));
+        assert_eq!(target_size(RenderMode::AsciiArt, Fit::Contain, 1000, 1000, 80, 24), (48, 24));
+
+        // Degenerate terminals never produce an odd or empty frame, whatever the mode.
+        for mode in RenderMode::ALL {
+            for (term_w, term_h) in [(0, 0), (1, 1), (3, 1), (1, 3), (5, 2)] {
+                for (w, h) in [(1920, 1080), (1080, 1920), (1, 1)] {
+                    let (tw, th) = target_size(mode, Fit::Contain, w, h, term_w, term_h);
+                    assert!(tw >= 2 && th >= 2 && tw % 2 == 0 && th % 2 == 0, "{:?} {}x{} in {}x{}: {}x{}", mode, w, h, term_w, term_h, tw, th);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn inverted_ramp_reverses_default_and_custom_ramps() {
+        let inverted = resolve_ramp(None, true);
+        assert!(inverted.iter().eq(DEFAULT_RAMP.iter().rev()));
+        assert_eq!(resolve_ramp(Some(&['a', 'b', 'c']), true), ['c', 'b', 'a']);
+        assert_eq!(resolve_ramp(Some(&['a', 'b']), false), ['a', 'b']);
+        // Inverting the same ramp again hands back the first copy.
+        assert!(std::ptr::eq(resolve_ramp(Some(&['x', 'y']), true), resolve_ramp(Some(&['x', 'y']), true)));
+    }
+
+    #[test]
+    fn stretch_fills_terminal_and_ramp_picks_glyphs() {
+        assert_eq!(target_size(RenderMode::PixelArt, Fit::Stretch, 1080, 1920, 200, 50), (200, 100));
+        assert_eq!(target_size(RenderMode::Blocks, Fit::Stretch, 1920, 1080, 80, 24), (80, 24));
+        // An ultrawide terminal: contain fits the height, width fills it and crops the rows.
+        assert_eq!(target_size(RenderMode::PixelArt, Fit::Contain, 1920, 1080, 300, 40), (142, 80));
+        assert_eq!(target_size(RenderMode::PixelArt, Fit::Width, 1920, 1080, 300, 40), (300, 80));
+        assert_eq!(target_size(RenderMode::AsciiArt, Fit::Height, 1920, 1080, 300, 40), (142, 40));
+        // A portrait video filling a wide terminal's height is still narrower than it.
+        assert_eq!(target_size(RenderMode::AsciiArt, Fit::Height, 1080, 1920, 80, 24), (26, 24));
+        assert_eq!(target_size(RenderMode::AsciiArt, Fit::Width, 1080, 1920, 80, 24), (80, 24));
+        let fill = Scaling { fill: true, ..Scaling::default() };
+        assert_eq!(fill.filter(300, 80), "scale=300:80:force_original_aspect_ratio=increase,crop=300:80");
+
+        let frame = gradient_fixture(4, 1);
+        let opts = RenderOptions { ramp: &['a', 'b'], ascii_color: AsciiColor::WHITE, ..RenderOptions::new(RenderMode::AsciiArt, 4, 1) };
+        let mut out = String::new();
+        render_frame(&mut out, &frame, 4, 1, &opts).unwrap();
+        assert!(out.contains("aaab"), "{:?}", out);
+    }
+
+    #[test]
+    fn offsets_for_given_terminal() {
+        let pixel = RenderOptions::new(RenderMode::PixelArt, 100, 30);
+        let ascii = RenderOptions::new(RenderMode::AsciiArt, 100, 30);
+        assert_eq!(frame_offsets(&pixel, 80, 40), (10, 5));
+        assert_eq!(frame_offsets(&ascii, 80, 20), (10, 5));
+        assert_eq!(frame_offsets(&ascii, 120, 40), (0, 0));
+
+        // Moved frames stop at the terminal's edges.
+        let shifted = RenderOptions { shift: (-3, 100), ..pixel };
+        assert_eq!(frame_offsets(&shifted, 80, 40), (7, 10));
+        assert_eq!(nudge(&shifted, 80, 40, (-20, 0)), (-10, 5));
+    }
+
+    #[test]
+    fn viewport_copies_the_window_and_clamps_it() {
+        // 4x3 gray frame numbered 0..12, row by row.
+        let frame: Vec<u8> = (0..12).collect();
+        let mut out = Vec::new();
+        viewport(&frame, (4, 3), 1, (1, 1), (2, 2), &mut out);
+        assert_eq!(out, [5, 6, 9, 10]);
+        viewport(&frame, (4, 3), 1, (9, 9), (2, 2), &mut out);
+        assert_eq!(out, [6, 7, 10, 11]);
+
+        let mut marks = String::new();
+        push_scroll_marks(&mut marks, 80, 24, (false, true, false, true));
+        assert!(marks.contains("\x1b[13;80H") && marks.contains('▶'));
+        assert!(marks.contains("\x1b[23;41H") && marks.contains('▼'));
+        assert!(!marks.contains('◀') && !marks.contains('▲'));
+    }
+
+    #[test]
+    fn short_buffer_is_rejected() {
+        let mut out = String::new();
+        assert!(render_frame(&mut out, &[0u8; 10], 4, 4, &RenderOptions::new(RenderMode::PixelArt, 4, 2)).is_none());
+    }
+}
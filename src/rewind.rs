@@ -0,0 +1,77 @@
+use std::collections::VecDeque;
+
+/// Frames kept for stepping back when `--rewind-frames` isn't given.
+pub const DEFAULT_FRAMES: usize = 60;
+/// Most frames `--rewind-frames` and the setting may ask for; 10000 frames of 320x90 rgb24
+/// already take about 860 MB.
+pub const MAX_FRAMES: usize = 10_000;
+/// Positions closer than this are the same frame.
+const EPSILON: f64 = 1e-6;
+
+/// The last frames drawn during playback with their positions, so stepping back through them in
+/// the inspector doesn't have to restart ffmpeg. Holds up to `capacity` frames of the decode size
+/// each: 60 frames of 320x90 rgb24 take about 5 MB.
+#[derive(Debug, Default)]
+pub struct RecentFrames {
+    frames: VecDeque<(f64, Vec<u8>)>,
+    capacity: usize,
+}
+
+impl RecentFrames {
+    pub fn new(capacity: usize) -> Self {
+        // `push` keeps the length within `capacity`; nothing is reserved for frames never drawn.
+        Self { frames: VecDeque::new(), capacity }
+    }
+
+    /// Keeps a copy of `frame`, shown at `position` seconds, dropping the oldest when full.
+    pub fn push(&mut self, position: f64, frame: &[u8]) {
+        if self.capacity == 0 {
+            return;
+        }
+        // Reuse the oldest frame's allocation rather than allocating one per frame.
+        let mut buffer = if self.frames.len() == self.capacity {
+            self.frames.pop_front().map(|(_, b)| b).unwrap_or_default()
+        } else {
+            Vec::new()
+        };
+        buffer.clear();
+        buffer.extend_from_slice(frame);
+        self.frames.push_back((position, buffer));
+    }
+
+    /// Forgets every frame, for when the decode size or the filters change.
+    pub fn clear(&mut self) {
+        self.frames.clear();
+    }
+
+    /// The latest frame shown before `position`.
+    pub fn before(&self, position: f64) -> Option<(f64, &[u8])> {
+        self.frames.iter().rev().find(|(p, _)| *p < position - EPSILON).map(|(p, f)| (*p, &f[..]))
+    }
+
+    /// The earliest frame shown after `position`.
+    pub fn after(&self, position: f64) -> Option<(f64, &[u8])> {
+        self.frames.iter().find(|(p, _)| *p > position + EPSILON).map(|(p, f)| (*p, &f[..]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_the_latest_frames_in_order() {
+        let mut recent = RecentFrames::new(3);
+        for i in 0..5 {
+            recent.push(i as f64, &[i as u8; 4]);
+        }
+        assert_eq!(recent.before(4.0), Some((3.0, &[3u8; 4][..])));
+        assert_eq!(recent.before(2.0), None);
+        assert_eq!(recent.after(2.0), Some((3.0, &[3u8; 4][..])));
+        assert_eq!(recent.after(4.0), None);
+
+        let mut off = RecentFrames::new(0);
+        off.push(0.0, &[1]);
+        assert_eq!(off.before(1.0), None);
+    }
+}
@@ -0,0 +1,152 @@
+use regex::Regex;
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+pub const IMAGE_EXTENSIONS: [&str; 5] = ["png", "jpg", "jpeg", "bmp", "webp"];
+
+/// Frame rate used for image sequences until the user picks another one.
+pub const DEFAULT_FPS: u32 = 24;
+
+/// Fewer numbered images than this are treated as ordinary pictures, not a video.
+const MIN_FRAMES: usize = 2;
+
+fn numbered_name() -> Regex {
+    Regex::new(r"^(.*?)(\d+)\.([A-Za-z]+)$").unwrap()
+}
+
+fn is_image(ext: &str) -> bool {
+    IMAGE_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str())
+}
+
+/// Whether `path` is an ffmpeg image2 pattern such as `shots/frame%04d.png`.
+pub fn is_sequence(path: &Path) -> bool {
+    path.file_name()
+        .map(|n| n.to_string_lossy().contains('%'))
+        .unwrap_or(false)
+}
+
+/// Groups numbered images (`frame0001.png`, `frame0002.png`, ...) into one pattern path each.
+///
+/// Names are grouped by directory, prefix and extension; a group becomes `prefix%0Nd.ext`
+/// when every number has the same width, or `prefix%d.ext` when none is zero-padded.
+pub fn detect(files: &[PathBuf]) -> Vec<PathBuf> {
+    let re = numbered_name();
+    let mut groups: BTreeMap<(PathBuf, String, String), Vec<String>> = BTreeMap::new();
+
+    for path in files {
+        let Some(name) = path.file_name().map(|n| n.to_string_lossy().to_string()) else { continue };
+        let Some(caps) = re.captures(&name) else { continue };
+        if !is_image(&caps[3]) {
+            continue;
+        }
+        let dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+        groups
+            .entry((dir, caps[1].to_string(), caps[3].to_string()))
+            .or_default()
+            .push(caps[2].to_string());
+    }
+
+    groups
+        .into_iter()
+        .filter(|(_, numbers)| numbers.len() >= MIN_FRAMES)
+        .filter_map(|((dir, prefix, ext), numbers)| {
+            let width = numbers[0].len();
+            let spec = if numbers.iter().all(|n| n.len() == width) {
+                format!("%0{}d", width)
+            } else if numbers.iter().all(|n| !n.starts_with('0') || n.len() == 1) {
+                "%d".to_string()
+            } else {
+                return None;
+            };
+            Some(dir.join(format!("{}{}.{}", prefix, spec, ext)))
+        })
+        .collect()
+}
+
+/// First frame number and length of the contiguous run ffmpeg will play for `pattern`.
+pub fn frames(pattern: &Path) -> Option<(u32, u32)> {
+    let name = pattern.file_name()?.to_string_lossy().to_string();
+    let (prefix, rest) = name.split_once('%')?;
+    let (spec, suffix) = rest.split_once('d')?;
+    let width: usize = spec.trim_start_matches('0').parse().unwrap_or(0);
+
+    let dir = match pattern.parent() {
+        Some(d) if !d.as_os_str().is_empty() => d.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+    let mut numbers: Vec<u32> = std::fs::read_dir(&dir)
+        .ok()?
+        .flatten()
+        .filter_map(|e| {
+            let n = e.file_name().to_string_lossy().to_string();
+            let digits = n.strip_prefix(prefix)?.strip_suffix(suffix)?;
+            if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+                return None;
+            }
+            if width > 0 && digits.len() != width {
+                return None;
+            }
+            digits.parse().ok()
+        })
+        .collect();
+    numbers.sort_unstable();
+
+    let start = *numbers.first()?;
+    let count = numbers
+        .iter()
+        .enumerate()
+        .take_while(|(i, &n)| n == start + *i as u32)
+        .count() as u32;
+    Some((start, count))
+}
+
+/// Extra ffmpeg/ffprobe input options needed to read `path`, placed before `-i`.
+pub fn input_args(path: &Path, fps: u32) -> Vec<String> {
+    if !is_sequence(path) {
+        return Vec::new();
+    }
+    let mut args = vec!["-framerate".to_string(), fps.to_string()];
+    if let Some((start, _)) = frames(path) {
+        args.push("-start_number".to_string());
+        args.push(start.to_string());
+    }
+    args
+}
+
+/// List label for a pattern: `frame####.png` instead of `frame%04d.png`.
+pub fn display_name(pattern: &Path) -> String {
+    let name = pattern.file_name().unwrap_or_default().to_string_lossy().to_string();
+    let Some((prefix, rest)) = name.split_once('%') else { return name };
+    let Some((spec, suffix)) = rest.split_once('d') else { return name };
+    let width: usize = spec.trim_start_matches('0').parse().unwrap_or(1);
+    format!("{}{}{}", prefix, "#".repeat(width.max(1)), suffix)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paths(names: &[&str]) -> Vec<PathBuf> {
+        names.iter().map(PathBuf::from).collect()
+    }
+
+    #[test]
+    fn groups_padded_frames_into_one_pattern() {
+        let found = detect(&paths(&["shots/frame0001.png", "shots/frame0002.png", "shots/frame0003.png", "cover.png"]));
+        assert_eq!(found, paths(&["shots/frame%04d.png"]));
+        assert_eq!(display_name(&found[0]), "frame####.png");
+    }
+
+    #[test]
+    fn unpadded_numbers_use_plain_spec() {
+        let found = detect(&paths(&["img1.jpg", "img2.jpg", "img10.jpg"]));
+        assert_eq!(found, paths(&["img%d.jpg"]));
+    }
+
+    #[test]
+    fn single_images_and_non_images_are_ignored() {
+        assert!(detect(&paths(&["photo1.png", "clip1.mp4", "clip2.mp4"])).is_empty());
+    }
+}
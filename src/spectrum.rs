@@ -0,0 +1,154 @@
+use std::fmt::Write;
+
+/// Samples per FFT window; must be a power of two.
+pub const FFT_SIZE: usize = 2048;
+pub const SAMPLE_RATE: u32 = 44100;
+
+/// Lowest and highest frequencies shown, spread logarithmically across the bars.
+const MIN_FREQ: f32 = 40.0;
+const MAX_FREQ: f32 = 16000.0;
+/// Magnitudes (in dB) mapped onto an empty and a full bar.
+const FLOOR_DB: f32 = -70.0;
+const CEIL_DB: f32 = -10.0;
+/// How much of the previous bar height survives into the next frame when the level drops.
+const DECAY: f32 = 0.75;
+
+/// In-place iterative radix-2 FFT. `re.len()` must be a power of two.
+fn fft(re: &mut [f32], im: &mut [f32]) {
+    let n = re.len();
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            re.swap(i, j);
+            im.swap(i, j);
+        }
+    }
+
+    let mut len = 2;
+    while len <= n {
+        let angle = -2.0 * std::f32::consts::PI / len as f32;
+        for start in (0..n).step_by(len) {
+            for k in 0..len / 2 {
+                let (sin, cos) = (angle * k as f32).sin_cos();
+                let (a, b) = (start + k, start + k + len / 2);
+                let tr = re[b] * cos - im[b] * sin;
+                let ti = re[b] * sin + im[b] * cos;
+                re[b] = re[a] - tr;
+                im[b] = im[a] - ti;
+                re[a] += tr;
+                im[a] += ti;
+            }
+        }
+        len <<= 1;
+    }
+}
+
+/// Rolling analyser turning PCM chunks into smoothed bar heights in `0.0..=1.0`.
+pub struct Spectrum {
+    window: Vec<f32>,
+    bars: Vec<f32>,
+}
+
+impl Spectrum {
+    pub fn new(bar_count: usize) -> Self {
+        Self { window: vec![0.0; FFT_SIZE], bars: vec![0.0; bar_count.max(1)] }
+    }
+
+    pub fn bars(&self) -> &[f32] {
+        &self.bars
+    }
+
+    /// Pushes newly decoded mono samples and recomputes the bars from the latest window.
+    pub fn push(&mut self, samples: &[i16]) {
+        let keep = FFT_SIZE.saturating_sub(samples.len());
+        self.window.drain(..FFT_SIZE - keep);
+        self.window.extend(samples.iter().skip(samples.len().saturating_sub(FFT_SIZE)).map(|&s| s as f32 / 32768.0));
+
+        // Hann window to keep the bins from smearing into each other.
+        let mut re: Vec<f32> = self.window
+            .iter()
+            .enumerate()
+            .map(|(i, s)| s * (0.5 - 0.5 * (2.0 * std::f32::consts::PI * i as f32 / FFT_SIZE as f32).cos()))
+            .collect();
+        let mut im = vec![0.0; FFT_SIZE];
+        fft(&mut re, &mut im);
+
+        let bin_hz = SAMPLE_RATE as f32 / FFT_SIZE as f32;
+        let count = self.bars.len();
+        for (i, bar) in self.bars.iter_mut().enumerate() {
+            let lo = MIN_FREQ * (MAX_FREQ / MIN_FREQ).powf(i as f32 / count as f32);
+            let hi = MIN_FREQ * (MAX_FREQ / MIN_FREQ).powf((i + 1) as f32 / count as f32);
+            let lo_bin = ((lo / bin_hz) as usize).max(1);
+            let hi_bin = ((hi / bin_hz) as usize).max(lo_bin + 1).min(FFT_SIZE / 2);
+
+            let peak = (lo_bin..hi_bin)
+                .map(|b| (re[b] * re[b] + im[b] * im[b]).sqrt() / (FFT_SIZE as f32 / 4.0))
+                .fold(0.0f32, f32::max);
+            let db = 20.0 * peak.max(1e-9).log10();
+            let level = ((db - FLOOR_DB) / (CEIL_DB - FLOOR_DB)).clamp(0.0, 1.0);
+            *bar = level.max(*bar * DECAY);
+        }
+    }
+}
+
+/// Draws `bars` as vertical gradient columns filling a `term_w`x`term_h` screen.
+pub fn render_bars(out: &mut String, bars: &[f32], term_w: u16, term_h: u16) {
+    const PARTIAL: [char; 8] = [' ', '▁', '▂', '▃', '▄', '▅', '▆', '▇'];
+    let rows = term_h.max(1) as usize;
+    let cols = (term_w as usize).min(bars.len() * 2);
+    let offset_x = (term_w as usize).saturating_sub(cols) / 2;
+
+    out.clear();
+    out.push_str("\x1b[H\x1b[0m");
+    for row in 0..rows {
+        // Eighths of a cell filled from the bottom up to this row's top edge.
+        let row_base = (rows - 1 - row) * 8;
+        write!(out, "{:width$}", "", width = offset_x).unwrap();
+        for (i, &level) in bars.iter().enumerate().take(cols / 2) {
+            let t = i as f32 / bars.len().max(1) as f32;
+            let (r, g, b) = ((255.0 * t) as u8, (255.0 * (1.0 - t)) as u8, 255u8);
+            let filled = (level * (rows * 8) as f32) as usize;
+            let ch = if filled >= row_base + 8 {
+                '█'
+            } else if filled > row_base {
+                PARTIAL[filled - row_base]
+            } else {
+                ' '
+            };
+            write!(out, "\x1b[38;2;{};{};{}m{} ", r, g, b, ch).unwrap();
+        }
+        out.push_str("\x1b[0m");
+        if row + 1 < rows {
+            out.push_str("\r\n");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fft_finds_pure_tone() {
+        let n = 64;
+        let mut re: Vec<f32> = (0..n).map(|i| (2.0 * std::f32::consts::PI * 5.0 * i as f32 / n as f32).sin()).collect();
+        let mut im = vec![0.0; n];
+        fft(&mut re, &mut im);
+        let mags: Vec<f32> = (0..n / 2).map(|k| (re[k] * re[k] + im[k] * im[k]).sqrt()).collect();
+        let peak = mags.iter().enumerate().max_by(|a, b| a.1.total_cmp(b.1)).unwrap().0;
+        assert_eq!(peak, 5);
+    }
+
+    #[test]
+    fn silence_gives_empty_bars() {
+        let mut spectrum = Spectrum::new(8);
+        spectrum.push(&[0; 1470]);
+        assert!(spectrum.bars().iter().all(|&b| b == 0.0));
+    }
+}
@@ -0,0 +1,321 @@
+use anyhow::{Context, Result};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Directory holding the small state files (favorites, history, ...).
+///
+/// `%APPDATA%\Vodeo2ASCII` on Windows, `$XDG_CONFIG_HOME/vodeo2ascii` or `~/.config/vodeo2ascii` elsewhere.
+pub fn state_dir() -> Option<PathBuf> {
+    if let Ok(dir) = std::env::var("VODEO2ASCII_STATE_DIR") {
+        return Some(PathBuf::from(dir));
+    }
+    if cfg!(target_os = "windows") {
+        return std::env::var_os("APPDATA").map(|d| PathBuf::from(d).join("Vodeo2ASCII"));
+    }
+    if let Some(d) = std::env::var_os("XDG_CONFIG_HOME") {
+        return Some(PathBuf::from(d).join("vodeo2ascii"));
+    }
+    std::env::var_os("HOME").map(|d| PathBuf::from(d).join(".config").join("vodeo2ascii"))
+}
+
+/// Reads a state file as non-empty lines. A missing file is just an empty list.
+pub fn load_lines(name: &str) -> Vec<String> {
+    let Some(path) = state_dir().map(|d| d.join(name)) else { return Vec::new() };
+    fs::read_to_string(path)
+        .map(|s| s.lines().filter(|l| !l.is_empty()).map(str::to_string).collect())
+        .unwrap_or_default()
+}
+
+pub fn save_lines<I, S>(name: &str, lines: I) -> Result<()>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    let dir = state_dir().context("No config directory available")?;
+    fs::create_dir_all(&dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+
+    let mut content = String::new();
+    for line in lines {
+        content.push_str(line.as_ref());
+        content.push('\n');
+    }
+    let path = dir.join(name);
+    fs::write(&path, content).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Absolute form of `path` used as the key for everything persisted per file.
+pub fn absolute(path: &Path) -> PathBuf {
+    if crate::remote::is_remote(path) {
+        return path.to_path_buf();
+    }
+    std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+const FAVORITES_FILE: &str = "favorites.txt";
+
+/// Favorited files, matched by absolute path so they survive rescans.
+#[derive(Default)]
+pub struct Favorites {
+    paths: BTreeSet<PathBuf>,
+}
+
+impl Favorites {
+    pub fn load() -> Self {
+        Self { paths: load_lines(FAVORITES_FILE).into_iter().map(PathBuf::from).collect() }
+    }
+
+    pub fn contains(&self, path: &Path) -> bool {
+        self.paths.contains(path)
+    }
+
+    /// Flips the favorite state of `path` and persists the list. Returns the new state.
+    pub fn toggle(&mut self, path: &Path) -> Result<bool> {
+        let added = if self.paths.remove(path) {
+            false
+        } else {
+            self.paths.insert(path.to_path_buf());
+            true
+        };
+        save_lines(FAVORITES_FILE, self.paths.iter().map(|p| p.to_string_lossy()))?;
+        Ok(added)
+    }
+}
+
+const HISTORY_FILE: &str = "history.txt";
+const HISTORY_LEN: usize = 20;
+
+pub struct HistoryEntry {
+    pub path: PathBuf,
+    /// Last known playback position in seconds.
+    pub position: f64,
+}
+
+/// Recently played files, most recent first, one entry per path.
+#[derive(Default)]
+pub struct History {
+    entries: Vec<HistoryEntry>,
+}
+
+impl History {
+    pub fn load() -> Self {
+        let entries = load_lines(HISTORY_FILE)
+            .into_iter()
+            .filter_map(|line| {
+                let (pos, path) = line.split_once('\t')?;
+                Some(HistoryEntry { path: PathBuf::from(path), position: pos.parse().unwrap_or(0.0) })
+            })
+            .take(HISTORY_LEN)
+            .collect();
+        Self { entries }
+    }
+
+    pub fn entries(&self) -> &[HistoryEntry] {
+        &self.entries
+    }
+
+    pub fn position(&self, path: &Path) -> Option<f64> {
+        self.entries.iter().find(|e| e.path == path).map(|e| e.position)
+    }
+
+    /// Moves `path` to the front with the given position, dropping the oldest entries past the cap.
+    pub fn record(&mut self, path: &Path, position: f64) -> Result<()> {
+        self.entries.retain(|e| e.path != path);
+        self.entries.insert(0, HistoryEntry { path: path.to_path_buf(), position });
+        self.entries.truncate(HISTORY_LEN);
+        save_lines(
+            HISTORY_FILE,
+            self.entries.iter().map(|e| format!("{:.1}\t{}", e.position, e.path.display())),
+        )
+    }
+}
+
+const NOTES_FILE: &str = "notes.txt";
+
+/// Short user notes per file, keyed by absolute path so they survive rescans.
+///
+/// Stored as `note\tpath` lines with tabs, newlines and backslashes in the note escaped.
+#[derive(Default)]
+pub struct Notes {
+    notes: BTreeMap<PathBuf, String>,
+}
+
+impl Notes {
+    pub fn load() -> Self {
+        let notes = load_lines(NOTES_FILE)
+            .into_iter()
+            .filter_map(|line| {
+                let (note, path) = line.split_once('\t')?;
+                Some((PathBuf::from(path), unescape(note)))
+            })
+            .collect();
+        Self { notes }
+    }
+
+    pub fn get(&self, path: &Path) -> Option<&str> {
+        self.notes.get(path).map(String::as_str)
+    }
+
+    /// Sets the note for `path`; an empty note removes it.
+    pub fn set(&mut self, path: &Path, note: &str) -> Result<()> {
+        let note = note.trim();
+        if note.is_empty() {
+            self.notes.remove(path);
+        } else {
+            self.notes.insert(path.to_path_buf(), note.to_string());
+        }
+        save_lines(
+            NOTES_FILE,
+            self.notes.iter().map(|(path, note)| format!("{}\t{}", escape(note), path.display())),
+        )
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('\t', "\\t").replace('\n', "\\n")
+}
+
+fn unescape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('t') => out.push('\t'),
+            Some('n') => out.push('\n'),
+            Some(other) => out.push(other),
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+const INPUT_HISTORY_FILE: &str = "input_history.txt";
+const INPUT_HISTORY_LEN: usize = 20;
+
+/// Paths typed into the manual input popup, most recent first, without duplicates.
+#[derive(Default)]
+pub struct InputHistory {
+    entries: Vec<String>,
+}
+
+impl InputHistory {
+    pub fn load() -> Self {
+        let mut entries = load_lines(INPUT_HISTORY_FILE);
+        entries.truncate(INPUT_HISTORY_LEN);
+        Self { entries }
+    }
+
+    pub fn entries(&self) -> &[String] {
+        &self.entries
+    }
+
+    pub fn record(&mut self, input: &str) -> Result<()> {
+        self.entries.retain(|e| e != input);
+        self.entries.insert(0, input.to_string());
+        self.entries.truncate(INPUT_HISTORY_LEN);
+        save_lines(INPUT_HISTORY_FILE, &self.entries)
+    }
+}
+
+const RESUME_FILE: &str = "resume.txt";
+
+/// Where playback of each file was stopped early, cleared once it plays to the end.
+#[derive(Default)]
+pub struct ResumePoints {
+    points: BTreeMap<PathBuf, f64>,
+}
+
+impl ResumePoints {
+    pub fn load() -> Self {
+        let points = load_lines(RESUME_FILE)
+            .into_iter()
+            .filter_map(|line| {
+                let (pos, path) = line.split_once('\t')?;
+                Some((PathBuf::from(path), pos.parse().ok()?))
+            })
+            .collect();
+        Self { points }
+    }
+
+    pub fn get(&self, path: &Path) -> Option<f64> {
+        self.points.get(path).copied()
+    }
+
+    pub fn set(&mut self, path: &Path, position: f64) -> Result<()> {
+        self.points.insert(path.to_path_buf(), position);
+        self.save()
+    }
+
+    pub fn clear(&mut self, path: &Path) -> Result<()> {
+        if self.points.remove(path).is_some() {
+            self.save()?;
+        }
+        Ok(())
+    }
+
+    fn save(&self) -> Result<()> {
+        save_lines(
+            RESUME_FILE,
+            self.points.iter().map(|(path, pos)| format!("{:.1}\t{}", pos, path.display())),
+        )
+    }
+}
+
+const SETTINGS_FILE: &str = "settings.txt";
+
+/// Menu preferences kept between runs, as `key=value` lines.
+#[derive(Default)]
+pub struct Settings {
+    values: BTreeMap<String, String>,
+}
+
+impl Settings {
+    pub fn load() -> Self {
+        Self::from_lines(load_lines(SETTINGS_FILE))
+    }
+
+    /// Settings from `key=value` lines; lines without a `=` are ignored.
+    pub fn from_lines(lines: impl IntoIterator<Item = String>) -> Self {
+        let values = lines
+            .into_iter()
+            .filter_map(|line| {
+                let (key, value) = line.split_once('=')?;
+                Some((key.to_string(), value.to_string()))
+            })
+            .collect();
+        Self { values }
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.values.get(key).map(String::as_str)
+    }
+
+    /// A `true`/`false` setting, or `default` when it was never saved.
+    pub fn flag(&self, key: &str, default: bool) -> bool {
+        self.get(key).map_or(default, |v| v == "true")
+    }
+
+    pub fn set(&mut self, key: &str, value: impl ToString) -> Result<()> {
+        self.values.insert(key.to_string(), value.to_string());
+        save_lines(SETTINGS_FILE, self.values.iter().map(|(k, v)| format!("{}={}", k, v)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn note_escaping_round_trips() {
+        let note = "好看的一集\t12:30 \\ 片尾\n彩蛋";
+        let escaped = escape(note);
+        assert!(!escaped.contains('\t') && !escaped.contains('\n'));
+        assert_eq!(unescape(&escaped), note);
+    }
+}
@@ -0,0 +1,133 @@
+use anyhow::{Context, Result};
+use std::{
+    fs::OpenOptions,
+    io::Write,
+    path::Path,
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Frames per second written to the pipe when `--fps-cap` doesn't say otherwise.
+pub const DEFAULT_FPS: u32 = 30;
+
+/// Live copy of the rendered frames, written to a named pipe at a fixed cadence.
+///
+/// A background thread waits for a reader to open the pipe, then writes the latest frame on every
+/// tick, repeating it if playback hasn't produced a new one. When the reader goes away the thread
+/// goes back to waiting for the next one, so consumers can come and go without affecting playback.
+#[derive(Debug, Clone)]
+pub struct FrameStream {
+    latest: Arc<Mutex<Option<String>>>,
+}
+
+impl FrameStream {
+    /// Creates the FIFO at `path` if needed and starts streaming to it at `fps` frames per second.
+    pub fn start(path: &Path, fps: u32) -> Result<Self> {
+        prepare_fifo(path)?;
+        let stream = Self { latest: Arc::new(Mutex::new(None)) };
+        let latest = Arc::clone(&stream.latest);
+        let path = path.to_path_buf();
+        let interval = Duration::from_secs_f64(1.0 / fps.max(1) as f64);
+        thread::spawn(move || serve(&path, &latest, interval));
+        Ok(stream)
+    }
+
+    /// Replaces the frame sent on the following ticks.
+    pub fn publish(&self, frame: &str) {
+        if let Ok(mut latest) = self.latest.lock() {
+            let slot = latest.get_or_insert_with(String::new);
+            slot.clear();
+            slot.push_str(frame);
+        }
+    }
+
+    /// Stops sending frames until the next `publish`, e.g. when playback returns to the menu.
+    pub fn clear(&self) {
+        if let Ok(mut latest) = self.latest.lock() {
+            *latest = None;
+        }
+    }
+}
+
+#[cfg(unix)]
+fn prepare_fifo(path: &Path) -> Result<()> {
+    use std::os::unix::fs::FileTypeExt;
+
+    match std::fs::metadata(path) {
+        Ok(meta) if meta.file_type().is_fifo() => Ok(()),
+        Ok(_) => anyhow::bail!("{} exists and is not a named pipe", path.display()),
+        Err(_) => {
+            let status = std::process::Command::new("mkfifo")
+                .arg(path)
+                .status()
+                .context("Failed to run mkfifo")?;
+            if !status.success() {
+                anyhow::bail!("mkfifo {} exited with {}", path.display(), status);
+            }
+            Ok(())
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn prepare_fifo(_path: &Path) -> Result<()> {
+    anyhow::bail!("FIFO output is only supported on Unix")
+}
+
+/// Streaming thread: one iteration of the outer loop per connected reader.
+fn serve(path: &Path, latest: &Mutex<Option<String>>, interval: Duration) {
+    loop {
+        // Blocks until a reader opens the other end.
+        let Ok(mut pipe) = OpenOptions::new().write(true).open(path) else {
+            thread::sleep(Duration::from_secs(1));
+            continue;
+        };
+        let mut next_tick = Instant::now();
+        loop {
+            let frame = latest.lock().ok().and_then(|f| f.clone());
+            if let Some(frame) = frame {
+                // A write error means the reader disconnected (Rust ignores SIGPIPE): wait for the next one.
+                if pipe.write_all(frame.as_bytes()).and_then(|_| pipe.flush()).is_err() {
+                    break;
+                }
+            }
+            next_tick += interval;
+            match next_tick.checked_duration_since(Instant::now()) {
+                Some(wait) => thread::sleep(wait),
+                None => next_tick = Instant::now(),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    #[cfg(unix)]
+    #[test]
+    fn reader_can_disconnect_and_reconnect() {
+        let dir = std::env::temp_dir().join(format!("vodeo2ascii-fifo-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("frames");
+        let _ = std::fs::remove_file(&path);
+
+        let stream = FrameStream::start(&path, 100).unwrap();
+        stream.publish("first");
+        for expected in ["first", "second"] {
+            let mut reader = std::fs::File::open(&path).unwrap();
+            let mut seen = String::new();
+            let mut buf = [0u8; 64];
+            while !seen.contains(expected) {
+                let n = reader.read(&mut buf).unwrap();
+                assert!(n > 0);
+                seen.push_str(std::str::from_utf8(&buf[..n]).unwrap());
+            }
+            drop(reader);
+            stream.publish("second");
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
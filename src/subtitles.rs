@@ -0,0 +1,230 @@
+use std::path::{Path, PathBuf};
+
+use crate::i18n::Msg;
+
+/// Codecs whose subtitles are pictures rather than text, so they can't be drawn as an overlay.
+const BITMAP_CODECS: [&str; 4] = ["hdmv_pgs_subtitle", "dvd_subtitle", "dvb_subtitle", "xsub"];
+
+/// A subtitle stream embedded in a file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Track {
+    /// Position among the file's subtitle streams, as in ffmpeg's `-map 0:s:N`.
+    pub index: usize,
+    pub codec: String,
+    pub language: Option<String>,
+    pub title: Option<String>,
+}
+
+impl Track {
+    /// Text-based tracks, which ffmpeg can convert to SRT for the overlay.
+    pub fn is_text(&self) -> bool {
+        !BITMAP_CODECS.contains(&self.codec.as_str())
+    }
+
+    /// Menu label: number, language and title where known, and the codec.
+    pub fn label(&self) -> String {
+        let mut label = format!("#{}", self.index + 1);
+        for part in [&self.language, &self.title].into_iter().flatten() {
+            label.push(' ');
+            label.push_str(part);
+        }
+        label.push_str(&format!(" ({})", self.codec));
+        if !self.is_text() {
+            label.push(' ');
+            label.push_str(Msg::BitmapTrack.text());
+        }
+        label
+    }
+}
+
+/// Reads the `[STREAM]` blocks of `ffprobe -select_streams s -show_entries stream=codec_name:stream_tags=language,title`.
+pub fn parse_tracks(output: &str) -> Vec<Track> {
+    let mut tracks: Vec<Track> = Vec::new();
+    for line in output.lines() {
+        let line = line.trim();
+        if line == "[STREAM]" {
+            tracks.push(Track { index: tracks.len(), codec: String::new(), language: None, title: None });
+            continue;
+        }
+        let Some(track) = tracks.last_mut() else { continue };
+        match line.split_once('=') {
+            Some((_, "")) => {}
+            Some(("codec_name", value)) => track.codec = value.to_string(),
+            Some((key, value)) if key.eq_ignore_ascii_case("TAG:language") => track.language = Some(value.to_string()),
+            Some((key, value)) if key.eq_ignore_ascii_case("TAG:title") => track.title = Some(value.to_string()),
+            _ => {}
+        }
+    }
+    tracks
+}
+
+/// Subtitles that ffmpeg draws into the picture for `--burn-subs`, instead of the text overlay.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Burn {
+    /// A text track of the file, by index, rendered with the `subtitles` filter.
+    Text(usize),
+    /// A bitmap track of the file (PGS, DVD), laid over the video with `overlay`.
+    Bitmap(usize),
+    /// A subtitle file next to the video, in any format the `subtitles` filter reads.
+    File(PathBuf),
+}
+
+impl Burn {
+    /// A track number from 1, as in the `U` menu, or a subtitle file. Tracks start out as text
+    /// until `resolve` sees their codec.
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.parse::<usize>() {
+            Ok(0) => None,
+            Ok(n) => Some(Burn::Text(n - 1)),
+            Err(_) if !s.is_empty() => Some(Burn::File(PathBuf::from(s))),
+            Err(_) => None,
+        }
+    }
+
+    /// Picks the filter for the track's codec; `None` when the file has no such track.
+    pub fn resolve(self, tracks: &[Track]) -> Option<Self> {
+        match self {
+            Burn::Text(index) | Burn::Bitmap(index) => {
+                let track = tracks.iter().find(|t| t.index == index)?;
+                Some(if track.is_text() { Burn::Text(index) } else { Burn::Bitmap(index) })
+            }
+            file => Some(file),
+        }
+    }
+
+    /// The filter that burns the subtitles into the video of `input`. The bitmap overlay reads the
+    /// subtitle stream as a second input, so it only works at the start of a `-filter_complex` graph.
+    pub fn filter(&self, input: &Path) -> String {
+        match self {
+            Burn::Text(index) => format!("subtitles=filename={}:si={}", escape_filter_path(input), index),
+            Burn::Bitmap(index) => format!("[0:v:0][0:s:{}]overlay", index),
+            Burn::File(path) => format!("subtitles=filename={}", escape_filter_path(path)),
+        }
+    }
+
+    /// The `subtitles` filter times cues by the frames' timestamps, so they have to stay those of
+    /// the file when playback starts part way in. The overlay's two streams are seeked together.
+    pub fn needs_source_timestamps(&self) -> bool {
+        !matches!(self, Burn::Bitmap(_))
+    }
+}
+
+/// Escapes `path` for a filter option inside a filtergraph: once for the option value, then again
+/// for the graph, as ffmpeg unescapes it twice.
+fn escape_filter_path(path: &Path) -> String {
+    let escape = |s: &str, special: &[char]| {
+        s.chars().fold(String::new(), |mut out, c| {
+            if special.contains(&c) {
+                out.push('\\');
+            }
+            out.push(c);
+            out
+        })
+    };
+    let value = escape(&path.to_string_lossy(), &['\\', '\'', ':']);
+    escape(&value, &['\\', '\'', '[', ']', ',', ';'])
+}
+
+/// One subtitle shown from `start` until `end`, in seconds.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cue {
+    pub start: f64,
+    pub end: f64,
+    pub text: Vec<String>,
+}
+
+/// Parses SRT as written by `ffmpeg -f srt`, dropping formatting tags; malformed blocks are skipped.
+pub fn parse_srt(srt: &str) -> Vec<Cue> {
+    let srt = srt.replace("\r\n", "\n");
+    let mut cues: Vec<Cue> = srt
+        .split("\n\n")
+        .filter_map(|block| {
+            let mut lines = block.lines().skip_while(|l| !l.contains("-->"));
+            let (start, end) = lines.next()?.split_once("-->")?;
+            let text: Vec<String> = lines.map(strip_tags).filter(|l| !l.is_empty()).collect();
+            Some(Cue { start: parse_timestamp(start)?, end: parse_timestamp(end)?, text })
+        })
+        .filter(|cue| !cue.text.is_empty())
+        .collect();
+    cues.sort_by(|a, b| a.start.total_cmp(&b.start));
+    cues
+}
+
+/// `HH:MM:SS,mmm` in seconds.
+fn parse_timestamp(s: &str) -> Option<f64> {
+    let (clock, millis) = s.trim().split_once([',', '.'])?;
+    let mut secs = 0.0;
+    for part in clock.split(':') {
+        secs = secs * 60.0 + part.parse::<f64>().ok()?;
+    }
+    Some(secs + millis.parse::<f64>().ok()? / 1000.0)
+}
+
+/// Removes `<i>`-style and `{\an8}`-style markup.
+fn strip_tags(line: &str) -> String {
+    let mut out = String::new();
+    let mut closer = None;
+    for c in line.chars() {
+        match (closer, c) {
+            (None, '<') => closer = Some('>'),
+            (None, '{') => closer = Some('}'),
+            (None, c) => out.push(c),
+            (Some(end), c) if c == end => closer = None,
+            _ => {}
+        }
+    }
+    out.trim().to_string()
+}
+
+/// The cue on screen at `position`, if any.
+pub fn cue_at(cues: &[Cue], position: f64) -> Option<&Cue> {
+    let i = cues.partition_point(|c| c.start <= position);
+    cues[..i].iter().rev().find(|c| position < c.end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tracks_are_parsed_with_bitmap_ones_flagged() {
+        let output = "[STREAM]\ncodec_name=subrip\nTAG:language=chi\nTAG:title=简体\n[/STREAM]\n[STREAM]\ncodec_name=hdmv_pgs_subtitle\nTAG:language=\n[/STREAM]\n";
+        let tracks = parse_tracks(output);
+        assert_eq!(tracks.len(), 2);
+        assert_eq!(tracks[0].label(), "#1 chi 简体 (subrip)");
+        assert!(tracks[0].is_text());
+        assert_eq!(tracks[1].language, None);
+        assert!(!tracks[1].is_text());
+        assert!(parse_tracks("").is_empty());
+    }
+
+    #[test]
+    fn srt_cues_are_parsed_and_looked_up_by_time() {
+        let srt = "1\r\n00:00:01,000 --> 00:00:02,500\r\n<i>Hello</i>\r\n{\\an8}world\r\n\r\n2\r\n00:01:00,000 --> 00:01:01,000\r\nBye\r\n\r\nbroken\r\n";
+        let cues = parse_srt(srt);
+        assert_eq!(cues.len(), 2);
+        assert_eq!(cues[0], Cue { start: 1.0, end: 2.5, text: vec!["Hello".into(), "world".into()] });
+        assert_eq!(cues[1].start, 60.0);
+
+        assert_eq!(cue_at(&cues, 0.5), None);
+        assert_eq!(cue_at(&cues, 2.0).map(|c| c.text[0].as_str()), Some("Hello"));
+        assert_eq!(cue_at(&cues, 3.0), None);
+        assert_eq!(cue_at(&cues, 60.5).map(|c| c.text[0].as_str()), Some("Bye"));
+    }
+
+    #[test]
+    fn burned_subtitles_pick_a_filter_per_track() {
+        let tracks = parse_tracks("[STREAM]\ncodec_name=subrip\n[/STREAM]\n[STREAM]\ncodec_name=hdmv_pgs_subtitle\n[/STREAM]\n");
+        assert_eq!(Burn::parse("2"), Some(Burn::Text(1)));
+        assert_eq!(Burn::parse("0"), None);
+        assert_eq!(Burn::Text(1).resolve(&tracks), Some(Burn::Bitmap(1)));
+        assert_eq!(Burn::Text(2).resolve(&tracks), None);
+
+        let input = Path::new("movie.mkv");
+        assert_eq!(Burn::Text(0).filter(input), "subtitles=filename=movie.mkv:si=0");
+        assert_eq!(Burn::Bitmap(1).filter(input), "[0:v:0][0:s:1]overlay");
+        assert!(!Burn::Bitmap(1).needs_source_timestamps());
+        let file = Burn::parse("C:\\subs\\it's [1],en.srt").unwrap();
+        assert_eq!(file.filter(input), r"subtitles=filename=C\\:\\\\subs\\\\it\\\'s \[1\]\,en.srt");
+    }
+}
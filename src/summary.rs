@@ -0,0 +1,124 @@
+use std::time::{Duration, Instant};
+
+/// Length of the windows the lowest and highest frame rates are measured over.
+const FPS_WINDOW: Duration = Duration::from_secs(1);
+
+/// What a playback session drew, collected whether or not the summary is shown.
+#[derive(Debug)]
+pub struct PlaybackStats {
+    started: Instant,
+    /// Frames written to the terminal.
+    pub rendered: u64,
+    /// Frames read but not drawn because of the FPS cap.
+    pub capped: u64,
+    /// Frames read but skipped to catch up with the newest one.
+    pub skipped: u64,
+    /// Bytes written to the terminal for frames.
+    pub bytes: u64,
+    window_start: Instant,
+    window_frames: u32,
+    /// Slowest and fastest whole `FPS_WINDOW`, in frames per second.
+    pub min_fps: Option<f64>,
+    pub max_fps: Option<f64>,
+}
+
+impl PlaybackStats {
+    pub fn new(now: Instant) -> Self {
+        Self { started: now, rendered: 0, capped: 0, skipped: 0, bytes: 0, window_start: now, window_frames: 0, min_fps: None, max_fps: None }
+    }
+
+    /// Records a frame of `bytes` written to the terminal at `now`.
+    pub fn drawn(&mut self, bytes: usize, now: Instant) {
+        self.rendered += 1;
+        self.bytes += bytes as u64;
+        self.window_frames += 1;
+        let window = now - self.window_start;
+        if window >= FPS_WINDOW {
+            let fps = self.window_frames as f64 / window.as_secs_f64();
+            self.min_fps = Some(self.min_fps.map_or(fps, |m| m.min(fps)));
+            self.max_fps = Some(self.max_fps.map_or(fps, |m| m.max(fps)));
+            self.window_start = now;
+            self.window_frames = 0;
+        }
+    }
+
+    /// Time since playback started, as of `now`.
+    pub fn elapsed(&self, now: Instant) -> Duration {
+        now - self.started
+    }
+
+    /// Frames drawn per second over the whole session, as of `now`.
+    pub fn average_fps(&self, now: Instant) -> f64 {
+        self.rendered as f64 / self.elapsed(now).as_secs_f64().max(f64::EPSILON)
+    }
+}
+
+/// Wall-clock time against media time played, summed over the segments a session is decoded in
+/// so seeks and pauses between them don't count. When the terminal can't keep up the wall clock
+/// pulls ahead, and the difference is how far playback has fallen behind.
+#[derive(Debug, Default)]
+pub struct LagClock {
+    /// Wall and media time of the segments before the current one.
+    done: (Duration, Duration),
+    current: (Duration, Duration),
+}
+
+impl LagClock {
+    /// Records a frame `media` into its segment, played `wall` after the segment's first frame.
+    /// A frame at the very start of a segment begins a new one.
+    pub fn tick(&mut self, wall: Duration, media: Duration) {
+        if media.is_zero() {
+            self.done = (self.done.0 + self.current.0, self.done.1 + self.current.1);
+        }
+        self.current = (wall, media);
+    }
+
+    pub fn wall(&self) -> Duration {
+        self.done.0 + self.current.0
+    }
+
+    pub fn media(&self) -> Duration {
+        self.done.1 + self.current.1
+    }
+
+    /// How far playback is behind the media; frames are never shown early, so it doesn't go below zero.
+    pub fn lag(&self) -> Duration {
+        self.wall().saturating_sub(self.media())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rates_come_from_whole_windows() {
+        let start = Instant::now();
+        let mut stats = PlaybackStats::new(start);
+        let ms = |n| start + Duration::from_millis(n);
+        // 10 fps for a second, then 20 fps for a second.
+        (1..=10).for_each(|i| stats.drawn(100, ms(i * 100)));
+        (1..=20).for_each(|i| stats.drawn(100, ms(1000 + i * 50)));
+        assert_eq!((stats.rendered, stats.bytes), (30, 3000));
+        assert_eq!((stats.min_fps, stats.max_fps), (Some(10.0), Some(20.0)));
+        assert_eq!(stats.average_fps(ms(2000)), 15.0);
+
+        // A partial window doesn't count towards the lowest rate.
+        stats.drawn(100, ms(2100));
+        assert_eq!(stats.min_fps, Some(10.0));
+    }
+
+    #[test]
+    fn lag_adds_up_across_segments() {
+        let ms = Duration::from_millis;
+        let mut clock = LagClock::default();
+        // A second of media in 1.2 s, then a seek and half a second more in 0.6 s.
+        clock.tick(ms(0), ms(0));
+        clock.tick(ms(1200), ms(1000));
+        clock.tick(ms(0), ms(0));
+        clock.tick(ms(600), ms(500));
+        assert_eq!((clock.wall(), clock.media(), clock.lag()), (ms(1800), ms(1500), ms(300)));
+        // Playback that keeps up shows no lag.
+        assert_eq!(LagClock { current: (ms(990), ms(1000)), ..Default::default() }.lag(), Duration::ZERO);
+    }
+}
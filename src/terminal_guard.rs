@@ -0,0 +1,49 @@
+use anyhow::Result;
+use crossterm::{
+    cursor, execute,
+    terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use std::{
+    io::Write,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// Guards currently alive; only the outermost one touches raw mode and the alternate screen.
+static DEPTH: AtomicUsize = AtomicUsize::new(0);
+
+/// Raw mode, the alternate screen and a hidden cursor for as long as the guard lives.
+///
+/// Dropping it restores the terminal on every exit path, including `?` returns and panics.
+/// Guards nest: playback takes one while the menu holds its own, and only the outermost guard sets
+/// up and tears down the terminal, so nothing is restored twice or while still in use.
+pub struct TerminalGuard {
+    outermost: bool,
+}
+
+impl TerminalGuard {
+    pub fn new() -> Result<Self> {
+        // Built before any setup so a failure half-way is still undone by `Drop`.
+        let guard = Self { outermost: DEPTH.fetch_add(1, Ordering::SeqCst) == 0 };
+        let mut out = std::io::stdout();
+        if guard.outermost {
+            terminal::enable_raw_mode()?;
+            execute!(out, EnterAlternateScreen)?;
+        }
+        execute!(out, cursor::Hide, Clear(ClearType::All))?;
+        Ok(guard)
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        let mut out = std::io::stdout();
+        let _ = out.write_all(b"\x1b[0m");
+        DEPTH.fetch_sub(1, Ordering::SeqCst);
+        if self.outermost {
+            let _ = execute!(out, cursor::Show, LeaveAlternateScreen);
+            let _ = terminal::disable_raw_mode();
+        } else {
+            let _ = out.flush();
+        }
+    }
+}
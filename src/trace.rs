@@ -0,0 +1,127 @@
+use std::{
+    fs::File,
+    io::{self, LineWriter, Write},
+    path::{Path, PathBuf},
+    process::Command,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+};
+
+use chrono::Local;
+
+/// `--verbose`: log every external command and playback decision to stderr.
+static ENABLED: AtomicBool = AtomicBool::new(false);
+/// Lines held back while the TUI owns the screen, printed once it's gone.
+static DEFERRED: Mutex<Option<Vec<String>>> = Mutex::new(None);
+/// `--log`: the same lines, timestamped, written to a file as they happen.
+static LOG_FILE: Mutex<Option<LineWriter<File>>> = Mutex::new(None);
+
+pub fn enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+/// Whether anything is logged, to stderr or a file.
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed) || LOG_FILE.lock().is_ok_and(|f| f.is_some())
+}
+
+/// Starts a fresh log at `path`, replacing an older one, for this session.
+pub fn log_to(path: &Path) -> io::Result<()> {
+    let mut file = LineWriter::new(File::create(path)?);
+    writeln!(file, "# vodeo2ascii {} log, started {}", env!("CARGO_PKG_VERSION"), Local::now().format("%Y-%m-%d %H:%M:%S"))?;
+    if let Ok(mut log) = LOG_FILE.lock() {
+        *log = Some(file);
+    }
+    Ok(())
+}
+
+/// Where the log goes when `RUST_LOG` asks for one without `--log` naming a file.
+pub fn default_log_path() -> PathBuf {
+    std::env::temp_dir().join("vodeo2ascii.log")
+}
+
+/// Holds log lines back until `flush`, so stderr doesn't scribble over the TUI.
+pub fn defer() {
+    if let Ok(mut deferred) = DEFERRED.lock() {
+        deferred.get_or_insert_with(Vec::new);
+    }
+}
+
+/// Prints everything held back by `defer` and goes back to logging immediately.
+pub fn flush() {
+    let lines = DEFERRED.lock().ok().and_then(|mut d| d.take()).unwrap_or_default();
+    for line in lines {
+        eprintln!("{}", line);
+    }
+}
+
+pub fn note(message: impl AsRef<str>) {
+    if let Ok(Some(file)) = LOG_FILE.lock().as_deref_mut() {
+        let _ = writeln!(file, "{} {}", Local::now().format("%H:%M:%S%.3f"), message.as_ref());
+    }
+    if !ENABLED.load(Ordering::Relaxed) {
+        return;
+    }
+    let line = format!("[vodeo2ascii] {}", message.as_ref());
+    match DEFERRED.lock().as_deref_mut() {
+        Ok(Some(lines)) => lines.push(line),
+        _ => eprintln!("{}", line),
+    }
+}
+
+/// Logs `cmd` as a copy-pasteable shell line.
+pub fn command(cmd: &Command) {
+    if enabled() {
+        note(format!("$ {}", format_command(cmd)));
+    }
+}
+
+/// Program and arguments of `cmd`, quoted where a shell would need it.
+pub fn format_command(cmd: &Command) -> String {
+    std::iter::once(cmd.get_program())
+        .chain(cmd.get_args())
+        .map(|arg| shell_quote(&arg.to_string_lossy()))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// `arg` as a shell reads it back: unchanged when that's safe, single-quoted otherwise.
+pub fn shell_quote(arg: &str) -> String {
+    let plain = !arg.is_empty()
+        && arg.chars().all(|c| c.is_alphanumeric() || "-_./:=,+%@".contains(c));
+    if plain {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', "'\\''"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn commands_are_quoted_for_the_shell() {
+        let mut cmd = Command::new("ffmpeg");
+        cmd.args(["-vf", "scale=120:68,format=rgb24", "-i", "My Videos/it's.mp4", ""]);
+        assert_eq!(
+            format_command(&cmd),
+            "ffmpeg -vf scale=120:68,format=rgb24 -i 'My Videos/it'\\''s.mp4' ''"
+        );
+    }
+
+    #[test]
+    fn notes_are_written_to_the_log_file() {
+        let path = std::env::temp_dir().join(format!("vodeo2ascii-trace-{}.log", std::process::id()));
+        log_to(&path).unwrap();
+        assert!(enabled());
+        note("probe ok");
+        *LOG_FILE.lock().unwrap() = None;
+        let log = std::fs::read_to_string(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert!(log.starts_with("# vodeo2ascii "));
+        assert!(log.lines().nth(1).is_some_and(|l| l.ends_with(" probe ok")));
+    }
+}
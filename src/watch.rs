@@ -0,0 +1,68 @@
+use std::{
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+/// Time between two scans of the directory.
+pub const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Notices files being added to or removed from the scan root by re-scanning it periodically.
+///
+/// A changed listing is only reported once two consecutive polls agree on it, so a burst of
+/// changes (a batch being copied in) settles into one update instead of many.
+pub struct DirWatcher {
+    known: Vec<PathBuf>,
+    pending: Option<Vec<PathBuf>>,
+    last_poll: Instant,
+}
+
+impl DirWatcher {
+    /// Starts from `known`, the listing currently shown.
+    pub fn new(known: Vec<PathBuf>) -> Self {
+        Self { known, pending: None, last_poll: Instant::now() }
+    }
+
+    /// Scans with `scan` when a poll is due; returns the previous and the new listing once a change
+    /// has settled.
+    pub fn poll(&mut self, scan: impl FnOnce() -> Vec<PathBuf>) -> Option<(Vec<PathBuf>, Vec<PathBuf>)> {
+        if self.last_poll.elapsed() < POLL_INTERVAL {
+            return None;
+        }
+        self.last_poll = Instant::now();
+        self.observe(scan())
+    }
+
+    fn observe(&mut self, listing: Vec<PathBuf>) -> Option<(Vec<PathBuf>, Vec<PathBuf>)> {
+        if listing == self.known {
+            self.pending = None;
+            return None;
+        }
+        if self.pending.as_ref() != Some(&listing) {
+            self.pending = Some(listing);
+            return None;
+        }
+        self.pending = None;
+        Some((std::mem::replace(&mut self.known, listing.clone()), listing))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn changes_are_reported_once_they_settle() {
+        let files = |names: &[&str]| names.iter().map(PathBuf::from).collect::<Vec<_>>();
+        let mut watcher = DirWatcher::new(files(&["a.mp4"]));
+        assert_eq!(watcher.observe(files(&["a.mp4"])), None);
+
+        // Still changing: nothing until two polls agree.
+        assert_eq!(watcher.observe(files(&["a.mp4", "b.mp4"])), None);
+        assert_eq!(watcher.observe(files(&["a.mp4", "b.mp4", "c.mp4"])), None);
+        let (before, after) = watcher.observe(files(&["a.mp4", "b.mp4", "c.mp4"])).unwrap();
+        assert_eq!(before, files(&["a.mp4"]));
+        assert_eq!(after, files(&["a.mp4", "b.mp4", "c.mp4"]));
+
+        assert_eq!(watcher.observe(files(&["a.mp4", "b.mp4", "c.mp4"])), None);
+    }
+}